@@ -13,7 +13,17 @@ import type {
 	EventClass,
 	Moto,
 	MotoWithEntries,
+	Penalty,
+	PenaltyKind,
+	Protest,
+	ProtestDecision,
+	ResultChange,
+	PracticeSessionResults,
 	RaceStateResponse,
+	RecordedPassing,
+	StartMode,
+	TimingChange,
+	TimingCorrectionRecord,
 	TrackOnboardingDiscoveryResponse
 } from './types';
 
@@ -117,7 +127,41 @@ export const events = {
 
 // Motos
 export const motos = {
-	get: (id: string) => request<MotoWithEntries>(`/motos/${id}`)
+	get: (id: string) => request<MotoWithEntries>(`/motos/${id}`),
+	corrections: (id: string) => request<TimingCorrectionRecord[]>(`/motos/${id}/corrections`),
+	history: (id: string) => request<ResultChange[]>(`/motos/${id}/history`)
+};
+
+// Penalties and protests on finished motos
+export const penalties = {
+	list: (motoId: string) => request<Penalty[]>(`/motos/${motoId}/penalties`),
+	issue: (motoId: string, riderId: string, penalty: PenaltyKind, reason: string, issuedBy: string) =>
+		request<Penalty>(`/motos/${motoId}/penalties`, {
+			method: 'POST',
+			body: JSON.stringify({ rider_id: riderId, penalty, reason, issued_by: issuedBy })
+		}),
+	revoke: (id: string, revokedBy: string, reason: string) =>
+		request<Penalty>(`/penalties/${id}/revoke`, {
+			method: 'POST',
+			body: JSON.stringify({ revoked_by: revokedBy, reason })
+		}),
+	listProtests: (motoId: string) => request<Protest[]>(`/motos/${motoId}/protests`),
+	fileProtest: (motoId: string, riderId: string | null, filedBy: string, description: string) =>
+		request<Protest>(`/motos/${motoId}/protests`, {
+			method: 'POST',
+			body: JSON.stringify({ rider_id: riderId, filed_by: filedBy, description })
+		}),
+	decideProtest: (
+		id: string,
+		decision: ProtestDecision,
+		decidedBy: string,
+		reason: string,
+		penalty?: { rider_id?: string; penalty: PenaltyKind }
+	) =>
+		request<Protest>(`/protests/${id}/decision`, {
+			method: 'POST',
+			body: JSON.stringify({ decision, decided_by: decidedBy, reason, penalty })
+		})
 };
 
 // Seed demo data
@@ -129,16 +173,51 @@ export const seed = {
 		)
 };
 
-// Race control
+// Race control, scoped to the track whose engine runs the race
+const raceBase = (trackId: string) => `/tracks/${trackId}/race`;
+
+// Motos overlap on a track, so some calls can name the one they mean
+const motoQuery = (motoId?: string) => (motoId ? `?moto_id=${encodeURIComponent(motoId)}` : '');
+
 export const race = {
-	getState: () => request<RaceStateResponse>('/race/state'),
-	stage: (motoId: string, trackId: string) =>
-		request<RaceStateResponse>('/race/stage', {
+	getState: (trackId: string) => request<RaceStateResponse>(`${raceBase(trackId)}/state`),
+	stage: (trackId: string, motoId: string, laps?: number, startMode?: StartMode) =>
+		request<RaceStateResponse>(`${raceBase(trackId)}/stage`, {
+			method: 'POST',
+			body: JSON.stringify({ moto_id: motoId, laps, start_mode: startMode })
+		}),
+	addToStartList: (trackId: string, motoId: string, riderIds: string[]) =>
+		request<RaceStateResponse>(`${raceBase(trackId)}/start-list`, {
+			method: 'POST',
+			body: JSON.stringify({ moto_id: motoId, rider_ids: riderIds })
+		}),
+	bindTransponder: (trackId: string, motoId: string, riderId: string, transponderId: number) =>
+		request<RaceStateResponse>(`${raceBase(trackId)}/bind-transponder`, {
 			method: 'POST',
-			body: JSON.stringify({ moto_id: motoId, track_id: trackId })
+			body: JSON.stringify({ moto_id: motoId, rider_id: riderId, transponder_id: transponderId })
 		}),
-	reset: () => request<RaceStateResponse>('/race/reset', { method: 'POST' }),
-	forceFinish: () => request<RaceStateResponse>('/race/force-finish', { method: 'POST' })
+	correct: (trackId: string, motoId: string, operator: string, reason: string, change: TimingChange) =>
+		request<RaceStateResponse>(`${raceBase(trackId)}/corrections`, {
+			method: 'POST',
+			body: JSON.stringify({ moto_id: motoId, operator, reason, change })
+		}),
+	passings: (trackId: string, motoId?: string) =>
+		request<RecordedPassing[]>(`${raceBase(trackId)}/passings${motoQuery(motoId)}`),
+	reset: (trackId: string) =>
+		request<RaceStateResponse>(`${raceBase(trackId)}/reset`, { method: 'POST' }),
+	forceFinish: (trackId: string, motoId?: string) =>
+		request<RaceStateResponse>(`${raceBase(trackId)}/force-finish${motoQuery(motoId)}`, {
+			method: 'POST'
+		}),
+	startPractice: (trackId: string) =>
+		request<RaceStateResponse>(`${raceBase(trackId)}/practice/start`, { method: 'POST' }),
+	stopPractice: (trackId: string) =>
+		request<RaceStateResponse>(`${raceBase(trackId)}/practice/stop`, { method: 'POST' })
+};
+
+// Practice results
+export const practice = {
+	get: (sessionId: string) => request<PracticeSessionResults>(`/practice/${sessionId}`)
 };
 
 // Track onboarding
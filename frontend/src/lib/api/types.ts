@@ -20,6 +20,7 @@ export interface TimingLoop {
 	is_finish: boolean;
 	is_start: boolean;
 	created_at: string;
+	debounce_ms: number;
 }
 
 export interface TrackSection {
@@ -76,6 +77,7 @@ export interface CreateLoopRequest {
 	position: number;
 	is_finish?: boolean;
 	is_start?: boolean;
+	debounce_ms?: number;
 }
 
 export interface CreateRiderRequest {
@@ -144,6 +146,11 @@ export interface MotoEntry {
 	dnf: boolean;
 	dns: boolean;
 	created_at: string;
+	// Results after penalties; these count for standings
+	effective_position: number | null;
+	effective_elapsed_us: number | null;
+	effective_points: number | null;
+	dsq: boolean;
 }
 
 export interface MotoWithEntries extends Moto {
@@ -154,8 +161,70 @@ export interface EntryWithRider extends MotoEntry {
 	rider: Rider | null;
 }
 
+export type PenaltyKind =
+	| { type: 'relegation'; positions?: number | null }
+	| { type: 'time_penalty'; time_us: number }
+	| { type: 'disqualification' };
+
+export interface Penalty {
+	id: string;
+	moto_id: string;
+	rider_id: string;
+	penalty: PenaltyKind;
+	reason: string;
+	issued_by: string;
+	protest_id: string | null;
+	status: 'active' | 'revoked';
+	revoked_by: string | null;
+	revoke_reason: string | null;
+	created_at: string;
+	revoked_at: string | null;
+}
+
+export type ProtestDecision = 'upheld' | 'denied';
+
+export interface Protest {
+	id: string;
+	moto_id: string;
+	rider_id: string | null;
+	filed_by: string;
+	description: string;
+	status: 'open' | ProtestDecision;
+	decided_by: string | null;
+	decision_reason: string | null;
+	created_at: string;
+	decided_at: string | null;
+}
+
+export interface EffectiveResult {
+	rider_id: string;
+	position: number | null;
+	elapsed_us: number | null;
+	points: number;
+	dnf: boolean;
+	dns: boolean;
+	dsq: boolean;
+}
+
+export interface ResultChange {
+	id: string;
+	action: string;
+	actor: string;
+	reason: string | null;
+	penalty_id: string | null;
+	protest_id: string | null;
+	results: EffectiveResult[] | null;
+	created_at: string;
+}
+
 // --- WebSocket P3 message types ---
 
+/** Raw TLV field the parser didn't recognize; value is uppercase hex */
+export interface RawTlvField {
+	tag: number;
+	value: string;
+}
+
 export interface PassingMessage {
 	message_type: 'PASSING';
 	passing_number: number;
@@ -166,6 +235,7 @@ export interface PassingMessage {
 	transponder_string?: string;
 	flags: number;
 	decoder_id?: string;
+	extra_fields?: RawTlvField[];
 }
 
 export interface StatusMessage {
@@ -175,6 +245,7 @@ export interface StatusMessage {
 	temperature: number;
 	satellites: number;
 	decoder_id?: string;
+	extra_fields?: RawTlvField[];
 }
 
 export interface VersionMessage {
@@ -183,9 +254,28 @@ export interface VersionMessage {
 	description: string;
 	version: string;
 	build?: number;
+	extra_fields?: RawTlvField[];
+}
+
+export interface ResendMessage {
+	message_type: 'RESEND';
+	from_passing: number;
+	until_passing: number;
+	extra_fields?: RawTlvField[];
+}
+
+export interface UnknownMessage {
+	message_type: 'UNKNOWN';
+	tor: number;
+	fields: RawTlvField[];
 }
 
-export type P3Message = PassingMessage | StatusMessage | VersionMessage;
+export type P3Message =
+	| PassingMessage
+	| StatusMessage
+	| VersionMessage
+	| ResendMessage
+	| UnknownMessage;
 
 export type LiveEnvelopeKind = 'snapshot' | 'event' | 'heartbeat' | 'error';
 
@@ -198,6 +288,7 @@ export interface LiveEnvelopeBase {
 	event_id: string | null;
 	seq: number;
 	ts_us: number;
+	resume_token: string | null;
 }
 
 export interface DecoderStatusRow {
@@ -255,6 +346,59 @@ export type DecoderLiveEnvelope =
 	| DecoderHeartbeatEnvelope
 	| DecoderErrorEnvelope;
 
+export type RaceEventPayload =
+	| { kind: 'race_staged'; moto_id: string; class_name: string; round_type: string; riders: StagedRider[]; laps: number; start_mode: StartMode }
+	| { kind: 'gate_drop'; moto_id: string; timestamp_us: number }
+	| { kind: 'rider_started'; moto_id: string; rider_id: string; timestamp_us: number }
+	| { kind: 'start_list_updated'; moto_id: string; riders: StagedRider[] }
+	| { kind: 'split_time'; moto_id: string; rider_id: string; lap: number; loop_name: string; is_finish: boolean; elapsed_us: number; position: number; gap_to_leader_us: number | null }
+	| { kind: 'split_corrected'; moto_id: string; rider_id: string; lap: number; loop_name: string; is_finish: boolean; previous_elapsed_us: number; elapsed_us: number; position: number; gap_to_leader_us: number | null }
+	| { kind: 'positions_update'; moto_id: string; positions: RiderPosition[] }
+	| { kind: 'rider_finished'; moto_id: string; rider_id: string; finish_position: number; elapsed_us: number; gap_to_leader_us: number | null }
+	| { kind: 'race_finished'; moto_id: string; results: FinishResult[] }
+	| { kind: 'timing_corrected'; moto_id: string; correction: TimingCorrection }
+	| { kind: 'unknown_transponder'; moto_id: string; transponder_id: number; loop_name: string; timestamp_us: number }
+	| { kind: 'unmapped_decoder'; moto_id: string; decoder_id: string | null; transponder_id: number; timestamp_us: number }
+	| { kind: 'clock_drift'; decoder_id: string; drift_ppm: number; offset_us: number; timestamp_us: number }
+	| { kind: 'race_reset' }
+	| { kind: 'practice_started'; session_id: string }
+	| { kind: 'practice_update'; lap: PracticeLap; leaderboard: PracticeLeaderboard }
+	| { kind: 'practice_ended'; leaderboard: PracticeLeaderboard }
+	| RaceStateSnapshotPayload;
+
+export interface RaceStateSnapshotPayload {
+	kind: 'state_snapshot';
+	phase: string;
+	moto_id: string | null;
+	class_name: string | null;
+	round_type: string | null;
+	riders: StagedRider[];
+	positions: RiderPosition[];
+	gate_drop_time_us: number | null;
+	finished_count: number;
+	total_riders: number;
+	laps: number;
+	practice: PracticeLeaderboard | null;
+}
+
+export interface RaceSnapshotEnvelope extends LiveEnvelopeBase {
+	kind: 'snapshot';
+	channel: 'race';
+	payload: RaceStateSnapshotPayload;
+}
+
+export interface RaceEventEnvelope extends LiveEnvelopeBase {
+	kind: 'event';
+	channel: 'race';
+	payload: RaceEventPayload;
+}
+
+export type RaceLiveEnvelope =
+	| RaceSnapshotEnvelope
+	| RaceEventEnvelope
+	| DecoderHeartbeatEnvelope
+	| DecoderErrorEnvelope;
+
 // --- WebSocket Race Event types ---
 
 export interface StagedRider {
@@ -266,6 +410,8 @@ export interface StagedRider {
 	lane: number;
 }
 
+export type StartMode = 'gate_drop' | 'time_trial';
+
 export interface RiderPosition {
 	rider_id: string;
 	plate_number: string;
@@ -278,6 +424,7 @@ export interface RiderPosition {
 	gap_to_leader_us: number | null;
 	finished: boolean;
 	dnf: boolean;
+	laps_completed: number;
 }
 
 export interface FinishResult {
@@ -290,6 +437,92 @@ export interface FinishResult {
 	gap_to_leader_us: number | null;
 	dnf: boolean;
 	dns: boolean;
+	lap_times_us: number[];
+}
+
+export type TimingChange =
+	| { type: 'insert_passing'; rider_id: string; loop_id: string; timestamp_us: number }
+	| { type: 'void_passing'; passing_id: string }
+	| { type: 'hand_timed_finish'; rider_id: string; elapsed_us: number };
+
+export interface TimingCorrection {
+	correction_id: string;
+	operator: string;
+	reason: string;
+	change: TimingChange;
+}
+
+export interface TimingCorrectionRecord extends TimingCorrection {
+	created_at: string;
+}
+
+export interface RecordedPassing {
+	passing_id: string;
+	transponder_id: number;
+	rider_id: string | null;
+	loop_name: string | null;
+	timestamp_us: number;
+	manual: boolean;
+	voided: boolean;
+}
+
+export interface SectorTime {
+	from_loop: string;
+	to_loop: string;
+	time_us: number;
+}
+
+export interface PracticeLap {
+	transponder_id: number;
+	rider_id: string | null;
+	lap_number: number;
+	lap_time_us: number;
+	sectors: SectorTime[];
+	personal_best: boolean;
+}
+
+export interface PracticeStanding {
+	position: number;
+	transponder_id: number;
+	rider_id: string | null;
+	first_name: string | null;
+	last_name: string | null;
+	plate_number: string | null;
+	laps: number;
+	best_lap_us: number;
+	last_lap_us: number;
+}
+
+export interface PracticeLeaderboard {
+	session_id: string;
+	best_laps: PracticeStanding[];
+	last_laps: PracticeStanding[];
+}
+
+export interface PracticeSessionResults {
+	id: string;
+	track_id: string;
+	started_at: string;
+	ended_at: string | null;
+	standings: {
+		transponder_id: number;
+		rider_id: string | null;
+		first_name: string | null;
+		last_name: string | null;
+		plate_number: string | null;
+		laps: number;
+		best_lap_us: number;
+	}[];
+	laps: {
+		transponder_id: number;
+		rider_id: string | null;
+		first_name: string | null;
+		last_name: string | null;
+		plate_number: string | null;
+		lap_number: number;
+		lap_time_us: number;
+		sectors: SectorTime[];
+	}[];
 }
 
 export interface RaceStateResponse {
@@ -325,11 +558,27 @@ export interface TrackOnboardingDiscoveryResponse {
 
 // Race event WebSocket messages
 export type RaceEventMessage =
-	| { event_type: 'race_staged'; moto_id: string; class_name: string; round_type: string; riders: StagedRider[] }
+	| { event_type: 'race_staged'; moto_id: string; class_name: string; round_type: string; riders: StagedRider[]; laps: number; start_mode: StartMode }
 	| { event_type: 'gate_drop'; moto_id: string; timestamp_us: number }
-	| { event_type: 'split_time'; moto_id: string; rider_id: string; loop_name: string; is_finish: boolean; elapsed_us: number; position: number; gap_to_leader_us: number | null }
+	| { event_type: 'rider_started'; moto_id: string; rider_id: string; timestamp_us: number }
+	| { event_type: 'start_list_updated'; moto_id: string; riders: StagedRider[] }
+	| { event_type: 'split_time'; moto_id: string; rider_id: string; lap: number; loop_name: string; is_finish: boolean; elapsed_us: number; position: number; gap_to_leader_us: number | null }
+	| { event_type: 'split_corrected'; moto_id: string; rider_id: string; lap: number; loop_name: string; is_finish: boolean; previous_elapsed_us: number; elapsed_us: number; position: number; gap_to_leader_us: number | null }
 	| { event_type: 'positions_update'; moto_id: string; positions: RiderPosition[] }
 	| { event_type: 'rider_finished'; moto_id: string; rider_id: string; finish_position: number; elapsed_us: number; gap_to_leader_us: number | null }
 	| { event_type: 'race_finished'; moto_id: string; results: FinishResult[] }
+	| { event_type: 'timing_corrected'; moto_id: string; correction: TimingCorrection }
+	| { event_type: 'unknown_transponder'; moto_id: string; transponder_id: number; loop_name: string; timestamp_us: number }
+	| { event_type: 'unmapped_decoder'; moto_id: string; decoder_id: string | null; transponder_id: number; timestamp_us: number }
+	| { event_type: 'clock_drift'; decoder_id: string; drift_ppm: number; offset_us: number; timestamp_us: number }
 	| { event_type: 'race_reset' }
-	| { event_type: 'state_snapshot'; phase: string; moto_id: string | null; class_name: string | null; round_type: string | null; riders: StagedRider[]; positions: RiderPosition[]; gate_drop_time_us: number | null; finished_count: number; total_riders: number };
+	| { event_type: 'practice_started'; session_id: string }
+	| { event_type: 'practice_update'; lap: PracticeLap; leaderboard: PracticeLeaderboard }
+	| { event_type: 'practice_ended'; leaderboard: PracticeLeaderboard }
+	| { event_type: 'state_snapshot'; phase: string; moto_id: string | null; class_name: string | null; round_type: string | null; riders: StagedRider[]; positions: RiderPosition[]; gate_drop_time_us: number | null; finished_count: number; total_riders: number; laps: number; practice: PracticeLeaderboard | null };
+
+// Alerts raised during a race for race control to resolve
+export type RaceAlert = Extract<
+	RaceEventMessage,
+	{ event_type: 'unknown_transponder' | 'unmapped_decoder' | 'clock_drift' }
+>;
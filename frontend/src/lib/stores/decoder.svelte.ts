@@ -17,6 +17,8 @@ let lastError = $state<LiveErrorPayload | null>(null);
 let messageCount = $state(0);
 let reconnectTimer = $state<ReturnType<typeof setTimeout> | null>(null);
 let connectionToken = $state(0);
+// Passed back as `from` so a reconnect replays what was missed
+let resumeToken: string | null = null;
 
 const MAX_RECENT = 50;
 const RECONNECT_DELAY_MS = 3000;
@@ -46,6 +48,7 @@ function resetTelemetry() {
 	lastStatus = null;
 	snapshotRows = [];
 	lastError = null;
+	resumeToken = null;
 }
 
 function handleP3Message(message: P3Message) {
@@ -97,7 +100,7 @@ function connect(trackId: string) {
 	const params = new URLSearchParams({
 		track_id: nextTrackId,
 		channels: 'decoder',
-		from: 'now'
+		from: resumeToken ?? 'now'
 	});
 	const ws = new WebSocket(`${protocol}//${window.location.host}/ws/v1/live?${params.toString()}`);
 
@@ -148,6 +151,8 @@ function connect(trackId: string) {
 			return;
 		}
 
+		resumeToken = envelope.resume_token ?? resumeToken;
+
 		switch (envelope.kind) {
 			case 'snapshot':
 				snapshotRows = envelope.payload.rows;
@@ -1,4 +1,12 @@
-import type { RaceEventMessage, RiderPosition, StagedRider, FinishResult } from '$lib/api/types';
+import type {
+	RaceEventMessage,
+	RiderPosition,
+	StagedRider,
+	FinishResult,
+	PracticeLap,
+	PracticeLeaderboard,
+	RaceAlert
+} from '$lib/api/types';
 
 let phase = $state<string>('idle');
 let motoId = $state<string | null>(null);
@@ -10,6 +18,9 @@ let gateDropTimeUs = $state<number | null>(null);
 let finishedCount = $state(0);
 let totalRiders = $state(0);
 let results = $state<FinishResult[]>([]);
+let alerts = $state<RaceAlert[]>([]);
+let practice = $state<PracticeLeaderboard | null>(null);
+let lastPracticeLap = $state<PracticeLap | null>(null);
 let connected = $state(false);
 let socket = $state<WebSocket | null>(null);
 
@@ -25,16 +36,18 @@ function handleMessage(msg: RaceEventMessage) {
 			gateDropTimeUs = msg.gate_drop_time_us;
 			finishedCount = msg.finished_count;
 			totalRiders = msg.total_riders;
+			practice = msg.practice;
 			break;
 
 		case 'race_staged':
-			phase = 'staged';
+			phase = msg.start_mode === 'time_trial' ? 'time_trial' : 'staged';
 			motoId = msg.moto_id;
 			className = msg.class_name;
 			roundType = msg.round_type;
 			riders = msg.riders;
 			positions = [];
 			results = [];
+			alerts = [];
 			gateDropTimeUs = null;
 			finishedCount = 0;
 			totalRiders = msg.riders.length;
@@ -45,6 +58,11 @@ function handleMessage(msg: RaceEventMessage) {
 			gateDropTimeUs = msg.timestamp_us;
 			break;
 
+		case 'start_list_updated':
+			riders = [...riders, ...msg.riders];
+			totalRiders = riders.length;
+			break;
+
 		case 'positions_update':
 			positions = msg.positions;
 			finishedCount = msg.positions.filter((p) => p.finished).length;
@@ -59,6 +77,12 @@ function handleMessage(msg: RaceEventMessage) {
 			results = msg.results;
 			break;
 
+		case 'unknown_transponder':
+		case 'unmapped_decoder':
+		case 'clock_drift':
+			alerts = [...alerts, msg];
+			break;
+
 		case 'race_reset':
 			phase = 'idle';
 			motoId = null;
@@ -67,16 +91,54 @@ function handleMessage(msg: RaceEventMessage) {
 			riders = [];
 			positions = [];
 			results = [];
+			alerts = [];
 			gateDropTimeUs = null;
 			finishedCount = 0;
 			totalRiders = 0;
+			practice = null;
+			lastPracticeLap = null;
+			break;
+
+		case 'practice_started':
+			phase = 'practice';
+			motoId = null;
+			riders = [];
+			positions = [];
+			results = [];
+			practice = { session_id: msg.session_id, best_laps: [], last_laps: [] };
+			lastPracticeLap = null;
+			break;
+
+		case 'practice_update':
+			practice = msg.leaderboard;
+			lastPracticeLap = msg.lap;
+			break;
+
+		case 'practice_ended':
+			phase = 'idle';
+			practice = msg.leaderboard;
 			break;
 	}
 }
 
-function connect() {
+// Track whose race events to follow; every track's when unset
+let trackId: string | undefined;
+// Socket the store reopens on close; replaced sockets are left closed
+let current: WebSocket | null = null;
+
+function connect(track?: string) {
+	trackId = track;
+	const previous = current;
+	current = null;
+	previous?.close();
+	open();
+}
+
+function open() {
 	const protocol = window.location.protocol === 'https:' ? 'wss:' : 'ws:';
-	const ws = new WebSocket(`${protocol}//${window.location.host}/ws`);
+	const query = trackId ? `?track_id=${encodeURIComponent(trackId)}` : '';
+	const ws = new WebSocket(`${protocol}//${window.location.host}/ws${query}`);
+	current = ws;
 
 	ws.onopen = () => {
 		connected = true;
@@ -84,9 +146,10 @@ function connect() {
 	};
 
 	ws.onclose = () => {
+		if (current !== ws) return;
 		connected = false;
 		socket = null;
-		setTimeout(connect, 3000);
+		setTimeout(open, 3000);
 	};
 
 	ws.onerror = () => {
@@ -118,6 +181,9 @@ export function getRaceStore() {
 		get finishedCount() { return finishedCount; },
 		get totalRiders() { return totalRiders; },
 		get results() { return results; },
+		get alerts() { return alerts; },
+		get practice() { return practice; },
+		get lastPracticeLap() { return lastPracticeLap; },
 		get connected() { return connected; },
 		connect,
 		disconnect
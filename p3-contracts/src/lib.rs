@@ -49,16 +49,43 @@ pub enum RaceEventPayloadV1 {
         class_name: String,
         round_type: String,
         riders: Vec<StagedRiderV1>,
+        #[serde(default = "single_lap")]
+        laps: u32,
+        #[serde(default)]
+        start_mode: StartModeV1,
     },
     GateDrop {
         moto_id: String,
         timestamp_us: u64,
     },
+    RiderStarted {
+        moto_id: String,
+        rider_id: String,
+        timestamp_us: u64,
+    },
+    StartListUpdated {
+        moto_id: String,
+        riders: Vec<StagedRiderV1>,
+    },
     SplitTime {
         moto_id: String,
         rider_id: String,
+        #[serde(default = "single_lap")]
+        lap: u32,
+        loop_name: String,
+        is_finish: bool,
+        elapsed_us: u64,
+        position: u32,
+        gap_to_leader_us: Option<u64>,
+    },
+    SplitCorrected {
+        moto_id: String,
+        rider_id: String,
+        #[serde(default = "single_lap")]
+        lap: u32,
         loop_name: String,
         is_finish: bool,
+        previous_elapsed_us: u64,
         elapsed_us: u64,
         position: u32,
         gap_to_leader_us: Option<u64>,
@@ -78,7 +105,44 @@ pub enum RaceEventPayloadV1 {
         moto_id: String,
         results: Vec<FinishResultV1>,
     },
+    /// An operator corrected the moto's timing
+    TimingCorrected {
+        moto_id: String,
+        correction: TimingCorrectionV1,
+    },
+    /// Alert: a transponder outside the moto crossed a timing loop
+    UnknownTransponder {
+        moto_id: String,
+        transponder_id: u32,
+        loop_name: String,
+        timestamp_us: u64,
+    },
+    /// Alert: a decoder with no timing loop on the track reported a passing
+    UnmappedDecoder {
+        moto_id: String,
+        decoder_id: Option<String>,
+        transponder_id: u32,
+        timestamp_us: u64,
+    },
+    /// Alert: a decoder's clock drifts from the track's time base beyond
+    /// tolerance
+    ClockDrift {
+        decoder_id: String,
+        drift_ppm: f64,
+        offset_us: i64,
+        timestamp_us: u64,
+    },
     RaceReset,
+    PracticeStarted {
+        session_id: String,
+    },
+    PracticeUpdate {
+        lap: PracticeLapV1,
+        leaderboard: PracticeLeaderboardV1,
+    },
+    PracticeEnded {
+        leaderboard: PracticeLeaderboardV1,
+    },
     StateSnapshot {
         phase: String,
         moto_id: Option<String>,
@@ -89,9 +153,22 @@ pub enum RaceEventPayloadV1 {
         gate_drop_time_us: Option<u64>,
         finished_count: u32,
         total_riders: u32,
+        #[serde(default = "single_lap")]
+        laps: u32,
+        /// Leaderboards while a practice session is open
+        #[serde(default)]
+        practice: Option<PracticeLeaderboardV1>,
     },
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StartModeV1 {
+    #[default]
+    GateDrop,
+    TimeTrial,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StagedRiderV1 {
     pub rider_id: String,
@@ -115,6 +192,8 @@ pub struct RiderPositionV1 {
     pub gap_to_leader_us: Option<u64>,
     pub finished: bool,
     pub dnf: bool,
+    #[serde(default)]
+    pub laps_completed: u32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -128,6 +207,97 @@ pub struct FinishResultV1 {
     pub gap_to_leader_us: Option<u64>,
     pub dnf: bool,
     pub dns: bool,
+    /// Duration of each completed lap, in order
+    #[serde(default)]
+    pub lap_times_us: Vec<u64>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RaceRulesV1 {
+    #[serde(default)]
+    pub dns_timeout_secs: Option<u32>,
+    #[serde(default)]
+    pub dnf_timeout_secs: Option<u32>,
+    #[serde(default)]
+    pub max_race_secs: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimingCorrectionV1 {
+    pub correction_id: String,
+    pub operator: String,
+    pub reason: String,
+    pub change: TimingChangeV1,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TimingChangeV1 {
+    /// A crossing the decoder missed, at decoder time `timestamp_us`
+    InsertPassing {
+        rider_id: String,
+        loop_id: String,
+        timestamp_us: u64,
+    },
+    /// `passing_id` is `<decoder_id>:<passing_number>` or `manual:<correction_id>`
+    VoidPassing {
+        passing_id: String,
+    },
+    HandTimedFinish {
+        rider_id: String,
+        elapsed_us: u64,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PracticeLapV1 {
+    pub transponder_id: u32,
+    /// `None` for transponders not registered to a rider
+    pub rider_id: Option<String>,
+    pub lap_number: u32,
+    pub lap_time_us: u64,
+    pub sectors: Vec<SectorTimeV1>,
+    pub personal_best: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SectorTimeV1 {
+    pub from_loop: String,
+    pub to_loop: String,
+    pub time_us: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PracticeStandingV1 {
+    pub position: u32,
+    pub transponder_id: u32,
+    pub rider_id: Option<String>,
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+    pub plate_number: Option<String>,
+    pub laps: u32,
+    pub best_lap_us: u64,
+    pub last_lap_us: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PracticeLeaderboardV1 {
+    pub session_id: String,
+    /// Fastest lap first
+    pub best_laps: Vec<PracticeStandingV1>,
+    /// Most recently completed lap first
+    pub last_laps: Vec<PracticeStandingV1>,
+}
+
+/// Registered rider that practice passings are matched against
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KnownRiderV1 {
+    pub rider_id: String,
+    pub first_name: String,
+    pub last_name: String,
+    pub plate_number: String,
+    pub transponder_id: u32,
+    pub transponder_string: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -146,6 +316,8 @@ pub struct LoopConfigV1 {
     pub position: u32,
     pub is_start: bool,
     pub is_finish: bool,
+    #[serde(default)]
+    pub debounce_ms: u32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -157,9 +329,54 @@ pub enum RaceControlIntentV1 {
         class_name: String,
         round_type: String,
         riders: Vec<StagedRiderV1>,
+        /// Laps to complete before a rider is finished
+        #[serde(default = "single_lap")]
+        laps: u32,
+        #[serde(default)]
+        start_mode: StartModeV1,
+        /// Automatic DNS/DNF rules; all disabled when absent
+        #[serde(default)]
+        rules: RaceRulesV1,
+    },
+    /// Time trial: append riders to the rolling start list
+    AddToStartList {
+        moto_id: String,
+        riders: Vec<StagedRiderV1>,
+    },
+    /// Give a staged rider a different transponder and re-process the
+    /// passings it has made during the moto
+    BindTransponder {
+        moto_id: String,
+        rider_id: String,
+        transponder_id: u32,
+    },
+    /// Insert, void or hand-time a passing; the moto is recomputed
+    CorrectTiming {
+        moto_id: String,
+        correction: TimingCorrectionV1,
     },
     Reset,
-    ForceFinish,
+    /// Finish a moto, by default the oldest one on course
+    ForceFinish {
+        #[serde(default)]
+        moto_id: Option<String>,
+    },
+    /// Open practice: time every transponder that crosses the loops
+    StartPractice {
+        session_id: String,
+        track_config: TrackConfigV1,
+        riders: Vec<KnownRiderV1>,
+    },
+    StopPractice,
+    /// Apply the DNS/DNF rules as of `now_us`. Published by the race worker
+    /// so replaying the stream rules out the same riders
+    CheckTimeouts {
+        now_us: u64,
+    },
+}
+
+fn single_lap() -> u32 {
+    1
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -206,6 +423,8 @@ pub struct LiveEnvelopeV1<T> {
     pub event_id: Option<String>,
     pub seq: u64,
     pub ts_us: u64,
+    /// Pass back as `from` on reconnect to resume after this envelope
+    pub resume_token: Option<String>,
     pub payload: T,
 }
 
@@ -261,6 +480,8 @@ pub fn message_type_from_message(message: &Message) -> &'static str {
         Message::Passing(_) => "PASSING",
         Message::Status(_) => "STATUS",
         Message::Version(_) => "VERSION",
+        Message::Resend(_) => "RESEND",
+        Message::Unknown { .. } => "UNKNOWN",
     }
 }
 
@@ -0,0 +1,87 @@
+//! Compare the owned parse path with the borrowing scratch-buffer path.
+//!
+//! Inputs are the live decoder captures under `tests/fixtures/live_capture`.
+//! Run with `cargo bench -p p3-parser`.
+
+use criterion::{Criterion, black_box, criterion_group, criterion_main};
+use p3_parser::{
+    FrameParser, Message, Parser, PassingMessage, StatusMessage, TlvDecoder, VersionMessage,
+};
+use p3_protocol::MessageType;
+
+#[path = "../tests/common/mod.rs"]
+mod common;
+
+fn load_fixtures() -> Vec<Vec<u8>> {
+    common::live_captures()
+        .into_iter()
+        .map(|(_, data)| data)
+        .collect()
+}
+
+/// The pre-borrowing path: owned frame body plus a `Vec<TlvField>` per message
+fn parse_owned(frame_parser: &FrameParser, tlv_decoder: &TlvDecoder, data: &[u8]) -> Message {
+    let frame = frame_parser.parse(data).unwrap();
+    let fields = tlv_decoder.decode(&frame.body).unwrap();
+    match frame.message_type() {
+        Some(MessageType::Passing) => {
+            Message::Passing(PassingMessage::from_tlv_fields(&fields).unwrap())
+        }
+        Some(MessageType::Status) => {
+            Message::Status(StatusMessage::from_tlv_fields(&fields).unwrap())
+        }
+        Some(MessageType::Version) => {
+            Message::Version(VersionMessage::from_tlv_fields(&fields).unwrap())
+        }
+        _ => unreachable!("live captures only contain PASSING, STATUS and VERSION"),
+    }
+}
+
+fn bench_parse(c: &mut Criterion) {
+    let fixtures = load_fixtures();
+    let frame_parser = FrameParser::new();
+    let tlv_decoder = TlvDecoder::new();
+    let parser = Parser::new();
+
+    let mut group = c.benchmark_group("live_capture");
+
+    group.bench_function("owned", |b| {
+        b.iter(|| {
+            for data in &fixtures {
+                black_box(parse_owned(&frame_parser, &tlv_decoder, black_box(data)));
+            }
+        })
+    });
+
+    group.bench_function("scratch", |b| {
+        let mut scratch = Vec::new();
+        b.iter(|| {
+            for data in &fixtures {
+                black_box(
+                    parser
+                        .parse_with_scratch(black_box(data), &mut scratch)
+                        .unwrap(),
+                );
+            }
+        })
+    });
+
+    group.bench_function("frame_ref_only", |b| {
+        let mut scratch = Vec::new();
+        b.iter(|| {
+            for data in &fixtures {
+                let frame = frame_parser
+                    .parse_into(black_box(data), &mut scratch)
+                    .unwrap();
+                for field in frame.fields() {
+                    black_box(field.unwrap());
+                }
+            }
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_parse);
+criterion_main!(benches);
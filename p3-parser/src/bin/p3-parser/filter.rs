@@ -0,0 +1,140 @@
+use clap::ValueEnum;
+use p3_parser::Message;
+
+/// Message types selectable with `--type`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum MessageKind {
+    Passing,
+    Status,
+    Version,
+    Resend,
+    Unknown,
+}
+
+impl MessageKind {
+    fn of(message: &Message) -> Self {
+        match message {
+            Message::Passing(_) => MessageKind::Passing,
+            Message::Status(_) => MessageKind::Status,
+            Message::Version(_) => MessageKind::Version,
+            Message::Resend(_) => MessageKind::Resend,
+            Message::Unknown { .. } => MessageKind::Unknown,
+        }
+    }
+}
+
+/// Message filter built from the `--type`, `--transponder` and `--decoder` flags
+///
+/// Each non-empty list must match; an empty list matches everything. Messages
+/// without a transponder (or decoder) never match a transponder (or decoder)
+/// filter.
+#[derive(Debug, Default)]
+pub struct Filter {
+    pub types: Vec<MessageKind>,
+    pub transponders: Vec<u32>,
+    pub decoders: Vec<String>,
+}
+
+impl Filter {
+    pub fn matches(&self, message: &Message) -> bool {
+        if !self.types.is_empty() && !self.types.contains(&MessageKind::of(message)) {
+            return false;
+        }
+
+        if !self.transponders.is_empty() {
+            let Message::Passing(passing) = message else {
+                return false;
+            };
+            if !self.transponders.contains(&passing.transponder_id) {
+                return false;
+            }
+        }
+
+        if !self.decoders.is_empty() {
+            let Some(decoder_id) = message.decoder_id() else {
+                return false;
+            };
+            if !self
+                .decoders
+                .iter()
+                .any(|d| d.eq_ignore_ascii_case(decoder_id))
+            {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use p3_parser::{PassingMessage, StatusMessage};
+
+    fn passing(transponder_id: u32, decoder_id: &str) -> Message {
+        Message::Passing(PassingMessage {
+            passing_number: 1,
+            transponder_id,
+            rtc_time_us: 0,
+            utc_time_us: None,
+            strength: None,
+            hits: None,
+            transponder_string: None,
+            flags: 0,
+            decoder_id: Some(decoder_id.to_string()),
+            extra_fields: Vec::new(),
+        })
+    }
+
+    fn status(decoder_id: Option<&str>) -> Message {
+        Message::Status(StatusMessage {
+            noise: 0,
+            gps_status: 0,
+            temperature: 0,
+            satellites: 0,
+            decoder_id: decoder_id.map(str::to_string),
+            extra_fields: Vec::new(),
+        })
+    }
+
+    #[test]
+    fn test_empty_filter_matches_everything() {
+        let filter = Filter::default();
+        assert!(filter.matches(&passing(1001, "D0000C00")));
+        assert!(filter.matches(&status(None)));
+    }
+
+    #[test]
+    fn test_type_filter() {
+        let filter = Filter {
+            types: vec![MessageKind::Status],
+            ..Filter::default()
+        };
+        assert!(filter.matches(&status(None)));
+        assert!(!filter.matches(&passing(1001, "D0000C00")));
+    }
+
+    #[test]
+    fn test_transponder_filter_excludes_other_types() {
+        let filter = Filter {
+            transponders: vec![9992],
+            ..Filter::default()
+        };
+        assert!(filter.matches(&passing(9992, "D0000C00")));
+        assert!(!filter.matches(&passing(1001, "D0000C00")));
+        assert!(!filter.matches(&status(Some("D0000C00"))));
+    }
+
+    #[test]
+    fn test_decoder_filter_is_case_insensitive() {
+        let filter = Filter {
+            decoders: vec!["d0000c00".to_string()],
+            ..Filter::default()
+        };
+        assert!(filter.matches(&passing(1001, "D0000C00")));
+        assert!(filter.matches(&status(Some("D0000C00"))));
+        assert!(!filter.matches(&status(Some("D1000C00"))));
+        assert!(!filter.matches(&status(None)));
+    }
+}
@@ -0,0 +1,280 @@
+use p3_protocol::fields::tag_name;
+use p3_protocol::{
+    EOR, HEADER_SIZE, MessageType, OFFSET_BODY, OFFSET_CRC, OFFSET_LENGTH, OFFSET_RESERVED,
+    OFFSET_TYPE, OFFSET_VERSION, SOR, VERSION, calculate_crc, unescape_data,
+};
+use std::fmt::Write;
+
+/// Bytes shown per hex line; longer values continue on following lines
+const BYTES_PER_LINE: usize = 8;
+
+/// Annotated hex dump of one escaped wire frame
+///
+/// Offsets refer to the unescaped frame, which is what LENGTH, CRC and the
+/// TLV layout are defined over. Every problem found is marked with `!!`, and
+/// the dump carries on past it as far as the bytes allow.
+pub fn annotate(frame: &[u8]) -> String {
+    let mut out = String::new();
+
+    let unescaped = match unescape_data(frame) {
+        Ok(unescaped) => unescaped,
+        Err(e) => {
+            let _ = writeln!(out, "  {} wire bytes, !! {}", frame.len(), e);
+            line(&mut out, 0, frame, "raw wire bytes");
+            return out;
+        }
+    };
+    let u = unescaped.as_slice();
+
+    let _ = writeln!(
+        out,
+        "  {} wire bytes, {} unescaped",
+        frame.len(),
+        unescaped.len()
+    );
+
+    if u.len() <= HEADER_SIZE {
+        line(&mut out, 0, u, "!! too short for header and EOR");
+        return out;
+    }
+
+    let sor = if u[0] == SOR {
+        "SOR".to_string()
+    } else {
+        format!("!! expected SOR 0x{:02X}", SOR)
+    };
+    line(&mut out, 0, &u[..OFFSET_VERSION], &sor);
+
+    let version = if u[OFFSET_VERSION] == VERSION {
+        format!("VERSION {}", VERSION)
+    } else {
+        format!("!! VERSION {}, expected {}", u[OFFSET_VERSION], VERSION)
+    };
+    line(
+        &mut out,
+        OFFSET_VERSION,
+        &u[OFFSET_VERSION..OFFSET_LENGTH],
+        &version,
+    );
+
+    let declared_len = u16_at(u, OFFSET_LENGTH) as usize;
+    let length = if declared_len == u.len() {
+        format!("LENGTH {}", declared_len)
+    } else {
+        format!("!! LENGTH {}, frame is {} bytes", declared_len, u.len())
+    };
+    line(
+        &mut out,
+        OFFSET_LENGTH,
+        &u[OFFSET_LENGTH..OFFSET_CRC],
+        &length,
+    );
+
+    let declared_crc = u16_at(u, OFFSET_CRC);
+    let mut zeroed = unescaped.clone();
+    zeroed[OFFSET_CRC] = 0;
+    zeroed[OFFSET_CRC + 1] = 0;
+    let computed_crc = calculate_crc(&zeroed);
+    let crc = if declared_crc == computed_crc {
+        format!("CRC 0x{:04X} ok", declared_crc)
+    } else {
+        format!(
+            "!! CRC 0x{:04X}, computed 0x{:04X}",
+            declared_crc, computed_crc
+        )
+    };
+    line(&mut out, OFFSET_CRC, &u[OFFSET_CRC..OFFSET_RESERVED], &crc);
+
+    line(
+        &mut out,
+        OFFSET_RESERVED,
+        &u[OFFSET_RESERVED..OFFSET_TYPE],
+        "RESERVED",
+    );
+
+    let tor = u16_at(u, OFFSET_TYPE);
+    let message_type = MessageType::from_u16(tor);
+    let type_name = message_type.map(MessageType::name).unwrap_or("unknown");
+    line(
+        &mut out,
+        OFFSET_TYPE,
+        &u[OFFSET_TYPE..OFFSET_BODY],
+        &format!("TOR 0x{:04X} {}", tor, type_name),
+    );
+
+    let eor_at = u.len() - 1;
+    let mut pos = OFFSET_BODY;
+    while pos < eor_at {
+        let remaining = eor_at - pos;
+        if remaining < 2 {
+            line(&mut out, pos, &u[pos..eor_at], "!! truncated TLV header");
+            break;
+        }
+
+        let tag = u[pos];
+        let len = u[pos + 1] as usize;
+        if len > remaining - 2 {
+            let note = format!(
+                "!! tag 0x{:02X} length {} overruns body by {} bytes",
+                tag,
+                len,
+                len - (remaining - 2)
+            );
+            line(&mut out, pos, &u[pos..eor_at], &note);
+            break;
+        }
+
+        let value = &u[pos + 2..pos + 2 + len];
+        let name = message_type
+            .and_then(|t| tag_name(t, tag))
+            .unwrap_or("unknown tag");
+        let mut note = format!("0x{:02X} {} len {}", tag, name, len);
+        if let Some(decoded) = decode_value(name, value) {
+            let _ = write!(note, " = {}", decoded);
+        }
+        line(&mut out, pos, &u[pos..pos + 2 + len], &note);
+
+        pos += 2 + len;
+    }
+
+    let eor = if u[eor_at] == EOR {
+        "EOR".to_string()
+    } else {
+        format!("!! expected EOR 0x{:02X}", EOR)
+    };
+    line(&mut out, eor_at, &u[eor_at..], &eor);
+
+    out
+}
+
+fn u16_at(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([data[offset], data[offset + 1]])
+}
+
+/// Best-effort reading of a TLV value: decoder IDs as wire-order hex,
+/// printable ASCII as text, else a little-endian integer
+fn decode_value(name: &str, value: &[u8]) -> Option<String> {
+    if name == "DECODER_ID" {
+        return Some(hex::encode_upper(value));
+    }
+    if !value.is_empty() && value.iter().all(|b| b.is_ascii_graphic() || *b == b' ') {
+        return Some(format!("{:?}", String::from_utf8_lossy(value)));
+    }
+
+    let mut bytes = [0u8; 8];
+    match value.len() {
+        1 | 2 | 4 | 8 => {
+            bytes[..value.len()].copy_from_slice(value);
+            Some(u64::from_le_bytes(bytes).to_string())
+        }
+        _ => None,
+    }
+}
+
+/// Write `bytes` starting at `offset` as hex lines, with `note` on the first line
+fn line(out: &mut String, offset: usize, bytes: &[u8], note: &str) {
+    let mut chunks: Vec<&[u8]> = bytes.chunks(BYTES_PER_LINE).collect();
+    if chunks.is_empty() {
+        chunks.push(&[]);
+    }
+
+    for (i, chunk) in chunks.into_iter().enumerate() {
+        let hex = chunk
+            .iter()
+            .map(|b| format!("{:02X}", b))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let note = if i == 0 { note } else { "" };
+        let text = format!(
+            "  {:04X}  {:<width$}  {}",
+            offset + i * BYTES_PER_LINE,
+            hex,
+            note,
+            width = BYTES_PER_LINE * 3 - 1
+        );
+        out.push_str(text.trim_end());
+        out.push('\n');
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use p3_protocol::build_frame;
+
+    fn status_frame() -> Vec<u8> {
+        // NOISE=53, GPS_STATUS=1, TEMPERATURE=16, SATINUSE=0
+        build_frame(
+            MessageType::Status,
+            &[
+                0x01, 0x02, 0x35, 0x00, 0x06, 0x01, 0x01, 0x07, 0x02, 0x10, 0x00, 0x0A, 0x01, 0x00,
+            ],
+        )
+    }
+
+    #[test]
+    fn test_annotates_valid_frame() {
+        let dump = annotate(&status_frame());
+
+        assert!(!dump.contains("!!"), "{}", dump);
+        assert!(dump.contains("  0000  8E"));
+        assert!(dump.contains("LENGTH 25"));
+        assert!(dump.contains("TOR 0x0002 STATUS"));
+        assert!(dump.contains("  000A  01 02 35 00"));
+        assert!(dump.contains("0x01 NOISE len 2 = 53"));
+        assert!(dump.contains("0x0A SATINUSE len 1 = 0"));
+        assert!(dump.trim_end().ends_with("8F                       EOR"));
+    }
+
+    #[test]
+    fn test_flags_bad_crc() {
+        let mut frame = status_frame();
+        let last_body = frame.len() - 2;
+        frame[last_body] ^= 0x01;
+
+        let dump = annotate(&frame);
+        assert!(dump.contains("!! CRC 0x"), "{}", dump);
+        assert!(dump.contains("computed 0x"));
+        // The TLV walk still happens
+        assert!(dump.contains("SATINUSE len 1 = 1"));
+    }
+
+    #[test]
+    fn test_flags_tlv_overrun() {
+        // NOISE claims 9 bytes but only 2 remain before EOR
+        let frame = build_frame(MessageType::Status, &[0x01, 0x09, 0x35, 0x00]);
+
+        let dump = annotate(&frame);
+        assert!(
+            dump.contains("!! tag 0x01 length 9 overruns body by 7 bytes"),
+            "{}",
+            dump
+        );
+        assert!(dump.contains("EOR"));
+    }
+
+    #[test]
+    fn test_flags_bad_escape() {
+        let dump = annotate(&[SOR, 0x02, 0x8D, 0x01, EOR]);
+        assert!(dump.contains("!! Invalid escape sequence"), "{}", dump);
+        assert!(dump.contains("raw wire bytes"));
+    }
+
+    #[test]
+    fn test_long_values_wrap() {
+        let frame = build_frame(
+            MessageType::Version,
+            &[
+                0x21, 0x0C, b'P', b'r', b'o', b'C', b'h', b'i', b'p', b' ', b'S', b'm', b'a', b'r',
+            ],
+        );
+
+        let dump = annotate(&frame);
+        assert!(
+            dump.contains("0x21 DESCRIPTION len 12 = \"ProChip Smar\""),
+            "{}",
+            dump
+        );
+        assert!(dump.contains("  0012  70 20 53 6D 61 72\n"));
+    }
+}
@@ -0,0 +1,379 @@
+mod filter;
+mod hexdump;
+mod output;
+
+#[cfg(test)]
+#[path = "../../../tests/common/mod.rs"]
+mod common;
+
+use clap::{Parser as ClapParser, Subcommand};
+use filter::{Filter, MessageKind};
+use output::{Format, Formatter};
+use p3_parser::stream::MessageFramer;
+use p3_parser::{CAPTURE_MAGIC, CaptureMetadata, CaptureReader, CaptureWriter};
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::net::TcpStream;
+
+#[derive(ClapParser)]
+#[command(name = "p3-parser")]
+#[command(about = "Parse MyLaps ProChip P3 binary protocol messages to JSON, CSV or a table")]
+struct Args {
+    /// Decoder host to connect to when reading over TCP
+    #[arg(short = 'H', long, default_value = "localhost", global = true)]
+    host: String,
+
+    #[arg(short, long, default_value = "5403", global = true)]
+    port: u16,
+
+    /// Read a raw byte dump or a capture file (see `record`) instead of TCP
+    #[arg(short, long, conflicts_with = "stdin")]
+    file: Option<PathBuf>,
+
+    /// Read raw bytes from stdin instead of TCP
+    #[arg(long)]
+    stdin: bool,
+
+    #[arg(long, value_enum, default_value_t = Format::Ndjson)]
+    format: Format,
+
+    /// Pretty-print JSON (ndjson format only)
+    #[arg(long)]
+    pretty: bool,
+
+    /// Only show these message types (comma-separated or repeated)
+    #[arg(long = "type", value_enum, value_delimiter = ',')]
+    types: Vec<MessageKind>,
+
+    /// Only show PASSING messages from these transponder IDs
+    #[arg(long, value_delimiter = ',')]
+    transponder: Vec<u32>,
+
+    /// Only show messages from these decoder IDs, e.g. D0000C00
+    #[arg(long, value_delimiter = ',')]
+    decoder: Vec<String>,
+
+    /// Print an annotated hex dump of each frame (header, TLV fields, CRC)
+    #[arg(long)]
+    hexdump: bool,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Record the raw decoder byte stream to a capture file until Ctrl+C
+    Record {
+        /// Capture file to write
+        #[arg(short, long)]
+        output: PathBuf,
+
+        /// Free-form note stored in the capture metadata (track, race day, ...)
+        #[arg(short, long)]
+        description: Option<String>,
+    },
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+
+    if let Some(Command::Record {
+        output,
+        description,
+    }) = &args.command
+    {
+        let mut stream = connect(&args.host, args.port).await?;
+        let metadata = CaptureMetadata {
+            source: format!("tcp://{}:{}", args.host, args.port),
+            started_at_us: unix_micros(),
+            recorder: format!("p3-parser {}", env!("CARGO_PKG_VERSION")),
+            description: description.clone(),
+        };
+        return record(&mut stream, output, &metadata).await;
+    }
+
+    let mut processor = Processor {
+        framer: MessageFramer::new(),
+        filter: Filter {
+            types: args.types,
+            transponders: args.transponder,
+            decoders: args.decoder,
+        },
+        formatter: Formatter::new(args.format, args.pretty),
+        hexdump: args.hexdump,
+        out: io::stdout().lock(),
+        frames: 0,
+    };
+
+    let result = if let Some(path) = &args.file {
+        process_file(path, &mut processor).await
+    } else if args.stdin {
+        process_reader(tokio::io::stdin(), &mut processor).await
+    } else {
+        let stream = connect(&args.host, args.port).await?;
+        process_reader(stream, &mut processor).await
+    };
+
+    // A closed pipe (e.g. `| head`) is a normal way to stop reading
+    match result {
+        Err(e) if is_broken_pipe(&e) => return Ok(()),
+        other => other?,
+    }
+
+    let stats = processor.framer.stats();
+    eprintln!(
+        "{} frames, {} bytes skipped, {} CRC failures, {} resyncs",
+        stats.frames, stats.bytes_skipped, stats.crc_failures, stats.resyncs
+    );
+
+    Ok(())
+}
+
+async fn connect(host: &str, port: u16) -> anyhow::Result<TcpStream> {
+    eprintln!("Connecting to {}:{}...", host, port);
+    let stream = TcpStream::connect((host, port)).await?;
+    eprintln!("Connected!");
+    Ok(stream)
+}
+
+fn is_broken_pipe(error: &anyhow::Error) -> bool {
+    error
+        .downcast_ref::<io::Error>()
+        .is_some_and(|e| e.kind() == io::ErrorKind::BrokenPipe)
+}
+
+/// Frames incoming bytes and writes matching messages (or hex dumps) to `out`
+struct Processor<W: Write> {
+    framer: MessageFramer,
+    filter: Filter,
+    formatter: Formatter,
+    hexdump: bool,
+    out: W,
+    frames: u64,
+}
+
+impl<W: Write> Processor<W> {
+    fn feed(&mut self, data: &[u8]) -> io::Result<()> {
+        let Self {
+            framer,
+            filter,
+            formatter,
+            hexdump,
+            out,
+            frames,
+        } = self;
+        let mut write_result = Ok(());
+
+        framer.feed_with(data, |raw, result| {
+            *frames += 1;
+            if write_result.is_err() {
+                return;
+            }
+
+            write_result = match (&result, *hexdump) {
+                (Ok(message), _) if !filter.matches(message) => Ok(()),
+                (Ok(message), false) => formatter.write(out, message),
+                (Err(e), false) => {
+                    eprintln!("Parse error: {}", e);
+                    Ok(())
+                }
+                (Ok(message), true) => writeln!(
+                    out,
+                    "frame {}: {}\n{}",
+                    frames,
+                    message.type_name(),
+                    hexdump::annotate(raw)
+                ),
+                (Err(e), true) => writeln!(
+                    out,
+                    "frame {}: !! {}\n{}",
+                    frames,
+                    e,
+                    hexdump::annotate(raw)
+                ),
+            };
+        });
+
+        write_result?;
+        self.out.flush()
+    }
+}
+
+async fn process_reader<W: Write>(
+    mut reader: impl AsyncRead + Unpin,
+    processor: &mut Processor<W>,
+) -> anyhow::Result<()> {
+    let mut chunk = [0u8; 4096];
+    loop {
+        let n = reader.read(&mut chunk).await?;
+        if n == 0 {
+            break;
+        }
+        processor.feed(&chunk[..n])?;
+    }
+    Ok(())
+}
+
+/// Process a raw byte dump, or the records of a capture file in order
+async fn process_file<W: Write>(path: &Path, processor: &mut Processor<W>) -> anyhow::Result<()> {
+    let mut file = File::open(path)?;
+
+    let mut magic = [0u8; 4];
+    let is_capture = file.read_exact(&mut magic).is_ok() && magic == CAPTURE_MAGIC;
+    file.seek(SeekFrom::Start(0))?;
+
+    if !is_capture {
+        return process_reader(tokio::fs::File::from_std(file), processor).await;
+    }
+
+    let reader = CaptureReader::new(BufReader::new(file))?;
+    eprintln!(
+        "Capture from {} ({})",
+        reader.metadata().source,
+        reader
+            .metadata()
+            .description
+            .as_deref()
+            .unwrap_or("no description")
+    );
+    for record in reader {
+        processor.feed(&record?.data)?;
+    }
+    Ok(())
+}
+
+/// Write every chunk read from the decoder to a capture file with its arrival time
+async fn record(
+    stream: &mut TcpStream,
+    output: &Path,
+    metadata: &CaptureMetadata,
+) -> anyhow::Result<()> {
+    let mut writer = CaptureWriter::new(BufWriter::new(File::create(output)?), metadata)?;
+    // Framing is only used for the summary; the capture keeps raw bytes
+    let mut framer = MessageFramer::new();
+    let mut bytes = 0u64;
+
+    eprintln!("Recording to {} (Ctrl+C to stop)", output.display());
+
+    let mut chunk = [0u8; 4096];
+    loop {
+        let n = tokio::select! {
+            read = stream.read(&mut chunk) => read?,
+            _ = tokio::signal::ctrl_c() => break,
+        };
+
+        if n == 0 {
+            eprintln!("Connection closed");
+            break;
+        }
+
+        writer.write_record(unix_micros(), &chunk[..n])?;
+        // Flush per read so an abrupt kill loses at most the current chunk
+        writer.flush()?;
+        framer.feed(&chunk[..n]);
+        bytes += n as u64;
+    }
+
+    writer.flush()?;
+    let stats = framer.stats();
+    eprintln!(
+        "Recorded {} reads, {} bytes, {} frames ({} CRC failures, {} resyncs)",
+        writer.records(),
+        bytes,
+        stats.frames,
+        stats.crc_failures,
+        stats.resyncs
+    );
+
+    Ok(())
+}
+
+fn unix_micros() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_micros() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use p3_protocol::{MessageType, build_frame};
+
+    fn processor(filter: Filter, hexdump: bool) -> Processor<Vec<u8>> {
+        Processor {
+            framer: MessageFramer::new(),
+            filter,
+            formatter: Formatter::new(Format::Ndjson, false),
+            hexdump,
+            out: Vec::new(),
+            frames: 0,
+        }
+    }
+
+    fn status_frame() -> Vec<u8> {
+        build_frame(
+            MessageType::Status,
+            &[
+                0x01, 0x02, 0x35, 0x00, 0x06, 0x01, 0x01, 0x07, 0x02, 0x10, 0x00, 0x0A, 0x01, 0x00,
+            ],
+        )
+    }
+
+    #[test]
+    fn test_live_captures_emit_every_message_type() {
+        let mut processor = processor(Filter::default(), false);
+
+        let captures = crate::common::live_captures();
+        for (_, data) in &captures {
+            processor.feed(data).unwrap();
+        }
+
+        let out = String::from_utf8(processor.out).unwrap();
+        assert_eq!(out.lines().count(), captures.len());
+        for kind in ["PASSING", "STATUS"] {
+            assert!(
+                out.contains(&format!("\"message_type\":\"{}\"", kind)),
+                "no {} in output",
+                kind
+            );
+        }
+    }
+
+    #[test]
+    fn test_filtered_messages_are_not_written() {
+        let mut processor = processor(
+            Filter {
+                types: vec![MessageKind::Passing],
+                ..Filter::default()
+            },
+            false,
+        );
+        processor.feed(&status_frame()).unwrap();
+
+        assert!(processor.out.is_empty());
+        assert_eq!(processor.frames, 1);
+    }
+
+    #[test]
+    fn test_hexdump_includes_bad_frames() {
+        let mut bad = status_frame();
+        let last_body = bad.len() - 2;
+        bad[last_body] ^= 0x01;
+        let mut stream = status_frame();
+        stream.extend(bad);
+
+        let mut processor = processor(Filter::default(), true);
+        processor.feed(&stream).unwrap();
+
+        let out = String::from_utf8(processor.out).unwrap();
+        assert!(out.contains("frame 1: STATUS\n"));
+        assert!(out.contains("frame 2: !! CRC validation failed"));
+        assert!(out.contains("!! CRC 0x"));
+    }
+}
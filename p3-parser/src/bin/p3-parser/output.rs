@@ -0,0 +1,330 @@
+use clap::ValueEnum;
+use p3_parser::Message;
+use std::fmt::Write as _;
+use std::io::{self, Write};
+
+/// Output formats selectable with `--format`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// One JSON object per line, tagged with `message_type`
+    Ndjson,
+    /// One row per message; columns are the union of all message fields
+    Csv,
+    /// Aligned human-readable lines
+    Table,
+}
+
+const CSV_COLUMNS: &[&str] = &[
+    "message_type",
+    "decoder_id",
+    "passing_number",
+    "transponder_id",
+    "transponder_string",
+    "rtc_time_us",
+    "utc_time_us",
+    "strength",
+    "hits",
+    "flags",
+    "noise",
+    "temperature",
+    "gps_status",
+    "satellites",
+    "description",
+    "version",
+    "build",
+    "from_passing",
+    "until_passing",
+    "tor",
+    "extra_fields",
+];
+
+/// Writes messages in the selected format, emitting a header before the first one
+pub struct Formatter {
+    format: Format,
+    pretty: bool,
+    header_written: bool,
+}
+
+impl Formatter {
+    pub fn new(format: Format, pretty: bool) -> Self {
+        Self {
+            format,
+            pretty,
+            header_written: false,
+        }
+    }
+
+    pub fn write(&mut self, out: &mut impl Write, message: &Message) -> io::Result<()> {
+        match self.format {
+            Format::Ndjson => {
+                let json = if self.pretty {
+                    serde_json::to_string_pretty(message)?
+                } else {
+                    serde_json::to_string(message)?
+                };
+                writeln!(out, "{}", json)
+            }
+            Format::Csv => {
+                if !self.header_written {
+                    writeln!(out, "{}", CSV_COLUMNS.join(","))?;
+                    self.header_written = true;
+                }
+                writeln!(out, "{}", csv_row(message).join(","))
+            }
+            Format::Table => {
+                if !self.header_written {
+                    writeln!(out, "{:<8} {:<8}  DETAILS", "TYPE", "DECODER")?;
+                    self.header_written = true;
+                }
+                writeln!(
+                    out,
+                    "{:<8} {:<8}  {}",
+                    message.type_name(),
+                    message.decoder_id().unwrap_or("-"),
+                    table_details(message)
+                )
+            }
+        }
+    }
+}
+
+fn csv_row(message: &Message) -> Vec<String> {
+    let mut row = vec![String::new(); CSV_COLUMNS.len()];
+    let mut set = |column: &str, value: String| {
+        let index = CSV_COLUMNS.iter().position(|c| *c == column).unwrap();
+        row[index] = csv_escape(&value);
+    };
+
+    set("message_type", message.type_name().to_string());
+    if let Some(decoder_id) = message.decoder_id() {
+        set("decoder_id", decoder_id.to_string());
+    }
+
+    let extra = match message {
+        Message::Passing(m) => {
+            set("passing_number", m.passing_number.to_string());
+            set("transponder_id", m.transponder_id.to_string());
+            set("transponder_string", opt(&m.transponder_string));
+            set("rtc_time_us", m.rtc_time_us.to_string());
+            set("utc_time_us", opt(&m.utc_time_us));
+            set("strength", opt(&m.strength));
+            set("hits", opt(&m.hits));
+            set("flags", m.flags.to_string());
+            &m.extra_fields
+        }
+        Message::Status(m) => {
+            set("noise", m.noise.to_string());
+            set("temperature", m.temperature.to_string());
+            set("gps_status", m.gps_status.to_string());
+            set("satellites", m.satellites.to_string());
+            &m.extra_fields
+        }
+        Message::Version(m) => {
+            set("description", m.description.clone());
+            set("version", m.version.clone());
+            set("build", opt(&m.build));
+            &m.extra_fields
+        }
+        Message::Resend(m) => {
+            set("from_passing", m.from_passing.to_string());
+            set("until_passing", m.until_passing.to_string());
+            &m.extra_fields
+        }
+        Message::Unknown { tor, fields } => {
+            set("tor", tor.to_string());
+            fields
+        }
+    };
+    set("extra_fields", raw_fields(extra));
+
+    row
+}
+
+fn opt<T: ToString>(value: &Option<T>) -> String {
+    value.as_ref().map(ToString::to_string).unwrap_or_default()
+}
+
+/// Quote a CSV value when it contains a delimiter, quote or line break
+fn csv_escape(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Raw TLV fields as space-separated `TAG=VALUE` hex pairs, e.g. "42=DEAD 43="
+fn raw_fields(fields: &[(u8, Vec<u8>)]) -> String {
+    fields
+        .iter()
+        .map(|(tag, value)| format!("{:02X}={}", tag, hex::encode_upper(value)))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn table_details(message: &Message) -> String {
+    let mut details = String::new();
+
+    let extra = match message {
+        Message::Passing(m) => {
+            let _ = write!(
+                details,
+                "#{} transponder={} rtc_us={}",
+                m.passing_number, m.transponder_id, m.rtc_time_us
+            );
+            if let Some(utc) = m.utc_time_us {
+                let _ = write!(details, " utc_us={}", utc);
+            }
+            if let Some(strength) = m.strength {
+                let _ = write!(details, " strength={}", strength);
+            }
+            if let Some(hits) = m.hits {
+                let _ = write!(details, " hits={}", hits);
+            }
+            if let Some(string) = &m.transponder_string {
+                let _ = write!(details, " string={}", string);
+            }
+            let _ = write!(details, " flags=0x{:04X}", m.flags);
+            &m.extra_fields
+        }
+        Message::Status(m) => {
+            let _ = write!(
+                details,
+                "noise={} temp={:.1}C gps={} sats={}",
+                m.noise,
+                f64::from(m.temperature) / 10.0,
+                m.gps_status,
+                m.satellites
+            );
+            &m.extra_fields
+        }
+        Message::Version(m) => {
+            let _ = write!(details, "{:?} version={}", m.description, m.version);
+            if let Some(build) = m.build {
+                let _ = write!(details, " build={}", build);
+            }
+            &m.extra_fields
+        }
+        Message::Resend(m) => {
+            let _ = write!(details, "passings {}..={}", m.from_passing, m.until_passing);
+            &m.extra_fields
+        }
+        Message::Unknown { tor, fields } => {
+            let _ = write!(details, "tor=0x{:04X} fields={}", tor, raw_fields(fields));
+            return details;
+        }
+    };
+
+    if !extra.is_empty() {
+        let _ = write!(details, " extra={}", raw_fields(extra));
+    }
+    details
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use p3_parser::{PassingMessage, StatusMessage, VersionMessage};
+
+    fn render(format: Format, messages: &[Message]) -> String {
+        let mut formatter = Formatter::new(format, false);
+        let mut out = Vec::new();
+        for message in messages {
+            formatter.write(&mut out, message).unwrap();
+        }
+        String::from_utf8(out).unwrap()
+    }
+
+    fn gate_passing() -> Message {
+        Message::Passing(PassingMessage {
+            passing_number: 8975,
+            transponder_id: 9992,
+            rtc_time_us: 1_762_286_699_916_839,
+            utc_time_us: None,
+            strength: None,
+            hits: None,
+            transponder_string: None,
+            flags: 0,
+            decoder_id: Some("D0000C00".to_string()),
+            extra_fields: vec![(0x42, vec![0xDE, 0xAD])],
+        })
+    }
+
+    fn status() -> Message {
+        Message::Status(StatusMessage {
+            noise: 53,
+            gps_status: 1,
+            temperature: -15,
+            satellites: 7,
+            decoder_id: Some("D0000C00".to_string()),
+            extra_fields: Vec::new(),
+        })
+    }
+
+    #[test]
+    fn test_ndjson_includes_message_type() {
+        let out = render(Format::Ndjson, &[status()]);
+        assert!(out.starts_with("{\"message_type\":\"STATUS\""));
+        assert_eq!(out.lines().count(), 1);
+    }
+
+    #[test]
+    fn test_csv_rows_share_one_header() {
+        let out = render(Format::Csv, &[gate_passing(), status()]);
+        let lines: Vec<_> = out.lines().collect();
+
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], CSV_COLUMNS.join(","));
+        assert_eq!(
+            lines[1],
+            "PASSING,D0000C00,8975,9992,,1762286699916839,,,,0,,,,,,,,,,,42=DEAD"
+        );
+        assert_eq!(lines[2], "STATUS,D0000C00,,,,,,,,,53,-15,1,7,,,,,,,");
+        assert!(
+            lines
+                .iter()
+                .all(|l| l.split(',').count() == CSV_COLUMNS.len())
+        );
+    }
+
+    #[test]
+    fn test_csv_quotes_values_with_delimiters() {
+        let version = Message::Version(VersionMessage {
+            decoder_id: "0C00D00000000000".to_string(),
+            description: "ProChip, \"Smart\"".to_string(),
+            version: "4.5".to_string(),
+            build: None,
+            extra_fields: Vec::new(),
+        });
+
+        let out = render(Format::Csv, &[version]);
+        assert!(out.contains(",\"ProChip, \"\"Smart\"\"\",4.5,"));
+    }
+
+    #[test]
+    fn test_table_lines() {
+        let out = render(
+            Format::Table,
+            &[
+                gate_passing(),
+                status(),
+                Message::Unknown {
+                    tor: 0x45,
+                    fields: vec![(0x01, vec![0xAA])],
+                },
+            ],
+        );
+        let lines: Vec<_> = out.lines().collect();
+
+        assert_eq!(lines[0], "TYPE     DECODER   DETAILS");
+        assert_eq!(
+            lines[1],
+            "PASSING  D0000C00  #8975 transponder=9992 rtc_us=1762286699916839 flags=0x0000 extra=42=DEAD"
+        );
+        assert_eq!(
+            lines[2],
+            "STATUS   D0000C00  noise=53 temp=-1.5C gps=1 sats=7"
+        );
+        assert_eq!(lines[3], "UNKNOWN  -         tor=0x0045 fields=01=AA");
+    }
+}
@@ -0,0 +1,302 @@
+//! Offline capture container for raw decoder byte streams
+//!
+//! A capture records exactly what arrived on the wire, read by read, with the
+//! arrival time of each chunk. Framing is deliberately not applied at record
+//! time: replaying a capture reproduces the original byte boundaries and any
+//! corruption, which is what makes race-day bugs reproducible.
+//!
+//! ## Layout
+//!
+//! All integers are little-endian, like the P3 protocol itself.
+//!
+//! ```text
+//! file header
+//!   magic           4 bytes   "P3CP"
+//!   version         u16       CAPTURE_VERSION
+//!   metadata_len    u32
+//!   metadata        JSON      CaptureMetadata, metadata_len bytes
+//! record (repeated until EOF)
+//!   captured_at_us  u64       Unix microseconds when the bytes arrived
+//!   len             u32
+//!   data            len bytes, raw as read from the source
+//! ```
+
+use crate::error::{CaptureError, CaptureResult};
+use serde::{Deserialize, Serialize};
+use std::io::{self, Read, Write};
+
+/// File magic identifying a P3 capture
+pub const CAPTURE_MAGIC: [u8; 4] = *b"P3CP";
+
+/// Current capture format version
+pub const CAPTURE_VERSION: u16 = 1;
+
+/// Largest metadata block accepted when reading
+pub const MAX_METADATA_LEN: u32 = 64 * 1024;
+
+/// Largest single record accepted when reading
+pub const MAX_RECORD_LEN: u32 = 1024 * 1024;
+
+/// Where and when a capture was recorded
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CaptureMetadata {
+    /// Source the bytes were read from, e.g. "tcp://10.0.0.20:5403"
+    pub source: String,
+    /// Unix microseconds when recording started
+    pub started_at_us: u64,
+    /// Tool that wrote the capture, e.g. "p3-parser 0.1.0"
+    pub recorder: String,
+    /// Free-form note, e.g. track name and race day
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+/// One chunk of raw bytes with its arrival time
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CaptureRecord {
+    /// Unix microseconds when the bytes arrived
+    pub captured_at_us: u64,
+    pub data: Vec<u8>,
+}
+
+/// Writes a capture file header followed by timestamped records
+pub struct CaptureWriter<W: Write> {
+    writer: W,
+    records: u64,
+}
+
+impl<W: Write> CaptureWriter<W> {
+    /// Write the file header and return a writer ready for records
+    pub fn new(mut writer: W, metadata: &CaptureMetadata) -> CaptureResult<Self> {
+        let metadata = serde_json::to_vec(metadata)?;
+        let metadata_len = u32::try_from(metadata.len())
+            .ok()
+            .filter(|&len| len <= MAX_METADATA_LEN)
+            .ok_or(CaptureError::MetadataTooLarge(metadata.len()))?;
+
+        writer.write_all(&CAPTURE_MAGIC)?;
+        writer.write_all(&CAPTURE_VERSION.to_le_bytes())?;
+        writer.write_all(&metadata_len.to_le_bytes())?;
+        writer.write_all(&metadata)?;
+
+        Ok(Self { writer, records: 0 })
+    }
+
+    /// Append one chunk of raw bytes received at `captured_at_us`
+    pub fn write_record(&mut self, captured_at_us: u64, data: &[u8]) -> CaptureResult<()> {
+        let len = u32::try_from(data.len())
+            .ok()
+            .filter(|&len| len <= MAX_RECORD_LEN)
+            .ok_or(CaptureError::RecordTooLarge(data.len()))?;
+
+        self.writer.write_all(&captured_at_us.to_le_bytes())?;
+        self.writer.write_all(&len.to_le_bytes())?;
+        self.writer.write_all(data)?;
+        self.records += 1;
+        Ok(())
+    }
+
+    /// Number of records written so far
+    pub fn records(&self) -> u64 {
+        self.records
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Reads a capture file header and iterates its records
+pub struct CaptureReader<R: Read> {
+    reader: R,
+    metadata: CaptureMetadata,
+    done: bool,
+}
+
+impl<R: Read> CaptureReader<R> {
+    /// Read and validate the file header
+    pub fn new(mut reader: R) -> CaptureResult<Self> {
+        let mut magic = [0u8; 4];
+        read_exact_or_truncated(&mut reader, &mut magic)?;
+        if magic != CAPTURE_MAGIC {
+            return Err(CaptureError::BadMagic(magic));
+        }
+
+        let mut version = [0u8; 2];
+        read_exact_or_truncated(&mut reader, &mut version)?;
+        let version = u16::from_le_bytes(version);
+        if version != CAPTURE_VERSION {
+            return Err(CaptureError::UnsupportedVersion(version));
+        }
+
+        let mut len = [0u8; 4];
+        read_exact_or_truncated(&mut reader, &mut len)?;
+        let len = u32::from_le_bytes(len);
+        if len > MAX_METADATA_LEN {
+            return Err(CaptureError::MetadataTooLarge(len as usize));
+        }
+
+        let mut metadata = vec![0u8; len as usize];
+        read_exact_or_truncated(&mut reader, &mut metadata)?;
+        let metadata = serde_json::from_slice(&metadata)?;
+
+        Ok(Self {
+            reader,
+            metadata,
+            done: false,
+        })
+    }
+
+    pub fn metadata(&self) -> &CaptureMetadata {
+        &self.metadata
+    }
+
+    /// Read the next record, or `None` at a clean end of file
+    ///
+    /// A file that ends partway through a record returns
+    /// [`CaptureError::Truncated`]; everything before it is still usable.
+    pub fn next_record(&mut self) -> CaptureResult<Option<CaptureRecord>> {
+        let mut header = [0u8; 12];
+        let mut filled = 0;
+        while filled < header.len() {
+            match self.reader.read(&mut header[filled..]) {
+                Ok(0) if filled == 0 => return Ok(None),
+                Ok(0) => return Err(CaptureError::Truncated),
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        let captured_at_us = u64::from_le_bytes(header[..8].try_into().unwrap());
+        let len = u32::from_le_bytes(header[8..].try_into().unwrap());
+        if len > MAX_RECORD_LEN {
+            return Err(CaptureError::RecordTooLarge(len as usize));
+        }
+
+        let mut data = vec![0u8; len as usize];
+        read_exact_or_truncated(&mut self.reader, &mut data)?;
+
+        Ok(Some(CaptureRecord {
+            captured_at_us,
+            data,
+        }))
+    }
+}
+
+impl<R: Read> Iterator for CaptureReader<R> {
+    type Item = CaptureResult<CaptureRecord>;
+
+    /// Yields records until end of file; stops after the first error
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let result = self.next_record().transpose();
+        if !matches!(result, Some(Ok(_))) {
+            self.done = true;
+        }
+        result
+    }
+}
+
+fn read_exact_or_truncated(reader: &mut impl Read, buf: &mut [u8]) -> CaptureResult<()> {
+    reader.read_exact(buf).map_err(|e| match e.kind() {
+        io::ErrorKind::UnexpectedEof => CaptureError::Truncated,
+        _ => e.into(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata() -> CaptureMetadata {
+        CaptureMetadata {
+            source: "tcp://10.0.0.20:5403".to_string(),
+            started_at_us: 1_762_286_699_000_000,
+            recorder: "p3-parser test".to_string(),
+            description: Some("Race day, moto 3".to_string()),
+        }
+    }
+
+    fn capture(records: &[(u64, &[u8])]) -> Vec<u8> {
+        let mut writer = CaptureWriter::new(Vec::new(), &metadata()).unwrap();
+        for (ts, data) in records {
+            writer.write_record(*ts, data).unwrap();
+        }
+        writer.into_inner()
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let bytes = capture(&[(100, &[0x8E, 0x02]), (250, &[]), (400, &[0x8F])]);
+
+        let reader = CaptureReader::new(bytes.as_slice()).unwrap();
+        assert_eq!(reader.metadata(), &metadata());
+
+        let records: Vec<_> = reader.collect::<CaptureResult<_>>().unwrap();
+        assert_eq!(
+            records,
+            vec![
+                CaptureRecord {
+                    captured_at_us: 100,
+                    data: vec![0x8E, 0x02],
+                },
+                CaptureRecord {
+                    captured_at_us: 250,
+                    data: vec![],
+                },
+                CaptureRecord {
+                    captured_at_us: 400,
+                    data: vec![0x8F],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_header_layout() {
+        let bytes = capture(&[]);
+        assert_eq!(&bytes[..4], b"P3CP");
+        assert_eq!(&bytes[4..6], &[0x01, 0x00]);
+
+        let len = u32::from_le_bytes(bytes[6..10].try_into().unwrap()) as usize;
+        assert_eq!(bytes.len(), 10 + len);
+    }
+
+    #[test]
+    fn test_bad_magic() {
+        let mut bytes = capture(&[]);
+        bytes[0] = b'X';
+        assert!(matches!(
+            CaptureReader::new(bytes.as_slice()),
+            Err(CaptureError::BadMagic(_))
+        ));
+    }
+
+    #[test]
+    fn test_unsupported_version() {
+        let mut bytes = capture(&[]);
+        bytes[4] = 0x02;
+        assert!(matches!(
+            CaptureReader::new(bytes.as_slice()),
+            Err(CaptureError::UnsupportedVersion(2))
+        ));
+    }
+
+    #[test]
+    fn test_truncated_record_keeps_earlier_records() {
+        let mut bytes = capture(&[(100, &[1, 2, 3]), (200, &[4, 5, 6])]);
+        bytes.truncate(bytes.len() - 1);
+
+        let mut reader = CaptureReader::new(bytes.as_slice()).unwrap();
+        assert_eq!(reader.next().unwrap().unwrap().data, vec![1, 2, 3]);
+        assert!(matches!(reader.next(), Some(Err(CaptureError::Truncated))));
+        assert!(reader.next().is_none());
+    }
+}
@@ -0,0 +1,235 @@
+//! Message encoder: the inverse of [`crate::Parser`]
+//!
+//! Turns parsed messages back into complete wire frames. Known TLV fields are
+//! emitted in the order observed from real decoders, so encoding a message
+//! parsed from a live capture reproduces the original bytes. Preserved
+//! `extra_fields` follow the known fields.
+
+use crate::error::{EncodeError, EncodeResult};
+use crate::messages::{Message, PassingMessage, ResendMessage, StatusMessage, VersionMessage};
+use crate::tlv::TlvEncoder;
+use p3_protocol::fields::{passing, resend, status, version};
+use p3_protocol::{MessageType, build_frame, build_frame_with_tor};
+
+/// Encodes messages into complete escaped P3 frames
+pub struct Encoder {}
+
+impl Encoder {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    /// Encode a message into a complete escaped frame including SOR and EOR
+    ///
+    /// LENGTH, CRC and escaping are handled by [`p3_protocol::build_frame`].
+    pub fn encode(&self, message: &Message) -> EncodeResult<Vec<u8>> {
+        let (message_type, body) = match message {
+            Message::Passing(m) => (MessageType::Passing, m.to_tlv_body()?),
+            Message::Status(m) => (MessageType::Status, m.to_tlv_body()?),
+            Message::Version(m) => (MessageType::Version, m.to_tlv_body()?),
+            Message::Resend(m) => (MessageType::Resend, m.to_tlv_body()?),
+            Message::Unknown { tor, fields } => {
+                let body = add_raw_fields(TlvEncoder::new(), fields)?.build();
+                return Ok(build_frame_with_tor(*tor, &body));
+            }
+        };
+
+        Ok(build_frame(message_type, &body))
+    }
+}
+
+impl Default for Encoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Message {
+    /// Encode this message into a complete escaped P3 frame
+    pub fn to_wire(&self) -> EncodeResult<Vec<u8>> {
+        Encoder::new().encode(self)
+    }
+}
+
+/// Append raw fields verbatim, e.g. unrecognized tags preserved by the parser
+fn add_raw_fields(mut tlv: TlvEncoder, fields: &[(u8, Vec<u8>)]) -> EncodeResult<TlvEncoder> {
+    for (tag, value) in fields {
+        tlv = tlv.add_bytes(*tag, value)?;
+    }
+    Ok(tlv)
+}
+
+/// Parse a wire-order hex decoder ID (e.g. "D0000C00") back into its bytes
+fn parse_decoder_id<const N: usize>(id: &str) -> EncodeResult<[u8; N]> {
+    let invalid = || EncodeError::InvalidDecoderId {
+        id: id.to_string(),
+        bytes: N,
+    };
+
+    if id.len() != N * 2 || !id.is_ascii() {
+        return Err(invalid());
+    }
+
+    let mut bytes = [0u8; N];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&id[i * 2..i * 2 + 2], 16).map_err(|_| invalid())?;
+    }
+    Ok(bytes)
+}
+
+impl PassingMessage {
+    /// Encode the TLV body of this PASSING message
+    pub fn to_tlv_body(&self) -> EncodeResult<Vec<u8>> {
+        let mut tlv = TlvEncoder::new()
+            .add_u32(passing::PASSING_NUMBER, self.passing_number)
+            .add_u32(passing::TRANSPONDER, self.transponder_id);
+
+        // Rider passings carry STRING, STRENGTH and HITS; gate passings don't
+        if let Some(string) = &self.transponder_string {
+            tlv = tlv.add_bytes(passing::STRING, string.as_bytes())?;
+        }
+        if let Some(strength) = self.strength {
+            tlv = tlv.add_u16(passing::STRENGTH, strength);
+        }
+        if let Some(hits) = self.hits {
+            tlv = tlv.add_u16(passing::HITS, hits);
+        }
+
+        tlv = tlv.add_u64(passing::RTC_TIME, self.rtc_time_us);
+        if let Some(utc_time_us) = self.utc_time_us {
+            tlv = tlv.add_u64(passing::UTC_TIME, utc_time_us);
+        }
+        tlv = tlv.add_u16(passing::FLAGS, self.flags);
+
+        if let Some(decoder_id) = &self.decoder_id {
+            tlv = tlv.add_bytes(passing::DECODER_ID, &parse_decoder_id::<4>(decoder_id)?)?;
+        }
+
+        Ok(add_raw_fields(tlv, &self.extra_fields)?.build())
+    }
+}
+
+impl StatusMessage {
+    /// Encode the TLV body of this STATUS message
+    pub fn to_tlv_body(&self) -> EncodeResult<Vec<u8>> {
+        let mut tlv = TlvEncoder::new()
+            .add_u16(status::NOISE, self.noise)
+            .add_i16(status::TEMPERATURE, self.temperature)
+            .add_u8(status::GPS_STATUS, self.gps_status)
+            .add_u8(status::SATINUSE, self.satellites);
+
+        if let Some(decoder_id) = &self.decoder_id {
+            tlv = tlv.add_bytes(status::DECODER_ID, &parse_decoder_id::<4>(decoder_id)?)?;
+        }
+
+        Ok(add_raw_fields(tlv, &self.extra_fields)?.build())
+    }
+}
+
+impl VersionMessage {
+    /// Encode the TLV body of this VERSION message
+    pub fn to_tlv_body(&self) -> EncodeResult<Vec<u8>> {
+        let mut tlv = TlvEncoder::new()
+            .add_bytes(
+                version::DECODER_ID,
+                &parse_decoder_id::<8>(&self.decoder_id)?,
+            )?
+            .add_bytes(version::DESCRIPTION, self.description.as_bytes())?
+            .add_bytes(version::VERSION, self.version.as_bytes())?;
+
+        if let Some(build) = self.build {
+            tlv = tlv.add_u16(version::BUILD, build);
+        }
+
+        Ok(add_raw_fields(tlv, &self.extra_fields)?.build())
+    }
+}
+
+impl ResendMessage {
+    /// Encode the TLV body of this RESEND request
+    pub fn to_tlv_body(&self) -> EncodeResult<Vec<u8>> {
+        let tlv = TlvEncoder::new()
+            .add_u32(resend::FROM, self.from_passing)
+            .add_u32(resend::UNTIL, self.until_passing);
+
+        Ok(add_raw_fields(tlv, &self.extra_fields)?.build())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Parser;
+
+    #[test]
+    fn test_parse_decoder_id() {
+        assert_eq!(
+            parse_decoder_id::<4>("D0000C00").unwrap(),
+            [0xD0, 0x00, 0x0C, 0x00]
+        );
+        assert!(parse_decoder_id::<4>("D0000C").is_err());
+        assert!(parse_decoder_id::<4>("D0000CZZ").is_err());
+        assert!(parse_decoder_id::<4>("D0000CÅ").is_err());
+    }
+
+    #[test]
+    fn test_resend_matches_protocol_encoder() {
+        let message = Message::Resend(ResendMessage {
+            from_passing: 8841,
+            until_passing: 8850,
+            extra_fields: Vec::new(),
+        });
+
+        assert_eq!(
+            message.to_wire().unwrap(),
+            p3_protocol::build_resend_request(8841, 8850)
+        );
+    }
+
+    #[test]
+    fn test_gate_passing_roundtrip() {
+        let message = Message::Passing(PassingMessage {
+            passing_number: 8975,
+            transponder_id: 9992,
+            rtc_time_us: 1_762_286_699_916_839,
+            utc_time_us: None,
+            strength: None,
+            hits: None,
+            transponder_string: None,
+            flags: 0,
+            decoder_id: Some("D0000C00".to_string()),
+            extra_fields: vec![(0x42, vec![0x8E, 0x01])],
+        });
+
+        let wire = message.to_wire().unwrap();
+        assert_eq!(Parser::new().parse(&wire).unwrap(), message);
+    }
+
+    #[test]
+    fn test_unknown_message_roundtrip() {
+        let message = Message::Unknown {
+            tor: 0x0045,
+            fields: vec![(0x01, vec![0x8D, 0x8F]), (0x02, vec![])],
+        };
+
+        let wire = message.to_wire().unwrap();
+        assert_eq!(Parser::new().parse(&wire).unwrap(), message);
+    }
+
+    #[test]
+    fn test_invalid_decoder_id_is_rejected() {
+        let message = Message::Status(StatusMessage {
+            noise: 53,
+            gps_status: 1,
+            temperature: 16,
+            satellites: 0,
+            decoder_id: Some("not-hex!".to_string()),
+            extra_fields: Vec::new(),
+        });
+
+        assert!(matches!(
+            message.to_wire(),
+            Err(EncodeError::InvalidDecoderId { bytes: 4, .. })
+        ));
+    }
+}
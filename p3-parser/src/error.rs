@@ -25,3 +25,43 @@ pub enum ParseError {
 }
 
 pub type ParseResult<T> = Result<T, ParseError>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum EncodeError {
+    /// Value length exceeds maximum of 255 bytes
+    #[error("TLV value length {actual} exceeds maximum of {max} bytes")]
+    ValueTooLong { actual: usize, max: usize },
+
+    /// Decoder ID is not a hex string of the expected byte width
+    #[error("Invalid decoder ID {id:?}: expected {bytes} bytes as hex")]
+    InvalidDecoderId { id: String, bytes: usize },
+}
+
+pub type EncodeResult<T> = Result<T, EncodeError>;
+
+#[derive(Debug, Error)]
+pub enum CaptureError {
+    #[error("Not a P3 capture file (magic {0:02X?})")]
+    BadMagic([u8; 4]),
+
+    #[error("Unsupported capture format version {0}")]
+    UnsupportedVersion(u16),
+
+    #[error("Capture metadata of {0} bytes is too large")]
+    MetadataTooLarge(usize),
+
+    #[error("Capture record of {0} bytes is too large")]
+    RecordTooLarge(usize),
+
+    #[error("Invalid capture metadata: {0}")]
+    InvalidMetadata(#[from] serde_json::Error),
+
+    /// File ended partway through a header or record
+    #[error("Capture file is truncated")]
+    Truncated,
+
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+pub type CaptureResult<T> = Result<T, CaptureError>;
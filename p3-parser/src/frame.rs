@@ -1,19 +1,72 @@
 use crate::error::{ParseError, ParseResult};
+use crate::tlv::TlvIter;
 use p3_protocol::{
-    EOR, MIN_FRAME_SIZE, MessageType, OFFSET_BODY, OFFSET_CRC, OFFSET_LENGTH, OFFSET_RESERVED,
-    OFFSET_SOR, OFFSET_TYPE, OFFSET_VERSION, SOR, VERSION, unescape_data, validate_crc,
+    CrcError, EOR, MIN_FRAME_SIZE, MessageType, OFFSET_BODY, OFFSET_CRC, OFFSET_LENGTH,
+    OFFSET_RESERVED, OFFSET_SOR, OFFSET_TYPE, OFFSET_VERSION, SOR, VERSION, calculate_crc,
+    unescape_into,
 };
 
 /// A parsed P3 message frame
 #[derive(Debug, Clone, PartialEq)]
 pub struct Frame {
-    pub message_type: MessageType,
+    /// Raw Type of Record; see [`Frame::message_type`]
+    pub tor: u16,
     /// Unescaped message body (TLV fields)
     pub body: Vec<u8>,
     pub crc: u16,
     pub reserved: u16,
 }
 
+impl Frame {
+    /// Known message type, or `None` for a TOR this crate doesn't model
+    pub fn message_type(&self) -> Option<MessageType> {
+        MessageType::from_u16(self.tor)
+    }
+
+    /// Borrow this frame as a [`FrameRef`]
+    pub fn as_frame_ref(&self) -> FrameRef<'_> {
+        FrameRef {
+            tor: self.tor,
+            body: &self.body,
+            crc: self.crc,
+            reserved: self.reserved,
+        }
+    }
+}
+
+/// A parsed P3 message frame borrowing its body from a scratch buffer
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameRef<'a> {
+    /// Raw Type of Record; see [`FrameRef::message_type`]
+    pub tor: u16,
+    /// Unescaped message body (TLV fields)
+    pub body: &'a [u8],
+    pub crc: u16,
+    pub reserved: u16,
+}
+
+impl<'a> FrameRef<'a> {
+    /// Known message type, or `None` for a TOR this crate doesn't model
+    pub fn message_type(&self) -> Option<MessageType> {
+        MessageType::from_u16(self.tor)
+    }
+
+    /// Iterate the TLV fields in the body without copying values
+    pub fn fields(&self) -> TlvIter<'a> {
+        TlvIter::new(self.body)
+    }
+
+    /// Copy into an owned [`Frame`]
+    pub fn to_frame(&self) -> Frame {
+        Frame {
+            tor: self.tor,
+            body: self.body.to_vec(),
+            crc: self.crc,
+            reserved: self.reserved,
+        }
+    }
+}
+
 pub struct FrameParser {
     // TODO: Add buffer management for stream parsing
 }
@@ -26,8 +79,24 @@ impl FrameParser {
     /// Parse a single frame from binary data
     ///
     /// The input data should be the complete escaped message including SOR and EOR.
+    /// Allocates an owned [`Frame`]; see [`FrameParser::parse_into`] for the
+    /// borrowing variant used on hot paths.
     pub fn parse(&self, data: &[u8]) -> ParseResult<Frame> {
-        // Minimum frame size: SOR + VERSION + LEN + CRC + RES + TYPE + EOR
+        let mut scratch = Vec::new();
+        self.parse_into(data, &mut scratch)
+            .map(|frame| frame.to_frame())
+    }
+
+    /// Parse a single frame, unescaping into a caller-owned scratch buffer
+    ///
+    /// The returned [`FrameRef`] borrows its body from `scratch`. Reusing the
+    /// same buffer across calls makes steady-state parsing allocation-free.
+    pub fn parse_into<'a>(
+        &self,
+        data: &[u8],
+        scratch: &'a mut Vec<u8>,
+    ) -> ParseResult<FrameRef<'a>> {
+        // Minimum frame size: SOR + VER + LEN + CRC + RES + TYPE + EOR
         if data.len() < MIN_FRAME_SIZE {
             return Err(ParseError::IncompleteMessage {
                 expected: MIN_FRAME_SIZE,
@@ -48,56 +117,56 @@ impl FrameParser {
             )));
         }
 
-        // Validate CRC (validates the complete escaped message)
-        validate_crc(data).map_err(|e| ParseError::CrcError(e))?;
-
-        // Unescape the data after CRC validation
-        let unescaped = unescape_data(data).map_err(|e| ParseError::EscapeError(e))?;
+        unescape_into(data, scratch).map_err(ParseError::EscapeError)?;
 
         // Now work with unescaped data
         // Header: SOR(1) + VER(1) + LEN(2) + CRC(2) + RES(2) + TYPE(2) = 10 bytes
-        if unescaped.len() < MIN_FRAME_SIZE {
+        if scratch.len() < MIN_FRAME_SIZE {
             // Need at least header + EOR
             return Err(ParseError::IncompleteMessage {
                 expected: MIN_FRAME_SIZE,
-                actual: unescaped.len(),
+                actual: scratch.len(),
             });
         }
 
-        // Extract header fields (all little-endian)
-        let length = u16::from_le_bytes([unescaped[OFFSET_LENGTH], unescaped[OFFSET_LENGTH + 1]]);
-        let crc = u16::from_le_bytes([unescaped[OFFSET_CRC], unescaped[OFFSET_CRC + 1]]);
-        let reserved =
-            u16::from_le_bytes([unescaped[OFFSET_RESERVED], unescaped[OFFSET_RESERVED + 1]]);
-        let message_type_raw =
-            u16::from_le_bytes([unescaped[OFFSET_TYPE], unescaped[OFFSET_TYPE + 1]]);
+        // CRC is calculated over the unescaped frame with the CRC field zeroed
+        let crc = u16::from_le_bytes([scratch[OFFSET_CRC], scratch[OFFSET_CRC + 1]]);
+        scratch[OFFSET_CRC..OFFSET_CRC + 2].fill(0);
+        let calculated = calculate_crc(scratch);
+        scratch[OFFSET_CRC..OFFSET_CRC + 2].copy_from_slice(&crc.to_le_bytes());
+        if calculated != crc {
+            return Err(ParseError::CrcError(CrcError::ValidationFailed {
+                expected: calculated,
+                actual: crc,
+            }));
+        }
 
-        let message_type = MessageType::from_u16(message_type_raw)
-            .ok_or_else(|| ParseError::UnknownMessageType(message_type_raw))?;
+        // Extract header fields (all little-endian)
+        let length = u16::from_le_bytes([scratch[OFFSET_LENGTH], scratch[OFFSET_LENGTH + 1]]);
+        let reserved = u16::from_le_bytes([scratch[OFFSET_RESERVED], scratch[OFFSET_RESERVED + 1]]);
+        // Unknown TORs are passed through; interpreting them is the caller's job
+        let tor = u16::from_le_bytes([scratch[OFFSET_TYPE], scratch[OFFSET_TYPE + 1]]);
 
         // Validate length matches actual data
         // Length field does NOT include escape bytes, and represents unescaped length
-        if unescaped.len() != length as usize {
+        if scratch.len() != length as usize {
             return Err(ParseError::InvalidFrame(format!(
                 "Length mismatch: header says {}, got {}",
                 length,
-                unescaped.len()
+                scratch.len()
             )));
         }
 
         // Validate EOR marker at the end
-        let eor_pos = unescaped.len() - 1;
-        if unescaped[eor_pos] != EOR {
+        let eor_pos = scratch.len() - 1;
+        if scratch[eor_pos] != EOR {
             return Err(ParseError::InvalidFrame("Missing EOR marker".into()));
         }
 
-        // Extract body (between header and EOR)
         // Body starts at OFFSET_BODY (after header) and ends before EOR
-        let body = unescaped[OFFSET_BODY..eor_pos].to_vec();
-
-        Ok(Frame {
-            message_type,
-            body,
+        Ok(FrameRef {
+            tor,
+            body: &scratch[OFFSET_BODY..eor_pos],
             crc,
             reserved,
         })
@@ -112,9 +181,58 @@ impl Default for FrameParser {
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+    use p3_protocol::build_frame;
+
+    #[test]
+    fn test_parse_into_borrows_body_from_scratch() {
+        let data = build_frame(MessageType::Status, &[0x01, 0x02, 0x8F, 0x00]);
+        let mut scratch = Vec::new();
+
+        let frame = FrameParser::new().parse_into(&data, &mut scratch).unwrap();
+        assert_eq!(frame.message_type(), Some(MessageType::Status));
+        assert_eq!(frame.body, &[0x01, 0x02, 0x8F, 0x00]);
+        assert_eq!(frame.reserved, 0);
+    }
+
+    #[test]
+    fn test_parse_into_reuses_scratch() {
+        let parser = FrameParser::new();
+        let first = build_frame(MessageType::Passing, &[0x01, 0x04, 0x01, 0x00, 0x00, 0x00]);
+        let second = build_frame(MessageType::Status, &[0x01, 0x02, 0x35, 0x00]);
+        let mut scratch = Vec::new();
+
+        parser.parse_into(&first, &mut scratch).unwrap();
+        let capacity = scratch.capacity();
+        let frame = parser.parse_into(&second, &mut scratch).unwrap();
+
+        assert_eq!(frame.message_type(), Some(MessageType::Status));
+        assert_eq!(frame.body.len(), 4);
+        assert_eq!(scratch.capacity(), capacity);
+    }
+
+    #[test]
+    fn test_parse_matches_parse_into() {
+        let data = build_frame(MessageType::Version, &[0x03, 0x02, 0x2C, 0x00]);
+        let parser = FrameParser::new();
+        let mut scratch = Vec::new();
+
+        let owned = parser.parse(&data).unwrap();
+        let borrowed = parser.parse_into(&data, &mut scratch).unwrap();
+        assert_eq!(owned.as_frame_ref(), borrowed);
+    }
+
     #[test]
-    #[ignore]
-    fn test_parse_frame() {
-        // TODO: Add tests
+    fn test_parse_rejects_bad_crc() {
+        let mut data = build_frame(MessageType::Status, &[0x01, 0x02, 0x35, 0x00]);
+        // Flip a body byte that needs no escaping
+        let last_body = data.len() - 2;
+        data[last_body] ^= 0x01;
+
+        let err = FrameParser::new().parse(&data).unwrap_err();
+        assert!(matches!(
+            err,
+            ParseError::CrcError(CrcError::ValidationFailed { .. })
+        ));
     }
 }
@@ -12,6 +12,11 @@
 //! - **PASSING** - Transponder detection with timing data
 //! - **STATUS** - Decoder operational status
 //! - **VERSION** - Hardware/firmware identification
+//! - **RESEND** - Retransmission request for a range of passings
+//!
+//! Records with any other TOR are returned as [`Message::Unknown`], and fields
+//! with unrecognized tags are kept in each message's `extra_fields`, so
+//! nothing a decoder sends is dropped.
 //!
 //! ## Example Usage
 //!
@@ -25,13 +30,20 @@
 //!
 //! // Convert to JSON
 //! let json = serde_json::to_string(&message)?;
+//!
+//! // Re-encode to wire bytes
+//! let frame = message.to_wire()?;
 
+pub mod capture;
+pub mod encode;
 pub mod error;
 pub mod frame;
 pub mod messages;
 pub mod stream;
 pub mod tlv;
 
+pub use capture::*;
+pub use encode::*;
 pub use error::*;
 pub use frame::*;
 pub use messages::*;
@@ -55,17 +67,29 @@ impl Parser {
 
     /// The input should be a complete escaped message including SOR and EOR markers.
     pub fn parse(&self, data: &[u8]) -> ParseResult<Message> {
-        let frame = self.frame_parser.parse(data)?;
+        let mut scratch = Vec::new();
+        self.parse_with_scratch(data, &mut scratch)
+    }
 
-        let fields = self.tlv_decoder.decode(&frame.body)?;
+    /// Like [`Parser::parse`], but unescapes into a reusable scratch buffer
+    ///
+    /// Only the returned [`Message`] itself is allocated; frame and TLV
+    /// decoding borrow from `scratch`.
+    pub fn parse_with_scratch(&self, data: &[u8], scratch: &mut Vec<u8>) -> ParseResult<Message> {
+        let frame = self.frame_parser.parse_into(data, scratch)?;
+        let fields = self.tlv_decoder.iter(frame.body);
 
-        let message = match frame.message_type {
-            MessageType::Passing => Message::Passing(PassingMessage::from_tlv_fields(&fields)?),
-            MessageType::Status => Message::Status(StatusMessage::from_tlv_fields(&fields)?),
-            MessageType::Version => Message::Version(VersionMessage::from_tlv_fields(&fields)?),
-            MessageType::Resend => {
-                return Err(ParseError::UnknownMessageType(MessageType::Resend.to_u16()));
-            }
+        let message = match frame.message_type() {
+            Some(MessageType::Passing) => Message::Passing(PassingMessage::from_tlv_iter(fields)?),
+            Some(MessageType::Status) => Message::Status(StatusMessage::from_tlv_iter(fields)?),
+            Some(MessageType::Version) => Message::Version(VersionMessage::from_tlv_iter(fields)?),
+            Some(MessageType::Resend) => Message::Resend(ResendMessage::from_tlv_iter(fields)?),
+            None => Message::Unknown {
+                tor: frame.tor,
+                fields: fields
+                    .map(|field| field.map(|f| (f.tag, f.value.to_vec())))
+                    .collect::<ParseResult<_>>()?,
+            },
         };
 
         Ok(message)
@@ -77,3 +101,38 @@ impl Default for Parser {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_resend_request() {
+        let frame = p3_protocol::build_resend_request(8841, 8850);
+        let message = Parser::new().parse(&frame).unwrap();
+
+        assert_eq!(
+            message,
+            Message::Resend(ResendMessage {
+                from_passing: 8841,
+                until_passing: 8850,
+                extra_fields: Vec::new(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_unknown_tor() {
+        let frame =
+            p3_protocol::build_frame_with_tor(0x0045, &[0x01, 0x02, 0xAA, 0xBB, 0x09, 0x00]);
+        let message = Parser::new().parse(&frame).unwrap();
+
+        assert_eq!(
+            message,
+            Message::Unknown {
+                tor: 0x0045,
+                fields: vec![(0x01, vec![0xAA, 0xBB]), (0x09, vec![])],
+            }
+        );
+    }
+}
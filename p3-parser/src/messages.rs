@@ -1,8 +1,8 @@
 //! Message type definitions for P3 protocol
 
 use crate::error::{ParseError, ParseResult};
-use crate::tlv::{TlvDecoder, TlvField};
-use p3_protocol::fields::{passing, status, version};
+use crate::tlv::{TlvDecoder, TlvField, TlvFieldRef};
+use p3_protocol::fields::{passing, resend, status, version};
 use serde::{Deserialize, Serialize};
 
 // Helper functions for common TLV field operations
@@ -39,6 +39,46 @@ fn decode_i16(bytes: &[u8]) -> Option<i16> {
         .map(i16::from_le_bytes)
 }
 
+/// Decode a UTF-8 string field
+fn decode_string(bytes: &[u8]) -> Option<String> {
+    std::str::from_utf8(bytes).ok().map(str::to_string)
+}
+
+/// Serde adapter for raw TLV fields: a list of `{ "tag": u8, "value": "<hex>" }`
+pub mod hex_fields {
+    use serde::de::Error as _;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Serialize, Deserialize)]
+    struct HexField {
+        tag: u8,
+        value: String,
+    }
+
+    pub fn serialize<S: Serializer>(
+        fields: &[(u8, Vec<u8>)],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(fields.iter().map(|(tag, value)| HexField {
+            tag: *tag,
+            value: hex::encode_upper(value),
+        }))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<(u8, Vec<u8>)>, D::Error> {
+        Vec::<HexField>::deserialize(deserializer)?
+            .into_iter()
+            .map(|field| {
+                hex::decode(&field.value)
+                    .map(|value| (field.tag, value))
+                    .map_err(D::Error::custom)
+            })
+            .collect()
+    }
+}
+
 /// Helper to extract required field with better error message
 fn require_field<T>(value: Option<T>, field_name: &str, tag: u8) -> ParseResult<T> {
     value.ok_or_else(|| {
@@ -83,6 +123,10 @@ pub struct PassingMessage {
     /// Decoder ID (hex string showing bytes in wire order)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub decoder_id: Option<String>,
+
+    /// TLV fields with tags this parser doesn't recognize, in wire order
+    #[serde(default, skip_serializing_if = "Vec::is_empty", with = "hex_fields")]
+    pub extra_fields: Vec<(u8, Vec<u8>)>,
 }
 
 /// A parsed STATUS message
@@ -103,6 +147,10 @@ pub struct StatusMessage {
     /// Decoder ID (hex string showing bytes in wire order)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub decoder_id: Option<String>,
+
+    /// TLV fields with tags this parser doesn't recognize, in wire order
+    #[serde(default, skip_serializing_if = "Vec::is_empty", with = "hex_fields")]
+    pub extra_fields: Vec<(u8, Vec<u8>)>,
 }
 
 /// A parsed VERSION message
@@ -120,11 +168,37 @@ pub struct VersionMessage {
     /// Build number
     #[serde(skip_serializing_if = "Option::is_none")]
     pub build: Option<u16>,
+
+    /// TLV fields with tags this parser doesn't recognize, in wire order
+    #[serde(default, skip_serializing_if = "Vec::is_empty", with = "hex_fields")]
+    pub extra_fields: Vec<(u8, Vec<u8>)>,
+}
+
+/// A parsed RESEND request (client → decoder)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResendMessage {
+    /// First passing number to retransmit
+    pub from_passing: u32,
+
+    /// Last passing number to retransmit (inclusive)
+    pub until_passing: u32,
+
+    /// TLV fields with tags this parser doesn't recognize, in wire order
+    #[serde(default, skip_serializing_if = "Vec::is_empty", with = "hex_fields")]
+    pub extra_fields: Vec<(u8, Vec<u8>)>,
 }
 
 impl PassingMessage {
     /// Parse a PASSING message from TLV fields
     pub fn from_tlv_fields(fields: &[TlvField]) -> ParseResult<Self> {
+        Self::from_tlv_iter(fields.iter().map(|field| Ok(field.as_field_ref())))
+    }
+
+    /// Parse a PASSING message from borrowed TLV fields, e.g. [`crate::FrameRef::fields`]
+    pub fn from_tlv_iter<'a>(
+        fields: impl IntoIterator<Item = ParseResult<TlvFieldRef<'a>>>,
+    ) -> ParseResult<Self> {
+        let mut extra_fields = Vec::new();
         let mut passing_number = None;
         let mut transponder_id = None;
         let mut rtc_time_us = None;
@@ -136,17 +210,18 @@ impl PassingMessage {
         let mut decoder_id = None;
 
         for field in fields {
+            let field = field?;
             match field.tag {
-                passing::PASSING_NUMBER => passing_number = TlvDecoder::decode_u32(&field.value),
-                passing::TRANSPONDER => transponder_id = TlvDecoder::decode_u32(&field.value),
-                passing::RTC_TIME => rtc_time_us = TlvDecoder::decode_u64(&field.value),
-                passing::UTC_TIME => utc_time_us = TlvDecoder::decode_u64(&field.value),
-                passing::STRENGTH => strength = TlvDecoder::decode_u16(&field.value),
-                passing::HITS => hits = TlvDecoder::decode_u16(&field.value),
-                passing::STRING => transponder_string = String::from_utf8(field.value.clone()).ok(),
-                passing::FLAGS => flags = TlvDecoder::decode_u16(&field.value),
-                passing::DECODER_ID => decoder_id = format_decoder_id_u32(&field.value),
-                _ => {} // Unknown field, skip
+                passing::PASSING_NUMBER => passing_number = TlvDecoder::decode_u32(field.value),
+                passing::TRANSPONDER => transponder_id = TlvDecoder::decode_u32(field.value),
+                passing::RTC_TIME => rtc_time_us = TlvDecoder::decode_u64(field.value),
+                passing::UTC_TIME => utc_time_us = TlvDecoder::decode_u64(field.value),
+                passing::STRENGTH => strength = TlvDecoder::decode_u16(field.value),
+                passing::HITS => hits = TlvDecoder::decode_u16(field.value),
+                passing::STRING => transponder_string = decode_string(field.value),
+                passing::FLAGS => flags = TlvDecoder::decode_u16(field.value),
+                passing::DECODER_ID => decoder_id = format_decoder_id_u32(field.value),
+                _ => extra_fields.push((field.tag, field.value.to_vec())),
             }
         }
 
@@ -164,6 +239,7 @@ impl PassingMessage {
             transponder_string,
             flags: require_field(flags, "FLAGS", passing::FLAGS)?,
             decoder_id,
+            extra_fields,
         })
     }
 }
@@ -171,6 +247,14 @@ impl PassingMessage {
 impl StatusMessage {
     /// Parse a STATUS message from TLV fields
     pub fn from_tlv_fields(fields: &[TlvField]) -> ParseResult<Self> {
+        Self::from_tlv_iter(fields.iter().map(|field| Ok(field.as_field_ref())))
+    }
+
+    /// Parse a STATUS message from borrowed TLV fields, e.g. [`crate::FrameRef::fields`]
+    pub fn from_tlv_iter<'a>(
+        fields: impl IntoIterator<Item = ParseResult<TlvFieldRef<'a>>>,
+    ) -> ParseResult<Self> {
+        let mut extra_fields = Vec::new();
         let mut noise = None;
         let mut gps_status = None;
         let mut temperature = None;
@@ -178,13 +262,14 @@ impl StatusMessage {
         let mut decoder_id = None;
 
         for field in fields {
+            let field = field?;
             match field.tag {
-                status::NOISE => noise = TlvDecoder::decode_u16(&field.value),
+                status::NOISE => noise = TlvDecoder::decode_u16(field.value),
                 status::GPS_STATUS => gps_status = field.value.first().copied(),
-                status::TEMPERATURE => temperature = decode_i16(&field.value),
+                status::TEMPERATURE => temperature = decode_i16(field.value),
                 status::SATINUSE => satellites = field.value.first().copied(),
-                status::DECODER_ID => decoder_id = format_decoder_id_u32(&field.value),
-                _ => {} // Unknown field, skip
+                status::DECODER_ID => decoder_id = format_decoder_id_u32(field.value),
+                _ => extra_fields.push((field.tag, field.value.to_vec())),
             }
         }
 
@@ -194,6 +279,7 @@ impl StatusMessage {
             temperature: require_field(temperature, "TEMPERATURE", status::TEMPERATURE)?,
             satellites: require_field(satellites, "SATINUSE", status::SATINUSE)?,
             decoder_id,
+            extra_fields,
         })
     }
 }
@@ -201,18 +287,27 @@ impl StatusMessage {
 impl VersionMessage {
     /// Parse a VERSION message from TLV fields
     pub fn from_tlv_fields(fields: &[TlvField]) -> ParseResult<Self> {
+        Self::from_tlv_iter(fields.iter().map(|field| Ok(field.as_field_ref())))
+    }
+
+    /// Parse a VERSION message from borrowed TLV fields, e.g. [`crate::FrameRef::fields`]
+    pub fn from_tlv_iter<'a>(
+        fields: impl IntoIterator<Item = ParseResult<TlvFieldRef<'a>>>,
+    ) -> ParseResult<Self> {
+        let mut extra_fields = Vec::new();
         let mut decoder_id = None;
         let mut description = None;
         let mut version_str = None;
         let mut build = None;
 
         for field in fields {
+            let field = field?;
             match field.tag {
-                version::DECODER_ID => decoder_id = format_decoder_id_u64(&field.value),
-                version::DESCRIPTION => description = String::from_utf8(field.value.clone()).ok(),
-                version::VERSION => version_str = String::from_utf8(field.value.clone()).ok(),
-                version::BUILD => build = TlvDecoder::decode_u16(&field.value),
-                _ => {} // Unknown field, skip
+                version::DECODER_ID => decoder_id = format_decoder_id_u64(field.value),
+                version::DESCRIPTION => description = decode_string(field.value),
+                version::VERSION => version_str = decode_string(field.value),
+                version::BUILD => build = TlvDecoder::decode_u16(field.value),
+                _ => extra_fields.push((field.tag, field.value.to_vec())),
             }
         }
 
@@ -221,6 +316,38 @@ impl VersionMessage {
             description: require_field(description, "DESCRIPTION", version::DESCRIPTION)?,
             version: require_field(version_str, "VERSION", version::VERSION)?,
             build,
+            extra_fields,
+        })
+    }
+}
+
+impl ResendMessage {
+    /// Parse a RESEND request from TLV fields
+    pub fn from_tlv_fields(fields: &[TlvField]) -> ParseResult<Self> {
+        Self::from_tlv_iter(fields.iter().map(|field| Ok(field.as_field_ref())))
+    }
+
+    /// Parse a RESEND request from borrowed TLV fields, e.g. [`crate::FrameRef::fields`]
+    pub fn from_tlv_iter<'a>(
+        fields: impl IntoIterator<Item = ParseResult<TlvFieldRef<'a>>>,
+    ) -> ParseResult<Self> {
+        let mut extra_fields = Vec::new();
+        let mut from_passing = None;
+        let mut until_passing = None;
+
+        for field in fields {
+            let field = field?;
+            match field.tag {
+                resend::FROM => from_passing = TlvDecoder::decode_u32(field.value),
+                resend::UNTIL => until_passing = TlvDecoder::decode_u32(field.value),
+                _ => extra_fields.push((field.tag, field.value.to_vec())),
+            }
+        }
+
+        Ok(ResendMessage {
+            from_passing: require_field(from_passing, "FROM", resend::FROM)?,
+            until_passing: require_field(until_passing, "UNTIL", resend::UNTIL)?,
+            extra_fields,
         })
     }
 }
@@ -237,6 +364,42 @@ pub enum Message {
 
     #[serde(rename = "VERSION")]
     Version(VersionMessage),
+
+    #[serde(rename = "RESEND")]
+    Resend(ResendMessage),
+
+    /// A record with a TOR this parser doesn't model, kept verbatim
+    #[serde(rename = "UNKNOWN")]
+    Unknown {
+        /// Raw Type of Record
+        tor: u16,
+        /// All TLV fields in wire order
+        #[serde(with = "hex_fields")]
+        fields: Vec<(u8, Vec<u8>)>,
+    },
+}
+
+impl Message {
+    /// The `message_type` tag used in JSON, e.g. "PASSING" or "UNKNOWN"
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Message::Passing(_) => "PASSING",
+            Message::Status(_) => "STATUS",
+            Message::Version(_) => "VERSION",
+            Message::Resend(_) => "RESEND",
+            Message::Unknown { .. } => "UNKNOWN",
+        }
+    }
+
+    /// Decoder ID carried by the message, if any
+    pub fn decoder_id(&self) -> Option<&str> {
+        match self {
+            Message::Passing(m) => m.decoder_id.as_deref(),
+            Message::Status(m) => m.decoder_id.as_deref(),
+            Message::Version(m) => Some(&m.decoder_id),
+            Message::Resend(_) | Message::Unknown { .. } => None,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -251,11 +414,129 @@ mod tests {
             temperature: 16,
             satellites: 0,
             decoder_id: Some("D0000C00".to_string()),
+            extra_fields: Vec::new(),
         });
 
         let json = serde_json::to_string(&msg).unwrap();
         assert!(json.contains("\"message_type\":\"STATUS\""));
         assert!(json.contains("\"noise\":53"));
         assert!(json.contains("\"D0000C00\""));
+        assert!(!json.contains("extra_fields"));
+    }
+
+    #[test]
+    fn test_unknown_tags_are_preserved() {
+        let fields = vec![
+            TlvField {
+                tag: status::NOISE,
+                value: vec![0x35, 0x00],
+            },
+            TlvField {
+                tag: 0x42,
+                value: vec![0xDE, 0xAD],
+            },
+            TlvField {
+                tag: status::GPS_STATUS,
+                value: vec![1],
+            },
+            TlvField {
+                tag: status::TEMPERATURE,
+                value: vec![0x10, 0x00],
+            },
+            TlvField {
+                tag: status::SATINUSE,
+                value: vec![0],
+            },
+        ];
+
+        let msg = StatusMessage::from_tlv_fields(&fields).unwrap();
+        assert_eq!(msg.extra_fields, vec![(0x42, vec![0xDE, 0xAD])]);
+
+        let json = serde_json::to_string(&Message::Status(msg.clone())).unwrap();
+        assert!(json.contains("\"extra_fields\":[{\"tag\":66,\"value\":\"DEAD\"}]"));
+
+        let back: Message = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, Message::Status(msg));
+    }
+
+    #[test]
+    fn test_missing_extra_fields_deserializes_empty() {
+        let json = r#"{"message_type":"RESEND","from_passing":1,"until_passing":2}"#;
+        let msg: Message = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            msg,
+            Message::Resend(ResendMessage {
+                from_passing: 1,
+                until_passing: 2,
+                extra_fields: Vec::new(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_type_name_matches_json_tag() {
+        let messages = [
+            Message::Resend(ResendMessage {
+                from_passing: 1,
+                until_passing: 2,
+                extra_fields: Vec::new(),
+            }),
+            Message::Unknown {
+                tor: 9,
+                fields: Vec::new(),
+            },
+        ];
+
+        for message in messages {
+            let json = serde_json::to_value(&message).unwrap();
+            assert_eq!(json["message_type"], message.type_name());
+            assert_eq!(message.decoder_id(), None);
+        }
+    }
+
+    #[test]
+    fn test_unknown_message_serialization() {
+        let msg = Message::Unknown {
+            tor: 0x0045,
+            fields: vec![(0x01, vec![0x8F, 0x00]), (0x81, vec![])],
+        };
+
+        let json = serde_json::to_string(&msg).unwrap();
+        assert_eq!(
+            json,
+            r#"{"message_type":"UNKNOWN","tor":69,"fields":[{"tag":1,"value":"8F00"},{"tag":129,"value":""}]}"#
+        );
+        assert_eq!(serde_json::from_str::<Message>(&json).unwrap(), msg);
+    }
+
+    #[test]
+    fn test_resend_from_tlv_fields() {
+        let fields = vec![
+            TlvField {
+                tag: resend::FROM,
+                value: 100u32.to_le_bytes().to_vec(),
+            },
+            TlvField {
+                tag: resend::UNTIL,
+                value: 105u32.to_le_bytes().to_vec(),
+            },
+        ];
+
+        let msg = ResendMessage::from_tlv_fields(&fields).unwrap();
+        assert_eq!(msg.from_passing, 100);
+        assert_eq!(msg.until_passing, 105);
+
+        let json = serde_json::to_string(&Message::Resend(msg)).unwrap();
+        assert!(json.contains("\"message_type\":\"RESEND\""));
+    }
+
+    #[test]
+    fn test_resend_missing_until() {
+        let fields = vec![TlvField {
+            tag: resend::FROM,
+            value: 100u32.to_le_bytes().to_vec(),
+        }];
+
+        assert!(ResendMessage::from_tlv_fields(&fields).is_err());
     }
 }
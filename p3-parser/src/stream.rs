@@ -1,37 +1,140 @@
 use crate::{Message, ParseError, Parser};
-use p3_protocol::{ESCAPE, SOR};
+use p3_protocol::{
+    CrcError, EOR, ESCAPE, ESCAPE_OFFSET, MIN_FRAME_SIZE, OFFSET_LENGTH, OFFSET_VERSION, SOR,
+    VERSION,
+};
 
 /// Parsed-message output from [`MessageFramer::feed`].
 pub type FrameResult = Result<Message, ParseError>;
 
+/// Largest unescaped frame LENGTH accepted before the header is treated as corrupt.
+///
+/// Real decoder frames are well under 100 bytes; anything near the u16 limit is
+/// garbage and would otherwise stall the framer waiting for bytes that never come.
+pub const MAX_FRAME_LENGTH: usize = 1024;
+
+/// Default cap on buffered, not-yet-framed bytes.
+pub const DEFAULT_MAX_BUFFER: usize = 64 * 1024;
+
+/// Corruption and recovery counters for a [`MessageFramer`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FramerStats {
+    /// Complete frames handed to the parser (successful or not)
+    pub frames: u64,
+    /// Bytes discarded while searching for the next SOR
+    pub bytes_skipped: u64,
+    /// Frames with intact SOR/EOR framing but a bad CRC
+    pub crc_failures: u64,
+    /// Times a candidate frame was abandoned and scanning restarted at the next SOR
+    pub resyncs: u64,
+    /// Times the buffer hit its cap and was flushed
+    pub buffer_overflows: u64,
+}
+
 /// Accumulates bytes from a TCP stream and yields complete parsed P3 messages.
 ///
 /// Handles escape-sequence-aware framing: the LENGTH field in the P3 header
 /// uses unescaped byte count while wire bytes may include escape sequences.
+///
+/// The framer never trusts a header blindly. A candidate frame is abandoned
+/// and scanning restarts at the next SOR when the version byte is wrong, the
+/// length is implausible, an invalid escape appears, a raw SOR/EOR shows up
+/// inside the frame, or the computed end is not an EOR. Junk before SOR is
+/// discarded and counted in [`FramerStats`].
 pub struct MessageFramer {
     buffer: Vec<u8>,
+    scratch: Vec<u8>,
     parser: Parser,
+    max_buffer: usize,
+    stats: FramerStats,
 }
 
 impl MessageFramer {
     pub fn new() -> Self {
+        Self::with_max_buffer(DEFAULT_MAX_BUFFER)
+    }
+
+    /// Create a framer that flushes its buffer once it exceeds `max_buffer` bytes
+    pub fn with_max_buffer(max_buffer: usize) -> Self {
         Self {
             buffer: Vec::with_capacity(4096),
+            scratch: Vec::with_capacity(256),
             parser: Parser::new(),
+            max_buffer,
+            stats: FramerStats::default(),
         }
     }
 
     /// Feed raw bytes and parse any complete frames now available.
     pub fn feed(&mut self, data: &[u8]) -> Vec<FrameResult> {
+        let mut results = Vec::new();
+        self.feed_with(data, |_, result| results.push(result));
+        results
+    }
+
+    /// Like [`feed`](Self::feed), but hands each complete frame's raw escaped
+    /// bytes to `on_frame` along with its parse result.
+    ///
+    /// Useful for tooling that needs the wire bytes of frames that failed to
+    /// parse, e.g. hex dumps.
+    pub fn feed_with(&mut self, data: &[u8], mut on_frame: impl FnMut(&[u8], FrameResult)) {
         self.buffer.extend_from_slice(data);
 
-        let mut results = Vec::new();
-        while let Some(message_end) = find_complete_message(&self.buffer) {
-            let message_data = &self.buffer[..message_end];
-            results.push(self.parser.parse(message_data));
-            self.buffer.drain(..message_end);
+        loop {
+            // Discard junk ahead of the next SOR
+            let skip = self
+                .buffer
+                .iter()
+                .position(|&b| b == SOR)
+                .unwrap_or(self.buffer.len());
+            if skip > 0 {
+                self.buffer.drain(..skip);
+                self.stats.bytes_skipped += skip as u64;
+            }
+            if self.buffer.is_empty() {
+                break;
+            }
+
+            match scan_frame(&self.buffer) {
+                Scan::Complete(end) => {
+                    let result = self
+                        .parser
+                        .parse_with_scratch(&self.buffer[..end], &mut self.scratch);
+                    self.stats.frames += 1;
+                    if matches!(
+                        result,
+                        Err(ParseError::CrcError(CrcError::ValidationFailed { .. }))
+                    ) {
+                        self.stats.crc_failures += 1;
+                    }
+                    on_frame(&self.buffer[..end], result);
+                    self.buffer.drain(..end);
+                }
+                Scan::Incomplete => break,
+                Scan::Invalid => {
+                    // Drop this SOR; the next iteration rescans from the following one
+                    self.buffer.drain(..1);
+                    self.stats.bytes_skipped += 1;
+                    self.stats.resyncs += 1;
+                }
+            }
         }
-        results
+
+        if self.buffer.len() > self.max_buffer {
+            self.stats.bytes_skipped += self.buffer.len() as u64;
+            self.stats.buffer_overflows += 1;
+            self.buffer.clear();
+        }
+    }
+
+    /// Corruption and recovery counters accumulated so far
+    pub fn stats(&self) -> FramerStats {
+        self.stats
+    }
+
+    /// Number of bytes currently buffered waiting for a complete frame
+    pub fn buffered_len(&self) -> usize {
+        self.buffer.len()
     }
 }
 
@@ -42,44 +145,229 @@ impl Default for MessageFramer {
 }
 
 /// Calculate the escaped-buffer end position for a frame of `unescaped_length`.
+///
+/// Returns `None` unless a valid frame declaring that length starts at
+/// `start_pos`.
+#[deprecated(note = "use `MessageFramer`, which validates and resynchronizes frames")]
 pub fn calculate_escaped_message_end(
     buffer: &[u8],
     start_pos: usize,
     unescaped_length: usize,
 ) -> Option<usize> {
-    let mut buffer_pos = start_pos;
-    let mut unescaped_count = 0;
+    let candidate = buffer
+        .get(start_pos..)
+        .filter(|c| c.first() == Some(&SOR))?;
+    let Scan::Complete(end) = scan_frame(candidate) else {
+        return None;
+    };
+    // Each escape pair stands for one unescaped byte
+    let escapes = candidate[..end].iter().filter(|&&b| b == ESCAPE).count();
+    (end - escapes == unescaped_length).then_some(start_pos + end)
+}
 
-    while unescaped_count < unescaped_length {
-        if buffer_pos >= buffer.len() {
-            return None;
-        }
+/// Find the end byte position of the next complete message in `buffer`.
+#[deprecated(note = "use `MessageFramer`, which validates and resynchronizes frames")]
+pub fn find_complete_message(buffer: &[u8]) -> Option<usize> {
+    let sor_pos = buffer.iter().position(|&b| b == SOR)?;
+    match scan_frame(&buffer[sor_pos..]) {
+        Scan::Complete(end) => Some(sor_pos + end),
+        Scan::Incomplete | Scan::Invalid => None,
+    }
+}
+
+/// Outcome of scanning a candidate frame that starts with SOR
+#[derive(Debug, PartialEq, Eq)]
+enum Scan {
+    /// Structurally valid frame ending at this (exclusive) buffer position
+    Complete(usize),
+    /// Consistent so far, but more bytes are needed
+    Incomplete,
+    /// Not a frame; resynchronize from the next SOR
+    Invalid,
+}
+
+/// Walk a candidate frame at `buffer[0] == SOR`, unescaping on the fly.
+///
+/// SOR and EOR are always escaped inside a frame, so a raw SOR or EOR
+/// anywhere but the final position proves the header lied about the length.
+fn scan_frame(buffer: &[u8]) -> Scan {
+    debug_assert_eq!(buffer.first(), Some(&SOR));
 
-        if buffer[buffer_pos] == ESCAPE {
-            if buffer_pos + 1 >= buffer.len() {
-                return None;
+    let mut header = [0u8; OFFSET_LENGTH + 2];
+    let mut length: Option<usize> = None;
+    let mut pos = 1;
+    let mut count = 1;
+
+    while length != Some(count) {
+        let Some(&byte) = buffer.get(pos) else {
+            return Scan::Incomplete;
+        };
+
+        let (value, width) = match byte {
+            ESCAPE => match buffer.get(pos + 1) {
+                None => return Scan::Incomplete,
+                Some(&next) if (0xAA..=0xAF).contains(&next) => (next - ESCAPE_OFFSET, 2),
+                Some(_) => return Scan::Invalid,
+            },
+            EOR if length == Some(count + 1) => (byte, 1),
+            SOR | EOR => return Scan::Invalid,
+            _ => (byte, 1),
+        };
+
+        if count < header.len() {
+            header[count] = value;
+            if count == OFFSET_VERSION && value != VERSION {
+                return Scan::Invalid;
+            }
+            if count == OFFSET_LENGTH + 1 {
+                let declared =
+                    u16::from_le_bytes([header[OFFSET_LENGTH], header[OFFSET_LENGTH + 1]]) as usize;
+                if !(MIN_FRAME_SIZE..=MAX_FRAME_LENGTH).contains(&declared) {
+                    return Scan::Invalid;
+                }
+                length = Some(declared);
             }
-            buffer_pos += 2;
-            unescaped_count += 1;
-        } else {
-            buffer_pos += 1;
-            unescaped_count += 1;
         }
+
+        pos += width;
+        count += 1;
     }
 
-    Some(buffer_pos)
+    // The loop only admits a raw EOR as the final byte; anything else means the end is wrong
+    if buffer[pos - 1] == EOR {
+        Scan::Complete(pos)
+    } else {
+        Scan::Invalid
+    }
 }
 
-/// Find the end byte position of the next complete message in `buffer`.
-pub fn find_complete_message(buffer: &[u8]) -> Option<usize> {
-    let sor_pos = buffer.iter().position(|&b| b == SOR)?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use p3_protocol::{MessageType, build_frame};
 
-    if buffer.len() < sor_pos + 4 {
-        return None;
+    fn status_frame() -> Vec<u8> {
+        // NOISE, GPS_STATUS, TEMPERATURE, SATINUSE
+        build_frame(
+            MessageType::Status,
+            &[
+                0x01, 0x02, 0x35, 0x00, 0x06, 0x01, 0x01, 0x07, 0x02, 0x10, 0x00, 0x0A, 0x01, 0x00,
+            ],
+        )
+    }
+
+    #[test]
+    fn test_scan_complete_frame() {
+        let frame = status_frame();
+        assert_eq!(scan_frame(&frame), Scan::Complete(frame.len()));
+        assert_eq!(scan_frame(&frame[..frame.len() - 1]), Scan::Incomplete);
+    }
+
+    #[test]
+    fn test_scan_rejects_wrong_version() {
+        let mut frame = status_frame();
+        frame[1] = 0x03;
+        assert_eq!(scan_frame(&frame), Scan::Invalid);
+    }
+
+    #[test]
+    fn test_scan_rejects_implausible_length() {
+        let mut frame = status_frame();
+        frame[2] = 0xFF;
+        frame[3] = 0x7F;
+        assert_eq!(scan_frame(&frame), Scan::Invalid);
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_deprecated_frame_end_helpers() {
+        let frame = status_frame();
+        let mut stream = vec![0x00, 0x11];
+        stream.extend(&frame);
+        let end = stream.len();
+
+        assert_eq!(find_complete_message(&stream), Some(end));
+        assert_eq!(find_complete_message(&stream[..end - 1]), None);
+        let length = u16::from_le_bytes([frame[2], frame[3]]) as usize;
+        assert_eq!(calculate_escaped_message_end(&stream, 2, length), Some(end));
+        assert_eq!(calculate_escaped_message_end(&stream, 0, length), None);
     }
 
-    let len_bytes = [buffer[sor_pos + 2], buffer[sor_pos + 3]];
-    let unescaped_length = u16::from_le_bytes(len_bytes) as usize;
+    #[test]
+    fn test_junk_before_sor_is_skipped() {
+        let mut stream = vec![0x00, 0x11, 0x8F, 0x22];
+        stream.extend(status_frame());
 
-    calculate_escaped_message_end(buffer, sor_pos, unescaped_length)
+        let mut framer = MessageFramer::new();
+        let results = framer.feed(&stream);
+
+        assert_eq!(results.len(), 1);
+        assert!(results[0].is_ok());
+        assert_eq!(framer.stats().bytes_skipped, 4);
+        assert_eq!(framer.stats().resyncs, 0);
+    }
+
+    #[test]
+    fn test_oversized_length_does_not_swallow_next_frame() {
+        let mut bad = status_frame();
+        // Claim a longer (but plausible) frame than is actually present
+        bad[2] = 0x40;
+        let mut stream = bad;
+        stream.extend(status_frame());
+
+        let mut framer = MessageFramer::new();
+        let results = framer.feed(&stream);
+
+        assert_eq!(results.len(), 1);
+        assert!(results[0].is_ok());
+        assert_eq!(framer.stats().resyncs, 1);
+        assert_eq!(framer.buffered_len(), 0);
+    }
+
+    #[test]
+    fn test_crc_failure_is_counted() {
+        let mut frame = status_frame();
+        let last_body = frame.len() - 2;
+        frame[last_body] ^= 0x01;
+
+        let mut framer = MessageFramer::new();
+        let results = framer.feed(&frame);
+
+        assert_eq!(results.len(), 1);
+        assert!(results[0].is_err());
+        assert_eq!(framer.stats().crc_failures, 1);
+    }
+
+    #[test]
+    fn test_feed_with_passes_raw_frame() {
+        let mut frame = status_frame();
+        let last_body = frame.len() - 2;
+        frame[last_body] ^= 0x01;
+        let mut stream = vec![0x00];
+        stream.extend(&frame);
+
+        let mut seen = Vec::new();
+        MessageFramer::new().feed_with(&stream, |raw, result| {
+            seen.push((raw.to_vec(), result.is_ok()));
+        });
+
+        assert_eq!(seen, vec![(frame, false)]);
+    }
+
+    #[test]
+    fn test_buffer_is_capped() {
+        let frame = status_frame();
+        let mut framer = MessageFramer::with_max_buffer(8);
+
+        // A partial frame larger than the cap is flushed
+        assert!(framer.feed(&frame[..12]).is_empty());
+        assert_eq!(framer.buffered_len(), 0);
+        assert_eq!(framer.stats().buffer_overflows, 1);
+
+        // The tail is junk without SOR; the next whole frame still parses
+        framer.feed(&frame[12..]);
+        let results = framer.feed(&frame);
+        assert_eq!(results.len(), 1);
+        assert!(results[0].is_ok());
+    }
 }
@@ -1,4 +1,4 @@
-use crate::error::{ParseError, ParseResult};
+use crate::error::{EncodeError, EncodeResult, ParseError, ParseResult};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct TlvField {
@@ -6,6 +6,90 @@ pub struct TlvField {
     pub value: Vec<u8>,
 }
 
+impl TlvField {
+    /// Borrow this field as a [`TlvFieldRef`]
+    pub fn as_field_ref(&self) -> TlvFieldRef<'_> {
+        TlvFieldRef {
+            tag: self.tag,
+            value: &self.value,
+        }
+    }
+}
+
+/// A TLV field borrowing its value from the message body
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TlvFieldRef<'a> {
+    pub tag: u8,
+    pub value: &'a [u8],
+}
+
+impl TlvFieldRef<'_> {
+    /// Copy into an owned [`TlvField`]
+    pub fn to_field(self) -> TlvField {
+        TlvField {
+            tag: self.tag,
+            value: self.value.to_vec(),
+        }
+    }
+}
+
+/// Iterator over the TLV fields of a message body
+///
+/// Yields an error and then stops if a field is truncated.
+#[derive(Debug, Clone)]
+pub struct TlvIter<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> TlvIter<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+}
+
+impl<'a> Iterator for TlvIter<'a> {
+    type Item = ParseResult<TlvFieldRef<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let data = self.data;
+        let pos = self.pos;
+        if pos >= data.len() {
+            return None;
+        }
+
+        // Need at least 2 bytes for tag and length
+        if pos + 2 > data.len() {
+            self.pos = data.len();
+            return Some(Err(ParseError::TlvError(format!(
+                "Incomplete TLV field at position {}",
+                pos
+            ))));
+        }
+
+        let tag = data[pos];
+        let length = data[pos + 1] as usize;
+        let value_start = pos + 2;
+
+        // Check if we have enough bytes for the value
+        if value_start + length > data.len() {
+            self.pos = data.len();
+            return Some(Err(ParseError::TlvError(format!(
+                "Incomplete TLV value for tag 0x{:02X}: expected {} bytes, got {}",
+                tag,
+                length,
+                data.len() - value_start
+            ))));
+        }
+
+        self.pos = value_start + length;
+        Some(Ok(TlvFieldRef {
+            tag,
+            value: &data[value_start..self.pos],
+        }))
+    }
+}
+
 pub struct TlvDecoder {
     // TODO: Add state management if needed
 }
@@ -20,40 +104,14 @@ impl TlvDecoder {
     /// Parses Tag-Length-Value fields from the message body.
     /// Each field: [Tag: 1 byte][Length: 1 byte][Value: Length bytes]
     pub fn decode(&self, data: &[u8]) -> ParseResult<Vec<TlvField>> {
-        let mut fields = Vec::new();
-        let mut pos = 0;
-
-        while pos < data.len() {
-            // Need at least 2 bytes for tag and length
-            if pos + 2 > data.len() {
-                return Err(ParseError::TlvError(format!(
-                    "Incomplete TLV field at position {}",
-                    pos
-                )));
-            }
-
-            let tag = data[pos];
-            let length = data[pos + 1] as usize;
-            pos += 2;
-
-            // Check if we have enough bytes for the value
-            if pos + length > data.len() {
-                return Err(ParseError::TlvError(format!(
-                    "Incomplete TLV value for tag 0x{:02X}: expected {} bytes, got {}",
-                    tag,
-                    length,
-                    data.len() - pos
-                )));
-            }
-
-            // Extract value bytes
-            let value = data[pos..pos + length].to_vec();
-            pos += length;
-
-            fields.push(TlvField { tag, value });
-        }
+        TlvIter::new(data)
+            .map(|field| field.map(TlvFieldRef::to_field))
+            .collect()
+    }
 
-        Ok(fields)
+    /// Iterate TLV fields from data without copying values
+    pub fn iter<'a>(&self, data: &'a [u8]) -> TlvIter<'a> {
+        TlvIter::new(data)
     }
 
     pub fn decode_u32(bytes: &[u8]) -> Option<u32> {
@@ -75,6 +133,143 @@ impl Default for TlvDecoder {
     }
 }
 
+/// Maximum TLV value length (the length field is a single byte)
+pub const MAX_TLV_VALUE_LEN: usize = 255;
+
+/// Encode a u8 value as TLV.
+///
+/// Format: [tag: 1 byte][length: 1][value: 1 byte]
+pub fn encode_u8(tag: u8, value: u8) -> Vec<u8> {
+    vec![tag, 1, value]
+}
+
+/// Encode a u16 value as TLV in little-endian format.
+///
+/// Format: [tag: 1 byte][length: 2][value: 2 bytes LE]
+pub fn encode_u16(tag: u8, value: u16) -> Vec<u8> {
+    let mut result = vec![tag, 2];
+    result.extend_from_slice(&value.to_le_bytes());
+    result
+}
+
+/// Encode an i16 value as TLV in little-endian format.
+///
+/// Format: [tag: 1 byte][length: 2][value: 2 bytes LE]
+pub fn encode_i16(tag: u8, value: i16) -> Vec<u8> {
+    let mut result = vec![tag, 2];
+    result.extend_from_slice(&value.to_le_bytes());
+    result
+}
+
+/// Encode a u32 value as TLV in little-endian format.
+///
+/// Format: [tag: 1 byte][length: 4][value: 4 bytes LE]
+pub fn encode_u32(tag: u8, value: u32) -> Vec<u8> {
+    let mut result = vec![tag, 4];
+    result.extend_from_slice(&value.to_le_bytes());
+    result
+}
+
+/// Encode a u64 value as TLV in little-endian format.
+///
+/// Format: [tag: 1 byte][length: 8][value: 8 bytes LE]
+pub fn encode_u64(tag: u8, value: u64) -> Vec<u8> {
+    let mut result = vec![tag, 8];
+    result.extend_from_slice(&value.to_le_bytes());
+    result
+}
+
+/// Encode a byte slice as TLV.
+///
+/// Format: [tag: 1 byte][length: N][value: N bytes]
+///
+/// # Errors
+/// Returns `EncodeError::ValueTooLong` if the byte slice length exceeds 255 bytes.
+pub fn encode_bytes(tag: u8, value: &[u8]) -> EncodeResult<Vec<u8>> {
+    if value.len() > MAX_TLV_VALUE_LEN {
+        return Err(EncodeError::ValueTooLong {
+            actual: value.len(),
+            max: MAX_TLV_VALUE_LEN,
+        });
+    }
+    let mut result = vec![tag, value.len() as u8];
+    result.extend_from_slice(value);
+    Ok(result)
+}
+
+/// Builder for constructing TLV-encoded message bodies.
+///
+/// The inverse of [`TlvDecoder`]; provides a fluent API for chaining
+/// multiple field additions.
+///
+/// # Example
+/// ```
+/// use p3_parser::tlv::TlvEncoder;
+///
+/// let body = TlvEncoder::new()
+///     .add_u32(0x01, 8841)           // PASSING_NUMBER
+///     .add_u32(0x03, 102758186)      // TRANSPONDER
+///     .add_u16(0x05, 127)            // STRENGTH
+///     .add_u16(0x06, 33)             // HITS
+///     .build();
+/// ```
+#[derive(Debug, Default)]
+pub struct TlvEncoder {
+    data: Vec<u8>,
+}
+
+impl TlvEncoder {
+    /// Create a new empty TLV encoder.
+    pub fn new() -> Self {
+        Self { data: Vec::new() }
+    }
+
+    /// Add a u8 field.
+    pub fn add_u8(mut self, tag: u8, value: u8) -> Self {
+        self.data.extend_from_slice(&encode_u8(tag, value));
+        self
+    }
+
+    /// Add a u16 field (little-endian).
+    pub fn add_u16(mut self, tag: u8, value: u16) -> Self {
+        self.data.extend_from_slice(&encode_u16(tag, value));
+        self
+    }
+
+    /// Add an i16 field (little-endian).
+    pub fn add_i16(mut self, tag: u8, value: i16) -> Self {
+        self.data.extend_from_slice(&encode_i16(tag, value));
+        self
+    }
+
+    /// Add a u32 field (little-endian).
+    pub fn add_u32(mut self, tag: u8, value: u32) -> Self {
+        self.data.extend_from_slice(&encode_u32(tag, value));
+        self
+    }
+
+    /// Add a u64 field (little-endian).
+    pub fn add_u64(mut self, tag: u8, value: u64) -> Self {
+        self.data.extend_from_slice(&encode_u64(tag, value));
+        self
+    }
+
+    /// Add a byte slice field.
+    ///
+    /// # Errors
+    /// Returns `EncodeError::ValueTooLong` if the byte slice length exceeds 255 bytes.
+    pub fn add_bytes(mut self, tag: u8, value: &[u8]) -> EncodeResult<Self> {
+        let encoded = encode_bytes(tag, value)?;
+        self.data.extend_from_slice(&encoded);
+        Ok(self)
+    }
+
+    /// Build and return the complete TLV-encoded body.
+    pub fn build(self) -> Vec<u8> {
+        self.data
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -92,8 +287,168 @@ mod tests {
     }
 
     #[test]
-    #[ignore]
     fn test_decode_tlv() {
-        // TODO: Add tests
+        let data = [0x01, 0x02, 0xAA, 0xBB, 0x02, 0x00, 0x03, 0x01, 0xCC];
+        let fields = TlvDecoder::new().decode(&data).unwrap();
+
+        assert_eq!(
+            fields,
+            vec![
+                TlvField {
+                    tag: 0x01,
+                    value: vec![0xAA, 0xBB]
+                },
+                TlvField {
+                    tag: 0x02,
+                    value: vec![]
+                },
+                TlvField {
+                    tag: 0x03,
+                    value: vec![0xCC]
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_iter_borrows_values() {
+        let data = [0x01, 0x02, 0xAA, 0xBB];
+        let field = TlvIter::new(&data).next().unwrap().unwrap();
+
+        assert_eq!(field.tag, 0x01);
+        assert!(std::ptr::eq(field.value.as_ptr(), data[2..].as_ptr()));
+    }
+
+    #[test]
+    fn test_iter_stops_after_truncated_value() {
+        let data = [0x01, 0x01, 0xAA, 0x02, 0x04, 0x00];
+        let mut iter = TlvIter::new(&data);
+
+        assert!(iter.next().unwrap().is_ok());
+        assert!(iter.next().unwrap().is_err());
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn test_encode_u8() {
+        let result = encode_u8(0x06, 1);
+        assert_eq!(result, vec![0x06, 0x01, 0x01]);
+    }
+
+    #[test]
+    fn test_encode_u16() {
+        // Test little-endian encoding
+        let result = encode_u16(0x05, 0x7F00);
+        assert_eq!(result, vec![0x05, 0x02, 0x00, 0x7F]);
+    }
+
+    #[test]
+    fn test_encode_u16_simple() {
+        let result = encode_u16(0x05, 127);
+        assert_eq!(result, vec![0x05, 0x02, 0x7F, 0x00]);
+    }
+
+    #[test]
+    fn test_encode_i16_positive() {
+        let result = encode_i16(0x07, 16);
+        assert_eq!(result, vec![0x07, 0x02, 0x10, 0x00]);
+    }
+
+    #[test]
+    fn test_encode_i16_negative() {
+        let result = encode_i16(0x07, -10);
+        assert_eq!(result, vec![0x07, 0x02, 0xF6, 0xFF]);
+    }
+
+    #[test]
+    fn test_encode_u32() {
+        // Test with value from live capture: passing number 8841
+        let result = encode_u32(0x01, 8841);
+        assert_eq!(result, vec![0x01, 0x04, 0x89, 0x22, 0x00, 0x00]);
+    }
+
+    #[test]
+    fn test_encode_u32_transponder() {
+        // Test with transponder ID from live capture: 102758186
+        let result = encode_u32(0x03, 102758186);
+        assert_eq!(result, vec![0x03, 0x04, 0x2A, 0xF7, 0x1F, 0x06]);
+    }
+
+    #[test]
+    fn test_encode_u64() {
+        // Test with timestamp from live capture
+        let result = encode_u64(0x04, 0x0006426530063546);
+        assert_eq!(
+            result,
+            vec![0x04, 0x08, 0x46, 0x35, 0x06, 0x30, 0x65, 0x42, 0x06, 0x00]
+        );
+    }
+
+    #[test]
+    fn test_encode_bytes() {
+        let string_data = b"FL-94890";
+        let result = encode_bytes(0x0A, string_data).unwrap();
+        assert_eq!(
+            result,
+            vec![0x0A, 0x08, b'F', b'L', b'-', b'9', b'4', b'8', b'9', b'0']
+        );
+    }
+
+    #[test]
+    fn test_encode_bytes_too_long() {
+        let long_data = vec![0u8; 256];
+        let result = encode_bytes(0x01, &long_data);
+        assert!(matches!(
+            result,
+            Err(EncodeError::ValueTooLong {
+                actual: 256,
+                max: 255
+            })
+        ));
+    }
+
+    #[test]
+    fn test_encoder_add_bytes_too_long() {
+        let long_data = vec![0u8; 256];
+        let result = TlvEncoder::new().add_bytes(0x01, &long_data);
+        assert!(matches!(
+            result,
+            Err(EncodeError::ValueTooLong {
+                actual: 256,
+                max: 255
+            })
+        ));
+    }
+
+    #[test]
+    fn test_encoder_chain() {
+        let result = TlvEncoder::new()
+            .add_u32(0x01, 8841)
+            .add_u32(0x03, 102758186)
+            .add_u16(0x05, 127)
+            .add_u16(0x06, 33)
+            .build();
+
+        let expected = vec![
+            0x01, 0x04, 0x89, 0x22, 0x00, 0x00, // PASSING_NUMBER
+            0x03, 0x04, 0x2A, 0xF7, 0x1F, 0x06, // TRANSPONDER
+            0x05, 0x02, 0x7F, 0x00, // STRENGTH
+            0x06, 0x02, 0x21, 0x00, // HITS
+        ];
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_encoder_decoder_roundtrip() {
+        let body = TlvEncoder::new()
+            .add_u32(0x01, 8841)
+            .add_bytes(0x0A, b"FL-94890")
+            .unwrap()
+            .build();
+
+        let fields = TlvDecoder::new().decode(&body).unwrap();
+        assert_eq!(fields.len(), 2);
+        assert_eq!(TlvDecoder::decode_u32(&fields[0].value), Some(8841));
+        assert_eq!(fields[1].value, b"FL-94890");
     }
 }
@@ -0,0 +1,23 @@
+//! Live decoder captures shared by the tests, benches and CLI tests.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Every frame under `tests/fixtures/live_capture`, with its path, in file
+/// name order.
+pub fn live_captures() -> Vec<(PathBuf, Vec<u8>)> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../tests/fixtures/live_capture");
+    let mut paths: Vec<_> = fs::read_dir(&dir)
+        .expect("live capture fixtures")
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "bin"))
+        .collect();
+    paths.sort();
+    paths
+        .into_iter()
+        .map(|path| {
+            let data = fs::read(&path).unwrap();
+            (path, data)
+        })
+        .collect()
+}
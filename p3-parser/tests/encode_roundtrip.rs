@@ -0,0 +1,203 @@
+//! Round-trip property tests: `Parser::parse(encode(m)) == m` for every message type,
+//! including preserved extra fields and unknown TORs.
+//!
+//! Also checks that re-encoding the live decoder captures in
+//! `tests/fixtures/live_capture` reproduces the original bytes exactly.
+
+use p3_parser::{
+    Encoder, Message, Parser, PassingMessage, ResendMessage, StatusMessage, VersionMessage,
+};
+use p3_protocol::fields::{passing, resend, status, version};
+use proptest::prelude::*;
+
+mod common;
+
+fn decoder_id(bytes: usize) -> impl Strategy<Value = String> {
+    prop::collection::vec(any::<u8>(), bytes)
+        .prop_map(|b| b.iter().map(|x| format!("{:02X}", x)).collect())
+}
+
+/// Strings whose UTF-8 encoding fits in a single TLV value
+fn tlv_string() -> impl Strategy<Value = String> {
+    ".{0,60}".prop_filter("fits in TLV", |s: &String| s.len() <= 255)
+}
+
+/// Raw TLV fields with any tag, each value fitting in a single TLV
+fn raw_fields() -> impl Strategy<Value = Vec<(u8, Vec<u8>)>> {
+    prop::collection::vec(
+        (any::<u8>(), prop::collection::vec(any::<u8>(), 0..=255)),
+        0..4,
+    )
+}
+
+/// Fields whose tags the message type doesn't know, so the parser keeps them as extras
+fn extra_fields(known: &'static [u8]) -> impl Strategy<Value = Vec<(u8, Vec<u8>)>> {
+    raw_fields().prop_map(move |fields| {
+        fields
+            .into_iter()
+            .filter(|(tag, _)| !known.contains(tag))
+            .collect()
+    })
+}
+
+const PASSING_TAGS: &[u8] = &[
+    passing::PASSING_NUMBER,
+    passing::TRANSPONDER,
+    passing::RTC_TIME,
+    passing::STRENGTH,
+    passing::HITS,
+    passing::FLAGS,
+    passing::STRING,
+    passing::UTC_TIME,
+    passing::DECODER_ID,
+];
+const STATUS_TAGS: &[u8] = &[
+    status::NOISE,
+    status::GPS_STATUS,
+    status::TEMPERATURE,
+    status::SATINUSE,
+    status::DECODER_ID,
+];
+const VERSION_TAGS: &[u8] = &[
+    version::DECODER_ID,
+    version::DESCRIPTION,
+    version::VERSION,
+    version::BUILD,
+];
+const RESEND_TAGS: &[u8] = &[resend::FROM, resend::UNTIL];
+
+fn passing() -> impl Strategy<Value = PassingMessage> {
+    (
+        (
+            any::<u32>(),
+            any::<u32>(),
+            any::<u64>(),
+            any::<Option<u64>>(),
+        ),
+        (
+            any::<Option<u16>>(),
+            any::<Option<u16>>(),
+            prop::option::of(tlv_string()),
+        ),
+        (
+            any::<u16>(),
+            prop::option::of(decoder_id(4)),
+            extra_fields(PASSING_TAGS),
+        ),
+    )
+        .prop_map(
+            |(
+                (passing_number, transponder_id, rtc_time_us, utc_time_us),
+                (strength, hits, transponder_string),
+                (flags, decoder_id, extra_fields),
+            )| PassingMessage {
+                passing_number,
+                transponder_id,
+                rtc_time_us,
+                utc_time_us,
+                strength,
+                hits,
+                transponder_string,
+                flags,
+                decoder_id,
+                extra_fields,
+            },
+        )
+}
+
+fn status() -> impl Strategy<Value = StatusMessage> {
+    (
+        any::<u16>(),
+        any::<u8>(),
+        any::<i16>(),
+        any::<u8>(),
+        prop::option::of(decoder_id(4)),
+        extra_fields(STATUS_TAGS),
+    )
+        .prop_map(
+            |(noise, gps_status, temperature, satellites, decoder_id, extra_fields)| {
+                StatusMessage {
+                    noise,
+                    gps_status,
+                    temperature,
+                    satellites,
+                    decoder_id,
+                    extra_fields,
+                }
+            },
+        )
+}
+
+fn version() -> impl Strategy<Value = VersionMessage> {
+    (
+        decoder_id(8),
+        tlv_string(),
+        tlv_string(),
+        any::<Option<u16>>(),
+        extra_fields(VERSION_TAGS),
+    )
+        .prop_map(
+            |(decoder_id, description, version, build, extra_fields)| VersionMessage {
+                decoder_id,
+                description,
+                version,
+                build,
+                extra_fields,
+            },
+        )
+}
+
+fn resend() -> impl Strategy<Value = ResendMessage> {
+    (any::<u32>(), any::<u32>(), extra_fields(RESEND_TAGS)).prop_map(
+        |(from_passing, until_passing, extra_fields)| ResendMessage {
+            from_passing,
+            until_passing,
+            extra_fields,
+        },
+    )
+}
+
+/// Any TOR other than the four message types the parser understands
+fn unknown() -> impl Strategy<Value = Message> {
+    (
+        any::<u16>().prop_filter("unknown TOR", |tor| !(1..=4).contains(tor)),
+        raw_fields(),
+    )
+        .prop_map(|(tor, fields)| Message::Unknown { tor, fields })
+}
+
+fn message() -> impl Strategy<Value = Message> {
+    prop_oneof![
+        passing().prop_map(Message::Passing),
+        status().prop_map(Message::Status),
+        version().prop_map(Message::Version),
+        resend().prop_map(Message::Resend),
+        unknown(),
+    ]
+}
+
+proptest! {
+    #[test]
+    fn encode_then_parse_is_identity(message in message()) {
+        let wire = Encoder::new().encode(&message).unwrap();
+        prop_assert_eq!(Parser::new().parse(&wire).unwrap(), message);
+    }
+
+    #[test]
+    fn to_wire_matches_encoder(message in message()) {
+        prop_assert_eq!(message.to_wire().unwrap(), Encoder::new().encode(&message).unwrap());
+    }
+}
+
+#[test]
+fn live_captures_reencode_byte_for_byte() {
+    let parser = Parser::new();
+    let captures = common::live_captures();
+
+    for (path, original) in &captures {
+        let message = parser.parse(original).unwrap();
+        assert_eq!(&message.to_wire().unwrap(), original, "{}", path.display());
+    }
+
+    assert!(!captures.is_empty());
+}
@@ -0,0 +1,181 @@
+//! Fuzz-style property tests for `MessageFramer` resynchronization.
+//!
+//! Streams are built from the live decoder captures in
+//! `tests/fixtures/live_capture`, then chunked, spliced with junk, corrupted
+//! or truncated. Whatever happens to one frame, every intact frame around it
+//! must still come out of the framer.
+
+use p3_parser::stream::MessageFramer;
+use p3_parser::{Message, Parser};
+use proptest::prelude::*;
+use std::sync::OnceLock;
+
+mod common;
+
+fn fixtures() -> &'static [Vec<u8>] {
+    static FIXTURES: OnceLock<Vec<Vec<u8>>> = OnceLock::new();
+    FIXTURES.get_or_init(|| {
+        common::live_captures()
+            .into_iter()
+            .map(|(_, data)| data)
+            .collect()
+    })
+}
+
+fn expected(frames: &[usize]) -> Vec<Message> {
+    let parser = Parser::new();
+    frames
+        .iter()
+        .map(|&i| parser.parse(&fixtures()[i]).unwrap())
+        .collect()
+}
+
+/// Feed `stream` in the given chunk sizes (cycled) and collect successfully parsed messages
+fn feed_chunked(framer: &mut MessageFramer, stream: &[u8], chunks: &[usize]) -> Vec<Message> {
+    let mut out = Vec::new();
+    let mut pos = 0;
+    for &size in chunks.iter().cycle() {
+        if pos >= stream.len() {
+            break;
+        }
+        let end = (pos + size).min(stream.len());
+        out.extend(
+            framer
+                .feed(&stream[pos..end])
+                .into_iter()
+                .filter_map(Result::ok),
+        );
+        pos = end;
+    }
+    out
+}
+
+fn frame_indices() -> impl Strategy<Value = Vec<usize>> {
+    prop::collection::vec(0..fixtures().len(), 1..12)
+}
+
+fn chunk_sizes() -> impl Strategy<Value = Vec<usize>> {
+    prop::collection::vec(1usize..64, 1..8)
+}
+
+proptest! {
+    #[test]
+    fn clean_stream_parses_regardless_of_chunking(
+        frames in frame_indices(),
+        chunks in chunk_sizes(),
+    ) {
+        let stream: Vec<u8> = frames.iter().flat_map(|&i| fixtures()[i].clone()).collect();
+
+        let mut framer = MessageFramer::new();
+        let parsed = feed_chunked(&mut framer, &stream, &chunks);
+
+        prop_assert_eq!(parsed, expected(&frames));
+        let stats = framer.stats();
+        prop_assert_eq!(stats.bytes_skipped, 0);
+        prop_assert_eq!(stats.resyncs, 0);
+        prop_assert_eq!(stats.crc_failures, 0);
+    }
+
+    #[test]
+    fn junk_between_frames_is_skipped(
+        frames in frame_indices(),
+        junk in prop::collection::vec(prop::collection::vec(any::<u8>(), 0..40), 12),
+        chunks in chunk_sizes(),
+    ) {
+        let mut stream = Vec::new();
+        for (n, &i) in frames.iter().enumerate() {
+            stream.extend_from_slice(&junk[n]);
+            stream.extend_from_slice(&fixtures()[i]);
+        }
+
+        let mut framer = MessageFramer::new();
+        let parsed = feed_chunked(&mut framer, &stream, &chunks);
+
+        prop_assert_eq!(parsed, expected(&frames));
+    }
+
+    #[test]
+    fn corrupted_frame_does_not_affect_neighbours(
+        frames in frame_indices(),
+        victim_seed in any::<prop::sample::Index>(),
+        byte_seed in any::<prop::sample::Index>(),
+        flip in 1u8..=255,
+        chunks in chunk_sizes(),
+    ) {
+        let victim = victim_seed.index(frames.len());
+        let mut stream = Vec::new();
+        for (n, &i) in frames.iter().enumerate() {
+            let mut frame = fixtures()[i].clone();
+            if n == victim {
+                let at = byte_seed.index(frame.len());
+                frame[at] ^= flip;
+            }
+            stream.extend(frame);
+        }
+
+        let mut framer = MessageFramer::new();
+        let parsed = feed_chunked(&mut framer, &stream, &chunks);
+
+        let survivors: Vec<usize> = frames
+            .iter()
+            .enumerate()
+            .filter(|&(n, _)| n != victim)
+            .map(|(_, &i)| i)
+            .collect();
+        prop_assert_eq!(parsed, expected(&survivors));
+
+        let stats = framer.stats();
+        prop_assert!(stats.crc_failures + stats.resyncs + stats.bytes_skipped > 0);
+    }
+
+    #[test]
+    fn truncated_frame_does_not_swallow_next(
+        frames in frame_indices(),
+        victim_seed in any::<prop::sample::Index>(),
+        cut_seed in any::<prop::sample::Index>(),
+        chunks in chunk_sizes(),
+    ) {
+        let victim = victim_seed.index(frames.len());
+        let mut stream = Vec::new();
+        for (n, &i) in frames.iter().enumerate() {
+            let frame = &fixtures()[i];
+            if n == victim {
+                // Keep at least SOR, drop at least EOR
+                let cut = 1 + cut_seed.index(frame.len() - 1);
+                stream.extend_from_slice(&frame[..cut]);
+            } else {
+                stream.extend_from_slice(frame);
+            }
+        }
+
+        let mut framer = MessageFramer::new();
+        let parsed = feed_chunked(&mut framer, &stream, &chunks);
+
+        let survivors: Vec<usize> = frames
+            .iter()
+            .enumerate()
+            .filter(|&(n, _)| n != victim)
+            .map(|(_, &i)| i)
+            .collect();
+        prop_assert_eq!(parsed, expected(&survivors));
+    }
+
+    #[test]
+    fn arbitrary_bytes_never_exceed_buffer_cap(
+        data in prop::collection::vec(any::<u8>(), 0..4096),
+        chunks in chunk_sizes(),
+    ) {
+        let cap = 256;
+        let mut framer = MessageFramer::with_max_buffer(cap);
+        let mut pos = 0;
+        for &size in chunks.iter().cycle() {
+            if pos >= data.len() {
+                break;
+            }
+            let end = (pos + size).min(data.len());
+            framer.feed(&data[pos..end]);
+            prop_assert!(framer.buffered_len() <= cap);
+            pos = end;
+        }
+    }
+}
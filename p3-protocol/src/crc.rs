@@ -182,7 +182,7 @@ fn unescape_message(escaped: &[u8]) -> Result<Vec<u8>, CrcError> {
 
             let next_byte = escaped[i + 1];
             // Valid escape: next byte should be in range 0xAA-0xAF (0x8A-0x8F + 0x20)
-            if next_byte >= 0xAA && next_byte <= 0xAF {
+            if (0xAA..=0xAF).contains(&next_byte) {
                 unescaped.push(next_byte.wrapping_sub(ESCAPE_OFFSET));
                 i += 2;
                 continue;
@@ -288,7 +288,7 @@ mod tests {
             0x8E, 0x02, 0x1F, 0x00, 0xFF, 0xFF, // Wrong CRC
             0x00, 0x00, 0x02, 0x00, 0x8F,
         ];
-        assert_eq!(validate_crc(&bad_crc).unwrap(), false);
+        assert!(!validate_crc(&bad_crc).unwrap());
     }
 
     #[test]
@@ -30,7 +30,7 @@ pub struct EscapeInfo {
 /// Bytes in range 0x8A-0x8F (inclusive) must be escaped.
 #[inline]
 pub fn needs_escape(byte: u8) -> bool {
-    byte >= ESCAPE_RANGE_START && byte <= ESCAPE_RANGE_END
+    (ESCAPE_RANGE_START..=ESCAPE_RANGE_END).contains(&byte)
 }
 
 /// Escapes a single byte
@@ -324,6 +324,25 @@ pub fn escape_data(data: &[u8]) -> Vec<u8> {
 /// ```
 pub fn unescape_data(data: &[u8]) -> Result<Vec<u8>, EscapeError> {
     let mut unescaped = Vec::with_capacity(data.len());
+    unescape_into(data, &mut unescaped)?;
+    Ok(unescaped)
+}
+
+/// Decodes data into a caller-provided buffer, reusing its allocation
+///
+/// `out` is cleared before writing. Validation is identical to [`unescape_data`].
+///
+/// # Example
+/// ```
+/// use p3_protocol::escape::unescape_into;
+///
+/// let mut scratch = Vec::new();
+/// unescape_into(&[0x01, 0x8D, 0xAF, 0x02], &mut scratch).unwrap();
+/// assert_eq!(scratch, vec![0x01, 0x8F, 0x02]);
+/// ```
+pub fn unescape_into(data: &[u8], out: &mut Vec<u8>) -> Result<(), EscapeError> {
+    out.clear();
+    out.reserve(data.len());
     let mut i = 0;
 
     while i < data.len() {
@@ -336,19 +355,19 @@ pub fn unescape_data(data: &[u8]) -> Result<Vec<u8>, EscapeError> {
             let next_byte = data[i + 1];
 
             // Validate that the escaped byte is in valid range (0xAA-0xAF)
-            if next_byte < 0xAA || next_byte > 0xAF {
+            if !(0xAA..=0xAF).contains(&next_byte) {
                 return Err(EscapeError::InvalidSequence(next_byte));
             }
 
             // Unescape: subtract offset to get original byte
             let original_byte = next_byte.wrapping_sub(ESCAPE_OFFSET);
-            unescaped.push(original_byte);
+            out.push(original_byte);
             i += 2; // Skip both escape and escaped byte
         } else {
-            unescaped.push(data[i]);
+            out.push(data[i]);
             i += 1;
         }
     }
 
-    Ok(unescaped)
+    Ok(())
 }
@@ -1,7 +1,9 @@
-/// TLV (Tag-Length-Value) field tags used in P3 messages
-///
-/// Each message body contains multiple fields encoded as:
-/// [Tag: 1 byte][Length: 1 byte][Value: N bytes]
+//! TLV (Tag-Length-Value) field tags used in P3 messages
+//!
+//! Each message body contains multiple fields encoded as:
+//! [Tag: 1 byte][Length: 1 byte][Value: N bytes]
+
+use crate::MessageType;
 
 /// PASSING message field tags
 ///
@@ -93,6 +95,51 @@ pub mod version {
     pub const BUILD: u8 = 0x23;
 }
 
+/// RESEND message field tags
+///
+/// A RESEND record is sent *to* the decoder to request retransmission of a
+/// range of PASSING records (inclusive on both ends).
+///
+/// NOTE: Not validated against live capture. Based on community documentation.
+pub mod resend {
+    /// First passing number to retransmit (u32)
+    pub const FROM: u8 = 0x01;
+
+    /// Last passing number to retransmit, inclusive (u32)
+    pub const UNTIL: u8 = 0x02;
+}
+
+/// Name of a known field tag for the given message type, e.g. "TRANSPONDER"
+///
+/// Returns `None` for tags this library doesn't define. Useful for annotating
+/// raw frames when debugging.
+pub fn tag_name(message_type: MessageType, tag: u8) -> Option<&'static str> {
+    let name = match (message_type, tag) {
+        (MessageType::Passing, passing::PASSING_NUMBER) => "PASSING_NUMBER",
+        (MessageType::Passing, passing::TRANSPONDER) => "TRANSPONDER",
+        (MessageType::Passing, passing::RTC_TIME) => "RTC_TIME",
+        (MessageType::Passing, passing::STRENGTH) => "STRENGTH",
+        (MessageType::Passing, passing::HITS) => "HITS",
+        (MessageType::Passing, passing::FLAGS) => "FLAGS",
+        (MessageType::Passing, passing::STRING) => "STRING",
+        (MessageType::Passing, passing::UTC_TIME) => "UTC_TIME",
+        (MessageType::Passing, passing::DECODER_ID) => "DECODER_ID",
+        (MessageType::Status, status::NOISE) => "NOISE",
+        (MessageType::Status, status::GPS_STATUS) => "GPS_STATUS",
+        (MessageType::Status, status::TEMPERATURE) => "TEMPERATURE",
+        (MessageType::Status, status::SATINUSE) => "SATINUSE",
+        (MessageType::Status, status::DECODER_ID) => "DECODER_ID",
+        (MessageType::Version, version::DECODER_ID) => "DECODER_ID",
+        (MessageType::Version, version::DESCRIPTION) => "DESCRIPTION",
+        (MessageType::Version, version::VERSION) => "VERSION",
+        (MessageType::Version, version::BUILD) => "BUILD",
+        (MessageType::Resend, resend::FROM) => "FROM",
+        (MessageType::Resend, resend::UNTIL) => "UNTIL",
+        _ => return None,
+    };
+    Some(name)
+}
+
 /// DEPRECATED: Field tags from community documentation that DO NOT match real decoders
 ///
 /// **⚠️ WARNING: DO NOT USE THESE TAGS ⚠️**
@@ -167,4 +214,14 @@ mod tests {
         assert!(!reserved_ids::is_reserved(1234567));
         assert!(!reserved_ids::is_reserved(102758186)); // Rider transponder from live capture
     }
+
+    #[test]
+    fn test_tag_name_depends_on_message_type() {
+        assert_eq!(tag_name(MessageType::Passing, 0x01), Some("PASSING_NUMBER"));
+        assert_eq!(tag_name(MessageType::Status, 0x01), Some("NOISE"));
+        assert_eq!(tag_name(MessageType::Resend, 0x01), Some("FROM"));
+        assert_eq!(tag_name(MessageType::Passing, 0x81), Some("DECODER_ID"));
+        assert_eq!(tag_name(MessageType::Version, 0x81), None);
+        assert_eq!(tag_name(MessageType::Status, 0x15), None);
+    }
 }
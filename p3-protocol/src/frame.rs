@@ -0,0 +1,118 @@
+use crate::crc::calculate_crc;
+/// Frame Encoder for P3 Protocol
+///
+/// Wraps a pre-encoded TLV body into a complete, transmittable P3 frame:
+/// header, CRC and escape sequences are handled here so that every producer
+/// of P3 frames (decoder simulator, track client, re-emitters) shares one
+/// implementation.
+///
+/// Process:
+/// 1. Build the unescaped frame with a zeroed CRC field
+/// 2. Calculate the CRC over the unescaped frame (including SOR and EOR)
+/// 3. Store the CRC little-endian at bytes 4-5
+/// 4. Escape everything between SOR and EOR
+use crate::escape::encode;
+use crate::types::{EOR, HEADER_SIZE, MessageType, OFFSET_CRC, SOR, VERSION};
+
+/// Build a complete escaped P3 frame around a TLV body
+///
+/// The LENGTH field is the unescaped frame length (header + body + EOR).
+/// RESERVED is always written as 0x0000.
+///
+/// # Example
+/// ```
+/// use p3_protocol::{MessageType, build_frame, validate_crc, SOR, EOR};
+///
+/// // STATUS body with a single NOISE field
+/// let frame = build_frame(MessageType::Status, &[0x01, 0x02, 0x35, 0x00]);
+///
+/// assert_eq!(frame[0], SOR);
+/// assert_eq!(*frame.last().unwrap(), EOR);
+/// assert!(validate_crc(&frame).unwrap());
+/// ```
+pub fn build_frame(message_type: MessageType, body: &[u8]) -> Vec<u8> {
+    build_frame_with_tor(message_type.to_u16(), body)
+}
+
+/// Build a complete escaped P3 frame for a raw TOR value
+///
+/// Used to re-emit records whose type this crate doesn't model; prefer
+/// [`build_frame`] for known message types.
+pub fn build_frame_with_tor(tor: u16, body: &[u8]) -> Vec<u8> {
+    // +1 for EOR
+    let unescaped_length = (HEADER_SIZE + body.len() + 1) as u16;
+
+    let mut unescaped = Vec::with_capacity(HEADER_SIZE + body.len() + 1);
+    unescaped.push(SOR);
+    unescaped.push(VERSION);
+    unescaped.extend_from_slice(&unescaped_length.to_le_bytes()); // LENGTH
+    unescaped.extend_from_slice(&[0x00, 0x00]); // CRC placeholder
+    unescaped.extend_from_slice(&[0x00, 0x00]); // RESERVED
+    unescaped.extend_from_slice(&tor.to_le_bytes()); // TYPE
+    unescaped.extend_from_slice(body);
+    unescaped.push(EOR);
+
+    let crc = calculate_crc(&unescaped);
+    unescaped[OFFSET_CRC..OFFSET_CRC + 2].copy_from_slice(&crc.to_le_bytes());
+
+    // SOR and EOR are never escaped; everything in between is
+    let escaped_data = encode(&unescaped[1..unescaped.len() - 1]);
+
+    let mut frame = Vec::with_capacity(escaped_data.len() + 2);
+    frame.push(SOR);
+    frame.extend_from_slice(&escaped_data);
+    frame.push(EOR);
+    frame
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crc::validate_crc;
+    use crate::escape::unescape_data;
+    use crate::types::{OFFSET_LENGTH, OFFSET_TYPE};
+
+    #[test]
+    fn test_build_frame_empty_body() {
+        let frame = build_frame(MessageType::Status, &[]);
+
+        assert_eq!(unescape_data(&frame).unwrap().len(), HEADER_SIZE + 1);
+        assert_eq!(frame[0], SOR);
+        assert_eq!(frame[frame.len() - 1], EOR);
+        assert!(validate_crc(&frame).unwrap());
+    }
+
+    #[test]
+    fn test_build_frame_header_fields() {
+        let body = [0x01, 0x04, 0x01, 0x00, 0x00, 0x00];
+        let frame = build_frame(MessageType::Resend, &body);
+        let unescaped = unescape_data(&frame).unwrap();
+
+        let length = u16::from_le_bytes([unescaped[OFFSET_LENGTH], unescaped[OFFSET_LENGTH + 1]]);
+        assert_eq!(length as usize, unescaped.len());
+
+        let tor = u16::from_le_bytes([unescaped[OFFSET_TYPE], unescaped[OFFSET_TYPE + 1]]);
+        assert_eq!(tor, MessageType::Resend.to_u16());
+    }
+
+    #[test]
+    fn test_build_frame_with_unknown_tor() {
+        let frame = build_frame_with_tor(0x0045, &[0x01, 0x01, 0x00]);
+        let unescaped = unescape_data(&frame).unwrap();
+
+        let tor = u16::from_le_bytes([unescaped[OFFSET_TYPE], unescaped[OFFSET_TYPE + 1]]);
+        assert_eq!(tor, 0x0045);
+        assert!(validate_crc(&frame).unwrap());
+    }
+
+    #[test]
+    fn test_build_frame_escapes_control_bytes_in_body() {
+        let body = [0x04, 0x02, 0x8F, 0x8E];
+        let frame = build_frame(MessageType::Passing, &body);
+
+        // Only the outer SOR/EOR may appear unescaped
+        assert_eq!(frame.iter().filter(|&&b| b == SOR).count(), 1);
+        assert_eq!(frame.iter().filter(|&&b| b == EOR).count(), 1);
+        assert!(validate_crc(&frame).unwrap());
+    }
+}
@@ -9,11 +9,13 @@
 //! - **TLV field definitions** for all message types
 //! - **Escape/unescape functions** for control byte handling
 //! - **CRC calculation and validation** (exact decoder algorithm)
+//! - **Frame encoding** (header, CRC and escaping around a TLV body)
+//! - **RESEND request encoding** for passing gap recovery
 //!
 //! ## What This Library Does NOT Provide
 //!
 //! - Message parsing (see `p3-parser` crate)
-//! - Message generation (see `p3-test-server` crate)
+//! - Decoder message generation (see `p3-test-server` crate)
 //! - I/O operations (TCP/serial)
 //!
 //! This is a pure logic library with zero I/O dependencies.
@@ -40,12 +42,16 @@ pub mod crc;
 pub mod error;
 pub mod escape;
 pub mod fields;
+pub mod frame;
+pub mod resend;
 pub mod types;
 
 // Re-export commonly used items at crate root
 pub use crc::{calculate_crc, calculate_message_crc, validate_crc};
 pub use error::*;
 pub use escape::{
-    EscapeInfo, encode, escape_data, escaped_length, unescape_data, unescaped_length,
+    EscapeInfo, encode, escape_data, escaped_length, unescape_data, unescape_into, unescaped_length,
 };
+pub use frame::{build_frame, build_frame_with_tor};
+pub use resend::build_resend_request;
 pub use types::*;
@@ -0,0 +1,73 @@
+use crate::fields::resend;
+/// RESEND Request Encoder for P3 Protocol
+///
+/// Clients recover missed PASSING records by sending a RESEND (TOR 0x0004)
+/// frame to the decoder with the inclusive passing-number range to replay.
+/// The decoder answers by retransmitting the original PASSING frames.
+///
+/// Body layout:
+/// ```text
+/// [FROM: 0x01][4][u32 LE][UNTIL: 0x02][4][u32 LE]
+/// ```
+use crate::frame::build_frame;
+use crate::types::MessageType;
+
+/// Build a complete escaped RESEND request frame
+///
+/// # Arguments
+/// * `from_passing` - First passing number to retransmit
+/// * `until_passing` - Last passing number to retransmit (inclusive)
+///
+/// # Example
+/// ```
+/// use p3_protocol::{build_resend_request, validate_crc};
+///
+/// let frame = build_resend_request(8841, 8850);
+/// assert!(validate_crc(&frame).unwrap());
+/// ```
+pub fn build_resend_request(from_passing: u32, until_passing: u32) -> Vec<u8> {
+    let mut body = Vec::with_capacity(12);
+    body.extend_from_slice(&[resend::FROM, 4]);
+    body.extend_from_slice(&from_passing.to_le_bytes());
+    body.extend_from_slice(&[resend::UNTIL, 4]);
+    body.extend_from_slice(&until_passing.to_le_bytes());
+
+    build_frame(MessageType::Resend, &body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crc::validate_crc;
+    use crate::escape::unescape_data;
+    use crate::types::{EOR, OFFSET_BODY, OFFSET_TYPE, SOR};
+
+    #[test]
+    fn test_resend_request_layout() {
+        let frame = build_resend_request(8841, 8850);
+        let unescaped = unescape_data(&frame).unwrap();
+
+        assert_eq!(unescaped[0], SOR);
+        assert_eq!(unescaped[unescaped.len() - 1], EOR);
+        assert_eq!(
+            u16::from_le_bytes([unescaped[OFFSET_TYPE], unescaped[OFFSET_TYPE + 1]]),
+            0x0004
+        );
+        assert_eq!(
+            &unescaped[OFFSET_BODY..unescaped.len() - 1],
+            &[
+                0x01, 0x04, 0x89, 0x22, 0x00, 0x00, 0x02, 0x04, 0x92, 0x22, 0x00, 0x00
+            ]
+        );
+        assert!(validate_crc(&frame).unwrap());
+    }
+
+    #[test]
+    fn test_resend_request_with_escaped_range() {
+        // 0x8F in the passing number must be escaped on the wire
+        let frame = build_resend_request(0x0000_008F, 0x0000_8E8F);
+
+        assert_eq!(frame.iter().filter(|&&b| b == EOR).count(), 1);
+        assert!(validate_crc(&frame).unwrap());
+    }
+}
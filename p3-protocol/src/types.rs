@@ -1,7 +1,7 @@
-/// P3 Protocol Constants
-///
-/// These define the frame structure and control bytes used in the
-/// MyLaps ProChip P3 binary protocol.
+//! P3 Protocol Constants
+//!
+//! These define the frame structure and control bytes used in the
+//! MyLaps ProChip P3 binary protocol.
 
 /// Start of Record - marks the beginning of a message
 pub const SOR: u8 = 0x8E;
@@ -91,6 +91,16 @@ impl MessageType {
             _ => None,
         }
     }
+
+    /// Upper-case record name as used in documentation and JSON, e.g. "PASSING"
+    pub fn name(self) -> &'static str {
+        match self {
+            MessageType::Passing => "PASSING",
+            MessageType::Status => "STATUS",
+            MessageType::Version => "VERSION",
+            MessageType::Resend => "RESEND",
+        }
+    }
 }
 
 impl From<MessageType> for u16 {
@@ -0,0 +1,22 @@
+-- Open practice sessions and the laps timed in them
+CREATE TABLE IF NOT EXISTS practice_sessions (
+    id          TEXT PRIMARY KEY,
+    track_id    TEXT NOT NULL REFERENCES tracks(id),
+    started_at  TEXT NOT NULL DEFAULT (datetime('now')),
+    ended_at    TEXT
+);
+
+-- rider_id is NULL for transponders not registered to a rider
+CREATE TABLE IF NOT EXISTS practice_laps (
+    id              TEXT PRIMARY KEY,
+    session_id      TEXT NOT NULL REFERENCES practice_sessions(id) ON DELETE CASCADE,
+    transponder_id  INTEGER NOT NULL,
+    rider_id        TEXT REFERENCES riders(id) ON DELETE SET NULL,
+    lap_number      INTEGER NOT NULL,
+    lap_time_us     INTEGER NOT NULL,
+    sectors_json    TEXT NOT NULL DEFAULT '[]',
+    created_at      TEXT NOT NULL DEFAULT (datetime('now')),
+    UNIQUE(session_id, transponder_id, lap_number)
+);
+
+CREATE INDEX IF NOT EXISTS idx_practice_laps_session ON practice_laps(session_id)
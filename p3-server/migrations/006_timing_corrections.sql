@@ -0,0 +1,11 @@
+-- Manual timing corrections applied by race control, in the order they were made
+CREATE TABLE IF NOT EXISTS timing_corrections (
+    id           TEXT PRIMARY KEY,
+    moto_id      TEXT NOT NULL REFERENCES motos(id) ON DELETE CASCADE,
+    operator     TEXT NOT NULL,
+    reason       TEXT NOT NULL,
+    change_json  TEXT NOT NULL,
+    created_at   TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_timing_corrections_moto ON timing_corrections(moto_id)
@@ -0,0 +1,48 @@
+-- Protests filed against a finished moto's results
+CREATE TABLE IF NOT EXISTS protests (
+    id               TEXT PRIMARY KEY,
+    moto_id          TEXT NOT NULL REFERENCES motos(id) ON DELETE CASCADE,
+    rider_id         TEXT REFERENCES riders(id) ON DELETE SET NULL,
+    filed_by         TEXT NOT NULL,
+    description      TEXT NOT NULL,
+    status           TEXT NOT NULL DEFAULT 'open' CHECK (status IN ('open', 'upheld', 'denied')),
+    decided_by       TEXT,
+    decision_reason  TEXT,
+    created_at       TEXT NOT NULL DEFAULT (datetime('now')),
+    decided_at       TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_protests_moto ON protests(moto_id);
+
+-- Relegations, time penalties and DQs. Revoked penalties are kept
+CREATE TABLE IF NOT EXISTS penalties (
+    id             TEXT PRIMARY KEY,
+    moto_id        TEXT NOT NULL REFERENCES motos(id) ON DELETE CASCADE,
+    rider_id       TEXT NOT NULL REFERENCES riders(id) ON DELETE CASCADE,
+    penalty_json   TEXT NOT NULL,
+    reason         TEXT NOT NULL,
+    issued_by      TEXT NOT NULL,
+    protest_id     TEXT REFERENCES protests(id) ON DELETE SET NULL,
+    status         TEXT NOT NULL DEFAULT 'active' CHECK (status IN ('active', 'revoked')),
+    revoked_by     TEXT,
+    revoke_reason  TEXT,
+    created_at     TEXT NOT NULL DEFAULT (datetime('now')),
+    revoked_at     TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_penalties_moto ON penalties(moto_id);
+
+-- Every change to a moto's effective results, with the results it produced
+CREATE TABLE IF NOT EXISTS result_changes (
+    id            TEXT PRIMARY KEY,
+    moto_id       TEXT NOT NULL REFERENCES motos(id) ON DELETE CASCADE,
+    action        TEXT NOT NULL,
+    actor         TEXT NOT NULL,
+    reason        TEXT,
+    penalty_id    TEXT,
+    protest_id    TEXT,
+    results_json  TEXT,
+    created_at    TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_result_changes_moto ON result_changes(moto_id)
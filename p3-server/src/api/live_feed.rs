@@ -0,0 +1,310 @@
+use std::str::FromStr;
+
+use async_nats::jetstream;
+use async_nats::jetstream::consumer::DeliverPolicy;
+use async_nats::jetstream::consumer::pull::{Ordered, OrderedConfig};
+use futures_util::StreamExt;
+use p3_contracts::{RaceEventEnvelopeV1, build_race_events_subject};
+use time::OffsetDateTime;
+use tracing::warn;
+
+use crate::ingest::publisher::RACE_EVENTS_STREAM_NAME;
+
+/// Start position requested with `/ws/v1/live?from=`
+///
+/// | `from` | Meaning |
+/// |--------|---------|
+/// | `now` | Live events only (default) |
+/// | `seq:<n>` | Replay from JetStream stream sequence `n` |
+/// | `ts_us:<n>` | Replay events published at or after `n` µs since the epoch |
+///
+/// Resume tokens handed out in envelopes use the `seq:` form, so a client
+/// passes its last token back as `from` unchanged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LiveFrom {
+    Now,
+    Sequence(u64),
+    TimestampUs(u64),
+}
+
+impl FromStr for LiveFrom {
+    type Err = ();
+
+    fn from_str(raw: &str) -> Result<Self, ()> {
+        if raw == "now" {
+            return Ok(Self::Now);
+        }
+
+        let (kind, value) = raw.split_once(':').ok_or(())?;
+        let value: u64 = value.parse().map_err(|_| ())?;
+        match kind {
+            "seq" if value > 0 => Ok(Self::Sequence(value)),
+            "ts_us" if start_time(value).is_some() => Ok(Self::TimestampUs(value)),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Token that resumes delivery at stream sequence `sequence`
+pub fn resume_token(sequence: u64) -> String {
+    format!("seq:{sequence}")
+}
+
+/// How a feed starts, given the range the stream still retains
+#[derive(Debug, PartialEq, Eq)]
+pub struct ReplayStart {
+    pub deliver_policy: DeliverPolicy,
+    pub resume_token: String,
+    /// History is replayed, so the client already holds the earlier state
+    pub replaying: bool,
+    /// The requested position is not in the stream; delivery starts live
+    pub unavailable: bool,
+}
+
+/// The range a stream still retains
+#[derive(Debug, Clone, Copy)]
+pub struct RetainedRange {
+    pub first_sequence: u64,
+    /// Time the message at `first_sequence` was stored
+    pub first_timestamp: OffsetDateTime,
+    pub last_sequence: u64,
+}
+
+pub fn replay_start(from: LiveFrom, retained: RetainedRange) -> ReplayStart {
+    let RetainedRange {
+        first_sequence,
+        first_timestamp,
+        last_sequence,
+    } = retained;
+    let live = |unavailable| ReplayStart {
+        deliver_policy: DeliverPolicy::ByStartSequence {
+            start_sequence: last_sequence + 1,
+        },
+        resume_token: resume_token(last_sequence + 1),
+        replaying: false,
+        unavailable,
+    };
+
+    match from {
+        LiveFrom::Now => live(false),
+        // Expired by retention, or a token from a stream that was recreated
+        LiveFrom::Sequence(sequence)
+            if sequence < first_sequence || sequence > last_sequence + 1 =>
+        {
+            live(true)
+        }
+        LiveFrom::Sequence(sequence) => ReplayStart {
+            deliver_policy: DeliverPolicy::ByStartSequence {
+                start_sequence: sequence,
+            },
+            resume_token: resume_token(sequence),
+            replaying: true,
+            unavailable: false,
+        },
+        LiveFrom::TimestampUs(ts_us) => {
+            let start_time = start_time(ts_us).unwrap_or(OffsetDateTime::UNIX_EPOCH);
+            // Events from that time on were stored, but retention has dropped some
+            if first_sequence > 1 && start_time < first_timestamp {
+                return live(true);
+            }
+            ReplayStart {
+                deliver_policy: DeliverPolicy::ByStartTime { start_time },
+                resume_token: format!("ts_us:{ts_us}"),
+                replaying: true,
+                unavailable: false,
+            }
+        }
+    }
+}
+
+fn start_time(ts_us: u64) -> Option<OffsetDateTime> {
+    OffsetDateTime::from_unix_timestamp_nanos(i128::from(ts_us) * 1_000).ok()
+}
+
+/// A track's race events, replayed from the requested position and then
+/// followed live through the same ephemeral ordered consumer, so the switch
+/// from history to live delivery neither drops nor repeats an event.
+pub struct RaceEventFeed {
+    messages: Ordered,
+    resume_token: String,
+    start: ReplayStart,
+}
+
+impl RaceEventFeed {
+    pub async fn open(nats_url: &str, track_id: &str, from: LiveFrom) -> anyhow::Result<Self> {
+        let client = async_nats::connect(nats_url).await?;
+        let jetstream = jetstream::new(client);
+        let stream = jetstream.get_stream(RACE_EVENTS_STREAM_NAME).await?;
+        let state = &stream.cached_info().state;
+        let start = replay_start(
+            from,
+            RetainedRange {
+                first_sequence: state.first_sequence,
+                first_timestamp: state.first_timestamp,
+                last_sequence: state.last_sequence,
+            },
+        );
+
+        let consumer = stream
+            .create_consumer(OrderedConfig {
+                filter_subject: build_race_events_subject(track_id),
+                deliver_policy: start.deliver_policy,
+                ..Default::default()
+            })
+            .await?;
+        let messages = consumer.messages().await?;
+
+        Ok(Self {
+            messages,
+            resume_token: start.resume_token.clone(),
+            start,
+        })
+    }
+
+    pub fn start(&self) -> &ReplayStart {
+        &self.start
+    }
+
+    /// Token that resumes right after the last event taken from the feed
+    pub fn resume_token(&self) -> &str {
+        &self.resume_token
+    }
+
+    /// Next race event envelope, or `None` once the consumer is gone.
+    ///
+    /// Messages that fail to parse are skipped but still advance the resume
+    /// token.
+    pub async fn next(&mut self) -> Option<RaceEventEnvelopeV1> {
+        loop {
+            let message = match self.messages.next().await? {
+                Ok(message) => message,
+                Err(error) => {
+                    warn!(error = %error, "Race event feed receive error");
+                    continue;
+                }
+            };
+
+            match message.info() {
+                Ok(info) => self.resume_token = resume_token(info.stream_sequence + 1),
+                Err(error) => warn!(error = %error, "Race event message has no stream sequence"),
+            }
+
+            match serde_json::from_slice(&message.payload) {
+                Ok(envelope) => return Some(envelope),
+                Err(error) => {
+                    warn!(error = %error, "Failed to parse race event envelope from NATS");
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_from() {
+        assert_eq!("now".parse(), Ok(LiveFrom::Now));
+        assert_eq!("seq:42".parse(), Ok(LiveFrom::Sequence(42)));
+        assert_eq!(
+            "ts_us:1767225600000000".parse(),
+            Ok(LiveFrom::TimestampUs(1_767_225_600_000_000))
+        );
+        assert_eq!(resume_token(42).parse(), Ok(LiveFrom::Sequence(42)));
+
+        for invalid in [
+            "", "later", "seq:", "seq:0", "seq:-1", "42", "ts:1", "ts_us:x",
+        ] {
+            assert_eq!(invalid.parse::<LiveFrom>(), Err(()), "{invalid}");
+        }
+        // Past the last year `time` can represent
+        assert_eq!(format!("ts_us:{}", u64::MAX).parse::<LiveFrom>(), Err(()));
+    }
+
+    /// Retains `first_sequence..=last_sequence`, the first stored at 1 s
+    fn retained(first_sequence: u64, last_sequence: u64) -> RetainedRange {
+        RetainedRange {
+            first_sequence,
+            first_timestamp: OffsetDateTime::from_unix_timestamp(1).unwrap(),
+            last_sequence,
+        }
+    }
+
+    #[test]
+    fn replay_start_from_now_follows_stream_head() {
+        let start = replay_start(LiveFrom::Now, retained(10, 20));
+        assert_eq!(
+            start.deliver_policy,
+            DeliverPolicy::ByStartSequence { start_sequence: 21 }
+        );
+        assert_eq!(start.resume_token, "seq:21");
+        assert!(!start.replaying);
+        assert!(!start.unavailable);
+
+        // A stream that never held a message
+        assert_eq!(
+            replay_start(LiveFrom::Now, retained(0, 0)).resume_token,
+            "seq:1"
+        );
+    }
+
+    #[test]
+    fn replay_start_from_retained_sequence_replays() {
+        for sequence in [10, 15, 21] {
+            let start = replay_start(LiveFrom::Sequence(sequence), retained(10, 20));
+            assert_eq!(
+                start.deliver_policy,
+                DeliverPolicy::ByStartSequence {
+                    start_sequence: sequence
+                }
+            );
+            assert_eq!(start.resume_token, resume_token(sequence));
+            assert!(start.replaying);
+            assert!(!start.unavailable);
+        }
+    }
+
+    #[test]
+    fn replay_start_from_missing_sequence_goes_live() {
+        for sequence in [9, 22] {
+            let start = replay_start(LiveFrom::Sequence(sequence), retained(10, 20));
+            assert_eq!(
+                start.deliver_policy,
+                DeliverPolicy::ByStartSequence { start_sequence: 21 }
+            );
+            assert!(!start.replaying);
+            assert!(start.unavailable);
+        }
+    }
+
+    #[test]
+    fn replay_start_from_timestamp() {
+        let start = replay_start(LiveFrom::TimestampUs(1_500_000), retained(10, 20));
+        assert_eq!(
+            start.deliver_policy,
+            DeliverPolicy::ByStartTime {
+                start_time: OffsetDateTime::from_unix_timestamp_nanos(1_500_000_000).unwrap()
+            }
+        );
+        // Until an event arrives, resuming repeats the original request
+        assert_eq!(start.resume_token, "ts_us:1500000");
+        assert!(start.replaying);
+    }
+
+    #[test]
+    fn replay_start_from_expired_timestamp_goes_live() {
+        let start = replay_start(LiveFrom::TimestampUs(500_000), retained(10, 20));
+        assert_eq!(
+            start.deliver_policy,
+            DeliverPolicy::ByStartSequence { start_sequence: 21 }
+        );
+        assert!(!start.replaying);
+        assert!(start.unavailable);
+
+        // Nothing was dropped before the stream's first event
+        let start = replay_start(LiveFrom::TimestampUs(500_000), retained(1, 20));
+        assert!(start.replaying);
+        assert!(!start.unavailable);
+    }
+}
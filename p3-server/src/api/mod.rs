@@ -1,4 +1,5 @@
 pub mod error;
+pub mod live_feed;
 pub mod routes;
 pub mod state;
 pub mod ws;
@@ -39,6 +40,10 @@ pub fn router(state: AppState) -> Router {
             "/api/tracks/{track_id}/loops/{loop_id}",
             put(routes::tracks::update_loop).delete(routes::tracks::delete_loop),
         )
+        .route(
+            "/api/tracks/{id}/race-rules",
+            put(routes::tracks::set_race_rules),
+        )
         .route(
             "/api/tracks/{track_id}/onboarding/discovery",
             get(routes::onboarding::discovery),
@@ -74,6 +79,10 @@ pub fn router(state: AppState) -> Router {
             "/api/events/{event_id}/classes/{class_id}",
             axum::routing::delete(routes::events::delete_class),
         )
+        .route(
+            "/api/events/{event_id}/classes/{class_id}/race-rules",
+            put(routes::events::set_class_race_rules),
+        )
         // Class riders
         .route(
             "/api/events/{event_id}/classes/{class_id}/riders",
@@ -97,16 +106,76 @@ pub fn router(state: AppState) -> Router {
             post(routes::motos::generate),
         )
         .route("/api/motos/{id}", get(routes::motos::get))
+        .route(
+            "/api/motos/{id}/corrections",
+            get(routes::motos::list_corrections),
+        )
+        // Penalties and protests
+        .route(
+            "/api/motos/{id}/penalties",
+            get(routes::penalties::list_penalties).post(routes::penalties::issue_penalty),
+        )
+        .route(
+            "/api/penalties/{id}/revoke",
+            post(routes::penalties::revoke_penalty),
+        )
+        .route(
+            "/api/motos/{id}/protests",
+            get(routes::penalties::list_protests).post(routes::penalties::file_protest),
+        )
+        .route(
+            "/api/protests/{id}/decision",
+            post(routes::penalties::decide_protest),
+        )
+        .route("/api/motos/{id}/history", get(routes::penalties::history))
         // Standings
         .route(
             "/api/events/{event_id}/classes/{class_id}/standings",
             get(routes::events::class_standings),
         )
-        // Race control
-        .route("/api/race/state", get(routes::race::get_state))
-        .route("/api/race/stage", post(routes::race::stage))
-        .route("/api/race/reset", post(routes::race::reset))
-        .route("/api/race/force-finish", post(routes::race::force_finish))
+        // Race control, one engine per track
+        .route(
+            "/api/tracks/{track_id}/race/state",
+            get(routes::race::get_state),
+        )
+        .route(
+            "/api/tracks/{track_id}/race/stage",
+            post(routes::race::stage),
+        )
+        .route(
+            "/api/tracks/{track_id}/race/reset",
+            post(routes::race::reset),
+        )
+        .route(
+            "/api/tracks/{track_id}/race/force-finish",
+            post(routes::race::force_finish),
+        )
+        .route(
+            "/api/tracks/{track_id}/race/start-list",
+            post(routes::race::add_to_start_list),
+        )
+        .route(
+            "/api/tracks/{track_id}/race/bind-transponder",
+            post(routes::race::bind_transponder),
+        )
+        .route(
+            "/api/tracks/{track_id}/race/corrections",
+            post(routes::race::correct_timing),
+        )
+        .route(
+            "/api/tracks/{track_id}/race/passings",
+            get(routes::race::list_passings),
+        )
+        .route(
+            "/api/tracks/{track_id}/race/practice/start",
+            post(routes::race::start_practice),
+        )
+        .route(
+            "/api/tracks/{track_id}/race/practice/stop",
+            post(routes::race::stop_practice),
+        )
+        // Practice results
+        .route("/api/practice/{id}", get(routes::practice::get_session))
         // Seed demo data
         .route("/api/seed-demo", post(routes::seed::seed_demo))
         // Track ingest v2
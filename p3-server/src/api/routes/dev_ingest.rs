@@ -123,9 +123,10 @@ pub async fn ingest_batch(
     .await?;
 
     for event in &req.events {
-        if let Message::Status(status) = &event.message {
-            if let Some(decoder_id) = &status.decoder_id {
-                sqlx::query(
+        if let Message::Status(status) = &event.message
+            && let Some(decoder_id) = &status.decoder_id
+        {
+            sqlx::query(
                     "INSERT INTO decoder_status (decoder_id, noise, temperature, gps_status, satellites, last_seen) \
                      VALUES (?, ?, ?, ?, ?, datetime('now')) \
                      ON CONFLICT(decoder_id) DO UPDATE SET \
@@ -142,7 +143,6 @@ pub async fn ingest_batch(
                 .bind(status.satellites as i64)
                 .execute(&state.db)
                 .await?;
-            }
         }
 
         let _ = state.message_tx.send(Arc::new(event.message.clone()));
@@ -239,29 +239,30 @@ fn message_type_name(message: &Message) -> &'static str {
         Message::Passing(_) => "PASSING",
         Message::Status(_) => "STATUS",
         Message::Version(_) => "VERSION",
+        Message::Resend(_) => "RESEND",
+        Message::Unknown { .. } => "UNKNOWN",
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::domain::race_event::RaceEvent;
-    use crate::engine::RaceEngine;
+    use crate::engine::{EngineRegistry, TrackRaceEvent};
     use p3_parser::{PassingMessage, StatusMessage};
     use std::sync::Arc;
-    use tokio::sync::{Mutex, broadcast};
+    use tokio::sync::broadcast;
 
     async fn test_state() -> AppState {
         let db = sqlx::SqlitePool::connect("sqlite::memory:").await.unwrap();
         crate::db::run_migrations(&db).await.unwrap();
 
         let (message_tx, _) = broadcast::channel(32);
-        let (race_event_tx, _) = broadcast::channel::<Arc<RaceEvent>>(32);
-        let engine = Arc::new(Mutex::new(RaceEngine::new(race_event_tx.clone())));
+        let (race_event_tx, _) = broadcast::channel::<Arc<TrackRaceEvent>>(32);
+        let engines = Arc::new(EngineRegistry::new(race_event_tx.clone()));
         AppState::new(
             message_tx,
             race_event_tx,
-            engine,
+            engines,
             db,
             None,
             "nats://127.0.0.1:4222".to_string(),
@@ -286,6 +287,7 @@ mod tests {
                         temperature: 215,
                         satellites: 7,
                         decoder_id: Some("D1000C00".to_string()),
+                        extra_fields: Vec::new(),
                     }),
                 },
                 IngestEvent {
@@ -301,6 +303,7 @@ mod tests {
                         transponder_string: Some("FL-01001".to_string()),
                         flags: 0,
                         decoder_id: Some("D1000C00".to_string()),
+                        extra_fields: Vec::new(),
                     }),
                 },
             ],
@@ -345,6 +348,7 @@ mod tests {
                     temperature: 210,
                     satellites: 8,
                     decoder_id: Some("D1000C00".to_string()),
+                    extra_fields: Vec::new(),
                 }),
             }],
         };
@@ -363,6 +367,7 @@ mod tests {
                     temperature: 211,
                     satellites: 7,
                     decoder_id: Some("D1000C00".to_string()),
+                    extra_fields: Vec::new(),
                 }),
             }],
         };
@@ -407,6 +412,7 @@ mod tests {
                     temperature: 220,
                     satellites: 8,
                     decoder_id: Some("D2000C00".to_string()),
+                    extra_fields: Vec::new(),
                 }),
             }],
         };
@@ -8,6 +8,7 @@ use crate::api::error::ApiError;
 use crate::api::state::AppState;
 use crate::db::models::{EventClassRow, EventRow, RiderRow};
 use crate::db::queries::events as queries;
+use crate::domain::race_event::RaceRules;
 
 // --- Request/Response types ---
 
@@ -155,6 +156,21 @@ pub async fn delete_class(
     Ok(Json(serde_json::json!({"deleted": true})))
 }
 
+/// PUT /api/events/:event_id/classes/:class_id/race-rules — DNS/DNF rules
+/// overriding the track's for this class
+pub async fn set_class_race_rules(
+    State(state): State<AppState>,
+    Path((_event_id, class_id)): Path<(String, String)>,
+    Json(rules): Json<RaceRules>,
+) -> Result<Json<EventClassRow>, ApiError> {
+    crate::api::routes::tracks::validate_race_rules(&rules)?;
+
+    queries::set_class_race_rules(&state.db, &class_id, &rules)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Class not found".into()))
+        .map(Json)
+}
+
 // --- Class Riders ---
 
 pub async fn add_class_rider(
@@ -3,6 +3,8 @@ pub mod events;
 pub mod ingest;
 pub mod motos;
 pub mod onboarding;
+pub mod penalties;
+pub mod practice;
 pub mod race;
 pub mod riders;
 pub mod seed;
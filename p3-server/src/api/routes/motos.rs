@@ -7,6 +7,7 @@ use serde::Serialize;
 use crate::api::error::ApiError;
 use crate::api::state::AppState;
 use crate::db::models::{MotoEntryRow, MotoRow, RiderRow};
+use crate::db::queries::corrections::{self as correction_queries, TimingCorrectionRecord};
 use crate::db::queries::{events as event_queries, motos as moto_queries};
 use crate::domain::race_format;
 
@@ -71,6 +72,18 @@ pub async fn get(
     }))
 }
 
+/// GET /api/motos/:id/corrections — Timing corrections applied to a moto, oldest first
+pub async fn list_corrections(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<TimingCorrectionRecord>>, ApiError> {
+    moto_queries::get_moto(&state.db, &id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Moto not found".into()))?;
+    let corrections = correction_queries::list_for_moto(&state.db, &id).await?;
+    Ok(Json(corrections))
+}
+
 /// POST /api/events/:event_id/classes/:class_id/generate-motos
 ///
 /// Generates moto sheets for qualifying rounds + elimination round placeholders.
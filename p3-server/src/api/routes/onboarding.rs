@@ -124,15 +124,17 @@ pub async fn discovery(
             ),
             Message::Status(status) => (status.decoder_id, "status", None),
             Message::Version(version) => (Some(version.decoder_id), "version", None),
+            // Client-originated; carries no decoder identity
+            Message::Resend(_) => continue,
+            // Undecoded record types; any decoder ID is buried in raw fields
+            Message::Unknown { .. } => continue,
         };
 
         let Some(decoder_id) = decoder_id else {
             continue;
         };
 
-        let aggregate = aggregates
-            .entry(decoder_id)
-            .or_insert_with(DecoderAggregate::default);
+        let aggregate = aggregates.entry(decoder_id).or_default();
 
         match message_kind {
             "passing" => aggregate.passing_count += 1,
@@ -188,7 +190,7 @@ pub async fn discovery(
             hits,
         })
         .collect();
-    gate_beacons.sort_by(|a, b| b.hits.cmp(&a.hits));
+    gate_beacons.sort_by_key(|beacon| std::cmp::Reverse(beacon.hits));
 
     Ok(Json(TrackOnboardingDiscoveryResponse {
         track_id,
@@ -204,11 +206,10 @@ pub async fn discovery(
 mod tests {
     use super::*;
     use crate::api::routes::dev_ingest::{IngestBatchRequest, IngestEvent, ingest_batch};
-    use crate::domain::race_event::RaceEvent;
-    use crate::engine::RaceEngine;
+    use crate::engine::{EngineRegistry, TrackRaceEvent};
     use p3_parser::{PassingMessage, StatusMessage};
     use std::sync::Arc;
-    use tokio::sync::{Mutex, broadcast};
+    use tokio::sync::broadcast;
 
     async fn test_state() -> AppState {
         let db = sqlx::SqlitePool::connect("sqlite::memory:").await.unwrap();
@@ -235,12 +236,12 @@ mod tests {
         .unwrap();
 
         let (message_tx, _) = broadcast::channel(32);
-        let (race_event_tx, _) = broadcast::channel::<Arc<RaceEvent>>(32);
-        let engine = Arc::new(Mutex::new(RaceEngine::new(race_event_tx.clone())));
+        let (race_event_tx, _) = broadcast::channel::<Arc<TrackRaceEvent>>(32);
+        let engines = Arc::new(EngineRegistry::new(race_event_tx.clone()));
         AppState::new(
             message_tx,
             race_event_tx,
-            engine,
+            engines,
             db,
             None,
             "nats://127.0.0.1:4222".to_string(),
@@ -266,6 +267,7 @@ mod tests {
                         temperature: 215,
                         satellites: 7,
                         decoder_id: Some("D1000C00".to_string()),
+                        extra_fields: Vec::new(),
                     }),
                 },
                 IngestEvent {
@@ -281,6 +283,7 @@ mod tests {
                         transponder_string: None,
                         flags: 0,
                         decoder_id: Some("D1000C00".to_string()),
+                        extra_fields: Vec::new(),
                     }),
                 },
             ],
@@ -300,6 +303,7 @@ mod tests {
                     temperature: 216,
                     satellites: 8,
                     decoder_id: Some("D2000C00".to_string()),
+                    extra_fields: Vec::new(),
                 }),
             }],
         };
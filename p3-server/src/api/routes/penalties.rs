@@ -0,0 +1,281 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+};
+use serde::Deserialize;
+
+use crate::api::error::ApiError;
+use crate::api::state::AppState;
+use crate::db::models::ProtestRow;
+use crate::db::queries::motos as moto_queries;
+use crate::db::queries::penalties::{
+    self as queries, NewPenalty, PenaltyRecord, ResultChangeRecord,
+};
+use crate::domain::penalties::{PenaltyKind, ProtestDecision};
+
+#[derive(Debug, Deserialize)]
+pub struct IssuePenaltyRequest {
+    pub rider_id: String,
+    pub penalty: PenaltyKind,
+    pub reason: String,
+    pub issued_by: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RevokePenaltyRequest {
+    pub revoked_by: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FileProtestRequest {
+    /// Rider the protest is against, if any
+    #[serde(default)]
+    pub rider_id: Option<String>,
+    pub filed_by: String,
+    pub description: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DecideProtestRequest {
+    pub decision: ProtestDecision,
+    pub decided_by: String,
+    pub reason: String,
+    /// Penalty to issue when the protest is upheld
+    #[serde(default)]
+    pub penalty: Option<ProtestPenalty>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ProtestPenalty {
+    /// Defaults to the rider the protest is against
+    #[serde(default)]
+    pub rider_id: Option<String>,
+    pub penalty: PenaltyKind,
+}
+
+/// GET /api/motos/:id/penalties — Penalties issued in a moto, revoked ones included
+pub async fn list_penalties(
+    State(state): State<AppState>,
+    Path(moto_id): Path<String>,
+) -> Result<Json<Vec<PenaltyRecord>>, ApiError> {
+    moto_queries::get_moto(&state.db, &moto_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Moto not found".into()))?;
+    Ok(Json(queries::list_penalties(&state.db, &moto_id).await?))
+}
+
+/// POST /api/motos/:id/penalties — Relegate, time-penalize or disqualify a rider
+///
+/// The moto's effective results and points are recomputed from its raw
+/// results and every active penalty.
+pub async fn issue_penalty(
+    State(state): State<AppState>,
+    Path(moto_id): Path<String>,
+    Json(req): Json<IssuePenaltyRequest>,
+) -> Result<Json<PenaltyRecord>, ApiError> {
+    require("issued_by", &req.issued_by)?;
+    require("reason", &req.reason)?;
+    validate_penalty(&req.penalty)?;
+    require_finished_entry(&state, &moto_id, &req.rider_id).await?;
+
+    let mut tx = state.db.begin().await?;
+    let penalty = queries::issue_penalty(
+        &mut tx,
+        &NewPenalty {
+            moto_id: &moto_id,
+            rider_id: &req.rider_id,
+            penalty: &req.penalty,
+            reason: req.reason.trim(),
+            issued_by: req.issued_by.trim(),
+            protest_id: None,
+        },
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(Json(penalty))
+}
+
+/// POST /api/penalties/:id/revoke — Withdraw a penalty and restore the results
+pub async fn revoke_penalty(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(req): Json<RevokePenaltyRequest>,
+) -> Result<Json<PenaltyRecord>, ApiError> {
+    require("revoked_by", &req.revoked_by)?;
+    require("reason", &req.reason)?;
+    queries::get_penalty(&state.db, &id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Penalty not found".into()))?;
+
+    let mut tx = state.db.begin().await?;
+    let penalty = queries::revoke_penalty(&mut tx, &id, req.revoked_by.trim(), req.reason.trim())
+        .await?
+        .ok_or_else(|| ApiError::BadRequest("Penalty is already revoked".into()))?;
+    tx.commit().await?;
+    Ok(Json(penalty))
+}
+
+/// GET /api/motos/:id/protests — Protests filed against a moto
+pub async fn list_protests(
+    State(state): State<AppState>,
+    Path(moto_id): Path<String>,
+) -> Result<Json<Vec<ProtestRow>>, ApiError> {
+    moto_queries::get_moto(&state.db, &moto_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Moto not found".into()))?;
+    Ok(Json(queries::list_protests(&state.db, &moto_id).await?))
+}
+
+/// POST /api/motos/:id/protests — File a protest against a finished moto
+pub async fn file_protest(
+    State(state): State<AppState>,
+    Path(moto_id): Path<String>,
+    Json(req): Json<FileProtestRequest>,
+) -> Result<Json<ProtestRow>, ApiError> {
+    require("filed_by", &req.filed_by)?;
+    require("description", &req.description)?;
+    match &req.rider_id {
+        Some(rider_id) => require_finished_entry(&state, &moto_id, rider_id).await?,
+        None => require_finished_moto(&state, &moto_id).await?,
+    }
+
+    let mut tx = state.db.begin().await?;
+    let protest = queries::file_protest(
+        &mut tx,
+        &moto_id,
+        req.rider_id.as_deref(),
+        req.filed_by.trim(),
+        req.description.trim(),
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(Json(protest))
+}
+
+/// POST /api/protests/:id/decision — Uphold or deny an open protest
+///
+/// An upheld protest can carry the penalty it results in, which is issued
+/// against the protested rider unless another is named. The decision and
+/// its penalty commit together or not at all.
+pub async fn decide_protest(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(req): Json<DecideProtestRequest>,
+) -> Result<Json<ProtestRow>, ApiError> {
+    require("decided_by", &req.decided_by)?;
+    require("reason", &req.reason)?;
+    let protest = queries::get_protest(&state.db, &id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Protest not found".into()))?;
+    if protest.status != "open" {
+        return Err(ApiError::BadRequest(format!(
+            "Protest is already {}",
+            protest.status
+        )));
+    }
+
+    let penalty = match (&req.penalty, req.decision) {
+        (None, _) => None,
+        (Some(_), ProtestDecision::Denied) => {
+            return Err(ApiError::BadRequest(
+                "A denied protest can't carry a penalty".into(),
+            ));
+        }
+        (Some(penalty), ProtestDecision::Upheld) => {
+            let rider_id = penalty
+                .rider_id
+                .clone()
+                .or_else(|| protest.rider_id.clone())
+                .ok_or_else(|| ApiError::BadRequest("Penalty needs a rider_id".into()))?;
+            validate_penalty(&penalty.penalty)?;
+            require_finished_entry(&state, &protest.moto_id, &rider_id).await?;
+            Some((rider_id, &penalty.penalty))
+        }
+    };
+
+    let mut tx = state.db.begin().await?;
+    let decided = queries::decide_protest(
+        &mut tx,
+        &id,
+        req.decision,
+        req.decided_by.trim(),
+        req.reason.trim(),
+    )
+    .await?
+    .ok_or_else(|| ApiError::BadRequest("Protest was already decided".into()))?;
+
+    if let Some((rider_id, penalty)) = penalty {
+        queries::issue_penalty(
+            &mut tx,
+            &NewPenalty {
+                moto_id: &decided.moto_id,
+                rider_id: &rider_id,
+                penalty,
+                reason: req.reason.trim(),
+                issued_by: req.decided_by.trim(),
+                protest_id: Some(&id),
+            },
+        )
+        .await?;
+    }
+    tx.commit().await?;
+    Ok(Json(decided))
+}
+
+/// GET /api/motos/:id/history — Result changes, penalties and protests, oldest first
+pub async fn history(
+    State(state): State<AppState>,
+    Path(moto_id): Path<String>,
+) -> Result<Json<Vec<ResultChangeRecord>>, ApiError> {
+    moto_queries::get_moto(&state.db, &moto_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Moto not found".into()))?;
+    Ok(Json(queries::list_changes(&state.db, &moto_id).await?))
+}
+
+fn require(field: &str, value: &str) -> Result<(), ApiError> {
+    if value.trim().is_empty() {
+        return Err(ApiError::BadRequest(format!("{field} is required")));
+    }
+    Ok(())
+}
+
+fn validate_penalty(penalty: &PenaltyKind) -> Result<(), ApiError> {
+    match penalty {
+        PenaltyKind::Relegation { positions: Some(0) } => Err(ApiError::BadRequest(
+            "Relegation must be at least one position".into(),
+        )),
+        PenaltyKind::TimePenalty { time_us: 0 } => {
+            Err(ApiError::BadRequest("Time penalty must be positive".into()))
+        }
+        _ => Ok(()),
+    }
+}
+
+async fn require_finished_moto(state: &AppState, moto_id: &str) -> Result<(), ApiError> {
+    let moto = moto_queries::get_moto(&state.db, moto_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Moto not found".into()))?;
+    if moto.status != "finished" {
+        return Err(ApiError::BadRequest(format!(
+            "Moto {moto_id} has not finished"
+        )));
+    }
+    Ok(())
+}
+
+async fn require_finished_entry(
+    state: &AppState,
+    moto_id: &str,
+    rider_id: &str,
+) -> Result<(), ApiError> {
+    require_finished_moto(state, moto_id).await?;
+    let entries = moto_queries::list_entries(&state.db, moto_id).await?;
+    if !entries.iter().any(|e| e.rider_id == rider_id) {
+        return Err(ApiError::NotFound(format!(
+            "Rider {rider_id} is not in moto {moto_id}"
+        )));
+    }
+    Ok(())
+}
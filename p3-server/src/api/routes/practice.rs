@@ -0,0 +1,39 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+};
+use serde::Serialize;
+
+use crate::api::error::ApiError;
+use crate::api::state::AppState;
+use crate::db::models::PracticeSessionRow;
+use crate::db::queries::practice::{
+    self as practice_queries, PracticeLapResult, PracticeSessionStanding,
+};
+
+#[derive(Debug, Serialize)]
+pub struct PracticeSessionResults {
+    #[serde(flatten)]
+    pub session: PracticeSessionRow,
+    /// Best lap per transponder, fastest first
+    pub standings: Vec<PracticeSessionStanding>,
+    pub laps: Vec<PracticeLapResult>,
+}
+
+/// GET /api/practice/:id — Persisted results of a practice session
+pub async fn get_session(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<PracticeSessionResults>, ApiError> {
+    let session = practice_queries::get_session(&state.db, &id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound(format!("Practice session {} not found", id)))?;
+    let standings = practice_queries::get_session_standings(&state.db, &id).await?;
+    let laps = practice_queries::list_laps(&state.db, &id).await?;
+
+    Ok(Json(PracticeSessionResults {
+        session,
+        standings,
+        laps,
+    }))
+}
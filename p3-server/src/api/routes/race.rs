@@ -1,21 +1,70 @@
-use axum::{Json, extract::State};
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+};
 use p3_contracts::{
-    LoopConfigV1, RACE_CONTROL_INTENT_ENVELOPE_CONTRACT_VERSION_V1, RaceControlIntentEnvelopeV1,
-    RaceControlIntentV1, StagedRiderV1, TrackConfigV1,
+    KnownRiderV1, LoopConfigV1, RACE_CONTROL_INTENT_ENVELOPE_CONTRACT_VERSION_V1,
+    RaceControlIntentEnvelopeV1, RaceControlIntentV1, RaceRulesV1, StagedRiderV1, StartModeV1,
+    TrackConfigV1,
 };
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::Mutex;
 use tracing::warn;
 use uuid::Uuid;
 
 use crate::api::error::ApiError;
 use crate::api::state::AppState;
-use crate::domain::race_event::{LoopConfig, RaceEvent, StagedRider, TrackConfig};
+use crate::db::queries::corrections as correction_queries;
+use crate::domain::race_event::{
+    KnownRider, LoopConfig, RaceEvent, RaceRules, RecordedPassing, StagedRider, TimingChange,
+    TimingCorrection, TrackConfig,
+};
+use crate::engine::{RaceCommand, RaceEngine, RacePhase, RaceState};
+use crate::workers::race::{map_correction_from_domain, map_start_mode};
 
 #[derive(Debug, Deserialize)]
 pub struct StageRequest {
     pub moto_id: String,
-    pub track_id: String,
+    /// Laps to complete before a rider finishes (default 1)
+    #[serde(default)]
+    pub laps: Option<u32>,
+    /// `time_trial` starts each rider's clock at their own start-loop crossing
+    #[serde(default)]
+    pub start_mode: StartModeV1,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StartListRequest {
+    pub moto_id: String,
+    /// Riders to append, in start order
+    pub rider_ids: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BindTransponderRequest {
+    pub moto_id: String,
+    pub rider_id: String,
+    /// Transponder the rider is actually carrying
+    pub transponder_id: u32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CorrectionRequest {
+    pub moto_id: String,
+    /// Who made the correction
+    pub operator: String,
+    /// Why it was made, kept with the correction for protests and audits
+    pub reason: String,
+    pub change: TimingChange,
+}
+
+/// Picks one of the motos on the track; defaults differ per route
+#[derive(Debug, Deserialize)]
+pub struct MotoQuery {
+    #[serde(default)]
+    pub moto_id: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -24,42 +73,19 @@ pub struct RaceStateResponse {
     pub snapshot: RaceEvent,
 }
 
-/// POST /api/race/stage — Load a moto onto the gate
+/// POST /api/tracks/:track_id/race/stage — Load a moto onto the track's gate,
+/// or start it as a time trial
 pub async fn stage(
     State(state): State<AppState>,
+    Path(track_id): Path<String>,
     Json(req): Json<StageRequest>,
 ) -> Result<Json<RaceStateResponse>, ApiError> {
-    // Load track config with loops from DB
-    let track_row =
-        sqlx::query_as::<_, crate::db::models::TrackRow>("SELECT * FROM tracks WHERE id = ?")
-            .bind(&req.track_id)
-            .fetch_optional(&state.db)
-            .await?
-            .ok_or_else(|| ApiError::NotFound(format!("Track {} not found", req.track_id)))?;
-
-    let loop_rows = sqlx::query_as::<_, crate::db::models::TimingLoopRow>(
-        "SELECT * FROM timing_loops WHERE track_id = ? ORDER BY position",
-    )
-    .bind(&req.track_id)
-    .fetch_all(&state.db)
-    .await?;
+    let laps = req.laps.unwrap_or(1);
+    if laps == 0 {
+        return Err(ApiError::BadRequest("laps must be at least 1".into()));
+    }
 
-    let track_config = TrackConfig {
-        track_id: track_row.id.clone(),
-        name: track_row.name.clone(),
-        gate_beacon_id: track_row.gate_beacon_id as u32,
-        loops: loop_rows
-            .iter()
-            .map(|l| LoopConfig {
-                loop_id: l.id.clone(),
-                name: l.name.clone(),
-                decoder_id: l.decoder_id.clone(),
-                position: l.position as u32,
-                is_start: l.is_start,
-                is_finish: l.is_finish,
-            })
-            .collect(),
-    };
+    let (track_config, track_rules) = load_track_config(&state, &track_id).await?;
 
     // Load moto with class info and rider entries
     let moto_row =
@@ -68,6 +94,12 @@ pub async fn stage(
             .fetch_optional(&state.db)
             .await?
             .ok_or_else(|| ApiError::NotFound(format!("Moto {} not found", req.moto_id)))?;
+    if moto_track_id(&state, &req.moto_id).await?.as_deref() != Some(track_id.as_str()) {
+        return Err(ApiError::BadRequest(format!(
+            "Moto {} is not raced on track {}",
+            req.moto_id, track_id
+        )));
+    }
 
     let class_row = sqlx::query_as::<_, crate::db::models::EventClassRow>(
         "SELECT * FROM event_classes WHERE id = ?",
@@ -76,6 +108,12 @@ pub async fn stage(
     .fetch_optional(&state.db)
     .await?
     .ok_or_else(|| ApiError::Internal("Moto references missing class".into()))?;
+    let rules = race_rules(
+        class_row.dns_timeout_secs,
+        class_row.dnf_timeout_secs,
+        class_row.max_race_secs,
+    )
+    .or(track_rules);
 
     // Load entries with rider info using a join
     let entries = sqlx::query_as::<_, crate::db::models::MotoEntryRow>(
@@ -120,8 +158,15 @@ pub async fn stage(
             .iter()
             .map(map_staged_rider_to_contract)
             .collect(),
+        laps,
+        start_mode: req.start_mode,
+        rules: RaceRulesV1 {
+            dns_timeout_secs: rules.dns_timeout_secs,
+            dnf_timeout_secs: rules.dnf_timeout_secs,
+            max_race_secs: rules.max_race_secs,
+        },
     };
-    let stage_envelope = build_control_intent_envelope(req.track_id.clone(), stage_intent);
+    let stage_envelope = build_control_intent_envelope(track_id.clone(), stage_intent);
 
     publisher
         .publish_race_control_intent(&stage_envelope)
@@ -134,74 +179,458 @@ pub async fn stage(
         .execute(&state.db)
         .await?;
 
-    // Configure and stage the engine
-    let mut engine = state.engine.lock().await;
-    engine.set_track(track_config);
-    engine.stage_moto(
-        req.moto_id,
-        class_row.name.clone(),
-        moto_row.round_type.clone(),
-        staged_riders,
-    );
+    // Configure and stage the track's engine
+    let engine = state.engines.engine(&track_id).await;
+    let mut engine = engine.lock().await;
+    engine.apply(RaceCommand::SetTrack(track_config));
+    engine.apply(RaceCommand::SetRaceRules(rules));
+    engine.apply(RaceCommand::Stage {
+        moto_id: req.moto_id,
+        class_name: class_row.name.clone(),
+        round_type: moto_row.round_type.clone(),
+        laps,
+        start_mode: map_start_mode(req.start_mode),
+        riders: staged_riders,
+    });
+
+    Ok(Json(state_response(engine.state())))
+}
+
+/// POST /api/tracks/:track_id/race/start-list — Add riders to the running
+/// time trial's start list
+pub async fn add_to_start_list(
+    State(state): State<AppState>,
+    Path(track_id): Path<String>,
+    Json(req): Json<StartListRequest>,
+) -> Result<Json<RaceStateResponse>, ApiError> {
+    if req.rider_ids.is_empty() {
+        return Err(ApiError::BadRequest("rider_ids must not be empty".into()));
+    }
+
+    let engine = running_engine(&state, &track_id).await?;
+    {
+        let engine = engine.lock().await;
+        if !matches!(
+            engine.state().moto_phase(&req.moto_id),
+            Some(RacePhase::TimeTrial { .. })
+        ) {
+            return Err(ApiError::BadRequest(format!(
+                "No time trial running for moto {}",
+                req.moto_id
+            )));
+        }
+    }
+
+    let mut riders = Vec::new();
+    for rider_id in &req.rider_ids {
+        let rider =
+            sqlx::query_as::<_, crate::db::models::RiderRow>("SELECT * FROM riders WHERE id = ?")
+                .bind(rider_id)
+                .fetch_optional(&state.db)
+                .await?
+                .ok_or_else(|| ApiError::NotFound(format!("Rider {} not found", rider_id)))?;
+
+        riders.push(StagedRider {
+            rider_id: rider.id,
+            first_name: rider.first_name,
+            last_name: rider.last_name,
+            plate_number: rider.plate_number,
+            transponder_id: rider.transponder_id as u32,
+            // Start order is assigned by the engine
+            lane: 0,
+        });
+    }
+
+    let publisher = state
+        .ingest_publisher
+        .as_ref()
+        .ok_or_else(|| ApiError::Internal("ingest publisher is not configured".to_string()))?;
+
+    let intent = RaceControlIntentV1::AddToStartList {
+        moto_id: req.moto_id.clone(),
+        riders: riders.iter().map(map_staged_rider_to_contract).collect(),
+    };
+    publisher
+        .publish_race_control_intent(&build_control_intent_envelope(track_id, intent))
+        .await
+        .map_err(|e| ApiError::Internal(format!("Failed to publish start list intent: {e}")))?;
+
+    let mut engine = engine.lock().await;
+    engine.apply(RaceCommand::AddToStartList {
+        moto_id: req.moto_id,
+        riders,
+    });
+    Ok(Json(state_response(engine.state())))
+}
+
+/// POST /api/tracks/:track_id/race/bind-transponder — Move a rider onto an
+/// unknown transponder
+///
+/// Passings the transponder already made in the moto count for the rider.
+pub async fn bind_transponder(
+    State(state): State<AppState>,
+    Path(track_id): Path<String>,
+    Json(req): Json<BindTransponderRequest>,
+) -> Result<Json<RaceStateResponse>, ApiError> {
+    let engine = running_engine(&state, &track_id).await?;
+    {
+        let engine = engine.lock().await;
+        let running = matches!(
+            engine.state().moto_phase(&req.moto_id),
+            Some(RacePhase::Staged { .. } | RacePhase::Racing { .. } | RacePhase::TimeTrial { .. })
+        );
+        let Some(RaceEvent::StateSnapshot { riders, .. }) = engine
+            .state()
+            .moto_snapshot(&req.moto_id)
+            .filter(|_| running)
+        else {
+            return Err(ApiError::BadRequest(format!(
+                "Moto {} is not running",
+                req.moto_id
+            )));
+        };
+        if !riders.iter().any(|r| r.rider_id == req.rider_id) {
+            return Err(ApiError::NotFound(format!(
+                "Rider {} is not in moto {}",
+                req.rider_id, req.moto_id
+            )));
+        }
+        if let Some(holder) = riders
+            .iter()
+            .find(|r| r.transponder_id == req.transponder_id)
+        {
+            return Err(ApiError::BadRequest(format!(
+                "Transponder {} already belongs to rider {}",
+                req.transponder_id, holder.rider_id
+            )));
+        }
+    }
+
+    let publisher = state
+        .ingest_publisher
+        .as_ref()
+        .ok_or_else(|| ApiError::Internal("ingest publisher is not configured".to_string()))?;
+
+    let intent = RaceControlIntentV1::BindTransponder {
+        moto_id: req.moto_id.clone(),
+        rider_id: req.rider_id.clone(),
+        transponder_id: req.transponder_id,
+    };
+    publisher
+        .publish_race_control_intent(&build_control_intent_envelope(track_id, intent))
+        .await
+        .map_err(|e| ApiError::Internal(format!("Failed to publish bind intent: {e}")))?;
+
+    let mut engine = engine.lock().await;
+    engine.apply(RaceCommand::BindTransponder {
+        moto_id: req.moto_id,
+        rider_id: req.rider_id,
+        transponder_id: req.transponder_id,
+    });
+    Ok(Json(state_response(engine.state())))
+}
+
+/// POST /api/tracks/:track_id/race/corrections — Insert, void or hand-time a passing
+///
+/// Results are recomputed from the moto's passings with every correction
+/// applied, so a finished moto gets new results. The race worker checks the
+/// correction against the moto as it holds it and drops one that doesn't
+/// apply. The response is the track's state as the worker last saved it.
+pub async fn correct_timing(
+    State(state): State<AppState>,
+    Path(track_id): Path<String>,
+    Json(req): Json<CorrectionRequest>,
+) -> Result<Json<RaceStateResponse>, ApiError> {
+    if req.operator.trim().is_empty() || req.reason.trim().is_empty() {
+        return Err(ApiError::BadRequest(
+            "Corrections need an operator and a reason".into(),
+        ));
+    }
+    let publisher = state
+        .ingest_publisher
+        .as_ref()
+        .ok_or_else(|| ApiError::Internal("ingest publisher is not configured".to_string()))?;
 
-    let snapshot = engine.state_snapshot();
-    let phase = engine.phase().name().to_string();
+    let correction = TimingCorrection {
+        correction_id: Uuid::new_v4().to_string(),
+        operator: req.operator.trim().to_string(),
+        reason: req.reason.trim().to_string(),
+        change: req.change,
+    };
+    let intent = RaceControlIntentV1::CorrectTiming {
+        moto_id: req.moto_id.clone(),
+        correction: map_correction_from_domain(correction.clone()),
+    };
+    publisher
+        .publish_race_control_intent(&build_control_intent_envelope(track_id.clone(), intent))
+        .await
+        .map_err(|e| ApiError::Internal(format!("Failed to publish correction intent: {e}")))?;
+    correction_queries::insert_correction(&state.db, &req.moto_id, &correction).await?;
+
+    // A moto timed from this server's own decoder is on its local engine
+    if let Some(engine) = state.engines.get(&track_id).await {
+        let mut engine = engine.lock().await;
+        if engine.state().moto_phase(&req.moto_id).is_some() {
+            engine.apply(RaceCommand::CorrectTiming {
+                moto_id: req.moto_id,
+                correction,
+            });
+        }
+    }
+
+    let engine = worker_race_state(&state, &track_id).await?;
+    Ok(Json(state_response(&engine.unwrap_or_default())))
+}
+
+/// GET /api/tracks/:track_id/race/passings — Passings of a moto on the track,
+/// by default the one staged last, with corrections marked
+///
+/// Read from the race worker's saved state, so passings from the last few
+/// seconds may not be listed yet.
+pub async fn list_passings(
+    State(state): State<AppState>,
+    Path(track_id): Path<String>,
+    Query(query): Query<MotoQuery>,
+) -> Result<Json<Vec<RecordedPassing>>, ApiError> {
+    let passings = worker_race_state(&state, &track_id)
+        .await?
+        .map(|engine| engine.recorded_passings(query.moto_id.as_deref()))
+        .unwrap_or_default();
+    Ok(Json(passings))
+}
+
+/// POST /api/tracks/:track_id/race/practice/start — Open a practice session
+/// on the track
+///
+/// Every transponder crossing the track's loops is timed. Names come from
+/// the riders table, matched by transponder ID or transponder string.
+pub async fn start_practice(
+    State(state): State<AppState>,
+    Path(track_id): Path<String>,
+) -> Result<Json<RaceStateResponse>, ApiError> {
+    let (track_config, _) = load_track_config(&state, &track_id).await?;
+    let engine = state.engines.engine(&track_id).await;
+    {
+        let engine = engine.lock().await;
+        if !engine.state().is_idle() {
+            return Err(ApiError::BadRequest(format!(
+                "Cannot start practice while {}",
+                engine.state().phase().name()
+            )));
+        }
+    }
+
+    let riders: Vec<KnownRider> = crate::db::queries::riders::list_riders(&state.db, None)
+        .await?
+        .into_iter()
+        .map(|rider| KnownRider {
+            rider_id: rider.id,
+            first_name: rider.first_name,
+            last_name: rider.last_name,
+            plate_number: rider.plate_number,
+            transponder_id: rider.transponder_id as u32,
+            transponder_string: rider.transponder_string,
+        })
+        .collect();
+
+    let publisher = state
+        .ingest_publisher
+        .as_ref()
+        .ok_or_else(|| ApiError::Internal("ingest publisher is not configured".to_string()))?;
+
+    let session_id = Uuid::new_v4().to_string();
+    crate::db::queries::practice::create_session(&state.db, &session_id, &track_id).await?;
 
-    Ok(Json(RaceStateResponse { phase, snapshot }))
+    let intent = RaceControlIntentV1::StartPractice {
+        session_id: session_id.clone(),
+        track_config: map_track_config_to_contract(&track_config),
+        riders: riders.iter().map(map_known_rider_to_contract).collect(),
+    };
+    publisher
+        .publish_race_control_intent(&build_control_intent_envelope(track_id, intent))
+        .await
+        .map_err(|e| ApiError::Internal(format!("Failed to publish practice intent: {e}")))?;
+
+    let mut engine = engine.lock().await;
+    engine.apply(RaceCommand::SetTrack(track_config));
+    engine.apply(RaceCommand::StartPractice { session_id, riders });
+    Ok(Json(state_response(engine.state())))
 }
 
-/// POST /api/race/reset — Reset race to idle
-pub async fn reset(State(state): State<AppState>) -> Json<RaceStateResponse> {
-    if let Some(track_id) = resolve_track_id_for_active_moto(&state).await {
-        if let Some(publisher) = &state.ingest_publisher {
-            let envelope =
-                build_control_intent_envelope(track_id, RaceControlIntentV1::Reset);
-            if let Err(error) = publisher.publish_race_control_intent(&envelope).await {
-                warn!(error = %error, "Failed to publish reset race control intent");
-            }
-        } else {
-            warn!("Skipping reset race control intent publish: ingest publisher unavailable");
+/// POST /api/tracks/:track_id/race/practice/stop — End the track's practice session
+pub async fn stop_practice(
+    State(state): State<AppState>,
+    Path(track_id): Path<String>,
+) -> Result<Json<RaceStateResponse>, ApiError> {
+    let engine = running_engine(&state, &track_id).await?;
+    if !matches!(
+        engine.lock().await.state().phase(),
+        RacePhase::Practice { .. }
+    ) {
+        return Err(ApiError::BadRequest("No practice session running".into()));
+    }
+
+    if let Some(publisher) = &state.ingest_publisher {
+        let envelope = build_control_intent_envelope(track_id, RaceControlIntentV1::StopPractice);
+        if let Err(error) = publisher.publish_race_control_intent(&envelope).await {
+            warn!(error = %error, "Failed to publish stop-practice race control intent");
         }
     }
 
-    let mut engine = state.engine.lock().await;
-    engine.reset();
-    let snapshot = engine.state_snapshot();
-    let phase = engine.phase().name().to_string();
-    Json(RaceStateResponse { phase, snapshot })
+    let mut engine = engine.lock().await;
+    engine.apply(RaceCommand::StopPractice);
+    Ok(Json(state_response(engine.state())))
 }
 
-/// POST /api/race/force-finish — Force the current race to finish
+/// POST /api/tracks/:track_id/race/reset — Reset the track's race to idle
+pub async fn reset(
+    State(state): State<AppState>,
+    Path(track_id): Path<String>,
+) -> Json<RaceStateResponse> {
+    let Some(engine) = state.engines.get(&track_id).await else {
+        return Json(state_response(&RaceState::new()));
+    };
+
+    if let Some(publisher) = &state.ingest_publisher {
+        let envelope = build_control_intent_envelope(track_id, RaceControlIntentV1::Reset);
+        if let Err(error) = publisher.publish_race_control_intent(&envelope).await {
+            warn!(error = %error, "Failed to publish reset race control intent");
+        }
+    } else {
+        warn!("Skipping reset race control intent publish: ingest publisher unavailable");
+    }
+
+    let mut engine = engine.lock().await;
+    engine.apply(RaceCommand::Reset);
+    Json(state_response(engine.state()))
+}
+
+/// POST /api/tracks/:track_id/race/force-finish — Force a moto to finish, by
+/// default the oldest one on course
 pub async fn force_finish(
     State(state): State<AppState>,
+    Path(track_id): Path<String>,
+    Query(query): Query<MotoQuery>,
 ) -> Result<Json<RaceStateResponse>, ApiError> {
-    if let Some(track_id) = resolve_track_id_for_active_moto(&state).await {
-        if let Some(publisher) = &state.ingest_publisher {
-            let envelope =
-                build_control_intent_envelope(track_id, RaceControlIntentV1::ForceFinish);
-            if let Err(error) = publisher.publish_race_control_intent(&envelope).await {
-                warn!(error = %error, "Failed to publish force-finish race control intent");
-            }
-        } else {
-            warn!(
-                "Skipping force-finish race control intent publish: ingest publisher unavailable"
-            );
+    let engine = running_engine(&state, &track_id).await?;
+    let moto_id = query.moto_id;
+
+    if let Some(publisher) = &state.ingest_publisher {
+        let intent = RaceControlIntentV1::ForceFinish {
+            moto_id: moto_id.clone(),
+        };
+        let envelope = build_control_intent_envelope(track_id, intent);
+        if let Err(error) = publisher.publish_race_control_intent(&envelope).await {
+            warn!(error = %error, "Failed to publish force-finish race control intent");
         }
+    } else {
+        warn!("Skipping force-finish race control intent publish: ingest publisher unavailable");
     }
 
-    let mut engine = state.engine.lock().await;
-    engine.force_finish();
-    let snapshot = engine.state_snapshot();
-    let phase = engine.phase().name().to_string();
-    Ok(Json(RaceStateResponse { phase, snapshot }))
+    let mut engine = engine.lock().await;
+    engine.apply(RaceCommand::ForceFinish { moto_id });
+    Ok(Json(state_response(engine.state())))
+}
+
+/// GET /api/tracks/:track_id/race/state — Get the track's current race state
+pub async fn get_state(
+    State(state): State<AppState>,
+    Path(track_id): Path<String>,
+) -> Json<RaceStateResponse> {
+    match state.engines.get(&track_id).await {
+        Some(engine) => Json(state_response(engine.lock().await.state())),
+        None => Json(state_response(&RaceState::new())),
+    }
+}
+
+fn state_response(engine: &RaceState) -> RaceStateResponse {
+    RaceStateResponse {
+        phase: engine.phase().name().to_string(),
+        snapshot: engine.state_snapshot(),
+    }
+}
+
+/// The track's race state as the race worker last saved it
+async fn worker_race_state(
+    state: &AppState,
+    track_id: &str,
+) -> Result<Option<RaceState>, ApiError> {
+    state
+        .worker_race_state(track_id)
+        .await
+        .map_err(|e| ApiError::Internal(format!("Failed to load race worker state: {e}")))
+}
+
+/// The track's engine, for commands that act on a race already running there
+async fn running_engine(
+    state: &AppState,
+    track_id: &str,
+) -> Result<Arc<Mutex<RaceEngine>>, ApiError> {
+    state
+        .engines
+        .get(track_id)
+        .await
+        .ok_or_else(|| ApiError::BadRequest(format!("Nothing is running on track {track_id}")))
+}
+
+/// Load a track and its timing loops as engine configuration, with the
+/// track's default race rules
+async fn load_track_config(
+    state: &AppState,
+    track_id: &str,
+) -> Result<(TrackConfig, RaceRules), ApiError> {
+    let track_row =
+        sqlx::query_as::<_, crate::db::models::TrackRow>("SELECT * FROM tracks WHERE id = ?")
+            .bind(track_id)
+            .fetch_optional(&state.db)
+            .await?
+            .ok_or_else(|| ApiError::NotFound(format!("Track {} not found", track_id)))?;
+
+    let loop_rows = sqlx::query_as::<_, crate::db::models::TimingLoopRow>(
+        "SELECT * FROM timing_loops WHERE track_id = ? ORDER BY position",
+    )
+    .bind(track_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    let rules = race_rules(
+        track_row.dns_timeout_secs,
+        track_row.dnf_timeout_secs,
+        track_row.max_race_secs,
+    );
+    let track_config = TrackConfig {
+        track_id: track_row.id.clone(),
+        name: track_row.name.clone(),
+        gate_beacon_id: track_row.gate_beacon_id as u32,
+        loops: loop_rows
+            .iter()
+            .map(|l| LoopConfig {
+                loop_id: l.id.clone(),
+                name: l.name.clone(),
+                decoder_id: l.decoder_id.clone(),
+                position: l.position as u32,
+                is_start: l.is_start,
+                is_finish: l.is_finish,
+                debounce_ms: l.debounce_ms as u32,
+            })
+            .collect(),
+    };
+
+    Ok((track_config, rules))
 }
 
-/// GET /api/race/state — Get current race state
-pub async fn get_state(State(state): State<AppState>) -> Json<RaceStateResponse> {
-    let engine = state.engine.lock().await;
-    let snapshot = engine.state_snapshot();
-    let phase = engine.phase().name().to_string();
-    Json(RaceStateResponse { phase, snapshot })
+/// Race rules from their nullable columns
+fn race_rules(
+    dns_timeout_secs: Option<i64>,
+    dnf_timeout_secs: Option<i64>,
+    max_race_secs: Option<i64>,
+) -> RaceRules {
+    let secs = |column: Option<i64>| column.and_then(|secs| u32::try_from(secs).ok());
+    RaceRules {
+        dns_timeout_secs: secs(dns_timeout_secs),
+        dnf_timeout_secs: secs(dnf_timeout_secs),
+        max_race_secs: secs(max_race_secs),
+    }
 }
 
 fn map_track_config_to_contract(track_config: &TrackConfig) -> TrackConfigV1 {
@@ -219,6 +648,7 @@ fn map_track_config_to_contract(track_config: &TrackConfig) -> TrackConfigV1 {
                 position: loop_config.position,
                 is_start: loop_config.is_start,
                 is_finish: loop_config.is_finish,
+                debounce_ms: loop_config.debounce_ms,
             })
             .collect(),
     }
@@ -235,6 +665,17 @@ fn map_staged_rider_to_contract(rider: &StagedRider) -> StagedRiderV1 {
     }
 }
 
+fn map_known_rider_to_contract(rider: &KnownRider) -> KnownRiderV1 {
+    KnownRiderV1 {
+        rider_id: rider.rider_id.clone(),
+        first_name: rider.first_name.clone(),
+        last_name: rider.last_name.clone(),
+        plate_number: rider.plate_number.clone(),
+        transponder_id: rider.transponder_id,
+        transponder_string: rider.transponder_string.clone(),
+    }
+}
+
 fn build_control_intent_envelope(
     track_id: String,
     intent: RaceControlIntentV1,
@@ -248,34 +689,15 @@ fn build_control_intent_envelope(
     }
 }
 
-async fn resolve_track_id_for_active_moto(state: &AppState) -> Option<String> {
-    let active_moto_id = {
-        let engine = state.engine.lock().await;
-        match engine.state_snapshot() {
-            RaceEvent::StateSnapshot { moto_id, .. } => moto_id,
-            _ => None,
-        }
-    };
-
-    let Some(moto_id) = active_moto_id else {
-        return None;
-    };
-
-    let row = match sqlx::query_as::<_, (String,)>(
+/// Track of the meeting a moto belongs to
+async fn moto_track_id(state: &AppState, moto_id: &str) -> Result<Option<String>, ApiError> {
+    let row = sqlx::query_as::<_, (String,)>(
         "SELECT events.track_id FROM motos JOIN events ON events.id = motos.event_id WHERE motos.id = ?",
     )
     .bind(moto_id)
     .fetch_optional(&state.db)
-    .await
-    {
-        Ok(row) => row,
-        Err(error) => {
-            warn!(error = %error, "Failed to resolve track id for active moto");
-            return None;
-        }
-    };
-
-    row.map(|(track_id,)| track_id)
+    .await?;
+    Ok(row.map(|(track_id,)| track_id))
 }
 
 fn now_unix_micros() -> u64 {
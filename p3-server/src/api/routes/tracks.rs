@@ -9,6 +9,7 @@ use crate::api::error::ApiError;
 use crate::api::state::AppState;
 use crate::db::models::{TimingLoopRow, TrackRow, TrackSectionRow};
 use crate::db::queries::tracks;
+use crate::domain::race_event::RaceRules;
 
 // Response type that includes track + its loops + sections
 #[derive(serde::Serialize)]
@@ -39,6 +40,21 @@ pub struct CreateLoopRequest {
     pub is_finish: bool,
     #[serde(default)]
     pub is_start: bool,
+    /// Window in which a stronger repeat hit replaces a rider's crossing
+    #[serde(default)]
+    pub debounce_ms: i64,
+}
+
+impl CreateLoopRequest {
+    fn validate(&self) -> Result<(), ApiError> {
+        if !(0..=i64::from(u32::MAX)).contains(&self.debounce_ms) {
+            return Err(ApiError::BadRequest(format!(
+                "debounce_ms must be between 0 and {}",
+                u32::MAX
+            )));
+        }
+        Ok(())
+    }
 }
 
 pub async fn list(State(state): State<AppState>) -> Result<Json<Vec<TrackRow>>, ApiError> {
@@ -141,6 +157,7 @@ pub async fn create_loop(
     tracks::get_track(&state.db, &track_id)
         .await?
         .ok_or_else(|| ApiError::NotFound(format!("Track {} not found", track_id)))?;
+    body.validate()?;
 
     let timing_loop = tracks::create_timing_loop(
         &state.db,
@@ -150,6 +167,7 @@ pub async fn create_loop(
         body.position,
         body.is_finish,
         body.is_start,
+        body.debounce_ms,
     )
     .await?;
 
@@ -161,6 +179,7 @@ pub async fn update_loop(
     Path((_track_id, loop_id)): Path<(String, String)>,
     Json(body): Json<CreateLoopRequest>,
 ) -> Result<Json<TimingLoopRow>, ApiError> {
+    body.validate()?;
     tracks::update_timing_loop(
         &state.db,
         &loop_id,
@@ -169,6 +188,7 @@ pub async fn update_loop(
         body.position,
         body.is_finish,
         body.is_start,
+        body.debounce_ms,
     )
     .await?
     .ok_or_else(|| ApiError::NotFound(format!("Loop {} not found", loop_id)))
@@ -227,6 +247,35 @@ pub async fn save_sections(
     Ok(Json(saved))
 }
 
+/// PUT /api/tracks/:id/race-rules — Default DNS/DNF rules for races on the track
+pub async fn set_race_rules(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(rules): Json<RaceRules>,
+) -> Result<Json<TrackRow>, ApiError> {
+    validate_race_rules(&rules)?;
+
+    tracks::set_race_rules(&state.db, &id, &rules)
+        .await?
+        .ok_or_else(|| ApiError::NotFound(format!("Track {} not found", id)))
+        .map(Json)
+}
+
+pub(crate) fn validate_race_rules(rules: &RaceRules) -> Result<(), ApiError> {
+    for (name, value) in [
+        ("dns_timeout_secs", rules.dns_timeout_secs),
+        ("dnf_timeout_secs", rules.dnf_timeout_secs),
+        ("max_race_secs", rules.max_race_secs),
+    ] {
+        if value == Some(0) {
+            return Err(ApiError::BadRequest(format!(
+                "{name} must be at least 1, or null to disable"
+            )));
+        }
+    }
+    Ok(())
+}
+
 fn validate_geo_fields(latitude: Option<f64>, longitude: Option<f64>) -> Result<(), ApiError> {
     if latitude.is_some() != longitude.is_some() {
         return Err(ApiError::BadRequest(
@@ -234,20 +283,20 @@ fn validate_geo_fields(latitude: Option<f64>, longitude: Option<f64>) -> Result<
         ));
     }
 
-    if let Some(lat) = latitude {
-        if !(-90.0..=90.0).contains(&lat) {
-            return Err(ApiError::BadRequest(
-                "latitude must be between -90 and 90".to_string(),
-            ));
-        }
+    if let Some(lat) = latitude
+        && !(-90.0..=90.0).contains(&lat)
+    {
+        return Err(ApiError::BadRequest(
+            "latitude must be between -90 and 90".to_string(),
+        ));
     }
 
-    if let Some(lon) = longitude {
-        if !(-180.0..=180.0).contains(&lon) {
-            return Err(ApiError::BadRequest(
-                "longitude must be between -180 and 180".to_string(),
-            ));
-        }
+    if let Some(lon) = longitude
+        && !(-180.0..=180.0).contains(&lon)
+    {
+        return Err(ApiError::BadRequest(
+            "longitude must be between -180 and 180".to_string(),
+        ));
     }
 
     Ok(())
@@ -1,21 +1,21 @@
 use p3_parser::Message;
 use sqlx::SqlitePool;
 use std::sync::Arc;
-use tokio::sync::{Mutex, broadcast};
+use tokio::sync::broadcast;
 
-use crate::domain::race_event::RaceEvent;
-use crate::engine::RaceEngine;
+use crate::engine::{EngineRegistry, RaceState, TrackRaceEvent};
 use crate::ingest::publisher::IngestPublisher;
+use crate::workers::race::load_track_engine;
 
 /// Shared application state available to all Axum handlers.
 #[derive(Clone)]
 pub struct AppState {
     /// Broadcast channel for real-time P3 messages to WebSocket clients.
     pub message_tx: broadcast::Sender<Arc<Message>>,
-    /// Broadcast channel for race events to WebSocket clients.
-    pub race_event_tx: broadcast::Sender<Arc<RaceEvent>>,
-    /// The race engine (mutable, behind a mutex for shared access).
-    pub engine: Arc<Mutex<RaceEngine>>,
+    /// Broadcast channel for race events to WebSocket clients, tagged by track.
+    pub race_event_tx: broadcast::Sender<Arc<TrackRaceEvent>>,
+    /// One race engine per track, each behind a mutex for shared access.
+    pub engines: Arc<EngineRegistry>,
     /// SQLite connection pool.
     pub db: SqlitePool,
     /// Track ingest publisher (JetStream).
@@ -27,8 +27,8 @@ pub struct AppState {
 impl AppState {
     pub fn new(
         message_tx: broadcast::Sender<Arc<Message>>,
-        race_event_tx: broadcast::Sender<Arc<RaceEvent>>,
-        engine: Arc<Mutex<RaceEngine>>,
+        race_event_tx: broadcast::Sender<Arc<TrackRaceEvent>>,
+        engines: Arc<EngineRegistry>,
         db: SqlitePool,
         ingest_publisher: Option<Arc<IngestPublisher>>,
         nats_url: String,
@@ -36,10 +36,20 @@ impl AppState {
         Self {
             message_tx,
             race_event_tx,
-            engine,
+            engines,
             db,
             ingest_publisher,
             nats_url,
         }
     }
+
+    /// The track's race state as the race worker last saved it. The worker
+    /// times races from the track client's passings, which the engines in
+    /// [`Self::engines`] never see.
+    pub async fn worker_race_state(&self, track_id: &str) -> anyhow::Result<Option<RaceState>> {
+        match &self.ingest_publisher {
+            Some(publisher) => load_track_engine(publisher.jetstream(), track_id).await,
+            None => Ok(None),
+        }
+    }
 }
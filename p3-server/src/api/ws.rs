@@ -10,23 +10,45 @@ use futures_util::{SinkExt, StreamExt};
 use p3_contracts::{
     DecoderEventPayloadV1, DecoderSnapshotPayloadV1, DecoderStatusRowV1, EmptyPayloadV1,
     LiveChannelV1, LiveEnvelopeKindV1, LiveEnvelopeV1, LiveErrorPayloadV1, RaceEventEnvelopeV1,
-    RaceEventPayloadV1, build_race_events_subject,
+    RaceEventPayloadV1,
 };
 use serde::Deserialize;
-use std::collections::BTreeSet;
+use sqlx::SqlitePool;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::time::{SystemTime, UNIX_EPOCH};
 use tokio::select;
 use tokio::time::{self, Duration};
 use tracing::{info, warn};
 
+use super::live_feed::{LiveFrom, RaceEventFeed};
 use super::state::AppState;
 use crate::db::queries::decoder_live::{
     DecoderSnapshotRow as DbDecoderSnapshotRow, list_decoder_snapshot_rows_for_track,
 };
+use crate::db::queries::motos::get_moto;
+use crate::domain::race_event::RaceEvent;
+use crate::engine::RaceState;
+use crate::workers::race::map_domain_event_to_payload;
+
+#[derive(Debug, Deserialize)]
+pub struct LegacyQuery {
+    track_id: Option<String>,
+}
 
 /// WebSocket upgrade handler — each connected client receives P3 messages and race events as JSON.
-pub async fn ws_handler(ws: WebSocketUpgrade, State(state): State<AppState>) -> Response {
-    ws.on_upgrade(|socket| handle_socket(socket, state))
+///
+/// `track_id` limits race events to that track's engine; without it the
+/// client gets every track's.
+pub async fn ws_handler(
+    ws: WebSocketUpgrade,
+    State(state): State<AppState>,
+    Query(query): Query<LegacyQuery>,
+) -> Response {
+    let track_id = query
+        .track_id
+        .map(|track_id| track_id.trim().to_string())
+        .filter(|track_id| !track_id.is_empty());
+    ws.on_upgrade(|socket| handle_socket(socket, state, track_id))
 }
 
 #[derive(Debug, Deserialize)]
@@ -37,19 +59,82 @@ pub struct LiveQuery {
     from: Option<String>,
 }
 
+/// Envelope sequence numbers, monotonic within each channel
 #[derive(Default)]
 struct LiveSeq {
-    next: u64,
+    next: BTreeMap<LiveChannelV1, u64>,
 }
 
 impl LiveSeq {
-    fn next(&mut self) -> u64 {
-        self.next += 1;
-        self.next
+    fn next(&mut self, channel: LiveChannelV1) -> u64 {
+        let next = self.next.entry(channel).or_default();
+        *next += 1;
+        *next
+    }
+}
+
+/// Scopes race events to the subscription's optional `event_id`.
+///
+/// Race events name a moto, so the meeting they belong to is looked up once
+/// per moto. Track-wide events (resets, idle snapshots) carry no moto and
+/// are delivered to every subscription on the track.
+struct RaceEventScope {
+    requested_event_id: Option<String>,
+    moto_event_ids: HashMap<String, Option<String>>,
+}
+
+impl RaceEventScope {
+    fn new(requested_event_id: Option<String>) -> Self {
+        Self {
+            requested_event_id,
+            moto_event_ids: HashMap::new(),
+        }
+    }
+
+    /// Meeting the payload belongs to, used as the envelope's `event_id`
+    async fn event_id_for(
+        &mut self,
+        db: &SqlitePool,
+        payload: &RaceEventPayloadV1,
+    ) -> Option<String> {
+        let Some(moto_id) = race_payload_moto_id(payload) else {
+            return self.requested_event_id.clone();
+        };
+
+        if let Some(event_id) = self.moto_event_ids.get(moto_id) {
+            return event_id.clone();
+        }
+
+        match get_moto(db, moto_id).await {
+            Ok(row) => {
+                let event_id = row.map(|moto| moto.event_id);
+                self.moto_event_ids
+                    .insert(moto_id.to_string(), event_id.clone());
+                event_id
+            }
+            Err(error) => {
+                warn!(error = %error, moto_id = %moto_id, "Failed to resolve event for moto");
+                None
+            }
+        }
+    }
+
+    fn admits(&self, event_id: Option<&str>) -> bool {
+        match self.requested_event_id.as_deref() {
+            Some(requested) => event_id == Some(requested),
+            None => true,
+        }
     }
 }
 
 /// ADR-aligned live stream endpoint backed by NATS race event subjects.
+///
+/// Each requested channel gets a snapshot on connect, then incremental
+/// events from `timing.race.events.v1.{track_id}`: decoder messages on
+/// `decoder`, derived race events on `race`. `seq` counts per channel.
+///
+/// `from` replays history first (see [`LiveFrom`]); every envelope carries
+/// a `resume_token` the client can pass back as `from` after a reconnect.
 pub async fn ws_live_handler(
     ws: WebSocketUpgrade,
     State(state): State<AppState>,
@@ -70,14 +155,15 @@ pub async fn ws_live_handler(
         ));
     }
 
-    if let Some(from) = from.as_deref() {
-        if from != "now" {
-            return Err((
+    let from = match from.as_deref().map(str::trim) {
+        None | Some("") => LiveFrom::Now,
+        Some(raw) => raw.parse::<LiveFrom>().map_err(|_| {
+            (
                 StatusCode::BAD_REQUEST,
-                "from must be 'now' for /ws/v1/live".to_string(),
-            ));
-        }
-    }
+                "from must be 'now', 'seq:<n>', 'ts_us:<n>' or a resume token".to_string(),
+            )
+        })?,
+    };
 
     let selection = classify_channels(channels.as_deref());
 
@@ -89,17 +175,16 @@ pub async fn ws_live_handler(
             event_id,
             selection.supported,
             selection.issues,
+            from,
         )
     }))
 }
 
-async fn handle_socket(mut socket: WebSocket, state: AppState) {
-    info!("WebSocket client connected");
+async fn handle_socket(mut socket: WebSocket, state: AppState, track_id: Option<String>) {
+    info!(track_id = ?track_id, "WebSocket client connected");
 
-    // Send current race state snapshot to newly connected client
-    {
-        let engine = state.engine.lock().await;
-        let snapshot = engine.state_snapshot();
+    // Send current race state snapshots to newly connected client
+    for snapshot in legacy_snapshots(&state, track_id.as_deref()).await {
         if let Ok(json) = serde_json::to_string(&snapshot) {
             let _ = socket.send(WsMessage::text(json)).await;
         }
@@ -137,7 +222,10 @@ async fn handle_socket(mut socket: WebSocket, state: AppState) {
             result = race_rx.recv() => {
                 match result {
                     Ok(event) => {
-                        let json = match serde_json::to_string(event.as_ref()) {
+                        if track_id.as_deref().is_some_and(|track_id| track_id != event.track_id) {
+                            continue;
+                        }
+                        let json = match serde_json::to_string(&event.event) {
                             Ok(j) => j,
                             Err(e) => {
                                 warn!(error = %e, "Failed to serialize race event");
@@ -162,6 +250,30 @@ async fn handle_socket(mut socket: WebSocket, state: AppState) {
     }
 }
 
+/// Connect-time snapshots for the legacy socket: the requested track's
+/// engine, or every engine, falling back to a single idle snapshot
+async fn legacy_snapshots(state: &AppState, track_id: Option<&str>) -> Vec<RaceEvent> {
+    let engines: Vec<_> = match track_id {
+        Some(track_id) => state.engines.get(track_id).await.into_iter().collect(),
+        None => state
+            .engines
+            .all()
+            .await
+            .into_iter()
+            .map(|(_, engine)| engine)
+            .collect(),
+    };
+
+    let mut snapshots = Vec::new();
+    for engine in engines {
+        snapshots.push(engine.lock().await.state().state_snapshot());
+    }
+    if snapshots.is_empty() {
+        snapshots.push(RaceState::new().state_snapshot());
+    }
+    snapshots
+}
+
 async fn handle_live_socket(
     socket: WebSocket,
     state: AppState,
@@ -169,78 +281,161 @@ async fn handle_live_socket(
     requested_event_id: Option<String>,
     channels: BTreeSet<LiveChannelV1>,
     channel_issues: Vec<ChannelIssue>,
+    from: LiveFrom,
 ) {
-    info!(track_id = %track_id, "WebSocket /ws/v1/live client connected");
+    info!(track_id = %track_id, from = ?from, "WebSocket /ws/v1/live client connected");
 
     let stream_decoder_channel = channels.contains(&LiveChannelV1::Decoder);
+    let stream_race_channel = channels.contains(&LiveChannelV1::Race);
 
-    let mut nats_sub = if stream_decoder_channel {
-        let nats_client = match async_nats::connect(&state.nats_url).await {
-            Ok(client) => client,
+    // Both channels are fed from the track's race event subject
+    let mut feed = if stream_decoder_channel || stream_race_channel {
+        match RaceEventFeed::open(&state.nats_url, &track_id, from).await {
+            Ok(feed) => Some(feed),
             Err(error) => {
-                warn!(error = %error, "Failed to connect to NATS for live socket");
-                return;
-            }
-        };
-
-        let subject = build_race_events_subject(&track_id);
-        match nats_client.subscribe(subject.clone()).await {
-            Ok(sub) => Some(sub),
-            Err(error) => {
-                warn!(error = %error, subject = %subject, "Failed to subscribe to live race events");
+                warn!(error = %error, track_id = %track_id, "Failed to open live race event feed");
                 return;
             }
         }
     } else {
         None
     };
+    let resume_token =
+        |feed: &Option<RaceEventFeed>| feed.as_ref().map(|feed| feed.resume_token().to_string());
+    // A replay takes the place of the snapshot: the client already holds
+    // the state up to its resume point
+    let (send_snapshots, resume_unavailable) = match &feed {
+        Some(feed) => (!feed.start().replaying, feed.start().unavailable),
+        None => (true, false),
+    };
 
     let (mut sender, mut receiver) = socket.split();
     let mut seq = LiveSeq::default();
+    let mut race_scope = RaceEventScope::new(requested_event_id.clone());
     let mut heartbeat = time::interval(Duration::from_secs(10));
     heartbeat.set_missed_tick_behavior(time::MissedTickBehavior::Skip);
 
-    for channel in &channels {
-        if *channel != LiveChannelV1::Decoder {
-            continue;
+    if resume_unavailable {
+        for channel in &channels {
+            let envelope = LiveEnvelopeV1 {
+                kind: LiveEnvelopeKindV1::Error,
+                channel: *channel,
+                track_id: track_id.clone(),
+                event_id: requested_event_id.clone(),
+                seq: seq.next(*channel),
+                ts_us: now_unix_micros(),
+                resume_token: resume_token(&feed),
+                payload: LiveErrorPayloadV1 {
+                    code: "resume_unavailable".to_string(),
+                    message: "Resume point is no longer available, starting from a snapshot"
+                        .to_string(),
+                    channel: None,
+                },
+            };
+            if send_live_envelope(&mut sender, &envelope).await.is_err() {
+                return;
+            }
         }
+    }
+
+    for channel in channels.iter().filter(|_| send_snapshots) {
+        match channel {
+            LiveChannelV1::Decoder => {
+                let snapshot_rows = match list_decoder_snapshot_rows_for_track(&state.db, &track_id)
+                    .await
+                {
+                    Ok(rows) => rows,
+                    Err(error) => {
+                        warn!(error = %error, track_id = %track_id, "Failed to query decoder snapshot rows");
+                        let envelope = LiveEnvelopeV1 {
+                            kind: LiveEnvelopeKindV1::Error,
+                            channel: *channel,
+                            track_id: track_id.clone(),
+                            event_id: requested_event_id.clone(),
+                            seq: seq.next(*channel),
+                            ts_us: now_unix_micros(),
+                            resume_token: resume_token(&feed),
+                            payload: LiveErrorPayloadV1 {
+                                code: "snapshot_query_failed".to_string(),
+                                message: "Failed to load decoder snapshot".to_string(),
+                                channel: Some("decoder".to_string()),
+                            },
+                        };
+
+                        if send_live_envelope(&mut sender, &envelope).await.is_err() {
+                            return;
+                        }
+                        continue;
+                    }
+                };
 
-        let snapshot_rows = match list_decoder_snapshot_rows_for_track(&state.db, &track_id).await {
-            Ok(rows) => rows,
-            Err(error) => {
-                warn!(error = %error, track_id = %track_id, "Failed to query decoder snapshot rows");
                 let envelope = LiveEnvelopeV1 {
-                    kind: LiveEnvelopeKindV1::Error,
+                    kind: LiveEnvelopeKindV1::Snapshot,
                     channel: *channel,
                     track_id: track_id.clone(),
                     event_id: requested_event_id.clone(),
-                    seq: seq.next(),
+                    seq: seq.next(*channel),
                     ts_us: now_unix_micros(),
-                    payload: LiveErrorPayloadV1 {
-                        code: "snapshot_query_failed".to_string(),
-                        message: "Failed to load decoder snapshot".to_string(),
-                        channel: Some("decoder".to_string()),
-                    },
+                    resume_token: resume_token(&feed),
+                    payload: map_decoder_snapshot_rows(snapshot_rows),
                 };
-
                 if send_live_envelope(&mut sender, &envelope).await.is_err() {
                     return;
                 }
-                continue;
             }
-        };
+            LiveChannelV1::Race => {
+                // The race worker publishes the live race events, so the
+                // snapshot comes from its state too
+                let payload = match state.worker_race_state(&track_id).await {
+                    Ok(Some(engine)) => race_snapshot_payload(&engine, &track_id),
+                    Ok(None) => idle_race_snapshot(),
+                    Err(error) => {
+                        warn!(error = %error, track_id = %track_id, "Failed to load race worker snapshot");
+                        let envelope = LiveEnvelopeV1 {
+                            kind: LiveEnvelopeKindV1::Error,
+                            channel: *channel,
+                            track_id: track_id.clone(),
+                            event_id: requested_event_id.clone(),
+                            seq: seq.next(*channel),
+                            ts_us: now_unix_micros(),
+                            resume_token: resume_token(&feed),
+                            payload: LiveErrorPayloadV1 {
+                                code: "snapshot_query_failed".to_string(),
+                                message: "Failed to load race snapshot".to_string(),
+                                channel: Some("race".to_string()),
+                            },
+                        };
 
-        let envelope = LiveEnvelopeV1 {
-            kind: LiveEnvelopeKindV1::Snapshot,
-            channel: *channel,
-            track_id: track_id.clone(),
-            event_id: requested_event_id.clone(),
-            seq: seq.next(),
-            ts_us: now_unix_micros(),
-            payload: map_decoder_snapshot_rows(snapshot_rows),
-        };
-        if send_live_envelope(&mut sender, &envelope).await.is_err() {
-            return;
+                        if send_live_envelope(&mut sender, &envelope).await.is_err() {
+                            return;
+                        }
+                        continue;
+                    }
+                };
+
+                // A moto from another meeting is not part of this subscription
+                let event_id = race_scope.event_id_for(&state.db, &payload).await;
+                let (event_id, payload) = if race_scope.admits(event_id.as_deref()) {
+                    (event_id, payload)
+                } else {
+                    (requested_event_id.clone(), idle_race_snapshot())
+                };
+
+                let envelope = LiveEnvelopeV1 {
+                    kind: LiveEnvelopeKindV1::Snapshot,
+                    channel: *channel,
+                    track_id: track_id.clone(),
+                    event_id,
+                    seq: seq.next(*channel),
+                    ts_us: now_unix_micros(),
+                    resume_token: resume_token(&feed),
+                    payload,
+                };
+                if send_live_envelope(&mut sender, &envelope).await.is_err() {
+                    return;
+                }
+            }
+            LiveChannelV1::Unknown => {}
         }
     }
 
@@ -250,8 +445,9 @@ async fn handle_live_socket(
             channel: issue.envelope_channel,
             track_id: track_id.clone(),
             event_id: requested_event_id.clone(),
-            seq: seq.next(),
+            seq: seq.next(issue.envelope_channel),
             ts_us: now_unix_micros(),
+            resume_token: resume_token(&feed),
             payload: LiveErrorPayloadV1 {
                 code: issue.code.to_string(),
                 message: issue.message,
@@ -265,39 +461,55 @@ async fn handle_live_socket(
 
     loop {
         select! {
-            nats_message = async {
-                if let Some(sub) = &mut nats_sub {
-                    sub.next().await
-                } else {
-                    None
+            derived = async {
+                match &mut feed {
+                    Some(feed) => feed.next().await,
+                    None => None,
                 }
-            }, if stream_decoder_channel => {
-                let Some(message) = nats_message else {
+            }, if feed.is_some() => {
+                let Some(derived) = derived else {
                     break;
                 };
 
-                let derived: RaceEventEnvelopeV1 = match serde_json::from_slice(&message.payload) {
-                    Ok(derived) => derived,
-                    Err(error) => {
-                        warn!(error = %error, "Failed to parse race event envelope from NATS");
-                        continue;
+                let sent = match live_channel_for_payload(&derived.payload) {
+                    LiveChannelV1::Decoder if stream_decoder_channel => {
+                        let Some(payload) = map_decoder_event_payload(&derived) else {
+                            continue;
+                        };
+                        let envelope = LiveEnvelopeV1 {
+                            kind: LiveEnvelopeKindV1::Event,
+                            channel: LiveChannelV1::Decoder,
+                            track_id: track_id.clone(),
+                            event_id: Some(derived.event_id.to_string()),
+                            seq: seq.next(LiveChannelV1::Decoder),
+                            ts_us: derived.ts_us,
+                            resume_token: resume_token(&feed),
+                            payload,
+                        };
+                        send_live_envelope(&mut sender, &envelope).await
+                    }
+                    LiveChannelV1::Race if stream_race_channel => {
+                        let event_id = race_scope.event_id_for(&state.db, &derived.payload).await;
+                        if !race_scope.admits(event_id.as_deref()) {
+                            continue;
+                        }
+                        let envelope = LiveEnvelopeV1 {
+                            kind: LiveEnvelopeKindV1::Event,
+                            channel: LiveChannelV1::Race,
+                            track_id: track_id.clone(),
+                            event_id,
+                            seq: seq.next(LiveChannelV1::Race),
+                            ts_us: derived.ts_us,
+                            resume_token: resume_token(&feed),
+                            payload: derived.payload,
+                        };
+                        send_live_envelope(&mut sender, &envelope).await
                     }
+                    _ => continue,
                 };
 
-                if let Some(payload) = map_decoder_event_payload(&derived) {
-                    let envelope = LiveEnvelopeV1 {
-                        kind: LiveEnvelopeKindV1::Event,
-                        channel: LiveChannelV1::Decoder,
-                        track_id: track_id.clone(),
-                        event_id: Some(derived.event_id.to_string()),
-                        seq: seq.next(),
-                        ts_us: derived.ts_us,
-                        payload,
-                    };
-
-                    if send_live_envelope(&mut sender, &envelope).await.is_err() {
-                        break;
-                    }
+                if sent.is_err() {
+                    break;
                 }
             }
             _ = heartbeat.tick() => {
@@ -307,8 +519,9 @@ async fn handle_live_socket(
                         channel: *channel,
                         track_id: track_id.clone(),
                         event_id: requested_event_id.clone(),
-                        seq: seq.next(),
+                        seq: seq.next(*channel),
                         ts_us: now_unix_micros(),
+                        resume_token: resume_token(&feed),
                         payload: EmptyPayloadV1 {},
                     };
 
@@ -363,12 +576,9 @@ fn classify_channels(raw: Option<&str>) -> ChannelSelection {
             "decoder" => {
                 supported.insert(LiveChannelV1::Decoder);
             }
-            "race" => issues.push(ChannelIssue {
-                requested_channel: "race".to_string(),
-                envelope_channel: LiveChannelV1::Race,
-                code: "unimplemented_channel",
-                message: "Channel 'race' is recognized but not implemented yet".to_string(),
-            }),
+            "race" => {
+                supported.insert(LiveChannelV1::Race);
+            }
             other => issues.push(ChannelIssue {
                 requested_channel: other.to_string(),
                 envelope_channel: LiveChannelV1::Unknown,
@@ -423,6 +633,65 @@ fn map_decoder_snapshot_rows(rows: Vec<DbDecoderSnapshotRow>) -> DecoderSnapshot
     }
 }
 
+/// Decoder messages go to the `decoder` channel, everything else is `race`
+fn live_channel_for_payload(payload: &RaceEventPayloadV1) -> LiveChannelV1 {
+    match payload {
+        RaceEventPayloadV1::DecoderMessage { .. } => LiveChannelV1::Decoder,
+        _ => LiveChannelV1::Race,
+    }
+}
+
+fn race_payload_moto_id(payload: &RaceEventPayloadV1) -> Option<&str> {
+    match payload {
+        RaceEventPayloadV1::RaceStaged { moto_id, .. }
+        | RaceEventPayloadV1::GateDrop { moto_id, .. }
+        | RaceEventPayloadV1::RiderStarted { moto_id, .. }
+        | RaceEventPayloadV1::StartListUpdated { moto_id, .. }
+        | RaceEventPayloadV1::SplitTime { moto_id, .. }
+        | RaceEventPayloadV1::SplitCorrected { moto_id, .. }
+        | RaceEventPayloadV1::PositionsUpdate { moto_id, .. }
+        | RaceEventPayloadV1::RiderFinished { moto_id, .. }
+        | RaceEventPayloadV1::RaceFinished { moto_id, .. }
+        | RaceEventPayloadV1::TimingCorrected { moto_id, .. }
+        | RaceEventPayloadV1::UnknownTransponder { moto_id, .. }
+        | RaceEventPayloadV1::UnmappedDecoder { moto_id, .. } => Some(moto_id),
+        RaceEventPayloadV1::StateSnapshot { moto_id, .. } => moto_id.as_deref(),
+        RaceEventPayloadV1::DecoderMessage { .. }
+        | RaceEventPayloadV1::ClockDrift { .. }
+        | RaceEventPayloadV1::RaceReset
+        | RaceEventPayloadV1::PracticeStarted { .. }
+        | RaceEventPayloadV1::PracticeUpdate { .. }
+        | RaceEventPayloadV1::PracticeEnded { .. } => None,
+    }
+}
+
+/// Current race state of `track_id` as seen by the track's engine.
+///
+/// An engine not configured for the track yet reports idle.
+fn race_snapshot_payload(engine: &RaceState, track_id: &str) -> RaceEventPayloadV1 {
+    if engine.track_id() != Some(track_id) {
+        return idle_race_snapshot();
+    }
+
+    map_domain_event_to_payload(engine.state_snapshot()).unwrap_or_else(idle_race_snapshot)
+}
+
+fn idle_race_snapshot() -> RaceEventPayloadV1 {
+    RaceEventPayloadV1::StateSnapshot {
+        phase: "idle".to_string(),
+        moto_id: None,
+        class_name: None,
+        round_type: None,
+        riders: Vec::new(),
+        positions: Vec::new(),
+        gate_drop_time_us: None,
+        finished_count: 0,
+        total_riders: 0,
+        laps: 1,
+        practice: None,
+    }
+}
+
 fn map_decoder_event_payload(derived: &RaceEventEnvelopeV1) -> Option<DecoderEventPayloadV1> {
     match &derived.payload {
         RaceEventPayloadV1::DecoderMessage { message } => Some(DecoderEventPayloadV1 {
@@ -443,6 +712,8 @@ fn now_unix_micros() -> u64 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::domain::race_event::{StagedRider, StartMode, TrackConfig};
+    use crate::engine::{RaceCommand, apply};
     use p3_parser::{Message, StatusMessage};
     use uuid::Uuid;
 
@@ -461,26 +732,157 @@ mod tests {
     }
 
     #[test]
-    fn classify_channels_tracks_unsupported_and_unimplemented() {
+    fn classify_channels_supports_race_and_tracks_unsupported() {
         let parsed = classify_channels(Some("decoder,race,invalid"));
-        assert_eq!(parsed.supported, BTreeSet::from([LiveChannelV1::Decoder]));
-        assert_eq!(parsed.issues.len(), 2);
+        assert_eq!(
+            parsed.supported,
+            BTreeSet::from([LiveChannelV1::Decoder, LiveChannelV1::Race])
+        );
+        assert_eq!(parsed.issues.len(), 1);
 
-        assert_eq!(parsed.issues[0].requested_channel, "race");
-        assert_eq!(parsed.issues[0].envelope_channel, LiveChannelV1::Race);
-        assert_eq!(parsed.issues[0].code, "unimplemented_channel");
+        assert_eq!(parsed.issues[0].requested_channel, "invalid");
+        assert_eq!(parsed.issues[0].envelope_channel, LiveChannelV1::Unknown);
+        assert_eq!(parsed.issues[0].code, "unsupported_channel");
 
-        assert_eq!(parsed.issues[1].requested_channel, "invalid");
-        assert_eq!(parsed.issues[1].envelope_channel, LiveChannelV1::Unknown);
-        assert_eq!(parsed.issues[1].code, "unsupported_channel");
+        let race_only = classify_channels(Some("race"));
+        assert_eq!(race_only.supported, BTreeSet::from([LiveChannelV1::Race]));
+        assert!(race_only.issues.is_empty());
     }
 
     #[test]
-    fn live_seq_is_monotonic() {
+    fn live_seq_is_monotonic_per_channel() {
         let mut seq = LiveSeq::default();
-        assert_eq!(seq.next(), 1);
-        assert_eq!(seq.next(), 2);
-        assert_eq!(seq.next(), 3);
+        assert_eq!(seq.next(LiveChannelV1::Decoder), 1);
+        assert_eq!(seq.next(LiveChannelV1::Decoder), 2);
+        assert_eq!(seq.next(LiveChannelV1::Race), 1);
+        assert_eq!(seq.next(LiveChannelV1::Decoder), 3);
+        assert_eq!(seq.next(LiveChannelV1::Race), 2);
+    }
+
+    #[test]
+    fn race_payloads_route_to_race_channel() {
+        let gate_drop = RaceEventPayloadV1::GateDrop {
+            moto_id: "moto-1".to_string(),
+            timestamp_us: 1,
+        };
+        assert_eq!(live_channel_for_payload(&gate_drop), LiveChannelV1::Race);
+        assert_eq!(race_payload_moto_id(&gate_drop), Some("moto-1"));
+
+        let reset = RaceEventPayloadV1::RaceReset;
+        assert_eq!(live_channel_for_payload(&reset), LiveChannelV1::Race);
+        assert_eq!(race_payload_moto_id(&reset), None);
+
+        let decoder = RaceEventPayloadV1::DecoderMessage {
+            message: Message::Status(StatusMessage {
+                noise: 1,
+                gps_status: 0,
+                temperature: 0,
+                satellites: 0,
+                decoder_id: None,
+                extra_fields: Vec::new(),
+            }),
+        };
+        assert_eq!(live_channel_for_payload(&decoder), LiveChannelV1::Decoder);
+    }
+
+    #[test]
+    fn race_snapshot_payload_is_track_scoped() {
+        let (engine, _) = apply(
+            RaceState::new(),
+            RaceCommand::SetTrack(TrackConfig {
+                track_id: "track-a".to_string(),
+                name: "Track A".to_string(),
+                gate_beacon_id: 9992,
+                loops: Vec::new(),
+            }),
+        );
+        let (engine, _) = apply(
+            engine,
+            RaceCommand::Stage {
+                moto_id: "moto-1".to_string(),
+                class_name: "Novice".to_string(),
+                round_type: "moto1".to_string(),
+                laps: 1,
+                start_mode: StartMode::GateDrop,
+                riders: vec![StagedRider {
+                    rider_id: "rider-1".to_string(),
+                    first_name: "Sam".to_string(),
+                    last_name: "Lee".to_string(),
+                    plate_number: "12".to_string(),
+                    transponder_id: 1001,
+                    lane: 1,
+                }],
+            },
+        );
+
+        let RaceEventPayloadV1::StateSnapshot {
+            phase,
+            moto_id,
+            total_riders,
+            ..
+        } = race_snapshot_payload(&engine, "track-a")
+        else {
+            panic!("expected state snapshot");
+        };
+        assert_eq!(phase, "staged");
+        assert_eq!(moto_id.as_deref(), Some("moto-1"));
+        assert_eq!(total_riders, 1);
+
+        let RaceEventPayloadV1::StateSnapshot { phase, moto_id, .. } =
+            race_snapshot_payload(&engine, "track-b")
+        else {
+            panic!("expected state snapshot");
+        };
+        assert_eq!(phase, "idle");
+        assert!(moto_id.is_none());
+    }
+
+    #[tokio::test]
+    async fn race_event_scope_filters_by_meeting() {
+        let db = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        crate::db::run_migrations(&db).await.unwrap();
+        for sql in [
+            "INSERT INTO tracks (id, name) VALUES ('track-a', 'Track A')",
+            "INSERT INTO events (id, name, date, track_id) VALUES \
+             ('event-1', 'Day 1', '2026-01-01', 'track-a'), \
+             ('event-2', 'Day 2', '2026-01-02', 'track-a')",
+            "INSERT INTO event_classes (id, event_id, name, race_format) VALUES \
+             ('class-1', 'event-1', 'Novice', 'motos_only'), \
+             ('class-2', 'event-2', 'Novice', 'motos_only')",
+            "INSERT INTO motos (id, event_id, class_id, round_type, sequence) VALUES \
+             ('moto-1', 'event-1', 'class-1', 'moto1', 1), \
+             ('moto-2', 'event-2', 'class-2', 'moto1', 1)",
+        ] {
+            sqlx::query(sql).execute(&db).await.unwrap();
+        }
+
+        let gate_drop = |moto_id: &str| RaceEventPayloadV1::GateDrop {
+            moto_id: moto_id.to_string(),
+            timestamp_us: 1,
+        };
+
+        let mut scope = RaceEventScope::new(Some("event-1".to_string()));
+        let own = scope.event_id_for(&db, &gate_drop("moto-1")).await;
+        assert_eq!(own.as_deref(), Some("event-1"));
+        assert!(scope.admits(own.as_deref()));
+
+        let other = scope.event_id_for(&db, &gate_drop("moto-2")).await;
+        assert_eq!(other.as_deref(), Some("event-2"));
+        assert!(!scope.admits(other.as_deref()));
+
+        let unknown = scope.event_id_for(&db, &gate_drop("moto-x")).await;
+        assert!(!scope.admits(unknown.as_deref()));
+
+        // Track-wide events stay visible to every meeting on the track
+        let reset = scope
+            .event_id_for(&db, &RaceEventPayloadV1::RaceReset)
+            .await;
+        assert!(scope.admits(reset.as_deref()));
+
+        let mut unscoped = RaceEventScope::new(None);
+        let event_id = unscoped.event_id_for(&db, &gate_drop("moto-2")).await;
+        assert_eq!(event_id.as_deref(), Some("event-2"));
+        assert!(unscoped.admits(event_id.as_deref()));
     }
 
     #[test]
@@ -526,6 +928,7 @@ mod tests {
             temperature: 180,
             satellites: 10,
             decoder_id: Some("D1000C00".to_string()),
+            extra_fields: Vec::new(),
         });
 
         let derived = RaceEventEnvelopeV1 {
@@ -27,6 +27,9 @@ pub async fn run_migrations(pool: &SqlitePool) -> anyhow::Result<()> {
         include_str!("../../migrations/002_track_sections.sql"),
         include_str!("../../migrations/003_dev_ingest.sql"),
         include_str!("../../migrations/004_projection_dedupe.sql"),
+        include_str!("../../migrations/005_practice_sessions.sql"),
+        include_str!("../../migrations/006_timing_corrections.sql"),
+        include_str!("../../migrations/007_penalties.sql"),
     ];
 
     for migration_sql in &migrations {
@@ -39,6 +42,9 @@ pub async fn run_migrations(pool: &SqlitePool) -> anyhow::Result<()> {
     }
 
     migrate_track_location_columns(pool).await?;
+    migrate_timing_loop_debounce_column(pool).await?;
+    migrate_race_rule_columns(pool).await?;
+    migrate_effective_result_columns(pool).await?;
     migrate_legacy_ingest_unique_key(pool).await?;
 
     info!("Database migrations applied");
@@ -46,27 +52,88 @@ pub async fn run_migrations(pool: &SqlitePool) -> anyhow::Result<()> {
 }
 
 async fn migrate_track_location_columns(pool: &SqlitePool) -> anyhow::Result<()> {
+    add_missing_columns(
+        pool,
+        "tracks",
+        &[
+            ("location_label", "TEXT"),
+            ("timezone", "TEXT"),
+            ("latitude", "REAL"),
+            ("longitude", "REAL"),
+        ],
+    )
+    .await
+}
+
+async fn migrate_timing_loop_debounce_column(pool: &SqlitePool) -> anyhow::Result<()> {
+    add_missing_columns(
+        pool,
+        "timing_loops",
+        &[(
+            "debounce_ms",
+            "INTEGER NOT NULL DEFAULT 0 CHECK (debounce_ms >= 0)",
+        )],
+    )
+    .await
+}
+
+/// DNS/DNF rules: tracks hold the defaults, classes override them
+async fn migrate_race_rule_columns(pool: &SqlitePool) -> anyhow::Result<()> {
+    let columns = [
+        ("dns_timeout_secs", "INTEGER CHECK (dns_timeout_secs > 0)"),
+        ("dnf_timeout_secs", "INTEGER CHECK (dnf_timeout_secs > 0)"),
+        ("max_race_secs", "INTEGER CHECK (max_race_secs > 0)"),
+    ];
+    add_missing_columns(pool, "tracks", &columns).await?;
+    add_missing_columns(pool, "event_classes", &columns).await
+}
+
+/// Results after penalties. Entries persisted before penalties existed
+/// start out equal to their raw results.
+async fn migrate_effective_result_columns(pool: &SqlitePool) -> anyhow::Result<()> {
+    add_missing_columns(
+        pool,
+        "moto_entries",
+        &[
+            ("effective_position", "INTEGER"),
+            ("effective_elapsed_us", "INTEGER"),
+            ("effective_points", "INTEGER"),
+            ("dsq", "INTEGER NOT NULL DEFAULT 0"),
+        ],
+    )
+    .await?;
+    sqlx::query(
+        "UPDATE moto_entries SET \
+         effective_position = finish_position, \
+         effective_elapsed_us = elapsed_us, \
+         effective_points = points \
+         WHERE effective_points IS NULL AND points IS NOT NULL",
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+async fn add_missing_columns(
+    pool: &SqlitePool,
+    table: &str,
+    required_columns: &[(&str, &str)],
+) -> anyhow::Result<()> {
     #[derive(sqlx::FromRow)]
     struct TableInfoRow {
         name: String,
     }
 
-    let rows = sqlx::query_as::<_, TableInfoRow>("PRAGMA table_info(tracks)")
+    let rows = sqlx::query_as::<_, TableInfoRow>(&format!("PRAGMA table_info({table})"))
         .fetch_all(pool)
         .await?;
 
     let existing: HashSet<String> = rows.into_iter().map(|r| r.name).collect();
-    let required_columns = [
-        ("location_label", "TEXT"),
-        ("timezone", "TEXT"),
-        ("latitude", "REAL"),
-        ("longitude", "REAL"),
-    ];
 
     for (column_name, column_type) in required_columns {
-        if !existing.contains(column_name) {
+        if !existing.contains(*column_name) {
             sqlx::query(&format!(
-                "ALTER TABLE tracks ADD COLUMN {column_name} {column_type}"
+                "ALTER TABLE {table} ADD COLUMN {column_name} {column_type}"
             ))
             .execute(pool)
             .await?;
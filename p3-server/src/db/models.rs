@@ -12,6 +12,9 @@ pub struct TrackRow {
     pub longitude: Option<f64>,
     pub created_at: String,
     pub updated_at: String,
+    pub dns_timeout_secs: Option<i64>,
+    pub dnf_timeout_secs: Option<i64>,
+    pub max_race_secs: Option<i64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
@@ -24,6 +27,7 @@ pub struct TimingLoopRow {
     pub is_finish: bool,
     pub is_start: bool,
     pub created_at: String,
+    pub debounce_ms: i64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
@@ -76,6 +80,9 @@ pub struct EventClassRow {
     pub race_format: String,
     pub scoring: String,
     pub created_at: String,
+    pub dns_timeout_secs: Option<i64>,
+    pub dnf_timeout_secs: Option<i64>,
+    pub max_race_secs: Option<i64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
@@ -102,4 +109,31 @@ pub struct MotoEntryRow {
     pub dnf: bool,
     pub dns: bool,
     pub created_at: String,
+    /// Results after penalties; these count for standings
+    pub effective_position: Option<i64>,
+    pub effective_elapsed_us: Option<i64>,
+    pub effective_points: Option<i64>,
+    pub dsq: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct PracticeSessionRow {
+    pub id: String,
+    pub track_id: String,
+    pub started_at: String,
+    pub ended_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ProtestRow {
+    pub id: String,
+    pub moto_id: String,
+    pub rider_id: Option<String>,
+    pub filed_by: String,
+    pub description: String,
+    pub status: String,
+    pub decided_by: Option<String>,
+    pub decision_reason: Option<String>,
+    pub created_at: String,
+    pub decided_at: Option<String>,
 }
@@ -0,0 +1,70 @@
+use sqlx::SqlitePool;
+use sqlx::types::Json;
+
+use crate::domain::race_event::{TimingChange, TimingCorrection};
+
+/// Record a correction applied to a moto. Replays of the same correction are ignored.
+pub async fn insert_correction(
+    pool: &SqlitePool,
+    moto_id: &str,
+    correction: &TimingCorrection,
+) -> sqlx::Result<()> {
+    sqlx::query(
+        "INSERT OR IGNORE INTO timing_corrections \
+         (id, moto_id, operator, reason, change_json) \
+         VALUES (?, ?, ?, ?, ?)",
+    )
+    .bind(&correction.correction_id)
+    .bind(moto_id)
+    .bind(&correction.operator)
+    .bind(&correction.reason)
+    .bind(Json(&correction.change))
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Every correction applied to a moto, oldest first.
+pub async fn list_for_moto(
+    pool: &SqlitePool,
+    moto_id: &str,
+) -> sqlx::Result<Vec<TimingCorrectionRecord>> {
+    let rows = sqlx::query_as::<_, TimingCorrectionRow>(
+        "SELECT id, operator, reason, change_json, created_at \
+         FROM timing_corrections \
+         WHERE moto_id = ? \
+         ORDER BY rowid",
+    )
+    .bind(moto_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| TimingCorrectionRecord {
+            correction_id: row.id,
+            operator: row.operator,
+            reason: row.reason,
+            change: row.change_json.0,
+            created_at: row.created_at,
+        })
+        .collect())
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+struct TimingCorrectionRow {
+    id: String,
+    operator: String,
+    reason: String,
+    change_json: Json<TimingChange>,
+    created_at: String,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct TimingCorrectionRecord {
+    pub correction_id: String,
+    pub operator: String,
+    pub reason: String,
+    pub change: TimingChange,
+    pub created_at: String,
+}
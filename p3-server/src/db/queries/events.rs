@@ -1,6 +1,7 @@
 use sqlx::SqlitePool;
 
 use crate::db::models::{EventClassRow, EventRow};
+use crate::domain::race_event::RaceRules;
 
 // --- Events ---
 
@@ -82,6 +83,7 @@ pub async fn get_class(pool: &SqlitePool, id: &str) -> Result<Option<EventClassR
         .await
 }
 
+#[allow(clippy::too_many_arguments)]
 pub async fn create_class(
     pool: &SqlitePool,
     id: &str,
@@ -113,6 +115,25 @@ pub async fn create_class(
     get_class(pool, id).await?.ok_or(sqlx::Error::RowNotFound)
 }
 
+/// Set the class's DNS/DNF rules; unset rules fall back to the track's
+pub async fn set_class_race_rules(
+    pool: &SqlitePool,
+    id: &str,
+    rules: &RaceRules,
+) -> Result<Option<EventClassRow>, sqlx::Error> {
+    sqlx::query(
+        "UPDATE event_classes SET dns_timeout_secs = ?, dnf_timeout_secs = ?, max_race_secs = ? WHERE id = ?",
+    )
+    .bind(rules.dns_timeout_secs)
+    .bind(rules.dnf_timeout_secs)
+    .bind(rules.max_race_secs)
+    .bind(id)
+    .execute(pool)
+    .await?;
+
+    get_class(pool, id).await
+}
+
 pub async fn delete_class(pool: &SqlitePool, id: &str) -> Result<(), sqlx::Error> {
     sqlx::query("DELETE FROM event_classes WHERE id = ?")
         .bind(id)
@@ -1,7 +1,10 @@
+pub mod corrections;
 pub mod decoder_live;
 pub mod dev_ingest;
 pub mod events;
 pub mod motos;
+pub mod penalties;
+pub mod practice;
 pub mod results;
 pub mod riders;
 pub mod tracks;
@@ -0,0 +1,731 @@
+use sqlx::types::Json;
+use sqlx::{SqliteConnection, SqliteExecutor, SqlitePool};
+use uuid::Uuid;
+
+use crate::db::models::ProtestRow;
+use crate::domain::penalties::{
+    EffectiveResult, PenaltyKind, ProtestDecision, RawResult, effective_results,
+};
+
+/// A penalty to issue against a rider's result.
+#[derive(Debug, Clone)]
+pub struct NewPenalty<'a> {
+    pub moto_id: &'a str,
+    pub rider_id: &'a str,
+    pub penalty: &'a PenaltyKind,
+    pub reason: &'a str,
+    pub issued_by: &'a str,
+    /// The upheld protest that led to the penalty, if any
+    pub protest_id: Option<&'a str>,
+}
+
+/// An entry for a moto's change history.
+#[derive(Debug, Clone)]
+struct ResultChange<'a> {
+    moto_id: &'a str,
+    action: &'a str,
+    actor: &'a str,
+    reason: Option<&'a str>,
+    penalty_id: Option<&'a str>,
+    protest_id: Option<&'a str>,
+}
+
+/// Recompute a moto's effective results from its raw results and active
+/// penalties, and store them on its entries.
+pub async fn recompute_effective_results(
+    conn: &mut SqliteConnection,
+    moto_id: &str,
+) -> sqlx::Result<Vec<EffectiveResult>> {
+    let entries = sqlx::query_as::<_, RawResultRow>(
+        "SELECT rider_id, finish_position, elapsed_us, dnf, dns \
+         FROM moto_entries WHERE moto_id = ? ORDER BY lane",
+    )
+    .bind(moto_id)
+    .fetch_all(&mut *conn)
+    .await?;
+    let raw: Vec<RawResult> = entries
+        .into_iter()
+        .map(|row| RawResult {
+            rider_id: row.rider_id,
+            finish_position: row.finish_position.map(|p| p as u32),
+            elapsed_us: row.elapsed_us.map(|us| us as u64),
+            dnf: row.dnf,
+            dns: row.dns,
+        })
+        .collect();
+
+    let penalties: Vec<(String, PenaltyKind)> = sqlx::query_as::<_, (String, Json<PenaltyKind>)>(
+        "SELECT rider_id, penalty_json FROM penalties \
+         WHERE moto_id = ? AND status = 'active' ORDER BY rowid",
+    )
+    .bind(moto_id)
+    .fetch_all(&mut *conn)
+    .await?
+    .into_iter()
+    .map(|(rider_id, penalty)| (rider_id, penalty.0))
+    .collect();
+
+    let results = effective_results(&raw, &penalties);
+    for result in &results {
+        sqlx::query(
+            "UPDATE moto_entries SET \
+             effective_position = ?, \
+             effective_elapsed_us = ?, \
+             effective_points = ?, \
+             dsq = ? \
+             WHERE moto_id = ? AND rider_id = ?",
+        )
+        .bind(result.position.map(i64::from))
+        .bind(result.elapsed_us.map(|us| us as i64))
+        .bind(result.points)
+        .bind(result.dsq)
+        .bind(moto_id)
+        .bind(&result.rider_id)
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(results)
+}
+
+/// Recompute effective results after new raw results were recorded.
+pub async fn record_raw_results(conn: &mut SqliteConnection, moto_id: &str) -> sqlx::Result<()> {
+    let results = recompute_effective_results(conn, moto_id).await?;
+    record_change(
+        conn,
+        &ResultChange {
+            moto_id,
+            action: "results_recorded",
+            actor: "timing",
+            reason: None,
+            penalty_id: None,
+            protest_id: None,
+        },
+        Some(&results),
+    )
+    .await
+}
+
+/// Issue a penalty and recompute the moto's effective results.
+pub async fn issue_penalty(
+    conn: &mut SqliteConnection,
+    penalty: &NewPenalty<'_>,
+) -> sqlx::Result<PenaltyRecord> {
+    let id = Uuid::new_v4().to_string();
+    sqlx::query(
+        "INSERT INTO penalties \
+         (id, moto_id, rider_id, penalty_json, reason, issued_by, protest_id) \
+         VALUES (?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&id)
+    .bind(penalty.moto_id)
+    .bind(penalty.rider_id)
+    .bind(Json(penalty.penalty))
+    .bind(penalty.reason)
+    .bind(penalty.issued_by)
+    .bind(penalty.protest_id)
+    .execute(&mut *conn)
+    .await?;
+
+    let results = recompute_effective_results(conn, penalty.moto_id).await?;
+    record_change(
+        conn,
+        &ResultChange {
+            moto_id: penalty.moto_id,
+            action: "penalty_issued",
+            actor: penalty.issued_by,
+            reason: Some(penalty.reason),
+            penalty_id: Some(&id),
+            protest_id: penalty.protest_id,
+        },
+        Some(&results),
+    )
+    .await?;
+
+    get_penalty(conn, &id)
+        .await?
+        .ok_or(sqlx::Error::RowNotFound)
+}
+
+/// Revoke an active penalty and recompute the moto's effective results.
+/// Returns `None` if the penalty is not active.
+pub async fn revoke_penalty(
+    conn: &mut SqliteConnection,
+    id: &str,
+    revoked_by: &str,
+    reason: &str,
+) -> sqlx::Result<Option<PenaltyRecord>> {
+    let revoked = sqlx::query(
+        "UPDATE penalties SET status = 'revoked', revoked_by = ?, revoke_reason = ?, \
+         revoked_at = datetime('now') WHERE id = ? AND status = 'active'",
+    )
+    .bind(revoked_by)
+    .bind(reason)
+    .bind(id)
+    .execute(&mut *conn)
+    .await?;
+    if revoked.rows_affected() == 0 {
+        return Ok(None);
+    }
+
+    let Some(penalty) = get_penalty(&mut *conn, id).await? else {
+        return Ok(None);
+    };
+    let results = recompute_effective_results(conn, &penalty.moto_id).await?;
+    record_change(
+        conn,
+        &ResultChange {
+            moto_id: &penalty.moto_id,
+            action: "penalty_revoked",
+            actor: revoked_by,
+            reason: Some(reason),
+            penalty_id: Some(id),
+            protest_id: penalty.protest_id.as_deref(),
+        },
+        Some(&results),
+    )
+    .await?;
+    Ok(Some(penalty))
+}
+
+pub async fn get_penalty(
+    executor: impl SqliteExecutor<'_>,
+    id: &str,
+) -> sqlx::Result<Option<PenaltyRecord>> {
+    let row = sqlx::query_as::<_, PenaltyRow>("SELECT * FROM penalties WHERE id = ?")
+        .bind(id)
+        .fetch_optional(executor)
+        .await?;
+    Ok(row.map(PenaltyRecord::from))
+}
+
+/// Every penalty issued in a moto, revoked ones included, oldest first.
+pub async fn list_penalties(pool: &SqlitePool, moto_id: &str) -> sqlx::Result<Vec<PenaltyRecord>> {
+    let rows =
+        sqlx::query_as::<_, PenaltyRow>("SELECT * FROM penalties WHERE moto_id = ? ORDER BY rowid")
+            .bind(moto_id)
+            .fetch_all(pool)
+            .await?;
+    Ok(rows.into_iter().map(PenaltyRecord::from).collect())
+}
+
+/// File a protest, optionally naming the rider it is against.
+pub async fn file_protest(
+    conn: &mut SqliteConnection,
+    moto_id: &str,
+    rider_id: Option<&str>,
+    filed_by: &str,
+    description: &str,
+) -> sqlx::Result<ProtestRow> {
+    let id = Uuid::new_v4().to_string();
+    sqlx::query(
+        "INSERT INTO protests (id, moto_id, rider_id, filed_by, description) \
+         VALUES (?, ?, ?, ?, ?)",
+    )
+    .bind(&id)
+    .bind(moto_id)
+    .bind(rider_id)
+    .bind(filed_by)
+    .bind(description)
+    .execute(&mut *conn)
+    .await?;
+
+    record_change(
+        conn,
+        &ResultChange {
+            moto_id,
+            action: "protest_filed",
+            actor: filed_by,
+            reason: Some(description),
+            penalty_id: None,
+            protest_id: Some(&id),
+        },
+        None,
+    )
+    .await?;
+
+    get_protest(conn, &id)
+        .await?
+        .ok_or(sqlx::Error::RowNotFound)
+}
+
+/// Decide an open protest. Returns `None` if it was already decided.
+pub async fn decide_protest(
+    conn: &mut SqliteConnection,
+    id: &str,
+    decision: ProtestDecision,
+    decided_by: &str,
+    reason: &str,
+) -> sqlx::Result<Option<ProtestRow>> {
+    let decided = sqlx::query(
+        "UPDATE protests SET status = ?, decided_by = ?, decision_reason = ?, \
+         decided_at = datetime('now') WHERE id = ? AND status = 'open'",
+    )
+    .bind(decision.as_str())
+    .bind(decided_by)
+    .bind(reason)
+    .bind(id)
+    .execute(&mut *conn)
+    .await?;
+    if decided.rows_affected() == 0 {
+        return Ok(None);
+    }
+
+    let Some(protest) = get_protest(&mut *conn, id).await? else {
+        return Ok(None);
+    };
+    let action = match decision {
+        ProtestDecision::Upheld => "protest_upheld",
+        ProtestDecision::Denied => "protest_denied",
+    };
+    record_change(
+        conn,
+        &ResultChange {
+            moto_id: &protest.moto_id,
+            action,
+            actor: decided_by,
+            reason: Some(reason),
+            penalty_id: None,
+            protest_id: Some(id),
+        },
+        None,
+    )
+    .await?;
+    Ok(Some(protest))
+}
+
+pub async fn get_protest(
+    executor: impl SqliteExecutor<'_>,
+    id: &str,
+) -> sqlx::Result<Option<ProtestRow>> {
+    sqlx::query_as::<_, ProtestRow>("SELECT * FROM protests WHERE id = ?")
+        .bind(id)
+        .fetch_optional(executor)
+        .await
+}
+
+pub async fn list_protests(pool: &SqlitePool, moto_id: &str) -> sqlx::Result<Vec<ProtestRow>> {
+    sqlx::query_as::<_, ProtestRow>("SELECT * FROM protests WHERE moto_id = ? ORDER BY rowid")
+        .bind(moto_id)
+        .fetch_all(pool)
+        .await
+}
+
+/// A moto's change history, oldest first.
+pub async fn list_changes(
+    pool: &SqlitePool,
+    moto_id: &str,
+) -> sqlx::Result<Vec<ResultChangeRecord>> {
+    let rows = sqlx::query_as::<_, ResultChangeRow>(
+        "SELECT id, action, actor, reason, penalty_id, protest_id, results_json, created_at \
+         FROM result_changes WHERE moto_id = ? ORDER BY rowid",
+    )
+    .bind(moto_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| ResultChangeRecord {
+            id: row.id,
+            action: row.action,
+            actor: row.actor,
+            reason: row.reason,
+            penalty_id: row.penalty_id,
+            protest_id: row.protest_id,
+            results: row.results_json.map(|results| results.0),
+            created_at: row.created_at,
+        })
+        .collect())
+}
+
+async fn record_change(
+    conn: &mut SqliteConnection,
+    change: &ResultChange<'_>,
+    results: Option<&[EffectiveResult]>,
+) -> sqlx::Result<()> {
+    sqlx::query(
+        "INSERT INTO result_changes \
+         (id, moto_id, action, actor, reason, penalty_id, protest_id, results_json) \
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(change.moto_id)
+    .bind(change.action)
+    .bind(change.actor)
+    .bind(change.reason)
+    .bind(change.penalty_id)
+    .bind(change.protest_id)
+    .bind(results.map(Json))
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+struct RawResultRow {
+    rider_id: String,
+    finish_position: Option<i64>,
+    elapsed_us: Option<i64>,
+    dnf: bool,
+    dns: bool,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+struct PenaltyRow {
+    id: String,
+    moto_id: String,
+    rider_id: String,
+    penalty_json: Json<PenaltyKind>,
+    reason: String,
+    issued_by: String,
+    protest_id: Option<String>,
+    status: String,
+    revoked_by: Option<String>,
+    revoke_reason: Option<String>,
+    created_at: String,
+    revoked_at: Option<String>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct PenaltyRecord {
+    pub id: String,
+    pub moto_id: String,
+    pub rider_id: String,
+    pub penalty: PenaltyKind,
+    pub reason: String,
+    pub issued_by: String,
+    pub protest_id: Option<String>,
+    pub status: String,
+    pub revoked_by: Option<String>,
+    pub revoke_reason: Option<String>,
+    pub created_at: String,
+    pub revoked_at: Option<String>,
+}
+
+impl From<PenaltyRow> for PenaltyRecord {
+    fn from(row: PenaltyRow) -> Self {
+        Self {
+            id: row.id,
+            moto_id: row.moto_id,
+            rider_id: row.rider_id,
+            penalty: row.penalty_json.0,
+            reason: row.reason,
+            issued_by: row.issued_by,
+            protest_id: row.protest_id,
+            status: row.status,
+            revoked_by: row.revoked_by,
+            revoke_reason: row.revoke_reason,
+            created_at: row.created_at,
+            revoked_at: row.revoked_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+struct ResultChangeRow {
+    id: String,
+    action: String,
+    actor: String,
+    reason: Option<String>,
+    penalty_id: Option<String>,
+    protest_id: Option<String>,
+    results_json: Option<Json<Vec<EffectiveResult>>>,
+    created_at: String,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ResultChangeRecord {
+    pub id: String,
+    pub action: String,
+    pub actor: String,
+    pub reason: Option<String>,
+    pub penalty_id: Option<String>,
+    pub protest_id: Option<String>,
+    /// Effective results after the change, for changes that alter them
+    pub results: Option<Vec<EffectiveResult>>,
+    pub created_at: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::queries::results::{get_class_standings, persist_results};
+    use crate::db::run_migrations;
+    use crate::domain::race_event::FinishResult;
+
+    fn finish(rider_id: &str, position: u32, elapsed_us: Option<u64>) -> FinishResult {
+        FinishResult {
+            rider_id: rider_id.into(),
+            plate_number: String::new(),
+            first_name: String::new(),
+            last_name: String::new(),
+            position,
+            elapsed_us,
+            gap_to_leader_us: None,
+            dnf: elapsed_us.is_none(),
+            dns: false,
+            lap_times_us: Vec::new(),
+        }
+    }
+
+    async fn finished_moto() -> SqlitePool {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        run_migrations(&pool).await.unwrap();
+
+        for statement in [
+            "INSERT INTO tracks (id, name) VALUES ('track-1', 'Test Track')",
+            "INSERT INTO events (id, name, date, track_id) \
+             VALUES ('event-1', 'Test Event', '2026-01-01', 'track-1')",
+            "INSERT INTO event_classes (id, event_id, name, race_format) \
+             VALUES ('class-1', 'event-1', 'Novice', 'motos_only')",
+            "INSERT INTO motos (id, event_id, class_id, round_type, sequence) \
+             VALUES ('moto-1', 'event-1', 'class-1', 'moto1', 1)",
+        ] {
+            sqlx::query(statement).execute(&pool).await.unwrap();
+        }
+        for (lane, rider_id) in ["rider-1", "rider-2", "rider-3"].iter().enumerate() {
+            sqlx::query(
+                "INSERT INTO riders (id, first_name, last_name, plate_number, transponder_id) \
+                 VALUES (?, 'First', 'Last', ?, ?)",
+            )
+            .bind(rider_id)
+            .bind(lane.to_string())
+            .bind(1001 + lane as i64)
+            .execute(&pool)
+            .await
+            .unwrap();
+            sqlx::query(
+                "INSERT INTO event_class_riders (id, class_id, rider_id) VALUES (?, 'class-1', ?)",
+            )
+            .bind(Uuid::new_v4().to_string())
+            .bind(rider_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+            sqlx::query(
+                "INSERT INTO moto_entries (id, moto_id, rider_id, lane) VALUES (?, 'moto-1', ?, ?)",
+            )
+            .bind(Uuid::new_v4().to_string())
+            .bind(rider_id)
+            .bind(lane as i64 + 1)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        persist_results(
+            &pool,
+            "moto-1",
+            &[
+                finish("rider-1", 1, Some(30_000_000)),
+                finish("rider-2", 2, Some(31_000_000)),
+                finish("rider-3", 3, Some(32_000_000)),
+            ],
+        )
+        .await
+        .unwrap();
+        pool
+    }
+
+    async fn standings(pool: &SqlitePool) -> Vec<(String, i64, i64)> {
+        get_class_standings(pool, "class-1")
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|s| (s.rider_id, s.total_points, s.dsq_count))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_penalties_change_effective_results_and_standings() {
+        let pool = finished_moto().await;
+        assert_eq!(
+            standings(&pool).await,
+            vec![
+                ("rider-1".into(), 1, 0),
+                ("rider-2".into(), 2, 0),
+                ("rider-3".into(), 3, 0),
+            ]
+        );
+
+        let relegation = issue_penalty(
+            &mut pool.acquire().await.unwrap(),
+            &NewPenalty {
+                moto_id: "moto-1",
+                rider_id: "rider-1",
+                penalty: &PenaltyKind::Relegation { positions: None },
+                reason: "Illegal move in the first turn",
+                issued_by: "head-official",
+                protest_id: None,
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            standings(&pool).await,
+            vec![
+                ("rider-2".into(), 1, 0),
+                ("rider-3".into(), 2, 0),
+                ("rider-1".into(), 3, 0),
+            ]
+        );
+
+        // A protest against rider 2 is upheld with a DQ
+        let protest = file_protest(
+            &mut pool.acquire().await.unwrap(),
+            "moto-1",
+            Some("rider-2"),
+            "rider-3-parent",
+            "Cut the course",
+        )
+        .await
+        .unwrap();
+        assert_eq!(protest.status, "open");
+        let upheld = decide_protest(
+            &mut pool.acquire().await.unwrap(),
+            &protest.id,
+            ProtestDecision::Upheld,
+            "head-official",
+            "Seen on video",
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        assert_eq!(upheld.status, "upheld");
+        assert!(
+            decide_protest(
+                &mut pool.acquire().await.unwrap(),
+                &protest.id,
+                ProtestDecision::Denied,
+                "x",
+                "y"
+            )
+            .await
+            .unwrap()
+            .is_none()
+        );
+        issue_penalty(
+            &mut pool.acquire().await.unwrap(),
+            &NewPenalty {
+                moto_id: "moto-1",
+                rider_id: "rider-2",
+                penalty: &PenaltyKind::Disqualification,
+                reason: "Seen on video",
+                issued_by: "head-official",
+                protest_id: Some(&protest.id),
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            standings(&pool).await,
+            vec![
+                ("rider-3".into(), 1, 0),
+                ("rider-1".into(), 2, 0),
+                ("rider-2".into(), 4, 1),
+            ]
+        );
+
+        // Re-recorded raw results keep the penalties
+        persist_results(
+            &pool,
+            "moto-1",
+            &[
+                finish("rider-1", 1, Some(30_000_000)),
+                finish("rider-2", 2, Some(31_000_000)),
+                finish("rider-3", 3, Some(32_000_000)),
+            ],
+        )
+        .await
+        .unwrap();
+        assert_eq!(standings(&pool).await[0], ("rider-3".into(), 1, 0));
+
+        let revoked = revoke_penalty(
+            &mut pool.acquire().await.unwrap(),
+            &relegation.id,
+            "head-official",
+            "Overturned",
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        assert_eq!(revoked.status, "revoked");
+        assert!(
+            revoke_penalty(
+                &mut pool.acquire().await.unwrap(),
+                &relegation.id,
+                "head-official",
+                "Again"
+            )
+            .await
+            .unwrap()
+            .is_none()
+        );
+        assert_eq!(
+            standings(&pool).await,
+            vec![
+                ("rider-1".into(), 1, 0),
+                ("rider-3".into(), 2, 0),
+                ("rider-2".into(), 4, 1),
+            ]
+        );
+
+        let actions: Vec<String> = list_changes(&pool, "moto-1")
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|c| c.action)
+            .collect();
+        assert_eq!(
+            actions,
+            vec![
+                "results_recorded",
+                "penalty_issued",
+                "protest_filed",
+                "protest_upheld",
+                "penalty_issued",
+                "results_recorded",
+                "penalty_revoked",
+            ]
+        );
+        assert_eq!(list_penalties(&pool, "moto-1").await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_protest_decision_rolls_back_with_its_penalty() {
+        let pool = finished_moto().await;
+        let protest = file_protest(
+            &mut pool.acquire().await.unwrap(),
+            "moto-1",
+            Some("rider-2"),
+            "rider-3-parent",
+            "Cut the course",
+        )
+        .await
+        .unwrap();
+
+        // The penalty never lands, so the upheld decision must not either
+        let mut tx = pool.begin().await.unwrap();
+        decide_protest(
+            &mut tx,
+            &protest.id,
+            ProtestDecision::Upheld,
+            "head-official",
+            "Seen on video",
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        drop(tx);
+
+        let protest = get_protest(&pool, &protest.id).await.unwrap().unwrap();
+        assert_eq!(protest.status, "open");
+        let actions: Vec<String> = list_changes(&pool, "moto-1")
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|c| c.action)
+            .collect();
+        assert_eq!(actions, vec!["results_recorded", "protest_filed"]);
+    }
+}
@@ -0,0 +1,252 @@
+use sqlx::SqlitePool;
+use sqlx::types::Json;
+use uuid::Uuid;
+
+use crate::db::models::PracticeSessionRow;
+use crate::domain::race_event::{PracticeLap, SectorTime};
+
+pub async fn create_session(pool: &SqlitePool, id: &str, track_id: &str) -> sqlx::Result<()> {
+    sqlx::query("INSERT INTO practice_sessions (id, track_id) VALUES (?, ?)")
+        .bind(id)
+        .bind(track_id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+pub async fn end_session(pool: &SqlitePool, id: &str) -> sqlx::Result<()> {
+    sqlx::query(
+        "UPDATE practice_sessions SET ended_at = datetime('now') WHERE id = ? AND ended_at IS NULL",
+    )
+    .bind(id)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn get_session(pool: &SqlitePool, id: &str) -> sqlx::Result<Option<PracticeSessionRow>> {
+    sqlx::query_as::<_, PracticeSessionRow>("SELECT * FROM practice_sessions WHERE id = ?")
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+}
+
+/// Record a completed practice lap. Replays of the same lap are ignored.
+pub async fn insert_lap(
+    pool: &SqlitePool,
+    session_id: &str,
+    lap: &PracticeLap,
+) -> sqlx::Result<()> {
+    sqlx::query(
+        "INSERT OR IGNORE INTO practice_laps \
+         (id, session_id, transponder_id, rider_id, lap_number, lap_time_us, sectors_json) \
+         VALUES (?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(session_id)
+    .bind(i64::from(lap.transponder_id))
+    .bind(&lap.rider_id)
+    .bind(i64::from(lap.lap_number))
+    .bind(lap.lap_time_us as i64)
+    .bind(Json(&lap.sectors))
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Every lap of a session, in the order they were completed.
+pub async fn list_laps(
+    pool: &SqlitePool,
+    session_id: &str,
+) -> sqlx::Result<Vec<PracticeLapResult>> {
+    let rows = sqlx::query_as::<_, PracticeLapRow>(
+        "SELECT \
+            pl.transponder_id, \
+            pl.rider_id, \
+            r.first_name, \
+            r.last_name, \
+            r.plate_number, \
+            pl.lap_number, \
+            pl.lap_time_us, \
+            pl.sectors_json \
+         FROM practice_laps pl \
+         LEFT JOIN riders r ON r.id = pl.rider_id \
+         WHERE pl.session_id = ? \
+         ORDER BY pl.rowid",
+    )
+    .bind(session_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| PracticeLapResult {
+            transponder_id: row.transponder_id,
+            rider_id: row.rider_id,
+            first_name: row.first_name,
+            last_name: row.last_name,
+            plate_number: row.plate_number,
+            lap_number: row.lap_number,
+            lap_time_us: row.lap_time_us,
+            sectors: row.sectors_json.0,
+        })
+        .collect())
+}
+
+/// Best lap per transponder, fastest first.
+pub async fn get_session_standings(
+    pool: &SqlitePool,
+    session_id: &str,
+) -> sqlx::Result<Vec<PracticeSessionStanding>> {
+    sqlx::query_as::<_, PracticeSessionStanding>(
+        "SELECT \
+            pl.transponder_id, \
+            pl.rider_id, \
+            r.first_name, \
+            r.last_name, \
+            r.plate_number, \
+            COUNT(*) as laps, \
+            MIN(pl.lap_time_us) as best_lap_us \
+         FROM practice_laps pl \
+         LEFT JOIN riders r ON r.id = pl.rider_id \
+         WHERE pl.session_id = ? \
+         GROUP BY pl.transponder_id \
+         ORDER BY best_lap_us ASC, pl.transponder_id ASC",
+    )
+    .bind(session_id)
+    .fetch_all(pool)
+    .await
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+struct PracticeLapRow {
+    transponder_id: i64,
+    rider_id: Option<String>,
+    first_name: Option<String>,
+    last_name: Option<String>,
+    plate_number: Option<String>,
+    lap_number: i64,
+    lap_time_us: i64,
+    sectors_json: Json<Vec<SectorTime>>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct PracticeLapResult {
+    pub transponder_id: i64,
+    pub rider_id: Option<String>,
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+    pub plate_number: Option<String>,
+    pub lap_number: i64,
+    pub lap_time_us: i64,
+    pub sectors: Vec<SectorTime>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, sqlx::FromRow)]
+pub struct PracticeSessionStanding {
+    pub transponder_id: i64,
+    pub rider_id: Option<String>,
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+    pub plate_number: Option<String>,
+    pub laps: i64,
+    pub best_lap_us: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::run_migrations;
+
+    fn lap(
+        transponder_id: u32,
+        rider_id: Option<&str>,
+        lap_number: u32,
+        time_us: u64,
+    ) -> PracticeLap {
+        PracticeLap {
+            transponder_id,
+            rider_id: rider_id.map(str::to_string),
+            lap_number,
+            lap_time_us: time_us,
+            sectors: vec![SectorTime {
+                from_loop: "Start Hill".into(),
+                to_loop: "Finish".into(),
+                time_us,
+            }],
+            personal_best: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_session_laps_and_standings() {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        run_migrations(&pool).await.unwrap();
+
+        sqlx::query("INSERT INTO tracks (id, name) VALUES ('track-1', 'Test Track')")
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query(
+            "INSERT INTO riders (id, first_name, last_name, plate_number, transponder_id) \
+             VALUES ('rider-1', 'Alice', 'Smith', '42', 1001)",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        create_session(&pool, "practice-1", "track-1")
+            .await
+            .unwrap();
+        insert_lap(
+            &pool,
+            "practice-1",
+            &lap(1001, Some("rider-1"), 1, 32_000_000),
+        )
+        .await
+        .unwrap();
+        insert_lap(
+            &pool,
+            "practice-1",
+            &lap(1001, Some("rider-1"), 2, 30_000_000),
+        )
+        .await
+        .unwrap();
+        insert_lap(&pool, "practice-1", &lap(7777, None, 1, 31_000_000))
+            .await
+            .unwrap();
+        // Redelivered lap
+        insert_lap(&pool, "practice-1", &lap(7777, None, 1, 31_000_000))
+            .await
+            .unwrap();
+        end_session(&pool, "practice-1").await.unwrap();
+
+        let session = get_session(&pool, "practice-1").await.unwrap().unwrap();
+        assert!(session.ended_at.is_some());
+
+        let laps = list_laps(&pool, "practice-1").await.unwrap();
+        assert_eq!(laps.len(), 3);
+        assert_eq!(laps[0].first_name.as_deref(), Some("Alice"));
+        assert_eq!(laps[0].sectors[0].to_loop, "Finish");
+
+        let standings = get_session_standings(&pool, "practice-1").await.unwrap();
+        let rows: Vec<_> = standings
+            .iter()
+            .map(|s| {
+                (
+                    s.transponder_id,
+                    s.laps,
+                    s.best_lap_us,
+                    s.plate_number.as_deref(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                (1001, 2, 30_000_000, Some("42")),
+                (7777, 1, 31_000_000, None)
+            ]
+        );
+    }
+}
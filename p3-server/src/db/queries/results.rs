@@ -1,26 +1,31 @@
 use sqlx::SqlitePool;
 
+use crate::db::queries::penalties;
 use crate::domain::race_event::FinishResult;
 
 /// Persist race results to the database after a moto finishes.
 /// Updates moto_entries with finish position, elapsed time, points, and DNF status.
-/// Also updates the moto status to 'finished'.
+/// Also updates the moto status to 'finished'. These raw results are kept as
+/// timed; effective results are recomputed from them with any penalties.
 pub async fn persist_results(
     pool: &SqlitePool,
     moto_id: &str,
     results: &[FinishResult],
 ) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
     // Update moto status
     sqlx::query("UPDATE motos SET status = 'finished' WHERE id = ?")
         .bind(moto_id)
-        .execute(pool)
+        .execute(&mut *tx)
         .await?;
 
     // Update each rider's moto entry
     for result in results {
         // Points: 1st=1, 2nd=2, 3rd=3, etc. (golf scoring, lower is better)
-        // DNF gets max points (rider count + 1 typically, but we'll use position)
-        let points = if result.dnf {
+        // DNF/DNS get max points (rider count + 1 typically, but we'll use position)
+        let classified = !(result.dnf || result.dns);
+        let points = if !classified {
             results.len() as i64 + 1
         } else {
             result.position as i64
@@ -35,25 +40,23 @@ pub async fn persist_results(
              dns = ? \
              WHERE moto_id = ? AND rider_id = ?",
         )
-        .bind(if result.dnf {
-            None
-        } else {
-            Some(result.position as i64)
-        })
+        .bind(classified.then_some(result.position as i64))
         .bind(result.elapsed_us.map(|us| us as i64))
         .bind(points)
         .bind(result.dnf)
         .bind(result.dns)
         .bind(moto_id)
         .bind(&result.rider_id)
-        .execute(pool)
+        .execute(&mut *tx)
         .await?;
     }
 
-    Ok(())
+    penalties::record_raw_results(&mut tx, moto_id).await?;
+    tx.commit().await
 }
 
-/// Get total points for a rider across all motos in a class.
+/// Get total points for a rider across all finished motos in a class,
+/// after penalties.
 pub async fn get_class_standings(
     pool: &SqlitePool,
     class_id: &str,
@@ -64,13 +67,14 @@ pub async fn get_class_standings(
             r.first_name, \
             r.last_name, \
             r.plate_number, \
-            COALESCE(SUM(me.points), 0) as total_points, \
-            COUNT(CASE WHEN me.finish_position IS NOT NULL THEN 1 END) as motos_completed, \
-            COUNT(CASE WHEN me.dnf = 1 THEN 1 END) as dnf_count \
+            COALESCE(SUM(me.effective_points), 0) as total_points, \
+            COUNT(me.effective_position) as motos_completed, \
+            COUNT(CASE WHEN me.dnf = 1 THEN 1 END) as dnf_count, \
+            COUNT(CASE WHEN me.dsq = 1 THEN 1 END) as dsq_count \
          FROM riders r \
          JOIN event_class_riders ecr ON ecr.rider_id = r.id \
-         LEFT JOIN moto_entries me ON me.rider_id = r.id \
-         LEFT JOIN motos m ON m.id = me.moto_id AND m.class_id = ? AND m.status = 'finished' \
+         LEFT JOIN motos m ON m.class_id = ? AND m.status = 'finished' \
+         LEFT JOIN moto_entries me ON me.moto_id = m.id AND me.rider_id = r.id \
          WHERE ecr.class_id = ? \
          GROUP BY r.id \
          ORDER BY total_points ASC, motos_completed DESC",
@@ -90,6 +94,7 @@ pub async fn get_class_standings(
             total_points: r.total_points,
             motos_completed: r.motos_completed,
             dnf_count: r.dnf_count,
+            dsq_count: r.dsq_count,
         })
         .collect())
 }
@@ -103,6 +108,7 @@ struct RiderStandingRow {
     total_points: i64,
     motos_completed: i64,
     dnf_count: i64,
+    dsq_count: i64,
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -114,4 +120,5 @@ pub struct RiderStanding {
     pub total_points: i64,
     pub motos_completed: i64,
     pub dnf_count: i64,
+    pub dsq_count: i64,
 }
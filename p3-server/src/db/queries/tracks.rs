@@ -2,6 +2,7 @@ use sqlx::SqlitePool;
 use uuid::Uuid;
 
 use crate::db::models::{TimingLoopRow, TrackRow, TrackSectionRow};
+use crate::domain::race_event::RaceRules;
 
 pub async fn list_tracks(pool: &SqlitePool) -> sqlx::Result<Vec<TrackRow>> {
     sqlx::query_as::<_, TrackRow>("SELECT * FROM tracks ORDER BY name")
@@ -16,6 +17,7 @@ pub async fn get_track(pool: &SqlitePool, id: &str) -> sqlx::Result<Option<Track
         .await
 }
 
+#[allow(clippy::too_many_arguments)]
 pub async fn create_track(
     pool: &SqlitePool,
     name: &str,
@@ -46,6 +48,7 @@ pub async fn create_track(
     get_track(pool, &id).await.map(|t| t.unwrap())
 }
 
+#[allow(clippy::too_many_arguments)]
 pub async fn update_track(
     pool: &SqlitePool,
     id: &str,
@@ -80,6 +83,31 @@ pub async fn update_track(
     get_track(pool, id).await
 }
 
+/// Set the track's default DNS/DNF rules
+pub async fn set_race_rules(
+    pool: &SqlitePool,
+    id: &str,
+    rules: &RaceRules,
+) -> sqlx::Result<Option<TrackRow>> {
+    let result = sqlx::query(
+        "UPDATE tracks \
+         SET dns_timeout_secs = ?, dnf_timeout_secs = ?, max_race_secs = ?, updated_at = datetime('now') \
+         WHERE id = ?",
+    )
+    .bind(rules.dns_timeout_secs)
+    .bind(rules.dnf_timeout_secs)
+    .bind(rules.max_race_secs)
+    .bind(id)
+    .execute(pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Ok(None);
+    }
+
+    get_track(pool, id).await
+}
+
 pub async fn delete_track(pool: &SqlitePool, id: &str) -> sqlx::Result<bool> {
     let result = sqlx::query("DELETE FROM tracks WHERE id = ?")
         .bind(id)
@@ -102,6 +130,21 @@ pub async fn get_loops_for_track(
     .await
 }
 
+/// Track whose timing loops are fed by the decoder
+pub async fn track_for_decoder(
+    pool: &SqlitePool,
+    decoder_id: &str,
+) -> sqlx::Result<Option<String>> {
+    let row = sqlx::query_as::<_, (String,)>(
+        "SELECT track_id FROM timing_loops WHERE decoder_id = ? ORDER BY track_id LIMIT 1",
+    )
+    .bind(decoder_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.map(|(track_id,)| track_id))
+}
+
+#[allow(clippy::too_many_arguments)]
 pub async fn create_timing_loop(
     pool: &SqlitePool,
     track_id: &str,
@@ -110,10 +153,11 @@ pub async fn create_timing_loop(
     position: i64,
     is_finish: bool,
     is_start: bool,
+    debounce_ms: i64,
 ) -> sqlx::Result<TimingLoopRow> {
     let id = Uuid::new_v4().to_string();
     sqlx::query(
-        "INSERT INTO timing_loops (id, track_id, name, decoder_id, position, is_finish, is_start) VALUES (?, ?, ?, ?, ?, ?, ?)",
+        "INSERT INTO timing_loops (id, track_id, name, decoder_id, position, is_finish, is_start, debounce_ms) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
     )
     .bind(&id)
     .bind(track_id)
@@ -122,17 +166,17 @@ pub async fn create_timing_loop(
     .bind(position)
     .bind(is_finish)
     .bind(is_start)
+    .bind(debounce_ms)
     .execute(pool)
     .await?;
 
-    Ok(
-        sqlx::query_as::<_, TimingLoopRow>("SELECT * FROM timing_loops WHERE id = ?")
-            .bind(&id)
-            .fetch_one(pool)
-            .await?,
-    )
+    sqlx::query_as::<_, TimingLoopRow>("SELECT * FROM timing_loops WHERE id = ?")
+        .bind(&id)
+        .fetch_one(pool)
+        .await
 }
 
+#[allow(clippy::too_many_arguments)]
 pub async fn update_timing_loop(
     pool: &SqlitePool,
     loop_id: &str,
@@ -141,15 +185,17 @@ pub async fn update_timing_loop(
     position: i64,
     is_finish: bool,
     is_start: bool,
+    debounce_ms: i64,
 ) -> sqlx::Result<Option<TimingLoopRow>> {
     let result = sqlx::query(
-        "UPDATE timing_loops SET name = ?, decoder_id = ?, position = ?, is_finish = ?, is_start = ? WHERE id = ?",
+        "UPDATE timing_loops SET name = ?, decoder_id = ?, position = ?, is_finish = ?, is_start = ?, debounce_ms = ? WHERE id = ?",
     )
     .bind(name)
     .bind(decoder_id)
     .bind(position)
     .bind(is_finish)
     .bind(is_start)
+    .bind(debounce_ms)
     .bind(loop_id)
     .execute(pool)
     .await?;
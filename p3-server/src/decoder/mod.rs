@@ -1,32 +1,31 @@
 pub mod stream;
 
 use p3_parser::Message;
+use p3_transport::{DecoderEndpoint, DecoderTransport};
 use stream::MessageFramer;
 use tokio::io::AsyncReadExt;
-use tokio::net::TcpStream;
 use tokio::sync::mpsc;
 use tracing::{error, info, warn};
 
-/// Manages a TCP connection to a MyLaps P3 decoder (or test server).
-/// Reads raw bytes, frames them into complete messages, and sends
-/// parsed messages on a channel.
+/// Manages a connection to a MyLaps P3 decoder (or test server) over TCP,
+/// serial or UDP. Reads raw bytes, frames them into complete messages, and
+/// sends parsed messages on a channel.
 pub struct DecoderConnection {
-    host: String,
-    port: u16,
+    endpoint: DecoderEndpoint,
 }
 
 impl DecoderConnection {
-    pub fn new(host: String, port: u16) -> Self {
-        Self { host, port }
+    pub fn new(endpoint: DecoderEndpoint) -> Self {
+        Self { endpoint }
     }
 
     /// Connect to the decoder and start reading messages.
     /// Parsed messages are sent on `tx`. Reconnects on disconnect.
     pub async fn run(self, tx: mpsc::Sender<Message>) {
         loop {
-            info!(host = %self.host, port = %self.port, "Connecting to decoder...");
+            info!(endpoint = %self.endpoint, "Connecting to decoder...");
 
-            match TcpStream::connect((self.host.as_str(), self.port)).await {
+            match self.endpoint.open().await {
                 Ok(stream) => {
                     info!("Connected to decoder");
                     if let Err(e) = self.read_loop(stream, &tx).await {
@@ -45,7 +44,7 @@ impl DecoderConnection {
 
     async fn read_loop(
         &self,
-        mut stream: TcpStream,
+        mut stream: Box<dyn DecoderTransport>,
         tx: &mpsc::Sender<Message>,
     ) -> anyhow::Result<()> {
         let mut framer = MessageFramer::new();
@@ -1,2 +1,3 @@
+pub mod penalties;
 pub mod race_event;
 pub mod race_format;
@@ -0,0 +1,242 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+/// A sanction officials apply to a rider's result in a finished moto.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PenaltyKind {
+    /// Move the rider down `positions` places, or to last of the finishers
+    Relegation {
+        #[serde(default)]
+        positions: Option<u32>,
+    },
+    /// Add time to the rider's elapsed time and re-rank the finishers
+    TimePenalty { time_us: u64 },
+    /// Remove the rider from the results; scored like a DNF
+    Disqualification,
+}
+
+/// How officials ruled on a protest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProtestDecision {
+    Upheld,
+    Denied,
+}
+
+impl ProtestDecision {
+    pub fn as_str(&self) -> &str {
+        match self {
+            ProtestDecision::Upheld => "upheld",
+            ProtestDecision::Denied => "denied",
+        }
+    }
+}
+
+/// A rider's result as timed, before any penalties.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawResult {
+    pub rider_id: String,
+    pub finish_position: Option<u32>,
+    pub elapsed_us: Option<u64>,
+    pub dnf: bool,
+    pub dns: bool,
+}
+
+/// A rider's result after penalties, as used for points and standings.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EffectiveResult {
+    pub rider_id: String,
+    pub position: Option<u32>,
+    pub elapsed_us: Option<u64>,
+    /// Golf scoring: position for finishers, entries + 1 otherwise
+    pub points: i64,
+    pub dnf: bool,
+    pub dns: bool,
+    pub dsq: bool,
+}
+
+/// Apply penalties to a moto's raw results.
+///
+/// Disqualifications come out first, then time penalties re-rank the
+/// remaining finishers by adjusted time, then relegations are applied in
+/// the order given. Results come back in the order of `raw`.
+pub fn effective_results(
+    raw: &[RawResult],
+    penalties: &[(String, PenaltyKind)],
+) -> Vec<EffectiveResult> {
+    let mut time_penalty_us: HashMap<&str, u64> = HashMap::new();
+    let mut disqualified: HashSet<&str> = HashSet::new();
+    let mut relegations: Vec<(&str, Option<u32>)> = Vec::new();
+    for (rider_id, kind) in penalties {
+        match kind {
+            PenaltyKind::Relegation { positions } => relegations.push((rider_id, *positions)),
+            PenaltyKind::TimePenalty { time_us } => {
+                *time_penalty_us.entry(rider_id).or_default() += time_us;
+            }
+            PenaltyKind::Disqualification => {
+                disqualified.insert(rider_id);
+            }
+        }
+    }
+
+    let adjusted_elapsed = |r: &RawResult| {
+        r.elapsed_us.map(|us| {
+            us + time_penalty_us
+                .get(r.rider_id.as_str())
+                .copied()
+                .unwrap_or(0)
+        })
+    };
+
+    let mut finishers: Vec<&RawResult> = raw
+        .iter()
+        .filter(|r| r.finish_position.is_some() && !disqualified.contains(r.rider_id.as_str()))
+        .collect();
+    finishers.sort_by_key(|r| r.finish_position);
+    if finishers
+        .iter()
+        .any(|r| time_penalty_us.contains_key(r.rider_id.as_str()))
+    {
+        finishers.sort_by_key(|r| (adjusted_elapsed(r).unwrap_or(u64::MAX), r.finish_position));
+    }
+
+    for (rider_id, positions) in relegations {
+        let Some(index) = finishers.iter().position(|r| r.rider_id == rider_id) else {
+            continue;
+        };
+        let last = finishers.len() - 1;
+        let target = positions.map_or(last, |p| (index + p as usize).min(last));
+        let rider = finishers.remove(index);
+        finishers.insert(target, rider);
+    }
+
+    let positions: HashMap<&str, u32> = finishers
+        .iter()
+        .enumerate()
+        .map(|(i, r)| (r.rider_id.as_str(), i as u32 + 1))
+        .collect();
+    let unclassified_points = raw.len() as i64 + 1;
+
+    raw.iter()
+        .map(|r| {
+            let position = positions.get(r.rider_id.as_str()).copied();
+            EffectiveResult {
+                rider_id: r.rider_id.clone(),
+                position,
+                elapsed_us: adjusted_elapsed(r),
+                points: position.map_or(unclassified_points, i64::from),
+                dnf: r.dnf,
+                dns: r.dns,
+                dsq: disqualified.contains(r.rider_id.as_str()),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn raw(rider_id: &str, finish_position: Option<u32>, elapsed_us: Option<u64>) -> RawResult {
+        RawResult {
+            rider_id: rider_id.into(),
+            finish_position,
+            elapsed_us,
+            dnf: finish_position.is_none(),
+            dns: false,
+        }
+    }
+
+    fn moto() -> Vec<RawResult> {
+        vec![
+            raw("a", Some(1), Some(30_000_000)),
+            raw("b", Some(2), Some(30_400_000)),
+            raw("c", Some(3), Some(31_000_000)),
+            raw("d", Some(4), Some(32_000_000)),
+            raw("e", None, None),
+        ]
+    }
+
+    fn summary(results: &[EffectiveResult]) -> Vec<(&str, Option<u32>, i64)> {
+        results
+            .iter()
+            .map(|r| (r.rider_id.as_str(), r.position, r.points))
+            .collect()
+    }
+
+    #[test]
+    fn test_no_penalties_keeps_raw_results() {
+        let results = effective_results(&moto(), &[]);
+        assert_eq!(
+            summary(&results),
+            vec![
+                ("a", Some(1), 1),
+                ("b", Some(2), 2),
+                ("c", Some(3), 3),
+                ("d", Some(4), 4),
+                ("e", None, 6),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_relegation_moves_rider_down() {
+        let results = effective_results(
+            &moto(),
+            &[("a".into(), PenaltyKind::Relegation { positions: Some(2) })],
+        );
+        assert_eq!(
+            summary(&results)[..4],
+            [
+                ("a", Some(3), 3),
+                ("b", Some(1), 1),
+                ("c", Some(2), 2),
+                ("d", Some(4), 4),
+            ]
+        );
+
+        let results = effective_results(
+            &moto(),
+            &[("b".into(), PenaltyKind::Relegation { positions: None })],
+        );
+        assert_eq!(results[1].position, Some(4));
+        assert_eq!(results[3].position, Some(3));
+    }
+
+    #[test]
+    fn test_time_penalty_reranks_by_adjusted_time() {
+        let results = effective_results(
+            &moto(),
+            &[("a".into(), PenaltyKind::TimePenalty { time_us: 1_500_000 })],
+        );
+        assert_eq!(results[0].elapsed_us, Some(31_500_000));
+        assert_eq!(
+            summary(&results)[..4],
+            [
+                ("a", Some(3), 3),
+                ("b", Some(1), 1),
+                ("c", Some(2), 2),
+                ("d", Some(4), 4),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_disqualification_scores_like_dnf() {
+        let results = effective_results(&moto(), &[("c".into(), PenaltyKind::Disqualification)]);
+        assert_eq!(
+            summary(&results),
+            vec![
+                ("a", Some(1), 1),
+                ("b", Some(2), 2),
+                ("c", None, 6),
+                ("d", Some(3), 3),
+                ("e", None, 6),
+            ]
+        );
+        assert!(results[2].dsq);
+        assert!(!results[2].dnf);
+    }
+}
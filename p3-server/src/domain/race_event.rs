@@ -13,17 +13,35 @@ pub enum RaceEvent {
         class_name: String,
         round_type: String,
         riders: Vec<StagedRider>,
+        laps: u32,
+        start_mode: StartMode,
     },
 
     /// Gate has dropped — race clock starts
     #[serde(rename = "gate_drop")]
     GateDrop { moto_id: String, timestamp_us: u64 },
 
+    /// Time trial: a rider crossed the start loop and their clock started
+    #[serde(rename = "rider_started")]
+    RiderStarted {
+        moto_id: String,
+        rider_id: String,
+        timestamp_us: u64,
+    },
+
+    /// Time trial: riders joined the rolling start list
+    #[serde(rename = "start_list_updated")]
+    StartListUpdated {
+        moto_id: String,
+        riders: Vec<StagedRider>,
+    },
+
     /// A rider crossed a timing loop (split or finish)
     #[serde(rename = "split_time")]
     SplitTime {
         moto_id: String,
         rider_id: String,
+        lap: u32,
         loop_name: String,
         is_finish: bool,
         elapsed_us: u64,
@@ -31,6 +49,21 @@ pub enum RaceEvent {
         gap_to_leader_us: Option<u64>,
     },
 
+    /// A published split was replaced by a better hit inside the loop's
+    /// debounce window
+    #[serde(rename = "split_corrected")]
+    SplitCorrected {
+        moto_id: String,
+        rider_id: String,
+        lap: u32,
+        loop_name: String,
+        is_finish: bool,
+        previous_elapsed_us: u64,
+        elapsed_us: u64,
+        position: u32,
+        gap_to_leader_us: Option<u64>,
+    },
+
     /// Current positions updated (sent after each split/finish)
     #[serde(rename = "positions_update")]
     PositionsUpdate {
@@ -55,10 +88,60 @@ pub enum RaceEvent {
         results: Vec<FinishResult>,
     },
 
+    /// An operator corrected the moto's timing; positions and results that
+    /// follow are recomputed with it
+    #[serde(rename = "timing_corrected")]
+    TimingCorrected {
+        moto_id: String,
+        correction: TimingCorrection,
+    },
+
+    /// A transponder that is not in the moto crossed a timing loop
+    #[serde(rename = "unknown_transponder")]
+    UnknownTransponder {
+        moto_id: String,
+        transponder_id: u32,
+        loop_name: String,
+        timestamp_us: u64,
+    },
+
+    /// A decoder with no timing loop on the track reported a passing
+    #[serde(rename = "unmapped_decoder")]
+    UnmappedDecoder {
+        moto_id: String,
+        decoder_id: Option<String>,
+        transponder_id: u32,
+        timestamp_us: u64,
+    },
+
+    /// A decoder's clock drifts from the track's time base beyond tolerance
+    #[serde(rename = "clock_drift")]
+    ClockDrift {
+        decoder_id: String,
+        drift_ppm: f64,
+        offset_us: i64,
+        timestamp_us: u64,
+    },
+
     /// Race has been reset back to idle
     #[serde(rename = "race_reset")]
     RaceReset,
 
+    /// An open practice session started
+    #[serde(rename = "practice_started")]
+    PracticeStarted { session_id: String },
+
+    /// A practice lap was completed; carries the updated leaderboards
+    #[serde(rename = "practice_update")]
+    PracticeUpdate {
+        lap: PracticeLap,
+        leaderboard: PracticeLeaderboard,
+    },
+
+    /// The practice session ended
+    #[serde(rename = "practice_ended")]
+    PracticeEnded { leaderboard: PracticeLeaderboard },
+
     /// Current race state snapshot (sent to newly connected clients)
     #[serde(rename = "state_snapshot")]
     StateSnapshot {
@@ -71,7 +154,82 @@ pub enum RaceEvent {
         gate_drop_time_us: Option<u64>,
         finished_count: u32,
         total_riders: u32,
+        laps: u32,
+        practice: Option<PracticeLeaderboard>,
+    },
+}
+
+/// How riders' clocks start in a staged moto.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StartMode {
+    /// Everyone starts together on the gate beacon
+    #[default]
+    GateDrop,
+    /// Each rider starts on their own crossing of the `is_start` loop
+    TimeTrial,
+}
+
+/// Automatic DNS/DNF rules for a gate race; `None` disables a rule.
+///
+/// Tracks set the defaults and classes override them rule by rule.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RaceRules {
+    /// Riders not seen at any loop this long after the gate drop are DNS
+    pub dns_timeout_secs: Option<u32>,
+    /// Riders still on course this long after the leader finishes are DNF
+    pub dnf_timeout_secs: Option<u32>,
+    /// The race is finished this long after the gate drop
+    pub max_race_secs: Option<u32>,
+}
+
+impl RaceRules {
+    /// These rules, with unset ones taken from `fallback`
+    pub fn or(self, fallback: RaceRules) -> RaceRules {
+        RaceRules {
+            dns_timeout_secs: self.dns_timeout_secs.or(fallback.dns_timeout_secs),
+            dnf_timeout_secs: self.dnf_timeout_secs.or(fallback.dnf_timeout_secs),
+            max_race_secs: self.max_race_secs.or(fallback.max_race_secs),
+        }
+    }
+}
+
+/// An operator's fix to a moto's timing, with who made it and why.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimingCorrection {
+    pub correction_id: String,
+    pub operator: String,
+    pub reason: String,
+    pub change: TimingChange,
+}
+
+/// What a [`TimingCorrection`] changes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TimingChange {
+    /// A crossing the decoder missed, at decoder time `timestamp_us`
+    InsertPassing {
+        rider_id: String,
+        loop_id: String,
+        timestamp_us: u64,
     },
+    /// Ignore a passing, e.g. a spurious read
+    VoidPassing { passing_id: String },
+    /// Finish the rider with a hand-timed elapsed time
+    HandTimedFinish { rider_id: String, elapsed_us: u64 },
+}
+
+/// A passing recorded during a moto, for operators to review and correct.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedPassing {
+    /// `<decoder_id>:<passing_number>`, or `manual:<correction_id>`
+    pub passing_id: String,
+    pub transponder_id: u32,
+    pub rider_id: Option<String>,
+    pub loop_name: Option<String>,
+    pub timestamp_us: u64,
+    pub manual: bool,
+    pub voided: bool,
 }
 
 /// A rider in a staged moto, before the race starts.
@@ -99,6 +257,7 @@ pub struct RiderPosition {
     pub gap_to_leader_us: Option<u64>,
     pub finished: bool,
     pub dnf: bool,
+    pub laps_completed: u32,
 }
 
 /// Final result for a rider in a finished moto.
@@ -113,10 +272,67 @@ pub struct FinishResult {
     pub gap_to_leader_us: Option<u64>,
     pub dnf: bool,
     pub dns: bool,
+    /// Duration of each completed lap, in order
+    pub lap_times_us: Vec<u64>,
+}
+
+/// A registered rider that practice passings can be matched to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KnownRider {
+    pub rider_id: String,
+    pub first_name: String,
+    pub last_name: String,
+    pub plate_number: String,
+    pub transponder_id: u32,
+    pub transponder_string: Option<String>,
+}
+
+/// A lap completed during practice.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PracticeLap {
+    pub transponder_id: u32,
+    pub rider_id: Option<String>,
+    /// The rider's lap count in this session, starting at 1
+    pub lap_number: u32,
+    pub lap_time_us: u64,
+    pub sectors: Vec<SectorTime>,
+    pub personal_best: bool,
+}
+
+/// Time between two consecutive loops within a lap.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SectorTime {
+    pub from_loop: String,
+    pub to_loop: String,
+    pub time_us: u64,
+}
+
+/// One transponder's row on a practice leaderboard.
+///
+/// Name fields are empty for transponders not found in `riders`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PracticeStanding {
+    pub position: u32,
+    pub transponder_id: u32,
+    pub rider_id: Option<String>,
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+    pub plate_number: Option<String>,
+    pub laps: u32,
+    pub best_lap_us: u64,
+    pub last_lap_us: u64,
+}
+
+/// Live practice boards: fastest lap first, and most recent lap first.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PracticeLeaderboard {
+    pub session_id: String,
+    pub best_laps: Vec<PracticeStanding>,
+    pub last_laps: Vec<PracticeStanding>,
 }
 
 /// Internal rider state tracked by the engine during a race.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RiderState {
     pub rider_id: String,
     pub first_name: String,
@@ -124,9 +340,18 @@ pub struct RiderState {
     pub plate_number: String,
     pub transponder_id: u32,
     pub lane: u32,
-    /// Split times keyed by loop_id → elapsed_us from gate drop
-    pub splits: HashMap<String, u64>,
-    /// The furthest loop (by position) the rider has been seen at
+    /// Time trial: rtc time of the rider's start-loop crossing
+    pub start_rtc_time_us: Option<u64>,
+    /// Split times keyed by (lap, loop_id) → elapsed_us from gate drop
+    #[serde(with = "pair_list")]
+    pub splits: HashMap<(u32, String), u64>,
+    /// Canonical hit behind each split, keyed by (lap, loop_id)
+    #[serde(with = "pair_list")]
+    pub crossings: HashMap<(u32, String), LoopCrossing>,
+    /// Elapsed time at the end of each completed lap
+    pub lap_elapsed_us: Vec<u64>,
+    /// The furthest loop (by position) the rider has been seen at in the
+    /// current lap
     pub last_loop_position: Option<u32>,
     pub last_loop_name: Option<String>,
     /// Elapsed time at the last seen loop
@@ -136,6 +361,7 @@ pub struct RiderState {
     pub finish_position: Option<u32>,
     pub finished: bool,
     pub dnf: bool,
+    pub dns: bool,
 }
 
 impl RiderState {
@@ -154,7 +380,10 @@ impl RiderState {
             plate_number,
             transponder_id,
             lane,
+            start_rtc_time_us: None,
             splits: HashMap::new(),
+            crossings: HashMap::new(),
+            lap_elapsed_us: Vec::new(),
             last_loop_position: None,
             last_loop_name: None,
             last_elapsed_us: None,
@@ -162,6 +391,7 @@ impl RiderState {
             finish_position: None,
             finished: false,
             dnf: false,
+            dns: false,
         }
     }
 
@@ -177,13 +407,49 @@ impl RiderState {
             elapsed_us: self.last_elapsed_us,
             gap_to_leader_us,
             finished: self.finished,
-            dnf: self.dnf,
+            dnf: self.dnf || self.dns,
+            laps_completed: self.laps_completed(),
         }
     }
+
+    pub fn laps_completed(&self) -> u32 {
+        self.lap_elapsed_us.len() as u32
+    }
+
+    /// Lap the rider is currently on (1-based)
+    pub fn current_lap(&self) -> u32 {
+        self.laps_completed() + 1
+    }
+
+    /// Duration of each completed lap
+    pub fn lap_times_us(&self) -> Vec<u64> {
+        let mut lap_start_us = 0;
+        self.lap_elapsed_us
+            .iter()
+            .map(|&lap_end_us| {
+                let lap_time = lap_end_us.saturating_sub(lap_start_us);
+                lap_start_us = lap_end_us;
+                lap_time
+            })
+            .collect()
+    }
+}
+
+/// The hit chosen as a rider's crossing of one loop.
+///
+/// A rider sitting on a loop produces several passings; the strongest one
+/// within the loop's debounce window is the canonical crossing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LoopCrossing {
+    /// First hit at the loop; the debounce window opens here
+    pub first_rtc_time_us: u64,
+    pub rtc_time_us: u64,
+    pub strength: u16,
+    pub hits: u16,
 }
 
 /// Track configuration loaded for the engine.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TrackConfig {
     pub track_id: String,
     pub name: String,
@@ -192,8 +458,15 @@ pub struct TrackConfig {
     pub loops: Vec<LoopConfig>,
 }
 
+impl TrackConfig {
+    /// The timing loop read by `decoder_id`, if it is on the track.
+    pub fn loop_for_decoder(&self, decoder_id: &str) -> Option<&LoopConfig> {
+        self.loops.iter().find(|l| l.decoder_id == decoder_id)
+    }
+}
+
 /// A single timing loop on the track.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LoopConfig {
     pub loop_id: String,
     pub name: String,
@@ -201,4 +474,33 @@ pub struct LoopConfig {
     pub position: u32,
     pub is_start: bool,
     pub is_finish: bool,
+    /// Later hits within this many ms of a rider's first hit may replace it
+    pub debounce_ms: u32,
+}
+
+/// Serde helper for maps keyed by tuples, which JSON objects can't hold:
+/// they are written as a list of `[key, value]` pairs.
+mod pair_list {
+    use std::collections::HashMap;
+    use std::hash::Hash;
+
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S, K, V>(map: &HashMap<K, V>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        K: Serialize,
+        V: Serialize,
+    {
+        serializer.collect_seq(map)
+    }
+
+    pub fn deserialize<'de, D, K, V>(deserializer: D) -> Result<HashMap<K, V>, D::Error>
+    where
+        D: Deserializer<'de>,
+        K: Deserialize<'de> + Eq + Hash,
+        V: Deserialize<'de>,
+    {
+        Vec::<(K, V)>::deserialize(deserializer).map(|pairs| pairs.into_iter().collect())
+    }
 }
@@ -66,7 +66,7 @@ pub fn generate_qualifying_motos(rider_ids: &[String]) -> Vec<MotoAssignment> {
     }
 
     // Determine number of heats per round
-    let heats_per_round = (rider_count + 7) / 8; // ceiling division
+    let heats_per_round = rider_count.div_ceil(8); // ceiling division
 
     let mut motos = Vec::new();
     let mut sequence: i64 = 1;
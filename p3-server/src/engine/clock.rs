@@ -0,0 +1,424 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use p3_parser::messages::PassingMessage;
+use serde::{Deserialize, Serialize};
+
+/// Decoders whose clock runs faster or slower than the time base by more
+/// than this are flagged
+pub const DRIFT_TOLERANCE_PPM: f64 = 100.0;
+
+/// Minimum decoder time between two samples from the same source
+const SAMPLE_INTERVAL_US: u64 = 10_000_000;
+/// Samples kept per decoder and source, about ten minutes' worth
+const MAX_SAMPLES: usize = 64;
+/// Drift is only estimated once a source's samples span this long; over a
+/// shorter span sample jitter outweighs it
+const MIN_DRIFT_SPAN_US: u64 = 300_000_000;
+/// Beacon reads by two decoders this close on the time base are taken as
+/// the same beacon crossing
+const BEACON_PAIRING_WINDOW_US: u64 = 500_000;
+
+/// Where a decoder's time base reference comes from, least precise first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClockSource {
+    /// The track client's clock when it captured the decoder's messages
+    HostClock,
+    /// Beacon crossings also read by a GPS-locked decoder
+    Beacon,
+    /// The decoder's own GPS time
+    Gps,
+}
+
+/// How a decoder's RTC maps onto the track's time base:
+/// `rtc + offset_us + drift_ppm * (rtc - anchor_rtc_us) / 1e6`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ClockModel {
+    pub source: ClockSource,
+    /// Decoder RTC time at which the clock is `offset_us` off
+    pub anchor_rtc_us: u64,
+    pub offset_us: i64,
+    /// `None` until the samples span long enough to tell
+    pub drift_ppm: Option<f64>,
+}
+
+impl ClockModel {
+    /// A decoder RTC time on the time base.
+    pub fn align(&self, rtc_time_us: u64) -> u64 {
+        let since_anchor_us = rtc_time_us as f64 - self.anchor_rtc_us as f64;
+        let drift_us = self.drift_ppm.unwrap_or(0.0) * since_anchor_us / 1e6;
+        let aligned_us = rtc_time_us as i64 + self.offset_us + drift_us.round() as i64;
+        aligned_us.max(0) as u64
+    }
+}
+
+/// Decoder clocks of one track, aligned onto a common time base (UTC).
+///
+/// Elapsed and split times subtract RTC times read by different decoders,
+/// which only holds if their clocks agree. Each decoder's offset and drift
+/// are estimated from the best source it has: its own GPS time, beacon
+/// crossings it shares with a GPS-locked decoder, or else the track
+/// client's capture time. Decoders with no samples are taken as they are.
+#[derive(Default, Clone, Serialize, Deserialize)]
+pub struct ClockAlignment {
+    decoders: BTreeMap<String, DecoderClock>,
+    /// Latest read of each beacon by each decoder, to pair with other
+    /// decoders' reads
+    beacons: Vec<BeaconSighting>,
+}
+
+#[derive(Default, Clone, Serialize, Deserialize)]
+struct DecoderClock {
+    gps: VecDeque<ClockSample>,
+    beacon: VecDeque<ClockSample>,
+    host: VecDeque<ClockSample>,
+    model: Option<ClockModel>,
+    /// Drift is out of tolerance and was flagged
+    flagged: bool,
+}
+
+/// A decoder RTC time and the time base instant it was read at
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct ClockSample {
+    rtc_time_us: u64,
+    reference_us: u64,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct BeaconSighting {
+    decoder_id: String,
+    transponder_id: u32,
+    rtc_time_us: u64,
+    aligned_us: u64,
+}
+
+impl ClockAlignment {
+    /// Learn from a passing the track client captured at `captured_at_us`
+    /// on its own clock. `is_beacon` marks gate and system beacon reads.
+    ///
+    /// Returns the decoders whose drift went out of tolerance, with their
+    /// clock models.
+    pub fn observe(
+        &mut self,
+        passing: &PassingMessage,
+        captured_at_us: Option<u64>,
+        is_beacon: bool,
+    ) -> Vec<(String, ClockModel)> {
+        let Some(decoder_id) = passing.decoder_id.as_deref() else {
+            return vec![];
+        };
+
+        let rtc_time_us = passing.rtc_time_us;
+        let clock = self.decoders.entry(decoder_id.to_string()).or_default();
+        if let Some(utc_time_us) = passing.utc_time_us {
+            clock.record(ClockSource::Gps, rtc_time_us, utc_time_us);
+        }
+        if let Some(captured_at_us) = captured_at_us {
+            clock.record(ClockSource::HostClock, rtc_time_us, captured_at_us);
+        }
+        if is_beacon {
+            self.pair_beacon(decoder_id, passing.transponder_id, rtc_time_us);
+        }
+
+        self.decoders
+            .iter_mut()
+            .filter_map(|(decoder_id, clock)| Some((decoder_id.clone(), clock.check_tolerance()?)))
+            .collect()
+    }
+
+    /// A decoder's RTC time on the time base.
+    pub fn align(&self, decoder_id: Option<&str>, rtc_time_us: u64) -> u64 {
+        match decoder_id.and_then(|id| self.model(id)) {
+            Some(model) => model.align(rtc_time_us),
+            None => rtc_time_us,
+        }
+    }
+
+    /// A decoder's current clock model, once it has samples.
+    pub fn model(&self, decoder_id: &str) -> Option<&ClockModel> {
+        self.decoders.get(decoder_id)?.model.as_ref()
+    }
+
+    fn gps_locked(&self, decoder_id: &str) -> bool {
+        self.model(decoder_id)
+            .is_some_and(|model| model.source == ClockSource::Gps)
+    }
+
+    /// Pair a beacon read with other decoders' reads of the same crossing.
+    ///
+    /// A decoder without GPS takes the GPS-locked decoder's time of the
+    /// crossing as a sample.
+    fn pair_beacon(&mut self, decoder_id: &str, transponder_id: u32, rtc_time_us: u64) {
+        let aligned_us = self.align(Some(decoder_id), rtc_time_us);
+        let locked = self.gps_locked(decoder_id);
+
+        let mut samples = Vec::new();
+        for sighting in self.beacons.iter().filter(|s| {
+            s.transponder_id == transponder_id
+                && s.decoder_id != decoder_id
+                && s.aligned_us.abs_diff(aligned_us) <= BEACON_PAIRING_WINDOW_US
+        }) {
+            match (locked, self.gps_locked(&sighting.decoder_id)) {
+                (false, true) => {
+                    samples.push((decoder_id.to_string(), rtc_time_us, sighting.aligned_us));
+                }
+                (true, false) => samples.push((
+                    sighting.decoder_id.clone(),
+                    sighting.rtc_time_us,
+                    aligned_us,
+                )),
+                _ => {}
+            }
+        }
+        for (decoder_id, rtc_time_us, reference_us) in samples {
+            if let Some(clock) = self.decoders.get_mut(&decoder_id) {
+                clock.record(ClockSource::Beacon, rtc_time_us, reference_us);
+            }
+        }
+
+        self.beacons
+            .retain(|s| !(s.decoder_id == decoder_id && s.transponder_id == transponder_id));
+        self.beacons.push(BeaconSighting {
+            decoder_id: decoder_id.to_string(),
+            transponder_id,
+            rtc_time_us,
+            aligned_us: self.align(Some(decoder_id), rtc_time_us),
+        });
+    }
+}
+
+impl DecoderClock {
+    fn samples_mut(&mut self, source: ClockSource) -> &mut VecDeque<ClockSample> {
+        match source {
+            ClockSource::Gps => &mut self.gps,
+            ClockSource::Beacon => &mut self.beacon,
+            ClockSource::HostClock => &mut self.host,
+        }
+    }
+
+    /// Keep a sample, at most one per [`SAMPLE_INTERVAL_US`], and refit.
+    ///
+    /// An RTC that went backwards was reset, so its earlier samples no
+    /// longer apply.
+    fn record(&mut self, source: ClockSource, rtc_time_us: u64, reference_us: u64) {
+        let samples = self.samples_mut(source);
+        match samples.back() {
+            Some(last) if rtc_time_us < last.rtc_time_us => samples.clear(),
+            Some(last) if rtc_time_us - last.rtc_time_us < SAMPLE_INTERVAL_US => return,
+            _ => {}
+        }
+        samples.push_back(ClockSample {
+            rtc_time_us,
+            reference_us,
+        });
+        if samples.len() > MAX_SAMPLES {
+            samples.pop_front();
+        }
+        self.refit();
+    }
+
+    /// Fit the model to the samples of the best source there are any of.
+    fn refit(&mut self) {
+        self.model = [
+            (ClockSource::Gps, &self.gps),
+            (ClockSource::Beacon, &self.beacon),
+            (ClockSource::HostClock, &self.host),
+        ]
+        .into_iter()
+        .find_map(|(source, samples)| fit(source, samples));
+    }
+
+    /// The model if its drift just went out of tolerance.
+    fn check_tolerance(&mut self) -> Option<ClockModel> {
+        let model = self.model?;
+        let out_of_tolerance = model
+            .drift_ppm
+            .is_some_and(|drift_ppm| drift_ppm.abs() > DRIFT_TOLERANCE_PPM);
+        let newly = out_of_tolerance && !self.flagged;
+        self.flagged = out_of_tolerance;
+        newly.then_some(model)
+    }
+}
+
+/// Least-squares fit of the clock offset against RTC time, anchored at the
+/// latest sample.
+///
+/// Capture times on the host clock only ever run late (transport latency),
+/// so its offset is taken from the quickest sample rather than the mean.
+fn fit(source: ClockSource, samples: &VecDeque<ClockSample>) -> Option<ClockModel> {
+    let anchor_rtc_us = samples.back()?.rtc_time_us;
+    let points: Vec<(f64, f64)> = samples
+        .iter()
+        .map(|s| {
+            let since_anchor_us = s.rtc_time_us as i64 - anchor_rtc_us as i64;
+            let offset_us = s.reference_us as i64 - s.rtc_time_us as i64;
+            (since_anchor_us as f64, offset_us as f64)
+        })
+        .collect();
+
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+    let span_us = anchor_rtc_us - samples.front()?.rtc_time_us;
+    let slope = if span_us >= MIN_DRIFT_SPAN_US {
+        let sxx: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+        let sxy: f64 = points
+            .iter()
+            .map(|(x, y)| (x - mean_x) * (y - mean_y))
+            .sum();
+        Some(sxy / sxx)
+    } else {
+        None
+    };
+
+    let drift = slope.unwrap_or(0.0);
+    let offset_us = match source {
+        ClockSource::HostClock => points
+            .iter()
+            .map(|(x, y)| y - drift * x)
+            .fold(f64::INFINITY, f64::min),
+        ClockSource::Gps | ClockSource::Beacon => mean_y - drift * mean_x,
+    };
+
+    Some(ClockModel {
+        source,
+        anchor_rtc_us,
+        offset_us: offset_us.round() as i64,
+        drift_ppm: slope.map(|slope| slope * 1e6),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn passing(decoder_id: &str, transponder_id: u32, rtc_time_us: u64) -> PassingMessage {
+        PassingMessage {
+            passing_number: 1,
+            transponder_id,
+            rtc_time_us,
+            utc_time_us: None,
+            strength: None,
+            hits: None,
+            transponder_string: None,
+            flags: 0,
+            decoder_id: Some(decoder_id.into()),
+            extra_fields: Vec::new(),
+        }
+    }
+
+    fn gps_passing(decoder_id: &str, rtc_time_us: u64, utc_time_us: u64) -> PassingMessage {
+        PassingMessage {
+            utc_time_us: Some(utc_time_us),
+            ..passing(decoder_id, 1001, rtc_time_us)
+        }
+    }
+
+    #[test]
+    fn test_decoder_without_samples_is_taken_as_is() {
+        let mut clocks = ClockAlignment::default();
+        clocks.observe(&passing("D1", 1001, 5_000_000), None, false);
+        assert_eq!(clocks.align(Some("D1"), 5_000_000), 5_000_000);
+        assert_eq!(clocks.align(None, 5_000_000), 5_000_000);
+        assert!(clocks.model("D1").is_none());
+    }
+
+    #[test]
+    fn test_gps_time_sets_offset_over_host_clock() {
+        let mut clocks = ClockAlignment::default();
+        clocks.observe(
+            &gps_passing("D1", 10_000_000, 12_000_000),
+            Some(15_000_000),
+            false,
+        );
+
+        let model = clocks.model("D1").unwrap();
+        assert_eq!(model.source, ClockSource::Gps);
+        assert_eq!(model.offset_us, 2_000_000);
+        assert_eq!(model.drift_ppm, None);
+        assert_eq!(clocks.align(Some("D1"), 20_000_000), 22_000_000);
+    }
+
+    #[test]
+    fn test_host_clock_offset_uses_quickest_capture() {
+        let mut clocks = ClockAlignment::default();
+        for (rtc_time_us, latency_us) in [(0, 30_000), (10_000_000, 5_000), (20_000_000, 12_000)] {
+            clocks.observe(
+                &passing("D1", 1001, rtc_time_us),
+                Some(1_000_000 + rtc_time_us + latency_us),
+                false,
+            );
+        }
+
+        let model = clocks.model("D1").unwrap();
+        assert_eq!(model.source, ClockSource::HostClock);
+        assert_eq!(model.offset_us, 1_005_000);
+    }
+
+    #[test]
+    fn test_drift_out_of_tolerance_is_flagged_once() {
+        let mut clocks = ClockAlignment::default();
+        let mut flagged = Vec::new();
+        // The decoder's clock loses 250 µs per second
+        for minute in 0..=10u64 {
+            let rtc_time_us = minute * 60_000_000;
+            let utc_time_us = rtc_time_us + rtc_time_us / 4_000;
+            flagged.extend(clocks.observe(
+                &gps_passing("D1", rtc_time_us, utc_time_us),
+                None,
+                false,
+            ));
+        }
+
+        let [(decoder_id, model)] = flagged.as_slice() else {
+            panic!("expected one flag, got {}", flagged.len());
+        };
+        assert_eq!(decoder_id, "D1");
+        assert!((model.drift_ppm.unwrap() - 250.0).abs() < 0.01);
+        assert_eq!(clocks.align(Some("D1"), 660_000_000), 660_165_000);
+    }
+
+    #[test]
+    fn test_drift_within_tolerance_is_not_flagged() {
+        let mut clocks = ClockAlignment::default();
+        for minute in 0..=10u64 {
+            let rtc_time_us = minute * 60_000_000;
+            let utc_time_us = 500 + rtc_time_us + rtc_time_us / 50_000;
+            let flagged = clocks.observe(&gps_passing("D1", rtc_time_us, utc_time_us), None, false);
+            assert!(flagged.is_empty());
+        }
+        let drift_ppm = clocks.model("D1").unwrap().drift_ppm.unwrap();
+        assert!((drift_ppm - 20.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_shared_beacon_aligns_decoder_without_gps() {
+        let mut clocks = ClockAlignment::default();
+        // D1 is GPS-locked and 200 ms ahead of UTC, D2 has no time source
+        // yet and its RTC reads 300 ms behind D1's
+        clocks.observe(&gps_passing("D1", 10_200_000, 10_000_000), None, false);
+        clocks.observe(&passing("D1", 9992, 60_200_000), None, true);
+        clocks.observe(&passing("D2", 9992, 59_900_000), None, true);
+
+        let model = clocks.model("D2").unwrap();
+        assert_eq!(model.source, ClockSource::Beacon);
+        assert_eq!(model.offset_us, 100_000);
+        assert_eq!(
+            clocks.align(Some("D2"), 69_900_000),
+            clocks.align(Some("D1"), 70_200_000)
+        );
+
+        // Reads of another beacon, or too far apart, are not paired
+        clocks.observe(&passing("D1", 9991, 120_200_000), None, true);
+        clocks.observe(&passing("D2", 9992, 125_000_000), None, true);
+        assert_eq!(clocks.model("D2").unwrap().offset_us, 100_000);
+    }
+
+    #[test]
+    fn test_rtc_reset_drops_earlier_samples() {
+        let mut clocks = ClockAlignment::default();
+        clocks.observe(&gps_passing("D1", 50_000_000, 51_000_000), None, false);
+        clocks.observe(&gps_passing("D1", 1_000_000, 60_000_000), None, false);
+        assert_eq!(clocks.model("D1").unwrap().offset_us, 59_000_000);
+    }
+}
@@ -0,0 +1,277 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use tokio::sync::{Mutex, broadcast};
+use tokio::time::Instant;
+
+use crate::domain::race_event::RaceEvent;
+
+use super::state::{RaceCommand, RaceState, apply};
+
+/// A race event and the track whose engine produced it.
+#[derive(Debug, Clone)]
+pub struct TrackRaceEvent {
+    pub track_id: String,
+    pub event: RaceEvent,
+}
+
+/// The time on a track's time base between passings: the engine's latest
+/// passing time, moved on by the host's monotonic clock since it was seen.
+///
+/// Server wall clocks and decoder clocks can be far apart, so the race
+/// rules are never timed by the server's clock directly.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TrackClock {
+    last_passing: Option<(u64, Instant)>,
+}
+
+impl TrackClock {
+    /// Catch up with the engine's latest passing time after a command.
+    pub fn observe(&mut self, state: &RaceState) {
+        self.last_passing = match (state.track_time_us(), self.last_passing) {
+            (None, _) => None,
+            (Some(track_us), Some((seen_us, seen_at))) if track_us == seen_us => {
+                Some((seen_us, seen_at))
+            }
+            (Some(track_us), _) => Some((track_us, Instant::now())),
+        };
+    }
+
+    /// The track time now, or `None` while the engine has no aligned
+    /// passing time to go by.
+    pub fn now_us(&self) -> Option<u64> {
+        let (track_us, seen_at) = self.last_passing?;
+        Some(track_us.saturating_add(seen_at.elapsed().as_micros() as u64))
+    }
+}
+
+/// A track's race engine run in-process, broadcasting the events it
+/// produces to WebSocket clients and the result persistence task.
+pub struct RaceEngine {
+    track_id: String,
+    state: RaceState,
+    clock: TrackClock,
+    event_tx: broadcast::Sender<Arc<TrackRaceEvent>>,
+}
+
+impl RaceEngine {
+    pub fn new(track_id: String, event_tx: broadcast::Sender<Arc<TrackRaceEvent>>) -> Self {
+        Self {
+            track_id,
+            state: RaceState::new(),
+            clock: TrackClock::default(),
+            event_tx,
+        }
+    }
+
+    pub fn state(&self) -> &RaceState {
+        &self.state
+    }
+
+    /// Apply a command, broadcast the events it produced and return them.
+    pub fn apply(&mut self, command: RaceCommand) -> Vec<RaceEvent> {
+        let (state, events) = apply(std::mem::take(&mut self.state), command);
+        self.state = state;
+        self.clock.observe(&self.state);
+        for event in &events {
+            // Ignore send errors (no subscribers is fine)
+            let _ = self.event_tx.send(Arc::new(TrackRaceEvent {
+                track_id: self.track_id.clone(),
+                event: event.clone(),
+            }));
+        }
+        events
+    }
+
+    /// Apply the race rules at the track's current time, so a race with
+    /// nobody left crossing loops still ends. Does nothing until the
+    /// engine has an aligned passing time to go by.
+    pub fn check_timeouts(&mut self) -> Vec<RaceEvent> {
+        match self.clock.now_us() {
+            Some(now_us) => self.apply(RaceCommand::CheckTimeouts { now_us }),
+            None => Vec::new(),
+        }
+    }
+}
+
+/// The race engines of every track this server runs, keyed by `track_id`.
+///
+/// Engines are created idle on first use and publish on one shared channel.
+pub struct EngineRegistry {
+    engines: Mutex<BTreeMap<String, Arc<Mutex<RaceEngine>>>>,
+    event_tx: broadcast::Sender<Arc<TrackRaceEvent>>,
+}
+
+impl EngineRegistry {
+    pub fn new(event_tx: broadcast::Sender<Arc<TrackRaceEvent>>) -> Self {
+        Self {
+            engines: Mutex::new(BTreeMap::new()),
+            event_tx,
+        }
+    }
+
+    /// The track's engine, created idle if the track has none yet
+    pub async fn engine(&self, track_id: &str) -> Arc<Mutex<RaceEngine>> {
+        self.engines
+            .lock()
+            .await
+            .entry(track_id.to_string())
+            .or_insert_with(|| {
+                Arc::new(Mutex::new(RaceEngine::new(
+                    track_id.to_string(),
+                    self.event_tx.clone(),
+                )))
+            })
+            .clone()
+    }
+
+    /// The track's engine, if one was created
+    pub async fn get(&self, track_id: &str) -> Option<Arc<Mutex<RaceEngine>>> {
+        self.engines.lock().await.get(track_id).cloned()
+    }
+
+    /// Every engine with its track, ordered by `track_id`
+    pub async fn all(&self) -> Vec<(String, Arc<Mutex<RaceEngine>>)> {
+        self.engines
+            .lock()
+            .await
+            .iter()
+            .map(|(track_id, engine)| (track_id.clone(), engine.clone()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use p3_parser::messages::PassingMessage;
+
+    use super::*;
+    use crate::domain::race_event::{RaceRules, StagedRider, StartMode, TrackConfig};
+    use crate::engine::RacePhase;
+
+    fn track(track_id: &str) -> TrackConfig {
+        TrackConfig {
+            track_id: track_id.into(),
+            name: track_id.into(),
+            gate_beacon_id: 9992,
+            loops: vec![],
+        }
+    }
+
+    fn stage(moto_id: &str) -> RaceCommand {
+        RaceCommand::Stage {
+            moto_id: moto_id.into(),
+            class_name: "Novice".into(),
+            round_type: "moto1".into(),
+            laps: 1,
+            start_mode: StartMode::GateDrop,
+            riders: vec![StagedRider {
+                rider_id: format!("{moto_id}-rider"),
+                first_name: "Ada".into(),
+                last_name: "Rider".into(),
+                plate_number: "1".into(),
+                transponder_id: 1001,
+                lane: 1,
+            }],
+        }
+    }
+
+    fn staged_moto(engine: &RaceEngine) -> Option<String> {
+        match engine.state().phase() {
+            RacePhase::Staged { moto_id, .. } => Some(moto_id.clone()),
+            _ => None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tracks_run_independent_engines() {
+        let (event_tx, mut event_rx) = broadcast::channel(32);
+        let registry = EngineRegistry::new(event_tx);
+
+        for (track_id, moto_id) in [("track-a", "moto-a"), ("track-b", "moto-b")] {
+            let engine = registry.engine(track_id).await;
+            let mut engine = engine.lock().await;
+            engine.apply(RaceCommand::SetTrack(track(track_id)));
+            engine.apply(stage(moto_id));
+        }
+
+        let a = registry.get("track-a").await.unwrap();
+        let b = registry.get("track-b").await.unwrap();
+        assert_eq!(staged_moto(&*a.lock().await).as_deref(), Some("moto-a"));
+        assert_eq!(staged_moto(&*b.lock().await).as_deref(), Some("moto-b"));
+
+        b.lock().await.apply(RaceCommand::Reset);
+        assert_eq!(staged_moto(&*a.lock().await).as_deref(), Some("moto-a"));
+        assert!(matches!(b.lock().await.state().phase(), RacePhase::Idle));
+
+        let mut tracks = Vec::new();
+        while let Ok(event) = event_rx.try_recv() {
+            if matches!(event.event, RaceEvent::RaceStaged { .. }) {
+                tracks.push(event.track_id.clone());
+            }
+        }
+        assert_eq!(tracks, ["track-a", "track-b"]);
+        assert!(registry.get("track-c").await.is_none());
+        assert_eq!(registry.all().await.len(), 2);
+    }
+
+    fn gate_drop(captured_at_us: Option<u64>) -> RaceCommand {
+        RaceCommand::Passing {
+            passing: PassingMessage {
+                passing_number: 1,
+                transponder_id: 9992,
+                rtc_time_us: 1_000_000_000,
+                utc_time_us: None,
+                strength: Some(100),
+                hits: Some(40),
+                transponder_string: None,
+                flags: 0,
+                decoder_id: Some("D0000C01".into()),
+                extra_fields: Vec::new(),
+            },
+            captured_at_us,
+        }
+    }
+
+    fn racing_engine(captured_at_us: Option<u64>) -> RaceEngine {
+        let (event_tx, _) = broadcast::channel(32);
+        let mut engine = RaceEngine::new("track-a".into(), event_tx);
+        engine.apply(RaceCommand::SetTrack(track("track-a")));
+        engine.apply(RaceCommand::SetRaceRules(RaceRules {
+            dns_timeout_secs: Some(30),
+            dnf_timeout_secs: None,
+            max_race_secs: None,
+        }));
+        engine.apply(stage("moto-a"));
+        engine.apply(gate_drop(captured_at_us));
+        assert_eq!(engine.state().phase().name(), "racing");
+        engine
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_rules_run_on_track_time_since_last_passing() {
+        // The decoder's clock is nowhere near the server's
+        let mut engine = racing_engine(Some(1_000_000_000));
+        assert!(engine.check_timeouts().is_empty());
+
+        tokio::time::sleep(Duration::from_secs(29)).await;
+        assert!(engine.check_timeouts().is_empty());
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        let events = engine.check_timeouts();
+        assert!(
+            events
+                .iter()
+                .any(|event| matches!(event, RaceEvent::RaceFinished { .. }))
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_rules_wait_for_a_decoder_clock_model() {
+        let mut engine = racing_engine(None);
+        tokio::time::sleep(Duration::from_secs(60)).await;
+        assert!(engine.check_timeouts().is_empty());
+        assert_eq!(engine.state().phase().name(), "racing");
+    }
+}
@@ -1,4 +1,9 @@
+mod clock;
+mod host;
+mod moto;
+mod practice;
 mod processor;
 mod state;
 
-pub use state::{RaceEngine, RacePhase};
+pub use host::{EngineRegistry, RaceEngine, TrackClock, TrackRaceEvent};
+pub use state::{RULES_CHECK_INTERVAL, RaceCommand, RacePhase, RaceState, apply};
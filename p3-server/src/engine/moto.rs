@@ -0,0 +1,1521 @@
+use std::collections::{HashMap, HashSet};
+
+use p3_parser::messages::PassingMessage;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::domain::race_event::{
+    FinishResult, LoopConfig, LoopCrossing, RaceEvent, RaceRules, RecordedPassing, RiderPosition,
+    RiderState, StagedRider, TimingChange, TimingCorrection, TrackConfig,
+};
+
+use super::processor;
+use super::state::RacePhase;
+
+/// Where a rider's race clock starts
+#[derive(Debug, Clone, Copy)]
+enum RaceClock {
+    /// Everyone's clock starts at the gate drop
+    Gate(u64),
+    /// Each rider's clock starts at their own start-loop crossing
+    IndividualStart,
+}
+
+/// One moto on the track, from staging until the next moto is staged
+/// after it finished.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Moto {
+    /// Staged, racing, time trial or finished
+    phase: RacePhase,
+    /// Track configuration the moto was staged with
+    track_config: Option<TrackConfig>,
+    /// Rider states keyed by transponder_id for fast lookup during racing
+    riders_by_transponder: HashMap<u32, RiderState>,
+    /// Rider states keyed by rider_id for result lookups
+    rider_ids: Vec<String>,
+    /// Next finish position to assign
+    next_finish_position: u32,
+    /// Laps a rider completes to finish the moto
+    laps: u32,
+    /// Automatic DNS/DNF rules for a gate race
+    rules: RaceRules,
+    /// Passings by transponders outside the moto, replayed if one is bound
+    /// to a rider
+    unmatched_passings: HashMap<u32, Vec<PassingMessage>>,
+    /// Unknown transponders already alerted, per loop_id
+    alerted_transponders: HashSet<(u32, String)>,
+    /// Unmapped decoders already alerted
+    alerted_decoders: HashSet<Option<String>>,
+    /// Every passing the moto has seen since the start, replayed to apply
+    /// timing corrections
+    passing_log: Vec<PassingMessage>,
+    /// Operator timing corrections to the moto, in the order made
+    corrections: Vec<TimingCorrection>,
+    /// Transponder binds, in the order made, replayed with the corrections
+    binds: Vec<TransponderBind>,
+    /// Set while the moto is replayed to apply a correction
+    #[serde(skip)]
+    replaying: bool,
+}
+
+/// A rider moved onto another transponder, e.g. after a bike swap.
+#[derive(Clone, Serialize, Deserialize)]
+struct TransponderBind {
+    rider_id: String,
+    previous_transponder_id: u32,
+    transponder_id: u32,
+    /// Decoder time of the moto's latest passing when the bind was made.
+    /// Later reads of the previous transponder no longer count for the
+    /// rider.
+    effective_us: u64,
+}
+
+impl Moto {
+    pub fn new(
+        phase: RacePhase,
+        track_config: Option<TrackConfig>,
+        rules: RaceRules,
+        laps: u32,
+        riders: &[StagedRider],
+    ) -> Self {
+        let mut moto = Self {
+            phase,
+            track_config,
+            riders_by_transponder: HashMap::new(),
+            rider_ids: Vec::new(),
+            next_finish_position: 1,
+            laps,
+            rules,
+            unmatched_passings: HashMap::new(),
+            alerted_transponders: HashSet::new(),
+            alerted_decoders: HashSet::new(),
+            passing_log: Vec::new(),
+            corrections: Vec::new(),
+            binds: Vec::new(),
+            replaying: false,
+        };
+        for rider in riders {
+            moto.load_rider(rider);
+        }
+        moto
+    }
+
+    pub fn moto_id(&self) -> &str {
+        match &self.phase {
+            RacePhase::Staged { moto_id, .. }
+            | RacePhase::Racing { moto_id, .. }
+            | RacePhase::TimeTrial { moto_id, .. }
+            | RacePhase::Finished { moto_id, .. } => moto_id,
+            RacePhase::Idle | RacePhase::Practice { .. } => "",
+        }
+    }
+
+    /// Staged, racing, time trial or finished.
+    pub fn phase(&self) -> &RacePhase {
+        &self.phase
+    }
+
+    /// Every rider in the moto.
+    #[cfg(test)]
+    pub fn riders(&self) -> impl Iterator<Item = &RiderState> {
+        self.riders_by_transponder.values()
+    }
+
+    /// The rider on a transponder, if one in the moto is.
+    pub fn rider(&self, transponder_id: u32) -> Option<&RiderState> {
+        self.riders_by_transponder.get(&transponder_id)
+    }
+
+    /// Staged or on course, not finished yet.
+    pub fn is_running(&self) -> bool {
+        matches!(
+            self.phase,
+            RacePhase::Staged { .. } | RacePhase::Racing { .. } | RacePhase::TimeTrial { .. }
+        )
+    }
+
+    /// Riders were on course at decoder time `rtc_time_us`.
+    pub fn on_course_at(&self, rtc_time_us: u64) -> bool {
+        match self.phase {
+            RacePhase::Racing {
+                gate_drop_time_us, ..
+            } => gate_drop_time_us <= rtc_time_us,
+            RacePhase::TimeTrial { .. } => true,
+            _ => false,
+        }
+    }
+
+    /// Append riders to a running time trial's start list.
+    ///
+    /// Riders join in the order given and `lane` is replaced by their start
+    /// order. Riders whose transponder is already on the list are skipped.
+    pub fn add_to_start_list(
+        &mut self,
+        moto_id: &str,
+        riders: Vec<StagedRider>,
+    ) -> Option<RaceEvent> {
+        if !matches!(self.phase, RacePhase::TimeTrial { .. }) {
+            warn!(
+                moto_id = %moto_id,
+                phase = self.phase.name(),
+                "Cannot add to start list: no time trial running"
+            );
+            return None;
+        }
+
+        let mut added = Vec::new();
+        for mut rider in riders {
+            if self
+                .riders_by_transponder
+                .contains_key(&rider.transponder_id)
+            {
+                warn!(
+                    rider = %rider.rider_id,
+                    transponder_id = rider.transponder_id,
+                    "Transponder already on the start list"
+                );
+                continue;
+            }
+
+            rider.lane = self.rider_ids.len() as u32 + 1;
+            // Reads from before joining the list are not part of the trial
+            self.unmatched_passings.remove(&rider.transponder_id);
+            self.load_rider(&rider);
+            added.push(rider);
+        }
+        if added.is_empty() {
+            return None;
+        }
+
+        info!(moto_id = %moto_id, added = added.len(), "Start list updated");
+        let event = RaceEvent::StartListUpdated {
+            moto_id: moto_id.to_string(),
+            riders: added,
+        };
+        Some(event)
+    }
+
+    /// Move a staged rider onto another transponder, e.g. after a bike swap.
+    ///
+    /// The transponder's passings so far this moto are re-processed as the
+    /// rider's, and the resulting race events returned.
+    pub fn bind_transponder(
+        &mut self,
+        moto_id: &str,
+        rider_id: &str,
+        transponder_id: u32,
+    ) -> Vec<RaceEvent> {
+        let (class_name, round_type, clock) = match &self.phase {
+            RacePhase::Staged {
+                class_name,
+                round_type,
+                ..
+            } => (class_name, round_type, None),
+            RacePhase::Racing {
+                class_name,
+                round_type,
+                gate_drop_time_us,
+                ..
+            } => (
+                class_name,
+                round_type,
+                Some(RaceClock::Gate(*gate_drop_time_us)),
+            ),
+            RacePhase::TimeTrial {
+                class_name,
+                round_type,
+                ..
+            } => (class_name, round_type, Some(RaceClock::IndividualStart)),
+            _ => {
+                warn!(
+                    moto_id = %moto_id,
+                    phase = self.phase.name(),
+                    "Cannot bind transponder: moto is not running"
+                );
+                return vec![];
+            }
+        };
+        let (class_name, round_type) = (class_name.clone(), round_type.clone());
+
+        if self.riders_by_transponder.contains_key(&transponder_id) {
+            warn!(
+                transponder_id,
+                "Transponder already belongs to a rider in the moto"
+            );
+            return vec![];
+        }
+        let effective_us = self
+            .passing_log
+            .iter()
+            .map(|p| p.rtc_time_us)
+            .max()
+            .unwrap_or(0);
+        let Some((previous_transponder_id, passings)) = self.rebind(rider_id, transponder_id)
+        else {
+            warn!(rider = %rider_id, "Cannot bind transponder: rider not in the moto");
+            return vec![];
+        };
+        self.binds.push(TransponderBind {
+            rider_id: rider_id.to_string(),
+            previous_transponder_id,
+            transponder_id,
+            effective_us,
+        });
+        info!(
+            moto_id = %moto_id,
+            rider = %rider_id,
+            previous_transponder_id,
+            transponder_id,
+            passings = passings.len(),
+            "Transponder bound to rider"
+        );
+
+        let Some(clock) = clock else {
+            return vec![];
+        };
+        let mut events = vec![];
+        for passing in &passings {
+            // Stop if the replay finishes the race
+            if !matches!(
+                self.phase,
+                RacePhase::Racing { .. } | RacePhase::TimeTrial { .. }
+            ) {
+                break;
+            }
+            events.extend(self.process_race_passing(
+                passing,
+                moto_id.to_string(),
+                class_name.clone(),
+                round_type.clone(),
+                clock,
+            ));
+        }
+        events
+    }
+
+    /// Move a rider onto `transponder_id`, returning their previous
+    /// transponder and the passings the new one already had in the moto.
+    fn rebind(
+        &mut self,
+        rider_id: &str,
+        transponder_id: u32,
+    ) -> Option<(u32, Vec<PassingMessage>)> {
+        let previous_transponder_id = self
+            .riders_by_transponder
+            .values()
+            .find(|r| r.rider_id == rider_id)?
+            .transponder_id;
+        let mut rider = self
+            .riders_by_transponder
+            .remove(&previous_transponder_id)?;
+
+        rider.transponder_id = transponder_id;
+        // A DNS only meant the old transponder was never read
+        if rider.splits.is_empty() {
+            rider.dns = false;
+        }
+        self.riders_by_transponder.insert(transponder_id, rider);
+
+        let passings = self
+            .unmatched_passings
+            .remove(&transponder_id)
+            .unwrap_or_default();
+        Some((previous_transponder_id, passings))
+    }
+
+    fn load_rider(&mut self, rider: &StagedRider) {
+        self.rider_ids.push(rider.rider_id.clone());
+        self.riders_by_transponder.insert(
+            rider.transponder_id,
+            RiderState::new(
+                rider.rider_id.clone(),
+                rider.first_name.clone(),
+                rider.last_name.clone(),
+                rider.plate_number.clone(),
+                rider.transponder_id,
+                rider.lane,
+            ),
+        );
+    }
+
+    /// Start the race on the gate beacon's passing.
+    pub fn drop_gate(&mut self, passing: &PassingMessage) -> Vec<RaceEvent> {
+        let RacePhase::Staged {
+            moto_id,
+            class_name,
+            round_type,
+        } = &self.phase
+        else {
+            return vec![];
+        };
+        let moto_id = moto_id.clone();
+        self.phase = RacePhase::Racing {
+            moto_id: moto_id.clone(),
+            class_name: class_name.clone(),
+            round_type: round_type.clone(),
+            gate_drop_time_us: passing.rtc_time_us,
+        };
+
+        info!(
+            moto_id = %moto_id,
+            timestamp = passing.rtc_time_us,
+            "Gate drop detected"
+        );
+
+        vec![RaceEvent::GateDrop {
+            moto_id,
+            timestamp_us: passing.rtc_time_us,
+        }]
+    }
+
+    /// Record a passing attributed to the moto while it is on course.
+    pub fn process_passing(&mut self, passing: &PassingMessage) -> Vec<RaceEvent> {
+        let (moto_id, class_name, round_type, clock) = match &self.phase {
+            RacePhase::Racing {
+                moto_id,
+                class_name,
+                round_type,
+                gate_drop_time_us,
+            } => (
+                moto_id,
+                class_name,
+                round_type,
+                RaceClock::Gate(*gate_drop_time_us),
+            ),
+            RacePhase::TimeTrial {
+                moto_id,
+                class_name,
+                round_type,
+            } => (moto_id, class_name, round_type, RaceClock::IndividualStart),
+            _ => return vec![],
+        };
+        let (moto_id, class_name, round_type) =
+            (moto_id.clone(), class_name.clone(), round_type.clone());
+        self.passing_log.push(passing.clone());
+        self.process_race_passing(passing, moto_id, class_name, round_type, clock)
+    }
+
+    /// Match a passing during a race to a rider and loop, and record it.
+    fn process_race_passing(
+        &mut self,
+        passing: &PassingMessage,
+        moto_id: String,
+        class_name: String,
+        round_type: String,
+        clock: RaceClock,
+    ) -> Vec<RaceEvent> {
+        let mut events = vec![];
+
+        // Try to match this passing to a rider and a loop
+        if let Some(loop_config) = passing
+            .decoder_id
+            .as_ref()
+            .and_then(|did| self.track_config.as_ref()?.loop_for_decoder(did))
+        {
+            let loop_config = loop_config.clone();
+            let laps = self.laps;
+
+            if let Some(rider) = self.riders_by_transponder.get_mut(&passing.transponder_id) {
+                // Ruled out of the race; later reads change nothing
+                if rider.dns || rider.dnf {
+                    return events;
+                }
+                let start_us = match clock {
+                    RaceClock::Gate(gate_drop_time_us) => gate_drop_time_us,
+                    RaceClock::IndividualStart => match rider.start_rtc_time_us {
+                        // Repeat reads of the start loop once the rider is on course
+                        Some(_) if loop_config.is_start && !loop_config.is_finish => {
+                            return events;
+                        }
+                        Some(start_us) => start_us,
+                        None if loop_config.is_start => {
+                            rider.start_rtc_time_us = Some(passing.rtc_time_us);
+                            let rider_id = rider.rider_id.clone();
+                            info!(
+                                moto_id = %moto_id,
+                                rider = %rider_id,
+                                timestamp = passing.rtc_time_us,
+                                "Rider started"
+                            );
+
+                            let started = RaceEvent::RiderStarted {
+                                moto_id: moto_id.clone(),
+                                rider_id,
+                                timestamp_us: passing.rtc_time_us,
+                            };
+                            let positions = RaceEvent::PositionsUpdate {
+                                moto_id,
+                                positions: self.calculate_positions(),
+                            };
+                            return vec![started, positions];
+                        }
+                        // Not on course yet
+                        None => return events,
+                    },
+                };
+                let elapsed_us = passing.rtc_time_us.saturating_sub(start_us);
+
+                // A finish hit belongs to the lap it completes, unless it is a
+                // repeat read of the lap the rider just completed
+                let laps_completed = rider.laps_completed();
+                let lap = if loop_config.is_finish
+                    && laps_completed > 0
+                    && (rider.finished
+                        || rider
+                            .crossings
+                            .get(&(laps_completed, loop_config.loop_id.clone()))
+                            .is_some_and(|c| processor::within_debounce(c, passing, &loop_config)))
+                {
+                    laps_completed
+                } else {
+                    rider.current_lap()
+                };
+                let key = (lap, loop_config.loop_id.clone());
+
+                // Repeat hits at a loop only matter if they improve the crossing
+                if let Some(crossing) = rider.crossings.get_mut(&key) {
+                    if !processor::supersedes(crossing, passing, &loop_config) {
+                        return events;
+                    }
+                    crossing.rtc_time_us = passing.rtc_time_us;
+                    crossing.strength = passing.strength.unwrap_or(0);
+                    crossing.hits = passing.hits.unwrap_or(0);
+
+                    let previous_elapsed_us =
+                        rider.splits.insert(key, elapsed_us).unwrap_or(elapsed_us);
+                    if previous_elapsed_us == elapsed_us {
+                        return events;
+                    }
+
+                    let is_latest_crossing = if loop_config.is_finish {
+                        rider.lap_elapsed_us[lap as usize - 1] = elapsed_us;
+                        lap == laps_completed && rider.last_loop_position.is_none()
+                    } else {
+                        rider.last_loop_position == Some(loop_config.position)
+                    };
+                    if is_latest_crossing {
+                        rider.last_elapsed_us = Some(elapsed_us);
+                    }
+                    if loop_config.is_finish && rider.finished && lap == laps {
+                        rider.finish_elapsed_us = Some(elapsed_us);
+                    }
+                    let rider_id = rider.rider_id.clone();
+
+                    return self.correct_split(
+                        moto_id,
+                        rider_id,
+                        lap,
+                        &loop_config,
+                        previous_elapsed_us,
+                        elapsed_us,
+                    );
+                }
+
+                if rider.finished {
+                    return events;
+                }
+
+                // Only record if this is a new loop (further along the lap)
+                // or if the rider hasn't been seen at this loop yet
+                let dominated = rider
+                    .last_loop_position
+                    .is_some_and(|last_pos| loop_config.position < last_pos);
+
+                if dominated && !loop_config.is_finish {
+                    // Rider went backwards or duplicate at an earlier loop — ignore
+                    return events;
+                }
+
+                // Record the split
+                rider.splits.insert(key.clone(), elapsed_us);
+                rider.crossings.insert(
+                    key,
+                    LoopCrossing {
+                        first_rtc_time_us: passing.rtc_time_us,
+                        rtc_time_us: passing.rtc_time_us,
+                        strength: passing.strength.unwrap_or(0),
+                        hits: passing.hits.unwrap_or(0),
+                    },
+                );
+                if loop_config.is_finish {
+                    // Lap complete, the next one starts from no loop
+                    rider.lap_elapsed_us.push(elapsed_us);
+                    rider.last_loop_position = None;
+                } else {
+                    rider.last_loop_position = Some(loop_config.position);
+                }
+                rider.last_loop_name = Some(loop_config.name.clone());
+                rider.last_elapsed_us = Some(elapsed_us);
+
+                let rider_id = rider.rider_id.clone();
+
+                if loop_config.is_finish && rider.laps_completed() >= laps {
+                    // Rider finished!
+                    rider.finished = true;
+                    rider.finish_elapsed_us = Some(elapsed_us);
+                    rider.finish_position = Some(self.next_finish_position);
+                    self.next_finish_position += 1;
+                    // Time trials rank by elapsed time, not by order of arrival
+                    if matches!(clock, RaceClock::IndividualStart) {
+                        self.rerank_finishers();
+                    }
+                    let pos = self.finish_position_of(&rider_id).unwrap_or(1);
+
+                    // Calculate gap to leader
+                    let leader_time = self.leader_finish_time();
+                    let gap = leader_time.map(|lt| elapsed_us.saturating_sub(lt));
+
+                    info!(
+                        rider = %rider_id,
+                        position = pos,
+                        elapsed_us = elapsed_us,
+                        "Rider finished"
+                    );
+
+                    let split_event = RaceEvent::SplitTime {
+                        moto_id: moto_id.clone(),
+                        rider_id: rider_id.clone(),
+                        lap,
+                        loop_name: loop_config.name.clone(),
+                        is_finish: true,
+                        elapsed_us,
+                        position: pos,
+                        gap_to_leader_us: gap,
+                    };
+                    events.push(split_event);
+
+                    let finish_event = RaceEvent::RiderFinished {
+                        moto_id: moto_id.clone(),
+                        rider_id: rider_id.clone(),
+                        finish_position: pos,
+                        elapsed_us,
+                        gap_to_leader_us: gap,
+                    };
+                    events.push(finish_event);
+                } else {
+                    // Split time at a non-finish loop, or the end of a lap
+                    let position = processor::calculate_position_at_loop(
+                        &self.riders_by_transponder,
+                        lap,
+                        &loop_config,
+                        &rider_id,
+                    );
+
+                    // Gap to leader at this loop on this lap
+                    let leader_time = processor::leader_time_at_loop(
+                        &self.riders_by_transponder,
+                        lap,
+                        &loop_config,
+                    );
+                    let gap = leader_time.map(|lt| elapsed_us.saturating_sub(lt));
+
+                    let split_event = RaceEvent::SplitTime {
+                        moto_id: moto_id.clone(),
+                        rider_id: rider_id.clone(),
+                        lap,
+                        loop_name: loop_config.name.clone(),
+                        is_finish: false,
+                        elapsed_us,
+                        position,
+                        gap_to_leader_us: gap,
+                    };
+                    events.push(split_event);
+                }
+
+                // Publish updated positions
+                let positions = self.calculate_positions();
+                events.push(RaceEvent::PositionsUpdate {
+                    moto_id: moto_id.clone(),
+                    positions,
+                });
+
+                // Time trials keep running for the rolling start list until
+                // the operator finishes them
+                if self.all_riders_done() && matches!(clock, RaceClock::Gate(_)) {
+                    info!(moto_id = %moto_id, "Race finished — all riders done");
+                    events.push(self.finish_race(moto_id, class_name, round_type));
+                }
+            } else {
+                events.extend(self.unknown_transponder(passing, moto_id, &loop_config));
+            }
+        } else {
+            events.extend(self.unmapped_decoder(passing, moto_id));
+        }
+
+        events
+    }
+
+    /// Finish the moto, ruling out riders still on course.
+    pub fn force_finish(&mut self) -> Option<RaceEvent> {
+        match &self.phase {
+            RacePhase::Racing {
+                moto_id,
+                class_name,
+                round_type,
+                ..
+            }
+            | RacePhase::TimeTrial {
+                moto_id,
+                class_name,
+                round_type,
+            } => {
+                let moto_id = moto_id.clone();
+                let class_name = class_name.clone();
+                let round_type = round_type.clone();
+                let time_trial = matches!(self.phase, RacePhase::TimeTrial { .. });
+
+                // Mark unfinished riders as DNF, or DNS if a time trial
+                // rider never crossed the start loop
+                for rider in self.riders_by_transponder.values_mut() {
+                    if rider.finished {
+                        continue;
+                    }
+                    if time_trial && rider.start_rtc_time_us.is_none() {
+                        rider.dns = true;
+                    } else {
+                        rider.dnf = true;
+                    }
+                }
+
+                info!(moto_id = %moto_id, "Race force-finished by operator");
+                Some(self.finish_race(moto_id, class_name, round_type))
+            }
+            _ => {
+                warn!(phase = self.phase.name(), "Cannot force-finish: not racing");
+                None
+            }
+        }
+    }
+
+    /// Apply the race rules at decoder time `now_us`.
+    ///
+    /// Riders a rule catches are DNS if they were never seen at a loop and
+    /// DNF otherwise; the race finishes once no rider is left on course.
+    pub fn check_timeouts(&mut self, now_us: u64) -> Vec<RaceEvent> {
+        let RacePhase::Racing {
+            moto_id,
+            class_name,
+            round_type,
+            gate_drop_time_us,
+        } = &self.phase
+        else {
+            return vec![];
+        };
+        let (moto_id, class_name, round_type) =
+            (moto_id.clone(), class_name.clone(), round_type.clone());
+
+        let elapsed_us = now_us.saturating_sub(*gate_drop_time_us);
+        let due = |timeout_secs: Option<u32>, from_us: u64| {
+            timeout_secs.is_some_and(|secs| {
+                elapsed_us >= from_us.saturating_add(u64::from(secs) * 1_000_000)
+            })
+        };
+        let dns_due = due(self.rules.dns_timeout_secs, 0);
+        let dnf_due = self
+            .leader_finish_time()
+            .is_some_and(|leader_us| due(self.rules.dnf_timeout_secs, leader_us));
+        let time_up = due(self.rules.max_race_secs, 0);
+
+        let mut ruled_out = 0;
+        for rider in self.riders_by_transponder.values_mut() {
+            if rider.finished || rider.dnf || rider.dns {
+                continue;
+            }
+            let seen = !rider.splits.is_empty();
+            if !seen && (dns_due || dnf_due || time_up) {
+                rider.dns = true;
+                ruled_out += 1;
+            } else if seen && (dnf_due || time_up) {
+                rider.dnf = true;
+                ruled_out += 1;
+            }
+        }
+
+        if ruled_out == 0 && !time_up {
+            return vec![];
+        }
+
+        let mut events = vec![];
+        if ruled_out > 0 {
+            info!(
+                moto_id = %moto_id,
+                riders = ruled_out,
+                elapsed_us,
+                "Riders ruled DNS/DNF by race rules"
+            );
+            events.push(RaceEvent::PositionsUpdate {
+                moto_id: moto_id.clone(),
+                positions: self.calculate_positions(),
+            });
+        }
+
+        if time_up || self.all_riders_done() {
+            info!(moto_id = %moto_id, time_up, "Race finished by race rules");
+            events.push(self.finish_race(moto_id, class_name, round_type));
+        }
+
+        events
+    }
+
+    /// Every passing recorded in the moto, oldest first, including
+    /// operator-inserted ones and those voided by a correction.
+    pub fn recorded_passings(&self) -> Vec<RecordedPassing> {
+        let voided = self.voided_passing_ids();
+        let mut recorded: Vec<RecordedPassing> = self
+            .passing_log
+            .iter()
+            .map(|passing| {
+                let passing_id = processor::passing_id(passing);
+                self.recorded_passing(
+                    passing,
+                    voided.contains(passing_id.as_str()),
+                    passing_id,
+                    false,
+                )
+            })
+            .collect();
+        for (correction_id, passing) in self.manual_passings() {
+            let passing_id = processor::manual_passing_id(correction_id);
+            recorded.push(self.recorded_passing(
+                &passing,
+                voided.contains(passing_id.as_str()),
+                passing_id,
+                true,
+            ));
+        }
+        recorded.sort_by_key(|r| r.timestamp_us);
+        recorded
+    }
+
+    /// Check that a timing correction applies to the moto.
+    pub fn validate_correction(&self, change: &TimingChange) -> Result<(), String> {
+        if !matches!(
+            self.phase,
+            RacePhase::Racing { .. } | RacePhase::TimeTrial { .. } | RacePhase::Finished { .. }
+        ) {
+            return Err(format!("Cannot correct timing while {}", self.phase.name()));
+        }
+
+        let has_rider = |rider_id: &str| {
+            self.riders_by_transponder
+                .values()
+                .any(|r| r.rider_id == rider_id)
+        };
+        match change {
+            TimingChange::InsertPassing {
+                rider_id, loop_id, ..
+            } => {
+                if !has_rider(rider_id) {
+                    return Err(format!("Rider {rider_id} is not in the moto"));
+                }
+                let on_track = self
+                    .track_config
+                    .iter()
+                    .flat_map(|t| &t.loops)
+                    .any(|l| l.loop_id == *loop_id);
+                if !on_track {
+                    return Err(format!("Loop {loop_id} is not on the track"));
+                }
+            }
+            TimingChange::VoidPassing { passing_id } => {
+                if !self
+                    .recorded_passings()
+                    .iter()
+                    .any(|p| p.passing_id == *passing_id)
+                {
+                    return Err(format!("Passing {passing_id} is not in the moto"));
+                }
+            }
+            TimingChange::HandTimedFinish {
+                rider_id,
+                elapsed_us,
+            } => {
+                if !has_rider(rider_id) {
+                    return Err(format!("Rider {rider_id} is not in the moto"));
+                }
+                if *elapsed_us == 0 {
+                    return Err("elapsed_us must be positive".into());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Apply an operator's timing correction to the moto.
+    ///
+    /// The moto is replayed from its passings with every correction so far
+    /// applied, so the outcome does not depend on the order passings and
+    /// corrections arrived in. A finished moto stays finished and publishes
+    /// its recomputed results.
+    pub fn apply_correction(
+        &mut self,
+        moto_id: &str,
+        correction: TimingCorrection,
+    ) -> Vec<RaceEvent> {
+        if let Err(reason) = self.validate_correction(&correction.change) {
+            warn!(moto_id = %moto_id, reason = %reason, "Timing correction rejected");
+            return vec![];
+        }
+
+        info!(
+            moto_id = %moto_id,
+            correction_id = %correction.correction_id,
+            operator = %correction.operator,
+            reason = %correction.reason,
+            change = ?correction.change,
+            "Timing correction applied"
+        );
+        self.corrections.push(correction.clone());
+        let finish = self.recompute();
+
+        let corrected = RaceEvent::TimingCorrected {
+            moto_id: moto_id.to_string(),
+            correction,
+        };
+        let positions = RaceEvent::PositionsUpdate {
+            moto_id: moto_id.to_string(),
+            positions: self.calculate_positions(),
+        };
+
+        let mut events = vec![corrected, positions];
+        if let Some((moto_id, class_name, round_type)) = finish {
+            events.push(self.finish_race(moto_id, class_name, round_type));
+        }
+        events
+    }
+
+    /// Every rider has finished or been ruled out.
+    fn all_riders_done(&self) -> bool {
+        !self.riders_by_transponder.is_empty()
+            && self
+                .riders_by_transponder
+                .values()
+                .all(|r| r.finished || r.dnf || r.dns)
+    }
+
+    /// Move to [`RacePhase::Finished`] and publish the results.
+    fn finish_race(
+        &mut self,
+        moto_id: String,
+        class_name: String,
+        round_type: String,
+    ) -> RaceEvent {
+        let gate_drop_time_us = match self.phase {
+            RacePhase::Racing {
+                gate_drop_time_us, ..
+            } => Some(gate_drop_time_us),
+            RacePhase::Finished {
+                gate_drop_time_us, ..
+            } => gate_drop_time_us,
+            _ => None,
+        };
+        let results = self.build_results();
+        self.phase = RacePhase::Finished {
+            moto_id: moto_id.clone(),
+            class_name,
+            round_type,
+            gate_drop_time_us,
+        };
+
+        RaceEvent::RaceFinished { moto_id, results }
+    }
+
+    /// Build a snapshot of the moto for newly connected clients.
+    pub fn state_snapshot(&self) -> RaceEvent {
+        let (moto_id, class_name, round_type, gate_drop_time_us) = match &self.phase {
+            RacePhase::Idle | RacePhase::Practice { .. } => (None, None, None, None),
+            RacePhase::Staged {
+                moto_id,
+                class_name,
+                round_type,
+            } => (
+                Some(moto_id.clone()),
+                Some(class_name.clone()),
+                Some(round_type.clone()),
+                None,
+            ),
+            RacePhase::Racing {
+                moto_id,
+                class_name,
+                round_type,
+                gate_drop_time_us,
+            } => (
+                Some(moto_id.clone()),
+                Some(class_name.clone()),
+                Some(round_type.clone()),
+                Some(*gate_drop_time_us),
+            ),
+            RacePhase::TimeTrial {
+                moto_id,
+                class_name,
+                round_type,
+            } => (
+                Some(moto_id.clone()),
+                Some(class_name.clone()),
+                Some(round_type.clone()),
+                None,
+            ),
+            RacePhase::Finished {
+                moto_id,
+                class_name,
+                round_type,
+                gate_drop_time_us,
+            } => (
+                Some(moto_id.clone()),
+                Some(class_name.clone()),
+                Some(round_type.clone()),
+                *gate_drop_time_us,
+            ),
+        };
+
+        let mut riders: Vec<StagedRider> = self
+            .riders_by_transponder
+            .values()
+            .map(|r| StagedRider {
+                rider_id: r.rider_id.clone(),
+                first_name: r.first_name.clone(),
+                last_name: r.last_name.clone(),
+                plate_number: r.plate_number.clone(),
+                transponder_id: r.transponder_id,
+                lane: r.lane,
+            })
+            .collect();
+        riders.sort_by_key(|r| r.lane);
+
+        let finished_count = self
+            .riders_by_transponder
+            .values()
+            .filter(|r| r.finished)
+            .count() as u32;
+
+        RaceEvent::StateSnapshot {
+            phase: self.phase.name().to_string(),
+            moto_id,
+            class_name,
+            round_type,
+            riders,
+            positions: self.calculate_positions(),
+            gate_drop_time_us,
+            finished_count,
+            total_riders: self.riders_by_transponder.len() as u32,
+            laps: self.laps,
+            practice: None,
+        }
+    }
+
+    /// Publish a corrected split and the positions it changes.
+    ///
+    /// A corrected finish time re-ranks every finisher by elapsed time. The
+    /// window closes with the race: hits after `RaceFinished` are ignored.
+    fn correct_split(
+        &mut self,
+        moto_id: String,
+        rider_id: String,
+        lap: u32,
+        loop_config: &LoopConfig,
+        previous_elapsed_us: u64,
+        elapsed_us: u64,
+    ) -> Vec<RaceEvent> {
+        let corrects_finish = loop_config.is_finish && lap == self.laps;
+        let (position, leader_time) = if corrects_finish {
+            self.rerank_finishers();
+            let position = self.finish_position_of(&rider_id).unwrap_or(1);
+            (position, self.leader_finish_time())
+        } else {
+            (
+                processor::calculate_position_at_loop(
+                    &self.riders_by_transponder,
+                    lap,
+                    loop_config,
+                    &rider_id,
+                ),
+                processor::leader_time_at_loop(&self.riders_by_transponder, lap, loop_config),
+            )
+        };
+        let gap = leader_time.map(|lt| elapsed_us.saturating_sub(lt));
+
+        info!(
+            rider = %rider_id,
+            lap,
+            loop_name = %loop_config.name,
+            previous_elapsed_us,
+            elapsed_us,
+            "Split corrected by a stronger hit"
+        );
+
+        let correction = RaceEvent::SplitCorrected {
+            moto_id: moto_id.clone(),
+            rider_id,
+            lap,
+            loop_name: loop_config.name.clone(),
+            is_finish: corrects_finish,
+            previous_elapsed_us,
+            elapsed_us,
+            position,
+            gap_to_leader_us: gap,
+        };
+
+        let positions = RaceEvent::PositionsUpdate {
+            moto_id,
+            positions: self.calculate_positions(),
+        };
+
+        vec![correction, positions]
+    }
+
+    /// Rebuild every rider's race from the moto's passings and corrections.
+    ///
+    /// Returns the moto to finish if it is over after the replay.
+    fn recompute(&mut self) -> Option<(String, String, String)> {
+        let (moto_id, class_name, round_type, clock, was_finished) = match &self.phase {
+            RacePhase::Racing {
+                moto_id,
+                class_name,
+                round_type,
+                gate_drop_time_us,
+            } => (
+                moto_id,
+                class_name,
+                round_type,
+                RaceClock::Gate(*gate_drop_time_us),
+                false,
+            ),
+            RacePhase::TimeTrial {
+                moto_id,
+                class_name,
+                round_type,
+            } => (
+                moto_id,
+                class_name,
+                round_type,
+                RaceClock::IndividualStart,
+                false,
+            ),
+            RacePhase::Finished {
+                moto_id,
+                class_name,
+                round_type,
+                gate_drop_time_us,
+            } => (
+                moto_id,
+                class_name,
+                round_type,
+                gate_drop_time_us.map_or(RaceClock::IndividualStart, RaceClock::Gate),
+                true,
+            ),
+            _ => return None,
+        };
+        let (moto_id, class_name, round_type) =
+            (moto_id.clone(), class_name.clone(), round_type.clone());
+
+        // DNS/DNF rulings come from rules and operators, not passings
+        let rulings: HashMap<String, (bool, bool)> = self
+            .riders_by_transponder
+            .values()
+            .map(|r| (r.rider_id.clone(), (r.dns, r.dnf)))
+            .collect();
+        // Riders start the replay on the transponder they were staged on
+        let passings = self.effective_passings();
+        let riders = std::mem::take(&mut self.riders_by_transponder);
+        for rider in riders.into_values() {
+            let transponder_id = self
+                .binds
+                .iter()
+                .find(|b| b.rider_id == rider.rider_id)
+                .map_or(rider.transponder_id, |b| b.previous_transponder_id);
+            self.riders_by_transponder.insert(
+                transponder_id,
+                RiderState::new(
+                    rider.rider_id,
+                    rider.first_name,
+                    rider.last_name,
+                    rider.plate_number,
+                    transponder_id,
+                    rider.lane,
+                ),
+            );
+        }
+        self.unmatched_passings.clear();
+        self.next_finish_position = 1;
+
+        let phase = std::mem::replace(
+            &mut self.phase,
+            match clock {
+                RaceClock::Gate(gate_drop_time_us) => RacePhase::Racing {
+                    moto_id: moto_id.clone(),
+                    class_name: class_name.clone(),
+                    round_type: round_type.clone(),
+                    gate_drop_time_us,
+                },
+                RaceClock::IndividualStart => RacePhase::TimeTrial {
+                    moto_id: moto_id.clone(),
+                    class_name: class_name.clone(),
+                    round_type: round_type.clone(),
+                },
+            },
+        );
+        self.replaying = true;
+        let binds = self.binds.clone();
+        let mut binds = binds.iter().peekable();
+        let mut passings = passings.into_iter().peekable();
+        loop {
+            // A bind takes effect after the passings it was made behind
+            let bind = binds.next_if(|bind| {
+                passings
+                    .peek()
+                    .is_none_or(|p| p.rtc_time_us > bind.effective_us)
+            });
+            let replayed = if let Some(bind) = bind {
+                self.rebind(&bind.rider_id, bind.transponder_id)
+                    .map(|(_, passings)| passings)
+                    .unwrap_or_default()
+            } else if let Some(passing) = passings.next() {
+                vec![passing]
+            } else {
+                break;
+            };
+            for passing in &replayed {
+                self.process_race_passing(
+                    passing,
+                    moto_id.clone(),
+                    class_name.clone(),
+                    round_type.clone(),
+                    clock,
+                );
+            }
+        }
+        self.replaying = false;
+        self.phase = phase;
+
+        // Hand times override whatever the decoders made of the finish
+        let hand_times: Vec<(String, u64)> = self
+            .corrections
+            .iter()
+            .filter_map(|c| match &c.change {
+                TimingChange::HandTimedFinish {
+                    rider_id,
+                    elapsed_us,
+                } => Some((rider_id.clone(), *elapsed_us)),
+                _ => None,
+            })
+            .collect();
+        for (rider_id, elapsed_us) in hand_times {
+            let next_position = self.next_finish_position;
+            let Some(rider) = self
+                .riders_by_transponder
+                .values_mut()
+                .find(|r| r.rider_id == rider_id)
+            else {
+                continue;
+            };
+            if rider.finish_position.is_none() {
+                rider.finish_position = Some(next_position);
+                self.next_finish_position += 1;
+            }
+            rider.finished = true;
+            rider.finish_elapsed_us = Some(elapsed_us);
+            rider.last_elapsed_us = Some(elapsed_us);
+        }
+        self.rerank_finishers();
+
+        for rider in self.riders_by_transponder.values_mut() {
+            if rider.finished {
+                continue;
+            }
+            let seen = !rider.splits.is_empty() || rider.start_rtc_time_us.is_some();
+            if was_finished {
+                rider.dns = !seen;
+                rider.dnf = seen;
+            } else if let Some(&(dns, dnf)) = rulings.get(&rider.rider_id) {
+                // A corrected crossing lifts a DNS
+                rider.dns = dns && !seen;
+                rider.dnf = dnf;
+            }
+        }
+
+        let finished =
+            was_finished || (matches!(clock, RaceClock::Gate(_)) && self.all_riders_done());
+        finished.then_some((moto_id, class_name, round_type))
+    }
+
+    /// The moto's passings with corrections applied, in time order.
+    fn effective_passings(&self) -> Vec<PassingMessage> {
+        let voided = self.voided_passing_ids();
+        let mut passings: Vec<PassingMessage> = self
+            .passing_log
+            .iter()
+            .filter(|p| !voided.contains(processor::passing_id(p).as_str()))
+            .cloned()
+            .collect();
+        passings.extend(
+            self.manual_passings()
+                .into_iter()
+                .filter(|(correction_id, _)| {
+                    !voided.contains(processor::manual_passing_id(correction_id).as_str())
+                })
+                .map(|(_, passing)| passing),
+        );
+        passings.sort_by_key(|p| p.rtc_time_us);
+        passings
+    }
+
+    fn voided_passing_ids(&self) -> HashSet<&str> {
+        self.corrections
+            .iter()
+            .filter_map(|c| match &c.change {
+                TimingChange::VoidPassing { passing_id } => Some(passing_id.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Passings inserted by corrections, keyed by correction ID.
+    ///
+    /// They carry the rider's current transponder, and maximum strength so
+    /// an operator's crossing wins over any decoder hit it competes with.
+    fn manual_passings(&self) -> Vec<(&str, PassingMessage)> {
+        self.corrections
+            .iter()
+            .filter_map(|c| {
+                let TimingChange::InsertPassing {
+                    rider_id,
+                    loop_id,
+                    timestamp_us,
+                } = &c.change
+                else {
+                    return None;
+                };
+                let transponder_id = self
+                    .riders_by_transponder
+                    .values()
+                    .find(|r| r.rider_id == *rider_id)?
+                    .transponder_id;
+                let loop_config = self
+                    .track_config
+                    .as_ref()?
+                    .loops
+                    .iter()
+                    .find(|l| l.loop_id == *loop_id)?;
+                let passing = PassingMessage {
+                    passing_number: 0,
+                    transponder_id,
+                    rtc_time_us: *timestamp_us,
+                    utc_time_us: None,
+                    strength: Some(u16::MAX),
+                    hits: Some(u16::MAX),
+                    transponder_string: None,
+                    flags: 0,
+                    decoder_id: Some(loop_config.decoder_id.clone()),
+                    extra_fields: Vec::new(),
+                };
+                Some((c.correction_id.as_str(), passing))
+            })
+            .collect()
+    }
+
+    fn recorded_passing(
+        &self,
+        passing: &PassingMessage,
+        voided: bool,
+        passing_id: String,
+        manual: bool,
+    ) -> RecordedPassing {
+        RecordedPassing {
+            passing_id,
+            transponder_id: passing.transponder_id,
+            rider_id: self
+                .riders_by_transponder
+                .get(&passing.transponder_id)
+                .map(|r| r.rider_id.clone()),
+            loop_name: passing
+                .decoder_id
+                .as_ref()
+                .and_then(|did| self.track_config.as_ref()?.loop_for_decoder(did))
+                .map(|l| l.name.clone()),
+            timestamp_us: passing.rtc_time_us,
+            manual,
+            voided,
+        }
+    }
+
+    /// Keep a passing by a transponder outside the moto, and alert the first
+    /// time it is seen at each loop.
+    fn unknown_transponder(
+        &mut self,
+        passing: &PassingMessage,
+        moto_id: String,
+        loop_config: &LoopConfig,
+    ) -> Vec<RaceEvent> {
+        // Kept while replaying too, for the binds replayed after it
+        self.unmatched_passings
+            .entry(passing.transponder_id)
+            .or_default()
+            .push(passing.clone());
+        if self.replaying {
+            return vec![];
+        }
+        if !self
+            .alerted_transponders
+            .insert((passing.transponder_id, loop_config.loop_id.clone()))
+        {
+            return vec![];
+        }
+
+        warn!(
+            moto_id = %moto_id,
+            transponder_id = passing.transponder_id,
+            loop_name = %loop_config.name,
+            "Unknown transponder on track"
+        );
+        let event = RaceEvent::UnknownTransponder {
+            moto_id,
+            transponder_id: passing.transponder_id,
+            loop_name: loop_config.name.clone(),
+            timestamp_us: passing.rtc_time_us,
+        };
+        vec![event]
+    }
+
+    /// Alert the first time a decoder without a timing loop reports a passing.
+    fn unmapped_decoder(&mut self, passing: &PassingMessage, moto_id: String) -> Vec<RaceEvent> {
+        if self.replaying || !self.alerted_decoders.insert(passing.decoder_id.clone()) {
+            return vec![];
+        }
+
+        warn!(
+            moto_id = %moto_id,
+            decoder_id = ?passing.decoder_id,
+            transponder_id = passing.transponder_id,
+            "Passing from a decoder with no timing loop"
+        );
+        let event = RaceEvent::UnmappedDecoder {
+            moto_id,
+            decoder_id: passing.decoder_id.clone(),
+            transponder_id: passing.transponder_id,
+            timestamp_us: passing.rtc_time_us,
+        };
+        vec![event]
+    }
+
+    /// Reassign finish positions in order of finish time
+    fn rerank_finishers(&mut self) {
+        let mut finishers: Vec<&mut RiderState> = self
+            .riders_by_transponder
+            .values_mut()
+            .filter(|r| r.finished)
+            .collect();
+        finishers.sort_by_key(|r| (r.finish_elapsed_us, r.finish_position));
+        for (position, rider) in (1u32..).zip(finishers) {
+            rider.finish_position = Some(position);
+        }
+    }
+
+    pub fn finish_position_of(&self, rider_id: &str) -> Option<u32> {
+        self.riders_by_transponder
+            .values()
+            .find(|r| r.rider_id == rider_id)
+            .and_then(|r| r.finish_position)
+    }
+
+    fn leader_finish_time(&self) -> Option<u64> {
+        self.riders_by_transponder
+            .values()
+            .filter(|r| r.finished)
+            .filter_map(|r| r.finish_elapsed_us)
+            .min()
+    }
+
+    /// Calculate current race positions based on:
+    /// 1. Finished riders ranked by finish position
+    /// 2. Unfinished riders ranked by laps completed, then furthest loop reached
+    ///    on the current lap, then elapsed time
+    fn calculate_positions(&self) -> Vec<RiderPosition> {
+        let mut finished: Vec<&RiderState> = self
+            .riders_by_transponder
+            .values()
+            .filter(|r| r.finished)
+            .collect();
+        finished.sort_by_key(|r| r.finish_position);
+
+        let mut racing: Vec<&RiderState> = self
+            .riders_by_transponder
+            .values()
+            .filter(|r| !r.finished && !r.dnf && !r.dns)
+            .collect();
+        // Sort by: laps (desc), furthest loop (desc), then elapsed time at that loop (asc)
+        racing.sort_by(|a, b| {
+            b.laps_completed()
+                .cmp(&a.laps_completed())
+                .then_with(|| b.last_loop_position.cmp(&a.last_loop_position))
+                .then_with(|| {
+                    a.last_elapsed_us
+                        .unwrap_or(u64::MAX)
+                        .cmp(&b.last_elapsed_us.unwrap_or(u64::MAX))
+                })
+                .then_with(|| a.lane.cmp(&b.lane))
+        });
+
+        let mut dnf: Vec<&RiderState> = self
+            .riders_by_transponder
+            .values()
+            .filter(|r| r.dnf || r.dns)
+            .collect();
+        dnf.sort_by_key(|r| (r.dns, r.lane));
+
+        let leader_finish = self.leader_finish_time();
+
+        let mut positions = Vec::new();
+
+        for (pos, rider) in (1u32..).zip(finished.iter().chain(racing.iter()).chain(dnf.iter())) {
+            let gap = match (leader_finish, rider.finish_elapsed_us) {
+                (Some(lt), Some(ft)) if pos > 1 => Some(ft.saturating_sub(lt)),
+                _ => rider.last_elapsed_us.and_then(|elapsed| {
+                    // For non-finished riders, gap is less meaningful
+                    // but we can show gap to leader at the same loop
+                    Some(elapsed).filter(|_| pos > 1)
+                }),
+            };
+
+            positions.push(rider.to_position(pos, gap));
+        }
+
+        positions
+    }
+
+    fn build_results(&self) -> Vec<FinishResult> {
+        let mut riders: Vec<&RiderState> = self.riders_by_transponder.values().collect();
+        riders.sort_by_key(|r| r.lane);
+
+        let mut results: Vec<FinishResult> = riders
+            .into_iter()
+            .map(|r| {
+                let leader_time = self.leader_finish_time();
+                let gap = match (leader_time, r.finish_elapsed_us) {
+                    (Some(lt), Some(ft)) if r.finish_position != Some(1) => {
+                        Some(ft.saturating_sub(lt))
+                    }
+                    _ => None,
+                };
+
+                FinishResult {
+                    rider_id: r.rider_id.clone(),
+                    plate_number: r.plate_number.clone(),
+                    first_name: r.first_name.clone(),
+                    last_name: r.last_name.clone(),
+                    position: r.finish_position.unwrap_or(0),
+                    elapsed_us: r.finish_elapsed_us,
+                    gap_to_leader_us: gap,
+                    dnf: r.dnf,
+                    dns: r.dns,
+                    lap_times_us: r.lap_times_us(),
+                }
+            })
+            .collect();
+
+        // Finished riders first (by position), then DNF, then DNS riders;
+        // the stable sort keeps ties in lane order
+        results.sort_by_key(|r| (r.dns, r.dnf, r.position));
+
+        results
+    }
+}
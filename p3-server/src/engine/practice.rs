@@ -0,0 +1,348 @@
+use std::collections::HashMap;
+
+use p3_parser::messages::PassingMessage;
+use serde::{Deserialize, Serialize};
+
+use crate::domain::race_event::{
+    KnownRider, LoopConfig, PracticeLap, PracticeLeaderboard, PracticeStanding, SectorTime,
+};
+
+/// An open practice session.
+///
+/// Any transponder on track is timed, registered or not. A lap runs from
+/// one crossing of the finish loop to the next; crossing the start loop
+/// (re)opens a lap, so riders pushing back up the start hill begin a fresh
+/// lap instead of logging the walk back. Sectors are the times between
+/// consecutive loop crossings within a lap.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PracticeSession {
+    session_id: String,
+    by_transponder_id: HashMap<u32, KnownRider>,
+    by_transponder_string: HashMap<String, KnownRider>,
+    transponders: HashMap<u32, PracticeTransponder>,
+    /// Laps completed in the session, used to order the last-lap board
+    completed_laps: u64,
+}
+
+#[derive(Default, Clone, Serialize, Deserialize)]
+struct PracticeTransponder {
+    rider: Option<KnownRider>,
+    /// Loop name, loop_id and time of the most recent crossing
+    last_crossing: Option<(String, String, u64)>,
+    lap_start_us: Option<u64>,
+    sectors: Vec<SectorTime>,
+    laps: u32,
+    best_lap_us: Option<u64>,
+    last_lap_us: Option<u64>,
+    /// Session-wide sequence number of this transponder's latest lap
+    last_lap_seq: u64,
+}
+
+impl PracticeSession {
+    pub fn new(session_id: String, riders: Vec<KnownRider>) -> Self {
+        let mut by_transponder_id = HashMap::new();
+        let mut by_transponder_string = HashMap::new();
+        for rider in riders {
+            if let Some(transponder_string) = &rider.transponder_string {
+                by_transponder_string.insert(transponder_string.clone(), rider.clone());
+            }
+            by_transponder_id.insert(rider.transponder_id, rider);
+        }
+
+        Self {
+            session_id,
+            by_transponder_id,
+            by_transponder_string,
+            transponders: HashMap::new(),
+            completed_laps: 0,
+        }
+    }
+
+    pub fn session_id(&self) -> &str {
+        &self.session_id
+    }
+
+    /// Registered rider for a passing: by transponder ID first, then by the
+    /// transponder string the decoder reported.
+    fn lookup(&self, passing: &PassingMessage) -> Option<KnownRider> {
+        self.by_transponder_id
+            .get(&passing.transponder_id)
+            .or_else(|| {
+                passing
+                    .transponder_string
+                    .as_ref()
+                    .and_then(|s| self.by_transponder_string.get(s))
+            })
+            .cloned()
+    }
+
+    /// Record a loop crossing. Returns the lap it completes, if any.
+    pub fn record_crossing(
+        &mut self,
+        passing: &PassingMessage,
+        loop_config: &LoopConfig,
+    ) -> Option<PracticeLap> {
+        let known = match self.transponders.get(&passing.transponder_id) {
+            Some(state) if state.rider.is_some() => None,
+            _ => self.lookup(passing),
+        };
+        let state = self.transponders.entry(passing.transponder_id).or_default();
+        if known.is_some() {
+            state.rider = known;
+        }
+
+        let rtc_us = passing.rtc_time_us;
+        let debounce_us = u64::from(loop_config.debounce_ms) * 1_000;
+
+        if let Some((from_loop, from_loop_id, from_us)) = &state.last_crossing {
+            // Repeat reads of the loop just crossed
+            if *from_loop_id == loop_config.loop_id && rtc_us.abs_diff(*from_us) <= debounce_us {
+                return None;
+            }
+            if rtc_us < *from_us {
+                return None;
+            }
+            if state.lap_start_us.is_some() {
+                state.sectors.push(SectorTime {
+                    from_loop: from_loop.clone(),
+                    to_loop: loop_config.name.clone(),
+                    time_us: rtc_us - from_us,
+                });
+            }
+        }
+        state.last_crossing = Some((
+            loop_config.name.clone(),
+            loop_config.loop_id.clone(),
+            rtc_us,
+        ));
+
+        let mut completed = None;
+        if loop_config.is_finish {
+            if let Some(lap_start_us) = state.lap_start_us {
+                let lap_time_us = rtc_us - lap_start_us;
+                let personal_best = state.best_lap_us.is_none_or(|best| lap_time_us < best);
+                self.completed_laps += 1;
+
+                state.laps += 1;
+                state.last_lap_us = Some(lap_time_us);
+                state.last_lap_seq = self.completed_laps;
+                if personal_best {
+                    state.best_lap_us = Some(lap_time_us);
+                }
+
+                completed = Some(PracticeLap {
+                    transponder_id: passing.transponder_id,
+                    rider_id: state.rider.as_ref().map(|r| r.rider_id.clone()),
+                    lap_number: state.laps,
+                    lap_time_us,
+                    sectors: std::mem::take(&mut state.sectors),
+                    personal_best,
+                });
+            }
+            // Crossing the line also starts the next lap
+            state.lap_start_us = Some(rtc_us);
+            state.sectors.clear();
+        } else if loop_config.is_start {
+            state.lap_start_us = Some(rtc_us);
+            state.sectors.clear();
+        }
+
+        completed
+    }
+
+    /// Best-lap and last-lap boards for every transponder with a lap.
+    pub fn leaderboard(&self) -> PracticeLeaderboard {
+        let mut timed: Vec<(&u32, &PracticeTransponder)> = self
+            .transponders
+            .iter()
+            .filter(|(_, state)| state.laps > 0)
+            .collect();
+
+        timed.sort_by_key(|(id, state)| (state.best_lap_us, **id));
+        let best_laps = standings(&timed);
+
+        timed.sort_by_key(|(_, state)| std::cmp::Reverse(state.last_lap_seq));
+        let last_laps = standings(&timed);
+
+        PracticeLeaderboard {
+            session_id: self.session_id.clone(),
+            best_laps,
+            last_laps,
+        }
+    }
+}
+
+fn standings(timed: &[(&u32, &PracticeTransponder)]) -> Vec<PracticeStanding> {
+    timed
+        .iter()
+        .enumerate()
+        .map(|(i, (transponder_id, state))| PracticeStanding {
+            position: i as u32 + 1,
+            transponder_id: **transponder_id,
+            rider_id: state.rider.as_ref().map(|r| r.rider_id.clone()),
+            first_name: state.rider.as_ref().map(|r| r.first_name.clone()),
+            last_name: state.rider.as_ref().map(|r| r.last_name.clone()),
+            plate_number: state.rider.as_ref().map(|r| r.plate_number.clone()),
+            laps: state.laps,
+            best_lap_us: state.best_lap_us.unwrap_or(0),
+            last_lap_us: state.last_lap_us.unwrap_or(0),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_loop(loop_id: &str, is_start: bool, is_finish: bool) -> LoopConfig {
+        LoopConfig {
+            loop_id: loop_id.into(),
+            name: loop_id.into(),
+            decoder_id: format!("D-{loop_id}"),
+            position: 0,
+            is_start,
+            is_finish,
+            debounce_ms: 500,
+        }
+    }
+
+    fn make_passing(transponder_id: u32, rtc_time_us: u64) -> PassingMessage {
+        PassingMessage {
+            passing_number: 1,
+            transponder_id,
+            rtc_time_us,
+            utc_time_us: None,
+            strength: Some(100),
+            hits: Some(40),
+            transponder_string: None,
+            flags: 0,
+            decoder_id: None,
+            extra_fields: Vec::new(),
+        }
+    }
+
+    fn known_rider() -> KnownRider {
+        KnownRider {
+            rider_id: "rider-1".into(),
+            first_name: "Alice".into(),
+            last_name: "Smith".into(),
+            plate_number: "42".into(),
+            transponder_id: 1001,
+            transponder_string: Some("KX-01001".into()),
+        }
+    }
+
+    #[test]
+    fn test_laps_and_sectors() {
+        let (start, corner, finish) = (
+            make_loop("start", true, false),
+            make_loop("corner", false, false),
+            make_loop("finish", false, true),
+        );
+        let mut session = PracticeSession::new("practice-1".into(), vec![]);
+
+        // Crossing the finish before any start only opens a lap
+        assert!(
+            session
+                .record_crossing(&make_passing(1001, 1_000_000), &finish)
+                .is_none()
+        );
+        assert!(
+            session
+                .record_crossing(&make_passing(1001, 5_000_000), &start)
+                .is_none()
+        );
+        // Repeat read of the start loop
+        assert!(
+            session
+                .record_crossing(&make_passing(1001, 5_200_000), &start)
+                .is_none()
+        );
+        session.record_crossing(&make_passing(1001, 9_000_000), &corner);
+        let lap = session
+            .record_crossing(&make_passing(1001, 35_000_000), &finish)
+            .unwrap();
+
+        assert_eq!(lap.lap_number, 1);
+        assert_eq!(lap.lap_time_us, 30_000_000);
+        assert!(lap.personal_best);
+        let sectors: Vec<_> = lap
+            .sectors
+            .iter()
+            .map(|s| (s.from_loop.as_str(), s.to_loop.as_str(), s.time_us))
+            .collect();
+        assert_eq!(
+            sectors,
+            vec![
+                ("start", "corner", 4_000_000),
+                ("corner", "finish", 26_000_000)
+            ]
+        );
+
+        // Straight round again without the start hill: finish to finish
+        let lap = session
+            .record_crossing(&make_passing(1001, 67_000_000), &finish)
+            .unwrap();
+        assert_eq!(lap.lap_number, 2);
+        assert_eq!(lap.lap_time_us, 32_000_000);
+        assert!(!lap.personal_best);
+    }
+
+    #[test]
+    fn test_unknown_and_known_transponders() {
+        let finish = make_loop("finish", true, true);
+        let mut session = PracticeSession::new("practice-1".into(), vec![known_rider()]);
+
+        // Registered by transponder string, reported under another ID
+        let mut by_string = make_passing(5555, 1_000_000);
+        by_string.transponder_string = Some("KX-01001".into());
+        session.record_crossing(&by_string, &finish);
+        let lap = session
+            .record_crossing(&make_passing(5555, 31_000_000), &finish)
+            .unwrap();
+        assert_eq!(lap.rider_id.as_deref(), Some("rider-1"));
+
+        session.record_crossing(&make_passing(7777, 2_000_000), &finish);
+        let lap = session
+            .record_crossing(&make_passing(7777, 30_000_000), &finish)
+            .unwrap();
+        assert_eq!(lap.rider_id, None);
+
+        let board = session.leaderboard();
+        let best: Vec<_> = board.best_laps.iter().map(|s| s.transponder_id).collect();
+        assert_eq!(best, vec![7777, 5555]);
+        assert_eq!(board.best_laps[1].first_name.as_deref(), Some("Alice"));
+        assert_eq!(board.best_laps[0].first_name, None);
+    }
+
+    #[test]
+    fn test_leaderboards() {
+        let finish = make_loop("finish", true, true);
+        let mut session = PracticeSession::new("practice-1".into(), vec![]);
+
+        for (transponder_id, crossings) in [
+            (1, vec![0, 30_000_000, 58_000_000]),
+            (2, vec![1_000_000, 30_000_000, 61_000_000]),
+        ] {
+            for rtc in crossings {
+                session.record_crossing(&make_passing(transponder_id, rtc), &finish);
+            }
+        }
+
+        let board = session.leaderboard();
+        let best: Vec<_> = board
+            .best_laps
+            .iter()
+            .map(|s| (s.position, s.transponder_id, s.best_lap_us, s.laps))
+            .collect();
+        assert_eq!(best, vec![(1, 1, 28_000_000, 2), (2, 2, 29_000_000, 2)]);
+
+        // Transponder 2 completed the most recent lap
+        let last: Vec<_> = board
+            .last_laps
+            .iter()
+            .map(|s| (s.transponder_id, s.last_lap_us))
+            .collect();
+        assert_eq!(last, vec![(2, 31_000_000), (1, 28_000_000)]);
+    }
+}
@@ -3,7 +3,7 @@ use std::collections::HashMap;
 use p3_parser::messages::PassingMessage;
 use p3_protocol::fields::reserved_ids;
 
-use crate::domain::race_event::{LoopConfig, RiderState, TrackConfig};
+use crate::domain::race_event::{LoopConfig, LoopCrossing, RiderState, TrackConfig};
 
 /// Check if a passing message is a gate drop signal.
 ///
@@ -15,18 +15,61 @@ pub fn is_gate_drop(passing: &PassingMessage, track: &TrackConfig) -> bool {
         || reserved_ids::is_reserved(passing.transponder_id)
 }
 
-/// Calculate a rider's position at a specific loop.
+/// Stable identity of a decoder passing: `<decoder_id>:<passing_number>`.
+pub fn passing_id(passing: &PassingMessage) -> String {
+    format!(
+        "{}:{}",
+        passing.decoder_id.as_deref().unwrap_or("unknown"),
+        passing.passing_number
+    )
+}
+
+/// Identity of the passing a manual-insert correction adds.
+pub fn manual_passing_id(correction_id: &str) -> String {
+    format!("manual:{correction_id}")
+}
+
+/// Check if a passing falls within the loop's debounce window of a crossing.
+pub fn within_debounce(
+    crossing: &LoopCrossing,
+    passing: &PassingMessage,
+    loop_config: &LoopConfig,
+) -> bool {
+    let debounce_us = u64::from(loop_config.debounce_ms) * 1_000;
+    passing.rtc_time_us.abs_diff(crossing.first_rtc_time_us) <= debounce_us
+}
+
+/// Check if a repeat passing should replace a rider's crossing of a loop.
+///
+/// Only hits within the loop's debounce window of the first hit compete,
+/// and the replacement must be stronger (signal strength, then hit count).
+pub fn supersedes(
+    crossing: &LoopCrossing,
+    passing: &PassingMessage,
+    loop_config: &LoopConfig,
+) -> bool {
+    if !within_debounce(crossing, passing, loop_config) {
+        return false;
+    }
+
+    let quality = (passing.strength.unwrap_or(0), passing.hits.unwrap_or(0));
+    quality > (crossing.strength, crossing.hits)
+}
+
+/// Calculate a rider's position at a specific loop on a given lap.
 /// Position is determined by comparing elapsed times at this loop across all riders
-/// who have reached it.
+/// who have reached it on that lap.
 pub fn calculate_position_at_loop(
     riders: &HashMap<u32, RiderState>,
+    lap: u32,
     loop_config: &LoopConfig,
     current_rider_id: &str,
 ) -> u32 {
+    let key = (lap, loop_config.loop_id.clone());
     let current_time = riders
         .values()
         .find(|r| r.rider_id == current_rider_id)
-        .and_then(|r| r.splits.get(&loop_config.loop_id))
+        .and_then(|r| r.splits.get(&key))
         .copied();
 
     let current_time = match current_time {
@@ -38,21 +81,23 @@ pub fn calculate_position_at_loop(
     let faster_count = riders
         .values()
         .filter(|r| r.rider_id != current_rider_id)
-        .filter_map(|r| r.splits.get(&loop_config.loop_id))
+        .filter_map(|r| r.splits.get(&key))
         .filter(|&&time| time < current_time)
         .count();
 
     (faster_count + 1) as u32
 }
 
-/// Find the leader's (fastest) time at a specific loop.
+/// Find the leader's (fastest) time at a specific loop on a given lap.
 pub fn leader_time_at_loop(
     riders: &HashMap<u32, RiderState>,
+    lap: u32,
     loop_config: &LoopConfig,
 ) -> Option<u64> {
+    let key = (lap, loop_config.loop_id.clone());
     riders
         .values()
-        .filter_map(|r| r.splits.get(&loop_config.loop_id))
+        .filter_map(|r| r.splits.get(&key))
         .copied()
         .min()
 }
@@ -81,6 +126,7 @@ mod tests {
             transponder_string: None,
             flags: 0,
             decoder_id: None,
+            extra_fields: Vec::new(),
         }
     }
 
@@ -105,6 +151,59 @@ mod tests {
         assert!(!is_gate_drop(&make_passing(5000), &track));
     }
 
+    #[test]
+    fn test_supersedes_within_debounce_window() {
+        let loop_config = LoopConfig {
+            loop_id: "loop-finish".into(),
+            name: "Finish".into(),
+            decoder_id: "D003".into(),
+            position: 2,
+            is_start: false,
+            is_finish: true,
+            debounce_ms: 500,
+        };
+        let crossing = LoopCrossing {
+            first_rtc_time_us: 1_000_000,
+            rtc_time_us: 1_000_000,
+            strength: 80,
+            hits: 10,
+        };
+        let hit = |rtc_time_us, strength, hits| PassingMessage {
+            rtc_time_us,
+            strength: Some(strength),
+            hits: Some(hits),
+            ..make_passing(1001)
+        };
+
+        // Stronger, or equally strong with more hits
+        assert!(supersedes(&crossing, &hit(1_200_000, 90, 5), &loop_config));
+        assert!(supersedes(&crossing, &hit(1_500_000, 80, 11), &loop_config));
+        // Weaker or identical
+        assert!(!supersedes(
+            &crossing,
+            &hit(1_200_000, 70, 50),
+            &loop_config
+        ));
+        assert!(!supersedes(
+            &crossing,
+            &hit(1_200_000, 80, 10),
+            &loop_config
+        ));
+        // Outside the window
+        assert!(!supersedes(
+            &crossing,
+            &hit(1_500_001, 120, 50),
+            &loop_config
+        ));
+
+        // Without a window the first hit always stands
+        let no_window = LoopConfig {
+            debounce_ms: 0,
+            ..loop_config
+        };
+        assert!(!supersedes(&crossing, &hit(1_000_001, 120, 50), &no_window));
+    }
+
     #[test]
     fn test_position_calculation() {
         let loop_config = LoopConfig {
@@ -114,28 +213,29 @@ mod tests {
             position: 1,
             is_start: false,
             is_finish: false,
+            debounce_ms: 0,
         };
 
         let mut riders = HashMap::new();
 
         // Rider A: 5.0s at loop-1
         let mut rider_a = RiderState::new("a".into(), "A".into(), "A".into(), "1".into(), 1001, 1);
-        rider_a.splits.insert("loop-1".into(), 5_000_000);
+        rider_a.splits.insert((1, "loop-1".into()), 5_000_000);
         riders.insert(1001, rider_a);
 
         // Rider B: 4.5s at loop-1 (fastest)
         let mut rider_b = RiderState::new("b".into(), "B".into(), "B".into(), "2".into(), 1002, 2);
-        rider_b.splits.insert("loop-1".into(), 4_500_000);
+        rider_b.splits.insert((1, "loop-1".into()), 4_500_000);
         riders.insert(1002, rider_b);
 
         // Rider C: 5.2s at loop-1
         let mut rider_c = RiderState::new("c".into(), "C".into(), "C".into(), "3".into(), 1003, 3);
-        rider_c.splits.insert("loop-1".into(), 5_200_000);
+        rider_c.splits.insert((1, "loop-1".into()), 5_200_000);
         riders.insert(1003, rider_c);
 
-        assert_eq!(calculate_position_at_loop(&riders, &loop_config, "a"), 2);
-        assert_eq!(calculate_position_at_loop(&riders, &loop_config, "b"), 1);
-        assert_eq!(calculate_position_at_loop(&riders, &loop_config, "c"), 3);
+        assert_eq!(calculate_position_at_loop(&riders, 1, &loop_config, "a"), 2);
+        assert_eq!(calculate_position_at_loop(&riders, 1, &loop_config, "b"), 1);
+        assert_eq!(calculate_position_at_loop(&riders, 1, &loop_config, "c"), 3);
     }
 
     #[test]
@@ -147,18 +247,32 @@ mod tests {
             position: 1,
             is_start: false,
             is_finish: false,
+            debounce_ms: 0,
         };
 
         let mut riders = HashMap::new();
 
         let mut rider_a = RiderState::new("a".into(), "A".into(), "A".into(), "1".into(), 1001, 1);
-        rider_a.splits.insert("loop-1".into(), 5_000_000);
+        rider_a.splits.insert((1, "loop-1".into()), 5_000_000);
         riders.insert(1001, rider_a);
 
         let mut rider_b = RiderState::new("b".into(), "B".into(), "B".into(), "2".into(), 1002, 2);
-        rider_b.splits.insert("loop-1".into(), 4_500_000);
+        rider_b.splits.insert((1, "loop-1".into()), 4_500_000);
         riders.insert(1002, rider_b);
 
-        assert_eq!(leader_time_at_loop(&riders, &loop_config), Some(4_500_000));
+        assert_eq!(
+            leader_time_at_loop(&riders, 1, &loop_config),
+            Some(4_500_000)
+        );
+        // Laps are ranked separately
+        riders
+            .get_mut(&1001)
+            .unwrap()
+            .splits
+            .insert((2, "loop-1".into()), 35_000_000);
+        assert_eq!(
+            leader_time_at_loop(&riders, 2, &loop_config),
+            Some(35_000_000)
+        );
     }
 }
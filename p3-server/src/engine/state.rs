@@ -1,18 +1,21 @@
-use std::collections::HashMap;
-use std::sync::Arc;
+use std::time::Duration;
 
 use p3_parser::messages::PassingMessage;
-use tokio::sync::broadcast;
+use serde::{Deserialize, Serialize};
 use tracing::{info, warn};
 
 use crate::domain::race_event::{
-    FinishResult, LoopConfig, RaceEvent, RiderPosition, RiderState, StagedRider, TrackConfig,
+    KnownRider, RaceEvent, RaceRules, RecordedPassing, RiderState, StagedRider, StartMode,
+    TimingChange, TimingCorrection, TrackConfig,
 };
 
+use super::clock::ClockAlignment;
+use super::moto::Moto;
+use super::practice::PracticeSession;
 use super::processor;
 
 /// The current phase of a race.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum RacePhase {
     /// No race in progress, waiting for operator to stage a moto.
     Idle,
@@ -32,11 +35,25 @@ pub enum RacePhase {
         gate_drop_time_us: u64,
     },
 
+    /// Time trial in progress: each rider's clock starts at their own
+    /// crossing of the start loop. Runs until the operator finishes it, so
+    /// riders can join the start list at any time.
+    TimeTrial {
+        moto_id: String,
+        class_name: String,
+        round_type: String,
+    },
+
+    /// Open practice: every transponder on track is timed lap by lap.
+    Practice { session_id: String },
+
     /// All riders have finished (or force-finished).
     Finished {
         moto_id: String,
         class_name: String,
         round_type: String,
+        /// `None` for time trials
+        gate_drop_time_us: Option<u64>,
     },
 }
 
@@ -46,550 +63,628 @@ impl RacePhase {
             RacePhase::Idle => "idle",
             RacePhase::Staged { .. } => "staged",
             RacePhase::Racing { .. } => "racing",
+            RacePhase::TimeTrial { .. } => "time_trial",
+            RacePhase::Practice { .. } => "practice",
             RacePhase::Finished { .. } => "finished",
         }
     }
 }
 
-/// The race engine processes P3 passings and produces race events.
-pub struct RaceEngine {
-    /// Current race phase
+/// How often hosts send [`RaceCommand::CheckTimeouts`] between passings
+pub const RULES_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Finished motos kept on the track for corrections after later motos
+/// are staged, most recent first
+const FINISHED_MOTOS_KEPT: usize = 8;
+
+/// An input to the race engine: an operator action, a decoder passing or
+/// the passage of time.
+#[derive(Debug, Clone)]
+pub enum RaceCommand {
+    /// Load a track's timing loops and gate beacon
+    SetTrack(TrackConfig),
+    /// Set the DNS/DNF rules for the motos staged from now on
+    SetRaceRules(RaceRules),
+    /// Put a moto on the gate, or start it as a time trial
+    Stage {
+        moto_id: String,
+        class_name: String,
+        round_type: String,
+        laps: u32,
+        start_mode: StartMode,
+        riders: Vec<StagedRider>,
+    },
+    /// Add riders to a running time trial's start list
+    AddToStartList {
+        moto_id: String,
+        riders: Vec<StagedRider>,
+    },
+    /// Move a rider onto another transponder
+    BindTransponder {
+        moto_id: String,
+        rider_id: String,
+        transponder_id: u32,
+    },
+    /// A passing read by one of the track's decoders, and when the track
+    /// client captured it on its own clock, if known
+    Passing {
+        passing: PassingMessage,
+        captured_at_us: Option<u64>,
+    },
+    /// Apply the race rules at `now_us` on the track's time base
+    CheckTimeouts {
+        now_us: u64,
+    },
+    /// An operator's timing correction to the current moto
+    CorrectTiming {
+        moto_id: String,
+        correction: TimingCorrection,
+    },
+    /// Finish a moto, by default the oldest on course, ruling out riders
+    /// still on course
+    ForceFinish {
+        moto_id: Option<String>,
+    },
+    /// Open a practice session on the current track
+    StartPractice {
+        session_id: String,
+        riders: Vec<KnownRider>,
+    },
+    StopPractice,
+    Reset,
+}
+
+/// Everything the race engine knows about the races on one track.
+///
+/// The state only changes through [`apply`], and serializes as is so hosts
+/// can persist it and pick a race up where they left off.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RaceState {
+    /// Track phase while no moto is on it: idle or practice
     phase: RacePhase,
     /// Current track configuration (timing loops, gate beacon ID)
     track_config: Option<TrackConfig>,
-    /// Rider states keyed by transponder_id for fast lookup during racing
-    riders_by_transponder: HashMap<u32, RiderState>,
-    /// Rider states keyed by rider_id for result lookups
-    rider_ids: Vec<String>,
-    /// Decoder ID → loop config mapping for fast lookup
-    decoder_to_loop: HashMap<String, LoopConfig>,
-    /// Next finish position to assign
-    next_finish_position: u32,
-    /// Broadcast channel for race events
-    event_tx: broadcast::Sender<Arc<RaceEvent>>,
+    /// Automatic DNS/DNF rules for the motos staged from now on
+    rules: RaceRules,
+    /// Motos on the track in gate order. The next moto can be staged while
+    /// earlier ones are still finishing; the last [`FINISHED_MOTOS_KEPT`]
+    /// finished motos stay so their timing can still be corrected.
+    motos: Vec<Moto>,
+    /// Open practice session, while in [`RacePhase::Practice`]
+    practice: Option<PracticeSession>,
+    /// Decoder clocks aligned onto the track's time base, which every
+    /// passing time is taken onto before it is processed
+    #[serde(default)]
+    clocks: ClockAlignment,
+    /// Latest passing time on the track's time base, unset while the
+    /// decoder of the last passing has no clock model
+    #[serde(default)]
+    track_time_us: Option<u64>,
+}
+
+/// Apply a command to a race state.
+///
+/// The next state and the events depend only on `state` and `command`;
+/// publishing the events is up to the host.
+pub fn apply(mut state: RaceState, command: RaceCommand) -> (RaceState, Vec<RaceEvent>) {
+    let events = state.handle(command);
+    (state, events)
+}
+
+impl Default for RaceState {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
-impl RaceEngine {
-    pub fn new(event_tx: broadcast::Sender<Arc<RaceEvent>>) -> Self {
+impl RaceState {
+    pub fn new() -> Self {
         Self {
             phase: RacePhase::Idle,
             track_config: None,
-            riders_by_transponder: HashMap::new(),
-            rider_ids: Vec::new(),
-            decoder_to_loop: HashMap::new(),
-            next_finish_position: 1,
-            event_tx,
+            rules: RaceRules::default(),
+            motos: Vec::new(),
+            practice: None,
+            clocks: ClockAlignment::default(),
+            track_time_us: None,
         }
     }
 
+    /// Phase of the moto staged last, or of the track when it has no moto.
     pub fn phase(&self) -> &RacePhase {
-        &self.phase
+        self.motos.last().map_or(&self.phase, |moto| moto.phase())
     }
 
-    /// Set the track configuration for the engine.
-    pub fn set_track(&mut self, config: TrackConfig) {
-        self.decoder_to_loop.clear();
-        for l in &config.loops {
-            self.decoder_to_loop.insert(l.decoder_id.clone(), l.clone());
+    /// Phase of a moto on the track.
+    pub fn moto_phase(&self, moto_id: &str) -> Option<&RacePhase> {
+        self.moto(moto_id).map(|moto| moto.phase())
+    }
+
+    /// No moto is on course and no practice session is open.
+    pub fn is_idle(&self) -> bool {
+        matches!(self.phase, RacePhase::Idle) && !self.motos.iter().any(Moto::is_running)
+    }
+
+    /// Latest passing time on the track's time base, which hosts move on
+    /// between passings to time [`RaceCommand::CheckTimeouts`]. `None`
+    /// while the last passing came from a decoder with no clock model, as
+    /// its RTC can't be compared with any other clock.
+    pub fn track_time_us(&self) -> Option<u64> {
+        self.track_time_us
+    }
+
+    /// Track the engine is configured for, if any.
+    pub fn track_id(&self) -> Option<&str> {
+        self.track_config.as_ref().map(|c| c.track_id.as_str())
+    }
+
+    fn handle(&mut self, command: RaceCommand) -> Vec<RaceEvent> {
+        match command {
+            RaceCommand::SetTrack(config) => {
+                self.set_track(config);
+                vec![]
+            }
+            RaceCommand::SetRaceRules(rules) => {
+                self.set_race_rules(rules);
+                vec![]
+            }
+            RaceCommand::Stage {
+                moto_id,
+                class_name,
+                round_type,
+                laps,
+                start_mode,
+                riders,
+            } => self.stage(moto_id, class_name, round_type, laps, start_mode, riders),
+            RaceCommand::AddToStartList { moto_id, riders } => self
+                .add_to_start_list(&moto_id, riders)
+                .into_iter()
+                .collect(),
+            RaceCommand::BindTransponder {
+                moto_id,
+                rider_id,
+                transponder_id,
+            } => self.bind_transponder(&moto_id, &rider_id, transponder_id),
+            RaceCommand::Passing {
+                passing,
+                captured_at_us,
+            } => self.receive_passing(passing, captured_at_us),
+            RaceCommand::CheckTimeouts { now_us } => self.check_timeouts(now_us),
+            RaceCommand::CorrectTiming {
+                moto_id,
+                correction,
+            } => self.apply_correction(&moto_id, correction),
+            RaceCommand::ForceFinish { moto_id } => {
+                self.force_finish(moto_id.as_deref()).into_iter().collect()
+            }
+            RaceCommand::StartPractice { session_id, riders } => {
+                self.start_practice(session_id, riders)
+            }
+            RaceCommand::StopPractice => self.stop_practice().into_iter().collect(),
+            RaceCommand::Reset => self.reset(),
         }
+    }
+
+    /// Set the track configuration for the engine.
+    fn set_track(&mut self, config: TrackConfig) {
         info!(track = %config.name, loops = config.loops.len(), "Track config loaded");
         self.track_config = Some(config);
     }
 
-    /// Stage a moto: load riders onto the gate, ready for gate drop.
-    pub fn stage_moto(
+    /// Set the DNS/DNF rules for the motos staged from now on.
+    fn set_race_rules(&mut self, rules: RaceRules) {
+        self.rules = rules;
+    }
+
+    /// Append riders to a running time trial's start list.
+    fn add_to_start_list(&mut self, moto_id: &str, riders: Vec<StagedRider>) -> Option<RaceEvent> {
+        let Some(moto) = self.moto_mut(moto_id) else {
+            warn!(moto_id = %moto_id, "Cannot add to start list: moto is not on the track");
+            return None;
+        };
+        moto.add_to_start_list(moto_id, riders)
+    }
+
+    /// Move a staged rider onto another transponder, e.g. after a bike swap.
+    fn bind_transponder(
+        &mut self,
+        moto_id: &str,
+        rider_id: &str,
+        transponder_id: u32,
+    ) -> Vec<RaceEvent> {
+        let Some(moto) = self.moto_mut(moto_id) else {
+            warn!(moto_id = %moto_id, "Cannot bind transponder: moto is not on the track");
+            return vec![];
+        };
+        moto.bind_transponder(moto_id, rider_id, transponder_id)
+    }
+
+    /// Put a moto on the track behind the motos still on course.
+    ///
+    /// A gate race waits on the gate for the gate drop, and riders finish
+    /// on their `laps`-th crossing of the finish loop. A time trial skips
+    /// the gate and goes straight to [`RacePhase::TimeTrial`], waiting for
+    /// riders to cross the start loop.
+    ///
+    /// Gate races overlap: the next moto can be staged once the previous
+    /// gate has dropped. A time trial has the track to itself. Restaging a
+    /// finished moto replaces it, and the oldest finished motos leave the
+    /// track once more than [`FINISHED_MOTOS_KEPT`] have finished.
+    fn stage(
         &mut self,
         moto_id: String,
         class_name: String,
         round_type: String,
+        laps: u32,
+        start_mode: StartMode,
         riders: Vec<StagedRider>,
-    ) {
-        if !matches!(self.phase, RacePhase::Idle | RacePhase::Finished { .. }) {
+    ) -> Vec<RaceEvent> {
+        if let Some(reason) = self.stage_conflict(&moto_id, start_mode) {
             warn!(
-                current_phase = self.phase.name(),
-                "Cannot stage moto: race is in progress"
-            );
-            return;
-        }
-
-        self.riders_by_transponder.clear();
-        self.rider_ids.clear();
-        self.next_finish_position = 1;
-
-        for rider in &riders {
-            self.rider_ids.push(rider.rider_id.clone());
-            self.riders_by_transponder.insert(
-                rider.transponder_id,
-                RiderState::new(
-                    rider.rider_id.clone(),
-                    rider.first_name.clone(),
-                    rider.last_name.clone(),
-                    rider.plate_number.clone(),
-                    rider.transponder_id,
-                    rider.lane,
-                ),
+                moto_id = %moto_id,
+                current_phase = self.phase().name(),
+                reason,
+                "Cannot stage moto"
             );
+            return vec![];
         }
 
+        let laps = laps.max(1);
         info!(
             moto_id = %moto_id,
             class = %class_name,
             round = %round_type,
+            laps,
+            start_mode = ?start_mode,
             riders = riders.len(),
+            on_course = self.motos.iter().filter(|m| m.is_running()).count(),
             "Moto staged"
         );
 
-        self.phase = RacePhase::Staged {
-            moto_id: moto_id.clone(),
-            class_name: class_name.clone(),
-            round_type: round_type.clone(),
+        let phase = match start_mode {
+            StartMode::GateDrop => RacePhase::Staged {
+                moto_id: moto_id.clone(),
+                class_name: class_name.clone(),
+                round_type: round_type.clone(),
+            },
+            StartMode::TimeTrial => RacePhase::TimeTrial {
+                moto_id: moto_id.clone(),
+                class_name: class_name.clone(),
+                round_type: round_type.clone(),
+            },
         };
+        self.motos
+            .retain(|m| m.is_running() || m.moto_id() != moto_id);
+        let finished = self.motos.iter().filter(|m| !m.is_running()).count();
+        let mut evicted = finished.saturating_sub(FINISHED_MOTOS_KEPT);
+        self.motos.retain(|m| {
+            if evicted > 0 && !m.is_running() {
+                evicted -= 1;
+                return false;
+            }
+            true
+        });
+        self.motos.push(Moto::new(
+            phase,
+            self.track_config.clone(),
+            self.rules,
+            laps,
+            &riders,
+        ));
 
-        self.broadcast(RaceEvent::RaceStaged {
+        vec![RaceEvent::RaceStaged {
             moto_id,
             class_name,
             round_type,
             riders,
-        });
+            laps,
+            start_mode,
+        }]
+    }
+
+    /// Why a moto can't be staged now, if it can't
+    fn stage_conflict(&self, moto_id: &str, start_mode: StartMode) -> Option<&'static str> {
+        if matches!(self.phase, RacePhase::Practice { .. }) {
+            return Some("practice is running");
+        }
+        for moto in self.motos.iter().filter(|m| m.is_running()) {
+            match moto.phase() {
+                RacePhase::Staged { .. } => return Some("another moto is on the gate"),
+                RacePhase::TimeTrial { .. } => return Some("a time trial is running"),
+                _ if start_mode == StartMode::TimeTrial => return Some("a race is in progress"),
+                _ if moto.moto_id() == moto_id => return Some("the moto is already racing"),
+                _ => {}
+            }
+        }
+        None
     }
 
-    /// Process an incoming P3 passing message.
-    /// Returns any race events generated.
-    pub fn process_passing(&mut self, passing: &PassingMessage) -> Vec<RaceEvent> {
-        let track = match &self.track_config {
-            Some(t) => t,
+    /// Take a decoder passing onto the track's time base and process it.
+    ///
+    /// The passing first updates its decoder's clock model, then its RTC
+    /// time is replaced by the aligned time, so times read by different
+    /// decoders compare on one clock. Decoders whose drift goes out of
+    /// tolerance are flagged.
+    fn receive_passing(
+        &mut self,
+        mut passing: PassingMessage,
+        captured_at_us: Option<u64>,
+    ) -> Vec<RaceEvent> {
+        let is_beacon = match &self.track_config {
+            Some(track) => processor::is_gate_drop(&passing, track),
             None => return vec![],
         };
 
-        match &self.phase {
-            RacePhase::Idle => vec![],
+        let drifting = self.clocks.observe(&passing, captured_at_us, is_beacon);
+        passing.rtc_time_us = self
+            .clocks
+            .align(passing.decoder_id.as_deref(), passing.rtc_time_us);
+        let aligned = passing
+            .decoder_id
+            .as_deref()
+            .is_some_and(|decoder_id| self.clocks.model(decoder_id).is_some());
+        self.track_time_us = aligned.then(|| {
+            self.track_time_us
+                .map_or(passing.rtc_time_us, |us| us.max(passing.rtc_time_us))
+        });
 
-            RacePhase::Staged {
-                moto_id,
-                class_name,
-                round_type,
-            } => {
-                // Only thing we're looking for is a gate drop
-                if processor::is_gate_drop(passing, track) {
-                    let moto_id = moto_id.clone();
-                    let class_name = class_name.clone();
-                    let round_type = round_type.clone();
-
-                    self.phase = RacePhase::Racing {
-                        moto_id: moto_id.clone(),
-                        class_name,
-                        round_type,
-                        gate_drop_time_us: passing.rtc_time_us,
-                    };
+        let mut events = Vec::new();
+        for (decoder_id, model) in drifting {
+            let drift_ppm = model.drift_ppm.unwrap_or_default();
+            warn!(
+                decoder_id = %decoder_id,
+                drift_ppm,
+                offset_us = model.offset_us,
+                source = ?model.source,
+                "Decoder clock drift out of tolerance"
+            );
+            events.push(RaceEvent::ClockDrift {
+                decoder_id,
+                drift_ppm,
+                offset_us: model.offset_us,
+                timestamp_us: passing.rtc_time_us,
+            });
+        }
+        events.extend(self.process_passing(&passing));
+        events
+    }
 
-                    info!(
-                        moto_id = %moto_id,
-                        timestamp = passing.rtc_time_us,
-                        "Gate drop detected"
-                    );
+    /// Process an incoming P3 passing message, timed on the track's time
+    /// base. Returns any race events generated.
+    ///
+    /// The gate beacon drops the gate of the moto on it. Other passings go
+    /// to the moto they belong to, see [`Self::moto_for_passing`].
+    fn process_passing(&mut self, passing: &PassingMessage) -> Vec<RaceEvent> {
+        let is_gate_drop = match &self.track_config {
+            Some(track) => processor::is_gate_drop(passing, track),
+            None => return vec![],
+        };
 
-                    let event = RaceEvent::GateDrop {
-                        moto_id,
-                        timestamp_us: passing.rtc_time_us,
-                    };
-                    self.broadcast(event.clone());
-                    vec![event]
-                } else {
-                    vec![]
-                }
+        if matches!(self.phase, RacePhase::Practice { .. }) {
+            if is_gate_drop {
+                return vec![];
             }
+            return self.process_practice_passing(passing);
+        }
 
-            RacePhase::Racing {
-                moto_id,
-                class_name,
-                round_type,
-                gate_drop_time_us,
-            } => {
-                let moto_id = moto_id.clone();
-                let class_name = class_name.clone();
-                let round_type = round_type.clone();
-                let gate_drop_time_us = *gate_drop_time_us;
-
-                // Ignore gate beacon passings during racing
-                if processor::is_gate_drop(passing, track) {
-                    return vec![];
-                }
-
-                let mut events = vec![];
-
-                // Try to match this passing to a rider and a loop
-                if let Some(loop_config) = passing
-                    .decoder_id
-                    .as_ref()
-                    .and_then(|did| self.decoder_to_loop.get(did))
-                {
-                    let loop_config = loop_config.clone();
-
-                    if let Some(rider) = self.riders_by_transponder.get_mut(&passing.transponder_id)
-                    {
-                        let elapsed_us = passing.rtc_time_us.saturating_sub(gate_drop_time_us);
-
-                        // Only record if this is a new loop (further along the track)
-                        // or if the rider hasn't been seen at this loop yet
-                        let dominated = rider
-                            .last_loop_position
-                            .is_some_and(|last_pos| loop_config.position < last_pos);
-
-                        if dominated && !loop_config.is_finish {
-                            // Rider went backwards or duplicate at an earlier loop — ignore
-                            return events;
-                        }
-
-                        // Record the split
-                        rider.splits.insert(loop_config.loop_id.clone(), elapsed_us);
-                        rider.last_loop_position = Some(loop_config.position);
-                        rider.last_loop_name = Some(loop_config.name.clone());
-                        rider.last_elapsed_us = Some(elapsed_us);
-
-                        let rider_id = rider.rider_id.clone();
-
-                        if loop_config.is_finish && !rider.finished {
-                            // Rider finished!
-                            rider.finished = true;
-                            rider.finish_elapsed_us = Some(elapsed_us);
-                            let pos = self.next_finish_position;
-                            rider.finish_position = Some(pos);
-                            self.next_finish_position += 1;
-
-                            // Calculate gap to leader
-                            let leader_time = self.leader_finish_time();
-                            let gap = leader_time.map(|lt| elapsed_us.saturating_sub(lt));
-
-                            info!(
-                                rider = %rider_id,
-                                position = pos,
-                                elapsed_us = elapsed_us,
-                                "Rider finished"
-                            );
-
-                            let split_event = RaceEvent::SplitTime {
-                                moto_id: moto_id.clone(),
-                                rider_id: rider_id.clone(),
-                                loop_name: loop_config.name.clone(),
-                                is_finish: true,
-                                elapsed_us,
-                                position: pos,
-                                gap_to_leader_us: gap,
-                            };
-                            events.push(split_event.clone());
-                            self.broadcast(split_event);
-
-                            let finish_event = RaceEvent::RiderFinished {
-                                moto_id: moto_id.clone(),
-                                rider_id: rider_id.clone(),
-                                finish_position: pos,
-                                elapsed_us,
-                                gap_to_leader_us: gap,
-                            };
-                            events.push(finish_event.clone());
-                            self.broadcast(finish_event);
-                        } else if !rider.finished {
-                            // Split time at a non-finish loop
-                            let position = processor::calculate_position_at_loop(
-                                &self.riders_by_transponder,
-                                &loop_config,
-                                &rider_id,
-                            );
-
-                            // Gap to leader at this loop
-                            let leader_time = processor::leader_time_at_loop(
-                                &self.riders_by_transponder,
-                                &loop_config,
-                            );
-                            let gap = leader_time.map(|lt| elapsed_us.saturating_sub(lt));
-
-                            let split_event = RaceEvent::SplitTime {
-                                moto_id: moto_id.clone(),
-                                rider_id: rider_id.clone(),
-                                loop_name: loop_config.name.clone(),
-                                is_finish: false,
-                                elapsed_us,
-                                position,
-                                gap_to_leader_us: gap,
-                            };
-                            events.push(split_event.clone());
-                            self.broadcast(split_event);
-                        }
-
-                        // Broadcast updated positions
-                        let positions = self.calculate_positions();
-                        let pos_event = RaceEvent::PositionsUpdate {
-                            moto_id: moto_id.clone(),
-                            positions,
-                        };
-                        events.push(pos_event.clone());
-                        self.broadcast(pos_event);
-
-                        // Check if all riders have finished
-                        let all_finished = self
-                            .riders_by_transponder
-                            .values()
-                            .all(|r| r.finished || r.dnf);
-
-                        if all_finished && !self.riders_by_transponder.is_empty() {
-                            let results = self.build_results();
-                            info!(moto_id = %moto_id, "Race finished — all riders done");
-
-                            self.phase = RacePhase::Finished {
-                                moto_id: moto_id.clone(),
-                                class_name,
-                                round_type,
-                            };
-
-                            let finish_event = RaceEvent::RaceFinished { moto_id, results };
-                            events.push(finish_event.clone());
-                            self.broadcast(finish_event);
-                        }
-                    }
-                }
-
-                events
+        // Any passing moves the decoder clock on
+        let mut events = self.check_timeouts(passing.rtc_time_us);
+        if is_gate_drop {
+            // Motos already racing ignore the gate beacon
+            if let Some(moto) = self
+                .motos
+                .iter_mut()
+                .find(|m| matches!(m.phase(), RacePhase::Staged { .. }))
+            {
+                events.extend(moto.drop_gate(passing));
             }
+            return events;
+        }
 
-            RacePhase::Finished { .. } => vec![],
+        if let Some(moto) = self.moto_for_passing(passing) {
+            events.extend(moto.process_passing(passing));
         }
+        events
     }
 
-    /// Force-finish the current race (operator action for timeouts, etc.)
-    pub fn force_finish(&mut self) -> Option<RaceEvent> {
-        match &self.phase {
-            RacePhase::Racing {
-                moto_id,
-                class_name,
-                round_type,
-                ..
-            } => {
-                let moto_id = moto_id.clone();
-                let class_name = class_name.clone();
-                let round_type = round_type.clone();
-
-                // Mark unfinished riders as DNF
-                for rider in self.riders_by_transponder.values_mut() {
-                    if !rider.finished {
-                        rider.dnf = true;
-                    }
-                }
+    /// The moto on course a passing belongs to.
+    ///
+    /// Only motos on course at the passing's time qualify. A transponder
+    /// counts for the oldest of them it is still racing in, or else the
+    /// newest it is in, so repeat reads reach the moto its rider finished.
+    /// A transponder in none of them goes to the moto that started last,
+    /// where it can be bound to a rider.
+    fn moto_for_passing(&mut self, passing: &PassingMessage) -> Option<&mut Moto> {
+        let on_course: Vec<usize> = (0..self.motos.len())
+            .filter(|&i| self.motos[i].on_course_at(passing.rtc_time_us))
+            .collect();
+        let riding: Vec<(usize, &RiderState)> = on_course
+            .iter()
+            .filter_map(|&i| {
+                let rider = self.motos[i].rider(passing.transponder_id)?;
+                Some((i, rider))
+            })
+            .collect();
+        let index = riding
+            .iter()
+            .find(|(_, rider)| !(rider.finished || rider.dnf || rider.dns))
+            .or(riding.last())
+            .map(|&(i, _)| i)
+            .or(on_course.last().copied())?;
+        self.motos.get_mut(index)
+    }
 
-                let results = self.build_results();
-                info!(moto_id = %moto_id, "Race force-finished by operator");
+    /// Open a practice session on the current track.
+    ///
+    /// `riders` is the rider directory used to put names to transponders;
+    /// transponders not in it are timed anonymously.
+    fn start_practice(&mut self, session_id: String, riders: Vec<KnownRider>) -> Vec<RaceEvent> {
+        if !self.is_idle() {
+            warn!(
+                current_phase = self.phase().name(),
+                "Cannot start practice: race is in progress"
+            );
+            return vec![];
+        }
 
-                self.phase = RacePhase::Finished {
-                    moto_id: moto_id.clone(),
-                    class_name,
-                    round_type,
-                };
+        self.motos.clear();
+        info!(session_id = %session_id, known_riders = riders.len(), "Practice started");
+        self.practice = Some(PracticeSession::new(session_id.clone(), riders));
+        self.phase = RacePhase::Practice {
+            session_id: session_id.clone(),
+        };
+        vec![RaceEvent::PracticeStarted { session_id }]
+    }
 
-                let event = RaceEvent::RaceFinished { moto_id, results };
-                self.broadcast(event.clone());
-                Some(event)
-            }
-            _ => {
-                warn!(phase = self.phase.name(), "Cannot force-finish: not racing");
-                None
-            }
+    /// Close the practice session with its final leaderboards.
+    fn stop_practice(&mut self) -> Option<RaceEvent> {
+        if !matches!(self.phase, RacePhase::Practice { .. }) {
+            warn!(
+                phase = self.phase.name(),
+                "Cannot stop practice: not practicing"
+            );
+            return None;
         }
-    }
+        let session = self.practice.take()?;
+        info!(session_id = %session.session_id(), "Practice ended");
 
-    /// Reset back to idle.
-    pub fn reset(&mut self) {
-        info!(phase = self.phase.name(), "Race reset to idle");
         self.phase = RacePhase::Idle;
-        self.riders_by_transponder.clear();
-        self.rider_ids.clear();
-        self.next_finish_position = 1;
-        self.broadcast(RaceEvent::RaceReset);
+        Some(RaceEvent::PracticeEnded {
+            leaderboard: session.leaderboard(),
+        })
     }
 
-    /// Build a snapshot of the current state for newly connected clients.
-    pub fn state_snapshot(&self) -> RaceEvent {
-        let (moto_id, class_name, round_type, gate_drop_time_us) = match &self.phase {
-            RacePhase::Idle => (None, None, None, None),
-            RacePhase::Staged {
-                moto_id,
-                class_name,
-                round_type,
-            } => (
-                Some(moto_id.clone()),
-                Some(class_name.clone()),
-                Some(round_type.clone()),
-                None,
-            ),
-            RacePhase::Racing {
-                moto_id,
-                class_name,
-                round_type,
-                gate_drop_time_us,
-            } => (
-                Some(moto_id.clone()),
-                Some(class_name.clone()),
-                Some(round_type.clone()),
-                Some(*gate_drop_time_us),
-            ),
-            RacePhase::Finished {
-                moto_id,
-                class_name,
-                round_type,
-            } => (
-                Some(moto_id.clone()),
-                Some(class_name.clone()),
-                Some(round_type.clone()),
-                None,
-            ),
+    fn process_practice_passing(&mut self, passing: &PassingMessage) -> Vec<RaceEvent> {
+        let Some(loop_config) = passing
+            .decoder_id
+            .as_ref()
+            .and_then(|did| self.track_config.as_ref()?.loop_for_decoder(did))
+        else {
+            return vec![];
+        };
+        let Some(session) = self.practice.as_mut() else {
+            return vec![];
         };
 
-        let riders: Vec<StagedRider> = self
-            .riders_by_transponder
-            .values()
-            .map(|r| StagedRider {
-                rider_id: r.rider_id.clone(),
-                first_name: r.first_name.clone(),
-                last_name: r.last_name.clone(),
-                plate_number: r.plate_number.clone(),
-                transponder_id: r.transponder_id,
-                lane: r.lane,
-            })
-            .collect();
-
-        let finished_count = self
-            .riders_by_transponder
-            .values()
-            .filter(|r| r.finished)
-            .count() as u32;
-
-        RaceEvent::StateSnapshot {
-            phase: self.phase.name().to_string(),
-            moto_id,
-            class_name,
-            round_type,
-            riders,
-            positions: self.calculate_positions(),
-            gate_drop_time_us,
-            finished_count,
-            total_riders: self.riders_by_transponder.len() as u32,
-        }
+        let Some(lap) = session.record_crossing(passing, loop_config) else {
+            return vec![];
+        };
+        vec![RaceEvent::PracticeUpdate {
+            lap,
+            leaderboard: session.leaderboard(),
+        }]
     }
 
-    // --- Private helpers ---
-
-    fn broadcast(&self, event: RaceEvent) {
-        // Ignore send errors (no subscribers is fine)
-        let _ = self.event_tx.send(Arc::new(event));
+    /// Force-finish a moto (operator action for timeouts, etc.), by
+    /// default the oldest one on course.
+    fn force_finish(&mut self, moto_id: Option<&str>) -> Option<RaceEvent> {
+        let moto = match moto_id {
+            Some(moto_id) => self.moto_mut(moto_id),
+            None => self.motos.iter_mut().find(|m| {
+                matches!(
+                    m.phase(),
+                    RacePhase::Racing { .. } | RacePhase::TimeTrial { .. }
+                )
+            }),
+        };
+        let Some(moto) = moto else {
+            warn!(moto_id = ?moto_id, phase = self.phase().name(), "Cannot force-finish: not racing");
+            return None;
+        };
+        moto.force_finish()
     }
 
-    fn leader_finish_time(&self) -> Option<u64> {
-        self.riders_by_transponder
-            .values()
-            .filter(|r| r.finished)
-            .filter_map(|r| r.finish_elapsed_us)
-            .min()
+    /// Apply the race rules to every moto on course at decoder time `now_us`.
+    ///
+    /// Runs on every passing, and should also be called periodically so a
+    /// race ends even when nobody crosses a loop.
+    fn check_timeouts(&mut self, now_us: u64) -> Vec<RaceEvent> {
+        self.motos
+            .iter_mut()
+            .flat_map(|moto| moto.check_timeouts(now_us))
+            .collect()
     }
 
-    /// Calculate current race positions based on:
-    /// 1. Finished riders ranked by finish position
-    /// 2. Unfinished riders ranked by furthest loop reached, then elapsed time
-    fn calculate_positions(&self) -> Vec<RiderPosition> {
-        let mut finished: Vec<&RiderState> = self
-            .riders_by_transponder
-            .values()
-            .filter(|r| r.finished)
-            .collect();
-        finished.sort_by_key(|r| r.finish_position);
-
-        let mut racing: Vec<&RiderState> = self
-            .riders_by_transponder
-            .values()
-            .filter(|r| !r.finished && !r.dnf)
-            .collect();
-        // Sort by: furthest loop (desc), then elapsed time at that loop (asc)
-        racing.sort_by(|a, b| {
-            let loop_cmp = b
-                .last_loop_position
-                .unwrap_or(0)
-                .cmp(&a.last_loop_position.unwrap_or(0));
-            if loop_cmp != std::cmp::Ordering::Equal {
-                return loop_cmp;
-            }
-            a.last_elapsed_us
-                .unwrap_or(u64::MAX)
-                .cmp(&b.last_elapsed_us.unwrap_or(u64::MAX))
-        });
-
-        let mut dnf: Vec<&RiderState> = self
-            .riders_by_transponder
-            .values()
-            .filter(|r| r.dnf)
-            .collect();
-        dnf.sort_by_key(|r| r.lane);
+    /// Every passing recorded in a moto, by default the one staged last,
+    /// oldest first, including operator-inserted ones and those voided by a
+    /// correction.
+    pub fn recorded_passings(&self, moto_id: Option<&str>) -> Vec<RecordedPassing> {
+        let moto = match moto_id {
+            Some(moto_id) => self.moto(moto_id),
+            None => self.motos.last(),
+        };
+        moto.map(Moto::recorded_passings).unwrap_or_default()
+    }
 
-        let leader_finish = self.leader_finish_time();
+    /// Check that a timing correction applies to a moto on the track.
+    pub fn validate_correction(&self, moto_id: &str, change: &TimingChange) -> Result<(), String> {
+        match self.moto(moto_id) {
+            Some(moto) => moto.validate_correction(change),
+            None => Err(format!("Moto {moto_id} is not on the track")),
+        }
+    }
 
-        let mut positions = Vec::new();
-        let mut pos: u32 = 1;
+    /// Apply an operator's timing correction to a moto on the track.
+    fn apply_correction(&mut self, moto_id: &str, correction: TimingCorrection) -> Vec<RaceEvent> {
+        let Some(moto) = self.moto_mut(moto_id) else {
+            warn!(moto_id = %moto_id, "Timing correction rejected: moto is not on the track");
+            return vec![];
+        };
+        moto.apply_correction(moto_id, correction)
+    }
 
-        for rider in finished.iter().chain(racing.iter()).chain(dnf.iter()) {
-            let gap = match (leader_finish, rider.finish_elapsed_us) {
-                (Some(lt), Some(ft)) if pos > 1 => Some(ft.saturating_sub(lt)),
-                _ => rider.last_elapsed_us.and_then(|elapsed| {
-                    // For non-finished riders, gap is less meaningful
-                    // but we can show gap to leader at the same loop
-                    Some(elapsed).filter(|_| pos > 1)
-                }),
-            };
+    /// Reset back to idle, taking every moto off the track.
+    fn reset(&mut self) -> Vec<RaceEvent> {
+        info!(
+            phase = self.phase().name(),
+            motos = self.motos.len(),
+            "Race reset to idle"
+        );
+        self.phase = RacePhase::Idle;
+        self.motos.clear();
+        self.practice = None;
+        vec![RaceEvent::RaceReset]
+    }
 
-            positions.push(rider.to_position(pos, gap));
-            pos += 1;
+    /// Build a snapshot of the current state for newly connected clients,
+    /// following the moto staged last.
+    pub fn state_snapshot(&self) -> RaceEvent {
+        if let Some(moto) = self.motos.last() {
+            return moto.state_snapshot();
+        }
+        RaceEvent::StateSnapshot {
+            phase: self.phase.name().to_string(),
+            moto_id: None,
+            class_name: None,
+            round_type: None,
+            riders: Vec::new(),
+            positions: Vec::new(),
+            gate_drop_time_us: None,
+            finished_count: 0,
+            total_riders: 0,
+            laps: 1,
+            practice: self.practice.as_ref().map(PracticeSession::leaderboard),
         }
-
-        positions
     }
 
-    fn build_results(&self) -> Vec<FinishResult> {
-        let mut results: Vec<FinishResult> = self
-            .riders_by_transponder
-            .values()
-            .map(|r| {
-                let leader_time = self.leader_finish_time();
-                let gap = match (leader_time, r.finish_elapsed_us) {
-                    (Some(lt), Some(ft)) if r.finish_position != Some(1) => {
-                        Some(ft.saturating_sub(lt))
-                    }
-                    _ => None,
-                };
-
-                FinishResult {
-                    rider_id: r.rider_id.clone(),
-                    plate_number: r.plate_number.clone(),
-                    first_name: r.first_name.clone(),
-                    last_name: r.last_name.clone(),
-                    position: r.finish_position.unwrap_or(0),
-                    elapsed_us: r.finish_elapsed_us,
-                    gap_to_leader_us: gap,
-                    dnf: r.dnf,
-                    dns: false,
-                }
-            })
-            .collect();
+    /// Snapshot of a moto on the track.
+    pub fn moto_snapshot(&self, moto_id: &str) -> Option<RaceEvent> {
+        self.moto(moto_id).map(Moto::state_snapshot)
+    }
 
-        // Finished riders first (by position), then DNF riders
-        results.sort_by(|a, b| match (a.dnf, b.dnf) {
-            (false, true) => std::cmp::Ordering::Less,
-            (true, false) => std::cmp::Ordering::Greater,
-            _ => a.position.cmp(&b.position),
-        });
+    fn moto(&self, moto_id: &str) -> Option<&Moto> {
+        self.motos.iter().find(|m| m.moto_id() == moto_id)
+    }
 
-        results
+    fn moto_mut(&mut self, moto_id: &str) -> Option<&mut Moto> {
+        self.motos.iter_mut().find(|m| m.moto_id() == moto_id)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::domain::race_event::StagedRider;
+    use crate::domain::race_event::{LoopConfig, StagedRider};
 
     fn test_track() -> TrackConfig {
         TrackConfig {
@@ -604,6 +699,7 @@ mod tests {
                     position: 0,
                     is_start: true,
                     is_finish: false,
+                    debounce_ms: 0,
                 },
                 LoopConfig {
                     loop_id: "loop-corner1".into(),
@@ -612,6 +708,7 @@ mod tests {
                     position: 1,
                     is_start: false,
                     is_finish: false,
+                    debounce_ms: 0,
                 },
                 LoopConfig {
                     loop_id: "loop-finish".into(),
@@ -620,6 +717,7 @@ mod tests {
                     position: 2,
                     is_start: false,
                     is_finish: true,
+                    debounce_ms: 0,
                 },
             ],
         }
@@ -665,13 +763,13 @@ mod tests {
             transponder_string: None,
             flags: 0,
             decoder_id: Some(decoder_id.to_string()),
+            extra_fields: Vec::new(),
         }
     }
 
     #[test]
     fn test_idle_ignores_passings() {
-        let (tx, _rx) = broadcast::channel(64);
-        let mut engine = RaceEngine::new(tx);
+        let mut engine = RaceState::new();
         engine.set_track(test_track());
 
         let passing = make_passing(1001, "D0000C01", 1_000_000);
@@ -681,35 +779,34 @@ mod tests {
 
     #[test]
     fn test_stage_moto() {
-        let (tx, mut rx) = broadcast::channel(64);
-        let mut engine = RaceEngine::new(tx);
+        let mut engine = RaceState::new();
         engine.set_track(test_track());
 
-        engine.stage_moto(
+        let events = engine.stage(
             "moto-1".into(),
             "Novice".into(),
             "moto1".into(),
+            1,
+            StartMode::GateDrop,
             test_riders(),
         );
 
         assert!(matches!(engine.phase(), RacePhase::Staged { .. }));
-
-        let event = rx.try_recv().unwrap();
-        assert!(matches!(event.as_ref(), RaceEvent::RaceStaged { .. }));
+        assert!(matches!(events[..], [RaceEvent::RaceStaged { .. }]));
     }
 
     #[test]
     fn test_gate_drop_transitions_to_racing() {
-        let (tx, mut rx) = broadcast::channel(64);
-        let mut engine = RaceEngine::new(tx);
+        let mut engine = RaceState::new();
         engine.set_track(test_track());
-        engine.stage_moto(
+        engine.stage(
             "moto-1".into(),
             "Novice".into(),
             "moto1".into(),
+            1,
+            StartMode::GateDrop,
             test_riders(),
         );
-        let _ = rx.try_recv(); // consume RaceStaged
 
         // Gate beacon passing
         let gate = make_passing(9992, "D0000C01", 10_000_000);
@@ -722,21 +819,20 @@ mod tests {
 
     #[test]
     fn test_split_time_and_positions() {
-        let (tx, mut rx) = broadcast::channel(64);
-        let mut engine = RaceEngine::new(tx);
+        let mut engine = RaceState::new();
         engine.set_track(test_track());
-        engine.stage_moto(
+        engine.stage(
             "moto-1".into(),
             "Novice".into(),
             "moto1".into(),
+            1,
+            StartMode::GateDrop,
             test_riders(),
         );
 
         // Gate drop at T=10_000_000
         let gate = make_passing(9992, "D0000C01", 10_000_000);
         engine.process_passing(&gate);
-        // Drain staged + gate events
-        while rx.try_recv().is_ok() {}
 
         // Rider 2 crosses start hill first (T+1s)
         let p1 = make_passing(1002, "D0000C01", 11_000_000);
@@ -765,13 +861,14 @@ mod tests {
 
     #[test]
     fn test_finish_and_race_complete() {
-        let (tx, _rx) = broadcast::channel(64);
-        let mut engine = RaceEngine::new(tx);
+        let mut engine = RaceState::new();
         engine.set_track(test_track());
-        engine.stage_moto(
+        engine.stage(
             "moto-1".into(),
             "Novice".into(),
             "moto1".into(),
+            1,
+            StartMode::GateDrop,
             test_riders(),
         );
 
@@ -815,81 +912,1549 @@ mod tests {
         assert!(matches!(engine.phase(), RacePhase::Finished { .. }));
     }
 
-    #[test]
-    fn test_force_finish() {
-        let (tx, _rx) = broadcast::channel(64);
-        let mut engine = RaceEngine::new(tx);
-        engine.set_track(test_track());
-        engine.stage_moto(
+    /// Engine past gate drop at T=10s
+    fn racing_engine(laps: u32, debounce_ms: u32) -> RaceState {
+        let mut engine = RaceState::new();
+        let mut track = test_track();
+        for loop_config in &mut track.loops {
+            loop_config.debounce_ms = debounce_ms;
+        }
+        engine.set_track(track);
+        engine.stage(
             "moto-1".into(),
             "Novice".into(),
             "moto1".into(),
+            laps,
+            StartMode::GateDrop,
             test_riders(),
         );
-
-        // Gate drop
         engine.process_passing(&make_passing(9992, "D0000C01", 10_000_000));
-
-        // Only rider 1 finishes
-        engine.process_passing(&make_passing(1001, "D0000C01", 11_000_000));
-        engine.process_passing(&make_passing(1001, "D0000C03", 20_000_000));
-
-        // Force finish
-        let event = engine.force_finish();
-        assert!(event.is_some());
-        assert!(matches!(engine.phase(), RacePhase::Finished { .. }));
-
-        if let Some(RaceEvent::RaceFinished { results, .. }) = event {
-            // 1 finished + 2 DNF
-            assert_eq!(results.len(), 3);
-            let dnf_count = results.iter().filter(|r| r.dnf).count();
-            assert_eq!(dnf_count, 2);
-        }
+        engine
     }
 
-    #[test]
-    fn test_reset_to_idle() {
-        let (tx, _rx) = broadcast::channel(64);
-        let mut engine = RaceEngine::new(tx);
+    /// A one-lap race staged under `rules`; motos keep the rules they were
+    /// staged with
+    fn ruled_engine(rules: RaceRules) -> RaceState {
+        let mut engine = RaceState::new();
         engine.set_track(test_track());
-        engine.stage_moto(
+        engine.set_race_rules(rules);
+        engine.stage(
             "moto-1".into(),
             "Novice".into(),
             "moto1".into(),
+            1,
+            StartMode::GateDrop,
             test_riders(),
         );
+        engine.process_passing(&make_passing(9992, "D0000C01", 10_000_000));
+        engine
+    }
 
-        engine.reset();
-        assert!(matches!(engine.phase(), RacePhase::Idle));
+    fn weak_passing(transponder_id: u32, decoder_id: &str, rtc_time_us: u64) -> PassingMessage {
+        PassingMessage {
+            strength: Some(60),
+            hits: Some(5),
+            ..make_passing(transponder_id, decoder_id, rtc_time_us)
+        }
     }
 
     #[test]
-    fn test_state_snapshot() {
-        let (tx, _rx) = broadcast::channel(64);
-        let mut engine = RaceEngine::new(tx);
-        engine.set_track(test_track());
-        engine.stage_moto(
-            "moto-1".into(),
-            "Novice".into(),
-            "moto1".into(),
-            test_riders(),
+    fn test_stronger_hit_within_debounce_corrects_split() {
+        let mut engine = racing_engine(1, 500);
+
+        engine.process_passing(&weak_passing(1001, "D0000C02", 15_000_000));
+        let events = engine.process_passing(&make_passing(1001, "D0000C02", 15_200_000));
+
+        assert!(events.iter().any(|e| matches!(
+            e,
+            RaceEvent::SplitCorrected {
+                rider_id,
+                previous_elapsed_us: 5_000_000,
+                elapsed_us: 5_200_000,
+                is_finish: false,
+                ..
+            } if rider_id == "rider-1"
+        )));
+        assert!(
+            events
+                .iter()
+                .any(|e| matches!(e, RaceEvent::PositionsUpdate { .. }))
         );
+    }
 
-        let snapshot = engine.state_snapshot();
-        if let RaceEvent::StateSnapshot {
-            phase,
-            moto_id,
-            riders,
-            total_riders,
-            ..
-        } = snapshot
-        {
-            assert_eq!(phase, "staged");
-            assert_eq!(moto_id, Some("moto-1".to_string()));
-            assert_eq!(riders.len(), 3);
-            assert_eq!(total_riders, 3);
-        } else {
-            panic!("Expected StateSnapshot");
+    #[test]
+    fn test_repeat_hits_outside_debounce_are_ignored() {
+        let mut engine = racing_engine(1, 500);
+
+        engine.process_passing(&weak_passing(1001, "D0000C02", 15_000_000));
+        // A weaker hit inside the window and a stronger one after it
+        assert!(
+            engine
+                .process_passing(&weak_passing(1001, "D0000C02", 15_100_000))
+                .is_empty()
+        );
+        assert!(
+            engine
+                .process_passing(&make_passing(1001, "D0000C02", 15_600_000))
+                .is_empty()
+        );
+
+        let rider = &engine.motos[0].rider(1001).unwrap();
+        assert_eq!(rider.splits[&(1, "loop-corner1".to_string())], 5_000_000);
+        assert_eq!(rider.last_elapsed_us, Some(5_000_000));
+    }
+
+    #[test]
+    fn test_finish_correction_reranks_finishers() {
+        let mut engine = racing_engine(1, 500);
+
+        // Rider 1's first finish hit is an early weak read
+        engine.process_passing(&weak_passing(1001, "D0000C03", 20_000_000));
+        engine.process_passing(&make_passing(1002, "D0000C03", 20_100_000));
+
+        let events = engine.process_passing(&make_passing(1001, "D0000C03", 20_300_000));
+        assert!(events.iter().any(|e| matches!(
+            e,
+            RaceEvent::SplitCorrected {
+                is_finish: true,
+                position: 2,
+                gap_to_leader_us: Some(200_000),
+                ..
+            }
+        )));
+
+        assert_eq!(
+            engine.motos[0].rider(1001).unwrap().finish_position,
+            Some(2)
+        );
+        assert_eq!(
+            engine.motos[0].rider(1002).unwrap().finish_position,
+            Some(1)
+        );
+    }
+
+    /// Cross the corner and finish loops `laps` times at 10s per lap
+    fn ride_laps(engine: &mut RaceState, transponder_id: u32, start_us: u64, laps: u64) {
+        for lap in 0..laps {
+            let lap_start = start_us + lap * 10_000_000;
+            engine.process_passing(&make_passing(
+                transponder_id,
+                "D0000C02",
+                lap_start + 4_000_000,
+            ));
+            engine.process_passing(&make_passing(
+                transponder_id,
+                "D0000C03",
+                lap_start + 10_000_000,
+            ));
+        }
+    }
+
+    #[test]
+    fn test_multi_lap_finish_after_last_lap() {
+        let mut engine = racing_engine(3, 0);
+
+        ride_laps(&mut engine, 1001, 10_000_000, 2);
+        let rider = &engine.motos[0].rider(1001).unwrap();
+        assert!(!rider.finished);
+        assert_eq!(rider.laps_completed(), 2);
+        assert_eq!(rider.splits[&(2, "loop-finish".to_string())], 20_000_000);
+
+        // Third crossing of the finish loop ends the race for rider 1
+        engine.process_passing(&make_passing(1001, "D0000C02", 35_000_000));
+        let events = engine.process_passing(&make_passing(1001, "D0000C03", 40_500_000));
+        assert!(events.iter().any(|e| matches!(
+            e,
+            RaceEvent::SplitTime {
+                lap: 3,
+                is_finish: true,
+                elapsed_us: 30_500_000,
+                ..
+            }
+        )));
+        assert!(
+            events
+                .iter()
+                .any(|e| matches!(e, RaceEvent::RiderFinished { .. }))
+        );
+
+        let event = engine.force_finish(None).unwrap();
+        let RaceEvent::RaceFinished { results, .. } = event else {
+            panic!("Expected RaceFinished");
+        };
+        assert_eq!(results[0].rider_id, "rider-1");
+        assert_eq!(
+            results[0].lap_times_us,
+            vec![10_000_000, 10_000_000, 10_500_000]
+        );
+    }
+
+    #[test]
+    fn test_multi_lap_positions_rank_laps_first() {
+        let mut engine = racing_engine(3, 0);
+
+        // Rider 2 is further around lap 1 than rider 1 is on lap 2
+        ride_laps(&mut engine, 1001, 10_000_000, 1);
+        engine.process_passing(&make_passing(1002, "D0000C02", 14_500_000));
+        let events = engine.process_passing(&make_passing(1001, "D0000C02", 24_000_000));
+
+        let Some(RaceEvent::PositionsUpdate { positions, .. }) = events.last() else {
+            panic!("Expected PositionsUpdate");
+        };
+        assert_eq!(positions[0].rider_id, "rider-1");
+        assert_eq!(positions[0].laps_completed, 1);
+        assert_eq!(positions[1].rider_id, "rider-2");
+        assert_eq!(positions[1].laps_completed, 0);
+        // Rider 3 has not crossed a loop yet
+        assert_eq!(positions[2].rider_id, "rider-3");
+    }
+
+    #[test]
+    fn test_multi_lap_repeat_finish_read_is_not_a_lap() {
+        let mut engine = racing_engine(2, 500);
+
+        engine.process_passing(&weak_passing(1001, "D0000C03", 20_000_000));
+        // A stronger read of the same crossing corrects lap 1 instead of ending lap 2
+        let events = engine.process_passing(&make_passing(1001, "D0000C03", 20_200_000));
+        assert!(events.iter().any(|e| matches!(
+            e,
+            RaceEvent::SplitCorrected {
+                lap: 1,
+                is_finish: false,
+                elapsed_us: 10_200_000,
+                ..
+            }
+        )));
+
+        let rider = &engine.motos[0].rider(1001).unwrap();
+        assert_eq!(rider.laps_completed(), 1);
+        assert_eq!(rider.lap_times_us(), vec![10_200_000]);
+        assert!(!rider.finished);
+    }
+
+    fn time_trial_engine() -> RaceState {
+        let mut engine = RaceState::new();
+        engine.set_track(test_track());
+        engine.stage(
+            "tt-1".into(),
+            "Pro".into(),
+            "time_trial".into(),
+            1,
+            StartMode::TimeTrial,
+            test_riders()[..2].to_vec(),
+        );
+        engine
+    }
+
+    #[test]
+    fn test_time_trial_clocks_start_per_rider() {
+        let mut engine = time_trial_engine();
+        assert!(matches!(engine.phase(), RacePhase::TimeTrial { .. }));
+
+        // Not on course yet: gate beacon and finish hits are ignored
+        assert!(
+            engine
+                .process_passing(&make_passing(9992, "D0000C01", 9_000_000))
+                .is_empty()
+        );
+        assert!(
+            engine
+                .process_passing(&make_passing(1001, "D0000C03", 9_500_000))
+                .is_empty()
+        );
+
+        let events = engine.process_passing(&make_passing(1001, "D0000C01", 10_000_000));
+        assert!(matches!(
+            &events[0],
+            RaceEvent::RiderStarted { rider_id, timestamp_us: 10_000_000, .. } if rider_id == "rider-1"
+        ));
+        engine.process_passing(&make_passing(1002, "D0000C01", 15_000_000));
+
+        // Rider 1 arrives first but rider 2 is faster over the course
+        engine.process_passing(&make_passing(1001, "D0000C03", 30_000_000));
+        let events = engine.process_passing(&make_passing(1002, "D0000C03", 33_000_000));
+        assert!(events.iter().any(|e| matches!(
+            e,
+            RaceEvent::RiderFinished {
+                rider_id,
+                finish_position: 1,
+                elapsed_us: 18_000_000,
+                ..
+            } if rider_id == "rider-2"
+        )));
+        assert_eq!(engine.motos[0].finish_position_of("rider-1"), Some(2));
+        assert_eq!(engine.motos[0].finish_position_of("rider-2"), Some(1));
+
+        // Everyone on the list is done, but the session stays open
+        assert!(matches!(engine.phase(), RacePhase::TimeTrial { .. }));
+    }
+
+    #[test]
+    fn test_time_trial_rolling_start_list() {
+        let mut engine = time_trial_engine();
+
+        let rider_3 = test_riders()[2].clone();
+        let event = engine
+            .add_to_start_list("tt-1", vec![rider_3.clone(), test_riders()[0].clone()])
+            .unwrap();
+        let RaceEvent::StartListUpdated { riders, .. } = event else {
+            panic!("Expected StartListUpdated");
+        };
+        // Rider 1 is already on the list
+        assert_eq!(riders.len(), 1);
+        assert_eq!(riders[0].rider_id, "rider-3");
+        assert_eq!(riders[0].lane, 3);
+        assert!(engine.add_to_start_list("other", vec![rider_3]).is_none());
+
+        engine.process_passing(&make_passing(1003, "D0000C01", 40_000_000));
+        engine.process_passing(&make_passing(1003, "D0000C03", 59_000_000));
+        engine.process_passing(&make_passing(1001, "D0000C01", 60_000_000));
+
+        let Some(RaceEvent::RaceFinished { results, .. }) = engine.force_finish(None) else {
+            panic!("Expected RaceFinished");
+        };
+        let flags: Vec<_> = results
+            .iter()
+            .map(|r| (r.rider_id.as_str(), r.position, r.dnf, r.dns))
+            .collect();
+        assert_eq!(
+            flags,
+            vec![
+                ("rider-3", 1, false, false),
+                ("rider-1", 0, true, false),
+                ("rider-2", 0, false, true),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_force_finish() {
+        let mut engine = RaceState::new();
+        engine.set_track(test_track());
+        engine.stage(
+            "moto-1".into(),
+            "Novice".into(),
+            "moto1".into(),
+            1,
+            StartMode::GateDrop,
+            test_riders(),
+        );
+
+        // Gate drop
+        engine.process_passing(&make_passing(9992, "D0000C01", 10_000_000));
+
+        // Only rider 1 finishes
+        engine.process_passing(&make_passing(1001, "D0000C01", 11_000_000));
+        engine.process_passing(&make_passing(1001, "D0000C03", 20_000_000));
+
+        // Force finish
+        let event = engine.force_finish(None);
+        assert!(event.is_some());
+        assert!(matches!(engine.phase(), RacePhase::Finished { .. }));
+
+        if let Some(RaceEvent::RaceFinished { results, .. }) = event {
+            // 1 finished + 2 DNF
+            assert_eq!(results.len(), 3);
+            let dnf_count = results.iter().filter(|r| r.dnf).count();
+            assert_eq!(dnf_count, 2);
+        }
+    }
+
+    fn finish_flags(events: &[RaceEvent]) -> Vec<(String, bool, bool)> {
+        let Some(RaceEvent::RaceFinished { results, .. }) = events.last() else {
+            panic!("expected race finished, got {events:?}");
+        };
+        results
+            .iter()
+            .map(|r| (r.rider_id.clone(), r.dns, r.dnf))
+            .collect()
+    }
+
+    #[test]
+    fn test_dns_timeout_after_gate_drop() {
+        let mut engine = ruled_engine(RaceRules {
+            dns_timeout_secs: Some(5),
+            ..RaceRules::default()
+        });
+        engine.process_passing(&make_passing(1001, "D0000C02", 13_000_000));
+
+        assert!(engine.check_timeouts(14_999_999).is_empty());
+        let events = engine.check_timeouts(15_000_000);
+        let [RaceEvent::PositionsUpdate { positions, .. }] = events.as_slice() else {
+            panic!("expected positions update, got {events:?}");
+        };
+        assert_eq!(positions.iter().filter(|p| p.dnf).count(), 2);
+
+        // A late read does not bring a DNS rider back
+        engine.process_passing(&make_passing(1002, "D0000C02", 16_000_000));
+        let events = engine.process_passing(&make_passing(1001, "D0000C03", 20_000_000));
+        assert_eq!(
+            finish_flags(&events),
+            vec![
+                ("rider-1".into(), false, false),
+                ("rider-2".into(), true, false),
+                ("rider-3".into(), true, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dnf_timeout_after_leader_finish() {
+        let mut engine = ruled_engine(RaceRules {
+            dnf_timeout_secs: Some(10),
+            ..RaceRules::default()
+        });
+        ride_laps(&mut engine, 1001, 10_000_000, 1);
+        engine.process_passing(&make_passing(1002, "D0000C02", 15_000_000));
+
+        // Leader finished 10s after the gate; the window runs 10s more
+        assert!(engine.check_timeouts(29_999_999).is_empty());
+        let events = engine.check_timeouts(30_000_000);
+        assert_eq!(
+            finish_flags(&events),
+            vec![
+                ("rider-1".into(), false, false),
+                ("rider-2".into(), false, true),
+                ("rider-3".into(), true, false),
+            ]
+        );
+        assert!(matches!(engine.phase(), RacePhase::Finished { .. }));
+    }
+
+    #[test]
+    fn test_max_race_time_finishes_race() {
+        let mut engine = ruled_engine(RaceRules {
+            max_race_secs: Some(30),
+            ..RaceRules::default()
+        });
+        engine.process_passing(&make_passing(1001, "D0000C02", 14_000_000));
+        assert!(engine.check_timeouts(39_000_000).is_empty());
+
+        // Any passing moves the clock, even a gate beacon
+        let events = engine.process_passing(&make_passing(9992, "D0000C01", 40_000_000));
+        assert_eq!(
+            finish_flags(&events),
+            vec![
+                ("rider-1".into(), false, true),
+                ("rider-2".into(), true, false),
+                ("rider-3".into(), true, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unknown_transponder_and_unmapped_decoder_alerts() {
+        let mut engine = racing_engine(1, 0);
+
+        let events = engine.process_passing(&make_passing(5555, "D0000C02", 12_000_000));
+        assert!(matches!(
+            events.as_slice(),
+            [RaceEvent::UnknownTransponder { transponder_id: 5555, loop_name, .. }]
+                if loop_name == "Corner 1"
+        ));
+        // Alerted once per loop
+        assert!(
+            engine
+                .process_passing(&make_passing(5555, "D0000C02", 12_100_000))
+                .is_empty()
+        );
+
+        let events = engine.process_passing(&make_passing(1001, "D0000C09", 12_500_000));
+        assert!(matches!(
+            events.as_slice(),
+            [RaceEvent::UnmappedDecoder { decoder_id: Some(decoder_id), transponder_id: 1001, .. }]
+                if decoder_id == "D0000C09"
+        ));
+        assert!(
+            engine
+                .process_passing(&make_passing(1002, "D0000C09", 12_600_000))
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_bind_transponder_replays_passings() {
+        let mut engine = racing_engine(1, 0);
+        engine.process_passing(&make_passing(1001, "D0000C02", 12_000_000));
+        // Rider 2 is on a bike with transponder 5555
+        engine.process_passing(&make_passing(5555, "D0000C02", 13_000_000));
+        engine.process_passing(&make_passing(5555, "D0000C03", 18_000_000));
+
+        let events = engine.bind_transponder("moto-1", "rider-2", 5555);
+        let finish = events.iter().find_map(|e| match e {
+            RaceEvent::RiderFinished {
+                rider_id,
+                finish_position,
+                elapsed_us,
+                ..
+            } => Some((rider_id.as_str(), *finish_position, *elapsed_us)),
+            _ => None,
+        });
+        assert_eq!(finish, Some(("rider-2", 1, 8_000_000)));
+
+        // The old transponder is now unknown
+        let events = engine.process_passing(&make_passing(1002, "D0000C03", 19_000_000));
+        assert!(matches!(
+            events.as_slice(),
+            [RaceEvent::UnknownTransponder {
+                transponder_id: 1002,
+                ..
+            }]
+        ));
+
+        // A transponder can't be bound to a second rider
+        assert!(
+            engine
+                .bind_transponder("moto-1", "rider-3", 5555)
+                .is_empty()
+        );
+        let RaceEvent::StateSnapshot { riders, .. } = engine.state_snapshot() else {
+            panic!("expected snapshot");
+        };
+        assert_eq!(riders[1].transponder_id, 5555);
+        assert_eq!(riders[2].transponder_id, 1003);
+    }
+
+    fn correction(id: &str, change: TimingChange) -> TimingCorrection {
+        TimingCorrection {
+            correction_id: id.into(),
+            operator: "chief-timer".into(),
+            reason: "test".into(),
+            change,
+        }
+    }
+
+    fn finish_order(events: &[RaceEvent]) -> Option<Vec<(String, u32)>> {
+        events.iter().find_map(|e| match e {
+            RaceEvent::RaceFinished { results, .. } => Some(
+                results
+                    .iter()
+                    .map(|r| (r.rider_id.clone(), r.position))
+                    .collect(),
+            ),
+            _ => None,
+        })
+    }
+
+    #[test]
+    fn test_correction_inserts_missed_finish() {
+        let mut engine = racing_engine(1, 0);
+        engine.process_passing(&make_passing(1001, "D0000C03", 20_000_000));
+        engine.process_passing(&make_passing(1002, "D0000C02", 14_000_000));
+        engine.process_passing(&make_passing(1003, "D0000C03", 22_000_000));
+        assert_eq!(engine.phase().name(), "racing");
+
+        // Rider 2's finish crossing was never read
+        let events = engine.apply_correction(
+            "moto-1",
+            correction(
+                "c-1",
+                TimingChange::InsertPassing {
+                    rider_id: "rider-2".into(),
+                    loop_id: "loop-finish".into(),
+                    timestamp_us: 21_000_000,
+                },
+            ),
+        );
+        assert!(matches!(events[0], RaceEvent::TimingCorrected { .. }));
+        assert_eq!(
+            finish_order(&events),
+            Some(vec![
+                ("rider-1".into(), 1),
+                ("rider-2".into(), 2),
+                ("rider-3".into(), 3),
+            ])
+        );
+
+        let manual: Vec<_> = engine
+            .recorded_passings(None)
+            .into_iter()
+            .filter(|p| p.manual)
+            .collect();
+        assert_eq!(manual.len(), 1);
+        assert_eq!(manual[0].passing_id, "manual:c-1");
+        assert_eq!(manual[0].rider_id.as_deref(), Some("rider-2"));
+    }
+
+    #[test]
+    fn test_correction_voids_spurious_passing() {
+        let mut engine = racing_engine(1, 0);
+        // A stray read puts rider 2 across the line first
+        engine.process_passing(&PassingMessage {
+            passing_number: 7,
+            ..make_passing(1002, "D0000C03", 19_000_000)
+        });
+        engine.process_passing(&PassingMessage {
+            passing_number: 8,
+            ..make_passing(1001, "D0000C03", 20_000_000)
+        });
+        assert_eq!(engine.motos[0].finish_position_of("rider-2"), Some(1));
+
+        let unknown = TimingChange::VoidPassing {
+            passing_id: "D0000C03:99".into(),
+        };
+        assert!(engine.validate_correction("moto-1", &unknown).is_err());
+        assert!(
+            engine
+                .apply_correction("moto-2", correction("c-0", unknown))
+                .is_empty()
+        );
+
+        engine.apply_correction(
+            "moto-1",
+            correction(
+                "c-1",
+                TimingChange::VoidPassing {
+                    passing_id: "D0000C03:7".into(),
+                },
+            ),
+        );
+        assert_eq!(engine.motos[0].finish_position_of("rider-1"), Some(1));
+        assert_eq!(engine.motos[0].finish_position_of("rider-2"), None);
+        assert!(
+            engine
+                .recorded_passings(None)
+                .iter()
+                .any(|p| p.passing_id == "D0000C03:7" && p.voided)
+        );
+
+        // Rider 2's real finish still counts
+        engine.process_passing(&PassingMessage {
+            passing_number: 9,
+            ..make_passing(1002, "D0000C03", 23_000_000)
+        });
+        assert_eq!(engine.motos[0].finish_position_of("rider-2"), Some(2));
+    }
+
+    #[test]
+    fn test_hand_timed_finish_recomputes_finished_moto() {
+        let mut engine = racing_engine(1, 0);
+        for (passing_number, transponder_id, rtc_time_us) in [
+            (1, 1001, 20_000_000),
+            (2, 1002, 21_000_000),
+            (3, 1003, 22_000_000),
+        ] {
+            engine.process_passing(&PassingMessage {
+                passing_number,
+                ..make_passing(transponder_id, "D0000C03", rtc_time_us)
+            });
+        }
+        assert_eq!(engine.phase().name(), "finished");
+
+        // The photo shows rider 3 ahead of everyone
+        let events = engine.apply_correction(
+            "moto-1",
+            correction(
+                "c-1",
+                TimingChange::HandTimedFinish {
+                    rider_id: "rider-3".into(),
+                    elapsed_us: 9_500_000,
+                },
+            ),
+        );
+        assert_eq!(
+            finish_order(&events),
+            Some(vec![
+                ("rider-3".into(), 1),
+                ("rider-1".into(), 2),
+                ("rider-2".into(), 3),
+            ])
+        );
+        assert!(matches!(
+            engine.phase(),
+            RacePhase::Finished {
+                gate_drop_time_us: Some(10_000_000),
+                ..
+            }
+        ));
+
+        // Every correction is replayed, so the hand time survives a later one
+        let events = engine.apply_correction(
+            "moto-1",
+            correction(
+                "c-2",
+                TimingChange::VoidPassing {
+                    passing_id: "D0000C03:1".into(),
+                },
+            ),
+        );
+        assert_eq!(
+            finish_order(&events),
+            Some(vec![
+                ("rider-3".into(), 1),
+                ("rider-2".into(), 2),
+                ("rider-1".into(), 0),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_correction_after_bike_swap_keeps_earlier_laps() {
+        let mut engine = racing_engine(2, 0);
+        engine.process_passing(&make_passing(1002, "D0000C02", 13_000_000));
+        engine.process_passing(&make_passing(1002, "D0000C03", 20_000_000));
+        // Rider 2 swaps onto a bike with transponder 5555 for the second lap
+        engine.process_passing(&make_passing(5555, "D0000C02", 24_000_000));
+        engine.bind_transponder("moto-1", "rider-2", 5555);
+        engine.process_passing(&make_passing(1001, "D0000C03", 27_000_000));
+        engine.process_passing(&PassingMessage {
+            passing_number: 2,
+            ..make_passing(5555, "D0000C03", 30_000_000)
+        });
+
+        engine.apply_correction(
+            "moto-1",
+            correction(
+                "c-1",
+                TimingChange::InsertPassing {
+                    rider_id: "rider-1".into(),
+                    loop_id: "loop-corner1".into(),
+                    timestamp_us: 12_000_000,
+                },
+            ),
+        );
+
+        let rider = engine.motos[0].rider(5555).unwrap();
+        assert_eq!(rider.rider_id, "rider-2");
+        assert_eq!(rider.laps_completed(), 2);
+        assert_eq!(rider.splits[&(1, "loop-corner1".to_string())], 3_000_000);
+        assert_eq!(rider.splits[&(1, "loop-finish".to_string())], 10_000_000);
+        assert_eq!(rider.splits[&(2, "loop-corner1".to_string())], 14_000_000);
+        assert_eq!(rider.splits[&(2, "loop-finish".to_string())], 20_000_000);
+        assert!(rider.finished);
+        assert!(engine.motos[0].rider(1002).is_none());
+    }
+
+    #[test]
+    fn test_practice_session() {
+        let mut engine = RaceState::new();
+        engine.set_track(test_track());
+        engine.start_practice(
+            "practice-1".into(),
+            vec![KnownRider {
+                rider_id: "rider-1".into(),
+                first_name: "Alice".into(),
+                last_name: "Smith".into(),
+                plate_number: "42".into(),
+                transponder_id: 1001,
+                transponder_string: None,
+            }],
+        );
+        assert_eq!(engine.phase().name(), "practice");
+
+        // Staging is refused while practice runs
+        engine.stage(
+            "moto-1".into(),
+            "Novice".into(),
+            "moto1".into(),
+            1,
+            StartMode::GateDrop,
+            test_riders(),
+        );
+        assert!(matches!(engine.phase(), RacePhase::Practice { .. }));
+
+        // A known rider and a transponder nobody registered
+        for transponder_id in [1001, 4242] {
+            assert!(
+                engine
+                    .process_passing(&make_passing(transponder_id, "D0000C01", 1_000_000))
+                    .is_empty()
+            );
+            engine.process_passing(&make_passing(transponder_id, "D0000C02", 5_000_000));
+            let events =
+                engine.process_passing(&make_passing(transponder_id, "D0000C03", 33_000_000));
+            let [RaceEvent::PracticeUpdate { lap, .. }] = events.as_slice() else {
+                panic!("expected a practice update, got {events:?}");
+            };
+            assert_eq!(lap.lap_time_us, 32_000_000);
+            assert_eq!(lap.sectors.len(), 2);
+        }
+
+        let RaceEvent::StateSnapshot { practice, .. } = engine.state_snapshot() else {
+            panic!("expected snapshot");
+        };
+        let practice = practice.unwrap();
+        assert_eq!(practice.best_laps.len(), 2);
+        assert_eq!(practice.last_laps[0].transponder_id, 4242);
+
+        let Some(RaceEvent::PracticeEnded { leaderboard }) = engine.stop_practice() else {
+            panic!("expected practice ended");
+        };
+        assert_eq!(leaderboard.session_id, "practice-1");
+        assert!(matches!(engine.phase(), RacePhase::Idle));
+    }
+
+    #[test]
+    fn test_reset_to_idle() {
+        let mut engine = RaceState::new();
+        engine.set_track(test_track());
+        engine.stage(
+            "moto-1".into(),
+            "Novice".into(),
+            "moto1".into(),
+            1,
+            StartMode::GateDrop,
+            test_riders(),
+        );
+
+        engine.reset();
+        assert!(matches!(engine.phase(), RacePhase::Idle));
+    }
+
+    #[test]
+    fn test_state_snapshot() {
+        let mut engine = RaceState::new();
+        engine.set_track(test_track());
+        engine.stage(
+            "moto-1".into(),
+            "Novice".into(),
+            "moto1".into(),
+            1,
+            StartMode::GateDrop,
+            test_riders(),
+        );
+
+        let snapshot = engine.state_snapshot();
+        if let RaceEvent::StateSnapshot {
+            phase,
+            moto_id,
+            riders,
+            total_riders,
+            ..
+        } = snapshot
+        {
+            assert_eq!(phase, "staged");
+            assert_eq!(moto_id, Some("moto-1".to_string()));
+            assert_eq!(riders.len(), 3);
+            assert_eq!(total_riders, 3);
+        } else {
+            panic!("Expected StateSnapshot");
+        }
+    }
+
+    #[test]
+    fn test_deserialized_state_finishes_race_like_original() {
+        let mut original = racing_engine(2, 0);
+        let passings = [
+            (1001, "D0000C01", 10_500_000),
+            (1002, "D0000C01", 10_600_000),
+            (1001, "D0000C03", 20_000_000),
+            (1002, "D0000C03", 20_400_000),
+            (1003, "D0000C03", 21_000_000),
+            (1001, "D0000C03", 30_000_000),
+            (1002, "D0000C03", 29_900_000),
+            (1003, "D0000C03", 31_500_000),
+        ];
+        let passing = |i: usize| {
+            let (transponder, decoder, rtc) = passings[i];
+            PassingMessage {
+                passing_number: i as u32 + 2,
+                ..make_passing(transponder, decoder, rtc)
+            }
+        };
+        for i in 0..4 {
+            original.process_passing(&passing(i));
+        }
+
+        let json = serde_json::to_string(&original).expect("serialize state");
+        let mut restored: RaceState = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.phase().name(), "racing");
+
+        let finished = |engine: &mut RaceState| {
+            (4..passings.len())
+                .flat_map(|i| engine.process_passing(&passing(i)))
+                .find(|event| matches!(event, RaceEvent::RaceFinished { .. }))
+                .map(|event| serde_json::to_value(event).unwrap())
+        };
+        let expected = finished(&mut original);
+        assert!(expected.is_some());
+        assert_eq!(finished(&mut restored), expected);
+    }
+
+    fn second_moto_riders() -> Vec<StagedRider> {
+        vec![
+            StagedRider {
+                rider_id: "rider-4".into(),
+                first_name: "Dana".into(),
+                last_name: "White".into(),
+                plate_number: "3".into(),
+                transponder_id: 2001,
+                lane: 1,
+            },
+            StagedRider {
+                rider_id: "rider-5".into(),
+                first_name: "Eve".into(),
+                last_name: "Green".into(),
+                plate_number: "18".into(),
+                transponder_id: 2002,
+                lane: 2,
+            },
+        ]
+    }
+
+    fn stage_second_moto(engine: &mut RaceState, riders: Vec<StagedRider>) -> Vec<RaceEvent> {
+        engine.stage(
+            "moto-2".into(),
+            "Expert".into(),
+            "moto1".into(),
+            1,
+            StartMode::GateDrop,
+            riders,
+        )
+    }
+
+    fn finished_riders(events: &[RaceEvent]) -> Vec<(&str, &str, u32, u64)> {
+        events
+            .iter()
+            .filter_map(|event| match event {
+                RaceEvent::RiderFinished {
+                    moto_id,
+                    rider_id,
+                    finish_position,
+                    elapsed_us,
+                    ..
+                } => Some((
+                    moto_id.as_str(),
+                    rider_id.as_str(),
+                    *finish_position,
+                    *elapsed_us,
+                )),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn finished_motos(events: &[RaceEvent]) -> Vec<&str> {
+        events
+            .iter()
+            .filter_map(|event| match event {
+                RaceEvent::RaceFinished { moto_id, .. } => Some(moto_id.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_next_moto_races_while_previous_finishes() {
+        let mut engine = racing_engine(1, 0);
+        ride_laps(&mut engine, 1001, 10_000_000, 1);
+
+        let events = stage_second_moto(&mut engine, second_moto_riders());
+        assert!(matches!(events[..], [RaceEvent::RaceStaged { .. }]));
+        assert!(matches!(engine.phase(), RacePhase::Staged { moto_id, .. } if moto_id == "moto-2"));
+
+        // The gate beacon drops the gate of the staged moto only
+        let events = engine.process_passing(&make_passing(9992, "D0000C01", 25_000_000));
+        assert!(matches!(
+            events.as_slice(),
+            [RaceEvent::GateDrop { moto_id, timestamp_us: 25_000_000 }] if moto_id == "moto-2"
+        ));
+        assert!(matches!(
+            engine.moto_phase("moto-1"),
+            Some(RacePhase::Racing {
+                gate_drop_time_us: 10_000_000,
+                ..
+            })
+        ));
+
+        // Riders are timed against their own moto's gate drop
+        let events = engine.process_passing(&make_passing(2001, "D0000C03", 33_000_000));
+        assert_eq!(
+            finished_riders(&events),
+            vec![("moto-2", "rider-4", 1, 8_000_000)]
+        );
+        let events = engine.process_passing(&make_passing(1002, "D0000C03", 34_000_000));
+        assert_eq!(
+            finished_riders(&events),
+            vec![("moto-1", "rider-2", 2, 24_000_000)]
+        );
+
+        // Each moto finishes on its own
+        let events = engine.process_passing(&make_passing(1003, "D0000C03", 35_000_000));
+        assert_eq!(finished_motos(&events), vec!["moto-1"]);
+        assert!(matches!(
+            engine.moto_phase("moto-1"),
+            Some(RacePhase::Finished { .. })
+        ));
+        assert!(matches!(engine.phase(), RacePhase::Racing { moto_id, .. } if moto_id == "moto-2"));
+        assert!(!engine.is_idle());
+
+        let events = engine.process_passing(&make_passing(2002, "D0000C03", 36_000_000));
+        assert_eq!(finished_motos(&events), vec!["moto-2"]);
+        assert!(engine.is_idle());
+
+        // Finished motos stay on the track when the next one is staged
+        let events = engine.stage(
+            "moto-3".into(),
+            "Novice".into(),
+            "moto2".into(),
+            1,
+            StartMode::GateDrop,
+            test_riders(),
+        );
+        assert!(matches!(events[..], [RaceEvent::RaceStaged { .. }]));
+        assert!(matches!(
+            engine.moto_phase("moto-1"),
+            Some(RacePhase::Finished { .. })
+        ));
+        assert!(matches!(
+            engine.moto_phase("moto-2"),
+            Some(RacePhase::Finished { .. })
+        ));
+    }
+
+    #[test]
+    fn test_finished_moto_is_corrected_after_next_is_staged() {
+        let mut engine = racing_engine(1, 0);
+        for (transponder_id, finish_us) in [(1001, 20_000_000), (1002, 21_000_000)] {
+            engine.process_passing(&make_passing(transponder_id, "D0000C03", finish_us));
+        }
+        engine.force_finish(Some("moto-1"));
+        stage_second_moto(&mut engine, second_moto_riders());
+
+        let passings = engine.recorded_passings(Some("moto-1"));
+        assert_eq!(passings.len(), 2);
+        let void = TimingChange::VoidPassing {
+            passing_id: passings[0].passing_id.clone(),
+        };
+        assert!(engine.validate_correction("moto-1", &void).is_ok());
+        let events = engine.apply_correction("moto-1", correction("c-1", void));
+        assert!(matches!(
+            &events[0],
+            RaceEvent::TimingCorrected { moto_id, .. } if moto_id == "moto-1"
+        ));
+        assert!(
+            engine
+                .recorded_passings(Some("moto-1"))
+                .iter()
+                .any(|p| p.voided)
+        );
+        assert!(matches!(engine.phase(), RacePhase::Staged { moto_id, .. } if moto_id == "moto-2"));
+    }
+
+    #[test]
+    fn test_oldest_finished_motos_leave_the_track() {
+        let mut engine = racing_engine(1, 0);
+        engine.force_finish(Some("moto-1"));
+        for moto in 2..=FINISHED_MOTOS_KEPT + 2 {
+            engine.stage(
+                format!("moto-{moto}"),
+                "Novice".into(),
+                "moto1".into(),
+                1,
+                StartMode::GateDrop,
+                test_riders(),
+            );
+            engine.process_passing(&make_passing(9992, "D0000C01", moto as u64 * 100_000_000));
+            engine.force_finish(None);
+        }
+
+        // Staging the last moto left the newest finished motos on the track
+        assert!(engine.moto_phase("moto-1").is_none());
+        assert!(engine.moto_phase("moto-2").is_some());
+        assert_eq!(engine.motos.len(), FINISHED_MOTOS_KEPT + 1);
+
+        // Restaging a finished moto replaces it
+        engine.stage(
+            "moto-3".into(),
+            "Novice".into(),
+            "moto1".into(),
+            1,
+            StartMode::GateDrop,
+            test_riders(),
+        );
+        assert!(matches!(
+            engine.moto_phase("moto-3"),
+            Some(RacePhase::Staged { .. })
+        ));
+        assert_eq!(
+            engine
+                .motos
+                .iter()
+                .filter(|m| m.moto_id() == "moto-3")
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_shared_transponder_follows_gate_drop_order() {
+        let mut engine = racing_engine(1, 0);
+        let mut riders = second_moto_riders();
+        // rider-1 races both motos on the same transponder
+        riders[0] = test_riders().remove(0);
+        stage_second_moto(&mut engine, riders);
+        engine.process_passing(&make_passing(9992, "D0000C01", 20_000_000));
+
+        // Still racing moto-1, and a read from before moto-2's gate drop
+        // can only belong to moto-1
+        let events = engine.process_passing(&make_passing(1001, "D0000C03", 19_000_000));
+        assert_eq!(
+            finished_riders(&events),
+            vec![("moto-1", "rider-1", 1, 9_000_000)]
+        );
+
+        // Finished in moto-1, so the next lap is moto-2's
+        let events = engine.process_passing(&make_passing(1001, "D0000C03", 30_000_000));
+        assert_eq!(
+            finished_riders(&events),
+            vec![("moto-2", "rider-1", 1, 10_000_000)]
+        );
+    }
+
+    #[test]
+    fn test_unknown_transponder_goes_to_latest_moto() {
+        let mut engine = racing_engine(1, 0);
+        stage_second_moto(&mut engine, second_moto_riders());
+        engine.process_passing(&make_passing(9992, "D0000C01", 20_000_000));
+
+        let events = engine.process_passing(&make_passing(5555, "D0000C02", 24_000_000));
+        assert!(matches!(
+            events.as_slice(),
+            [RaceEvent::UnknownTransponder { moto_id, .. }] if moto_id == "moto-2"
+        ));
+
+        // Bound in moto-2, the transponder's reads count there
+        engine.bind_transponder("moto-2", "rider-5", 5555);
+        let passings = engine.recorded_passings(Some("moto-2"));
+        assert_eq!(passings.len(), 1);
+        assert_eq!(passings[0].rider_id.as_deref(), Some("rider-5"));
+        assert!(engine.recorded_passings(Some("moto-1")).is_empty());
+    }
+
+    #[test]
+    fn test_stage_refused_while_gate_is_occupied() {
+        let mut engine = RaceState::new();
+        engine.set_track(test_track());
+        engine.stage(
+            "moto-1".into(),
+            "Novice".into(),
+            "moto1".into(),
+            1,
+            StartMode::GateDrop,
+            test_riders(),
+        );
+
+        // The gate has not dropped for moto-1 yet
+        assert!(stage_second_moto(&mut engine, second_moto_riders()).is_empty());
+
+        // A time trial needs the track to itself
+        engine.process_passing(&make_passing(9992, "D0000C01", 10_000_000));
+        let events = engine.stage(
+            "moto-2".into(),
+            "Expert".into(),
+            "time_trial".into(),
+            1,
+            StartMode::TimeTrial,
+            second_moto_riders(),
+        );
+        assert!(events.is_empty());
+        assert!(matches!(engine.phase(), RacePhase::Racing { moto_id, .. } if moto_id == "moto-1"));
+    }
+
+    #[test]
+    fn test_force_finish_picks_moto() {
+        let mut engine = racing_engine(1, 0);
+        stage_second_moto(&mut engine, second_moto_riders());
+        engine.process_passing(&make_passing(9992, "D0000C01", 20_000_000));
+
+        let Some(RaceEvent::RaceFinished { moto_id, .. }) = engine.force_finish(Some("moto-2"))
+        else {
+            panic!("expected moto-2 to finish");
+        };
+        assert_eq!(moto_id, "moto-2");
+        assert!(matches!(
+            engine.moto_phase("moto-1"),
+            Some(RacePhase::Racing { .. })
+        ));
+
+        // By default the oldest moto on course finishes
+        let Some(RaceEvent::RaceFinished { moto_id, .. }) = engine.force_finish(None) else {
+            panic!("expected moto-1 to finish");
+        };
+        assert_eq!(moto_id, "moto-1");
+        assert!(engine.force_finish(None).is_none());
+    }
+
+    fn gps_passing(
+        transponder_id: u32,
+        decoder_id: &str,
+        rtc_time_us: u64,
+        utc_time_us: u64,
+    ) -> PassingMessage {
+        PassingMessage {
+            utc_time_us: Some(utc_time_us),
+            ..make_passing(transponder_id, decoder_id, rtc_time_us)
+        }
+    }
+
+    #[test]
+    fn test_elapsed_time_uses_aligned_decoder_clocks() {
+        let mut engine = RaceState::new();
+        engine.set_track(test_track());
+        engine.stage(
+            "moto-1".into(),
+            "Novice".into(),
+            "moto1".into(),
+            1,
+            StartMode::GateDrop,
+            test_riders(),
+        );
+
+        // The start decoder agrees with GPS time, the finish decoder's RTC
+        // runs 2 s behind it
+        engine.receive_passing(gps_passing(9992, "D0000C01", 10_000_000, 10_000_000), None);
+        engine.receive_passing(make_passing(1001, "D0000C02", 14_000_000), None);
+        let events =
+            engine.receive_passing(gps_passing(1001, "D0000C03", 28_000_000, 30_000_000), None);
+        assert_eq!(
+            finished_riders(&events),
+            vec![("moto-1", "rider-1", 1, 20_000_000)]
+        );
+        assert_eq!(
+            engine.recorded_passings(None).last().unwrap().timestamp_us,
+            30_000_000
+        );
+    }
+
+    #[test]
+    fn test_drifting_decoder_is_flagged() {
+        let mut engine = RaceState::new();
+        engine.set_track(test_track());
+
+        // The finish decoder's clock loses 250 µs per second
+        let mut events = Vec::new();
+        for minute in 0..=10u64 {
+            let rtc_time_us = minute * 60_000_000;
+            events.extend(engine.receive_passing(
+                gps_passing(
+                    5555,
+                    "D0000C03",
+                    rtc_time_us,
+                    rtc_time_us + rtc_time_us / 4_000,
+                ),
+                None,
+            ));
+        }
+        let [
+            RaceEvent::ClockDrift {
+                decoder_id,
+                drift_ppm,
+                ..
+            },
+        ] = events.as_slice()
+        else {
+            panic!("expected one drift alert, got {events:?}");
+        };
+        assert_eq!(decoder_id, "D0000C03");
+        assert!((drift_ppm - 250.0).abs() < 0.01);
+    }
+}
+
+#[cfg(test)]
+mod proptests {
+    use super::*;
+    use crate::domain::race_event::{FinishResult, LoopConfig, RiderPosition};
+    use proptest::prelude::*;
+
+    const GATE_BEACON: u32 = 9992;
+    const GATE_DROP_US: u64 = 10_000_000;
+    /// Staged riders' transponders; anything else is a stranger
+    const TRANSPONDERS: [u32; 4] = [1001, 1002, 1003, 1004];
+    /// Start, corner and finish loops, then a decoder with no loop
+    const DECODERS: [&str; 4] = ["D0000C01", "D0000C02", "D0000C03", "D0000C09"];
+
+    fn track(debounce_ms: u32) -> TrackConfig {
+        let loop_config = |position: usize, is_start, is_finish| LoopConfig {
+            loop_id: format!("loop-{position}"),
+            name: format!("Loop {position}"),
+            decoder_id: DECODERS[position].into(),
+            position: position as u32,
+            is_start,
+            is_finish,
+            debounce_ms,
+        };
+        TrackConfig {
+            track_id: "track-1".into(),
+            name: "Test BMX Track".into(),
+            gate_beacon_id: GATE_BEACON,
+            loops: vec![
+                loop_config(0, true, false),
+                loop_config(1, false, false),
+                loop_config(2, false, true),
+            ],
+        }
+    }
+
+    fn riders() -> Vec<StagedRider> {
+        (1..)
+            .zip(TRANSPONDERS)
+            .map(|(lane, transponder_id)| StagedRider {
+                rider_id: format!("rider-{lane}"),
+                first_name: "Rider".into(),
+                last_name: lane.to_string(),
+                plate_number: lane.to_string(),
+                transponder_id,
+                lane,
+            })
+            .collect()
+    }
+
+    /// What happens on track between two commands.
+    #[derive(Debug, Clone)]
+    enum Step {
+        Passing {
+            transponder_id: u32,
+            decoder: usize,
+            /// Decoder clock advance since the previous step
+            after_us: u64,
+            /// How late the read arrives relative to the clock
+            late_us: u64,
+            strength: u16,
+        },
+        CheckTimeouts {
+            after_us: u64,
+        },
+        InsertPassing {
+            rider: usize,
+            decoder: usize,
+            after_us: u64,
+        },
+        HandTimedFinish {
+            rider: usize,
+            elapsed_us: u64,
+        },
+        ForceFinish,
+    }
+
+    fn step() -> impl Strategy<Value = Step> {
+        let transponder = prop_oneof![
+            9 => prop::sample::select(TRANSPONDERS.to_vec()),
+            1 => Just(4242u32),
+        ];
+        prop_oneof![
+            20 => (transponder, 0..DECODERS.len(), 0..4_000_000u64, 0..600_000u64, 50..200u16)
+                .prop_map(|(transponder_id, decoder, after_us, late_us, strength)| {
+                    Step::Passing { transponder_id, decoder, after_us, late_us, strength }
+                }),
+            2 => (0..30_000_000u64).prop_map(|after_us| Step::CheckTimeouts { after_us }),
+            1 => (0..TRANSPONDERS.len(), 0..3usize, 0..4_000_000u64)
+                .prop_map(|(rider, decoder, after_us)| Step::InsertPassing { rider, decoder, after_us }),
+            1 => (0..TRANSPONDERS.len(), 1..60_000_000u64)
+                .prop_map(|(rider, elapsed_us)| Step::HandTimedFinish { rider, elapsed_us }),
+            1 => Just(Step::ForceFinish),
+        ]
+    }
+
+    /// A staged moto followed by whatever `steps` throw at it.
+    fn commands(
+        laps: u32,
+        time_trial: bool,
+        debounce_ms: u32,
+        rules: RaceRules,
+        steps: &[Step],
+    ) -> Vec<RaceCommand> {
+        let mut commands = vec![
+            RaceCommand::SetTrack(track(debounce_ms)),
+            RaceCommand::SetRaceRules(rules),
+            RaceCommand::Stage {
+                moto_id: "moto-1".into(),
+                class_name: "Novice".into(),
+                round_type: "moto1".into(),
+                laps,
+                start_mode: if time_trial {
+                    StartMode::TimeTrial
+                } else {
+                    StartMode::GateDrop
+                },
+                riders: riders(),
+            },
+        ];
+        let passing = |passing_number, transponder_id, decoder: usize, rtc_time_us, strength| {
+            RaceCommand::Passing {
+                passing: PassingMessage {
+                    passing_number,
+                    transponder_id,
+                    rtc_time_us,
+                    utc_time_us: None,
+                    strength: Some(strength),
+                    hits: Some(strength / 4),
+                    transponder_string: None,
+                    flags: 0,
+                    decoder_id: Some(DECODERS[decoder].into()),
+                    extra_fields: Vec::new(),
+                },
+                captured_at_us: None,
+            }
+        };
+        if !time_trial {
+            commands.push(passing(1, GATE_BEACON, 0, GATE_DROP_US, 100));
+        }
+
+        let mut now_us = GATE_DROP_US;
+        for (index, step) in steps.iter().enumerate() {
+            let passing_number = index as u32 + 2;
+            let correction = |change| RaceCommand::CorrectTiming {
+                moto_id: "moto-1".into(),
+                correction: TimingCorrection {
+                    correction_id: format!("correction-{index}"),
+                    operator: "official".into(),
+                    reason: "test".into(),
+                    change,
+                },
+            };
+            commands.push(match *step {
+                Step::Passing {
+                    transponder_id,
+                    decoder,
+                    after_us,
+                    late_us,
+                    strength,
+                } => {
+                    now_us += after_us;
+                    let rtc_time_us = now_us.saturating_sub(late_us).max(GATE_DROP_US);
+                    passing(
+                        passing_number,
+                        transponder_id,
+                        decoder,
+                        rtc_time_us,
+                        strength,
+                    )
+                }
+                Step::CheckTimeouts { after_us } => {
+                    now_us += after_us;
+                    RaceCommand::CheckTimeouts { now_us }
+                }
+                Step::InsertPassing {
+                    rider,
+                    decoder,
+                    after_us,
+                } => {
+                    now_us += after_us;
+                    correction(TimingChange::InsertPassing {
+                        rider_id: format!("rider-{}", rider + 1),
+                        loop_id: format!("loop-{decoder}"),
+                        timestamp_us: now_us,
+                    })
+                }
+                Step::HandTimedFinish { rider, elapsed_us } => {
+                    correction(TimingChange::HandTimedFinish {
+                        rider_id: format!("rider-{}", rider + 1),
+                        elapsed_us,
+                    })
+                }
+                Step::ForceFinish => RaceCommand::ForceFinish { moto_id: None },
+            });
+        }
+        commands
+    }
+
+    fn race() -> impl Strategy<Value = Vec<RaceCommand>> {
+        (
+            1..=3u32,
+            prop::bool::weighted(0.25),
+            prop::sample::select(vec![0u32, 500]),
+            prop::option::of(5..40u32),
+            prop::option::of(5..40u32),
+            prop::collection::vec(step(), 0..60),
+        )
+            .prop_map(
+                |(laps, time_trial, debounce_ms, dns_timeout_secs, dnf_timeout_secs, steps)| {
+                    let rules = RaceRules {
+                        dns_timeout_secs,
+                        dnf_timeout_secs,
+                        max_race_secs: None,
+                    };
+                    commands(laps, time_trial, debounce_ms, rules, &steps)
+                },
+            )
+    }
+
+    /// Positions rank every rider in the moto exactly once, from 1 down.
+    fn check_positions(
+        state: &RaceState,
+        positions: &[RiderPosition],
+    ) -> Result<(), TestCaseError> {
+        let mut rider_ids: Vec<&str> = positions.iter().map(|p| p.rider_id.as_str()).collect();
+        rider_ids.sort();
+        let mut in_moto: Vec<&str> = state
+            .motos
+            .iter()
+            .flat_map(Moto::riders)
+            .map(|r| r.rider_id.as_str())
+            .collect();
+        in_moto.sort();
+        prop_assert_eq!(rider_ids, in_moto);
+        let ranks: Vec<u32> = positions.iter().map(|p| p.position).collect();
+        prop_assert_eq!(ranks, (1..=positions.len() as u32).collect::<Vec<_>>());
+        Ok(())
+    }
+
+    /// Finishers hold positions 1..=n between them; nobody else holds one.
+    fn check_finish_positions(state: &RaceState) -> Result<(), TestCaseError> {
+        let mut finish_positions = Vec::new();
+        for rider in state.motos.iter().flat_map(Moto::riders) {
+            prop_assert_eq!(rider.finished, rider.finish_position.is_some());
+            finish_positions.extend(rider.finish_position);
+        }
+        finish_positions.sort();
+        let expected: Vec<u32> = (1..=finish_positions.len() as u32).collect();
+        prop_assert_eq!(finish_positions, expected);
+        Ok(())
+    }
+
+    fn check_results(results: &[FinishResult]) -> Result<(), TestCaseError> {
+        let mut positions: Vec<u32> = results
+            .iter()
+            .filter(|r| !r.dnf && !r.dns)
+            .map(|r| r.position)
+            .collect();
+        positions.sort();
+        prop_assert_eq!(
+            positions.clone(),
+            (1..=positions.len() as u32).collect::<Vec<_>>()
+        );
+        Ok(())
+    }
+
+    proptest! {
+        #[test]
+        fn positions_are_a_permutation_and_finish_positions_contiguous(commands in race()) {
+            let mut state = RaceState::new();
+            for command in commands {
+                let (next, events) = apply(state, command);
+                state = next;
+
+                check_finish_positions(&state)?;
+                for event in &events {
+                    match event {
+                        RaceEvent::PositionsUpdate { positions, .. } => {
+                            check_positions(&state, positions)?
+                        }
+                        RaceEvent::RaceFinished { results, .. } => check_results(results)?,
+                        _ => {}
+                    }
+                }
+                let RaceEvent::StateSnapshot { positions, .. } = state.state_snapshot() else {
+                    unreachable!("state_snapshot builds a StateSnapshot");
+                };
+                check_positions(&state, &positions)?;
+            }
+        }
+
+        #[test]
+        fn same_commands_give_same_events(commands in race()) {
+            let run = |commands: Vec<RaceCommand>| {
+                let mut state = RaceState::new();
+                let mut events = Vec::new();
+                for command in commands {
+                    let (next, produced) = apply(state, command);
+                    state = next;
+                    events.extend(produced);
+                }
+                serde_json::to_value(events).unwrap()
+            };
+            prop_assert_eq!(run(commands.clone()), run(commands));
         }
     }
 }
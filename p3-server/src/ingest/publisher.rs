@@ -38,11 +38,16 @@ pub struct PublishOutcome {
 
 impl IngestPublisher {
     pub async fn connect_and_provision(nats_url: &str) -> anyhow::Result<Self> {
-        let jetstream = connect_jetstream_and_provision_raw_race_events_and_race_control(nats_url).await?;
+        let jetstream =
+            connect_jetstream_and_provision_raw_race_events_and_race_control(nats_url).await?;
 
         Ok(Self { jetstream })
     }
 
+    pub fn jetstream(&self) -> &jetstream::Context {
+        &self.jetstream
+    }
+
     pub async fn publish_event(&self, event: &TrackIngestEvent) -> anyhow::Result<PublishOutcome> {
         let subject = build_raw_ingest_subject(&event.track_id);
         let msg_id = build_idempotency_key(&event.track_id, &event.event_id_context);
@@ -5,13 +5,16 @@ use p3_server::api::state::AppState;
 use p3_server::db;
 use p3_server::decoder::DecoderConnection;
 use p3_server::domain::race_event::RaceEvent;
-use p3_server::engine::RaceEngine;
+use p3_server::engine::{EngineRegistry, RULES_CHECK_INTERVAL, RaceCommand, TrackRaceEvent};
 use p3_server::ingest::publisher::IngestPublisher;
 use p3_server::workers::projection;
 use p3_server::workers::race;
+use p3_transport::DecoderEndpoint;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
 use tokio::net::TcpListener;
-use tokio::sync::{Mutex, broadcast, mpsc};
+use tokio::sync::{broadcast, mpsc};
 use tracing::{info, warn};
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum)]
@@ -29,6 +32,11 @@ struct Args {
     #[arg(long, value_enum, default_value_t = RuntimeRole::Api)]
     role: RuntimeRole,
 
+    /// Decoder URI: tcp://host:port, serial:///dev/ttyUSB0?baud=9600 or
+    /// udp://0.0.0.0:5403 (defaults to tcp://<decoder-host>:<decoder-port>)
+    #[arg(long, conflicts_with_all = ["decoder_host", "decoder_port"])]
+    decoder: Option<DecoderEndpoint>,
+
     /// Decoder hostname
     #[arg(long, default_value = "localhost")]
     decoder_host: String,
@@ -52,6 +60,11 @@ struct Args {
     /// Run without connecting to a decoder (UI-only mode)
     #[arg(long)]
     no_decoder: bool,
+
+    /// Track the local decoder times (defaults to the track whose timing
+    /// loops name the decoder)
+    #[arg(long)]
+    track_id: Option<String>,
 }
 
 #[tokio::main]
@@ -80,10 +93,10 @@ async fn main() -> anyhow::Result<()> {
 async fn run_api_role(args: &Args, pool: sqlx::SqlitePool) -> anyhow::Result<()> {
     // Broadcast channels
     let (broadcast_tx, _) = broadcast::channel::<Arc<Message>>(256);
-    let (race_event_tx, _) = broadcast::channel::<Arc<RaceEvent>>(256);
+    let (race_event_tx, _) = broadcast::channel::<Arc<TrackRaceEvent>>(256);
 
-    // Race engine
-    let engine = Arc::new(Mutex::new(RaceEngine::new(race_event_tx.clone())));
+    // Race engines, one per track
+    let engines = Arc::new(EngineRegistry::new(race_event_tx.clone()));
 
     // NATS/JetStream ingest publisher
     let ingest_publisher = Arc::new(IngestPublisher::connect_and_provision(&args.nats_url).await?);
@@ -92,25 +105,21 @@ async fn run_api_role(args: &Args, pool: sqlx::SqlitePool) -> anyhow::Result<()>
     let state = AppState::new(
         broadcast_tx.clone(),
         race_event_tx.clone(),
-        engine.clone(),
+        engines.clone(),
         pool.clone(),
         Some(ingest_publisher),
         args.nats_url.clone(),
     );
 
-    // Task: persist race results when a race finishes
+    // Task: persist race results when a race finishes, and practice laps
     {
         let mut results_rx = race_event_tx.subscribe();
         let results_pool = pool.clone();
         tokio::spawn(async move {
             loop {
                 match results_rx.recv().await {
-                    Ok(event) => {
-                        if let RaceEvent::RaceFinished {
-                            ref moto_id,
-                            ref results,
-                        } = *event
-                        {
+                    Ok(event) => match &event.event {
+                        RaceEvent::RaceFinished { moto_id, results } => {
                             info!(moto_id = %moto_id, results = results.len(), "Persisting race results");
                             if let Err(e) = p3_server::db::queries::results::persist_results(
                                 &results_pool,
@@ -122,7 +131,30 @@ async fn run_api_role(args: &Args, pool: sqlx::SqlitePool) -> anyhow::Result<()>
                                 warn!(error = %e, "Failed to persist race results");
                             }
                         }
-                    }
+                        RaceEvent::PracticeUpdate { lap, leaderboard } => {
+                            if let Err(e) = p3_server::db::queries::practice::insert_lap(
+                                &results_pool,
+                                &leaderboard.session_id,
+                                lap,
+                            )
+                            .await
+                            {
+                                warn!(error = %e, "Failed to persist practice lap");
+                            }
+                        }
+                        RaceEvent::PracticeEnded { leaderboard } => {
+                            info!(session_id = %leaderboard.session_id, "Practice session ended");
+                            if let Err(e) = p3_server::db::queries::practice::end_session(
+                                &results_pool,
+                                &leaderboard.session_id,
+                            )
+                            .await
+                            {
+                                warn!(error = %e, "Failed to persist practice session end");
+                            }
+                        }
+                        _ => {}
+                    },
                     Err(broadcast::error::RecvError::Lagged(n)) => {
                         warn!(skipped = n, "Result persistence task lagged");
                     }
@@ -134,25 +166,49 @@ async fn run_api_role(args: &Args, pool: sqlx::SqlitePool) -> anyhow::Result<()>
         });
     }
 
+    // Task: apply DNS/DNF rules while no passings arrive to move the clock
+    {
+        let rules_engines = engines.clone();
+        tokio::spawn(async move {
+            let mut tick = tokio::time::interval(RULES_CHECK_INTERVAL);
+            loop {
+                tick.tick().await;
+                for (_, engine) in rules_engines.all().await {
+                    engine.lock().await.check_timeouts();
+                }
+            }
+        });
+    }
+
     // Spawn decoder connection unless --no-decoder
     if !args.no_decoder {
         let (msg_tx, mut msg_rx) = mpsc::channel::<Message>(256);
-        let decoder = DecoderConnection::new(args.decoder_host.clone(), args.decoder_port);
+        let endpoint = args
+            .decoder
+            .clone()
+            .unwrap_or_else(|| DecoderEndpoint::tcp(args.decoder_host.clone(), args.decoder_port));
+        let decoder = DecoderConnection::new(endpoint.clone());
 
-        // Task: read from decoder TCP → mpsc channel
+        // Task: read from decoder → mpsc channel
         tokio::spawn(async move {
             decoder.run(msg_tx).await;
         });
 
-        // Task: relay from mpsc → broadcast + feed race engine
+        // Task: relay from mpsc → broadcast + feed the decoder's track engine
         let relay_tx = broadcast_tx.clone();
-        let relay_engine = engine.clone();
+        let relay_engines = engines.clone();
+        let mut router = DecoderTrackRouter::new(args.track_id.clone(), pool.clone());
         tokio::spawn(async move {
             while let Some(message) = msg_rx.recv().await {
-                // Feed passing messages to the race engine
-                if let Message::Passing(ref passing) = message {
-                    let mut eng = relay_engine.lock().await;
-                    eng.process_passing(passing);
+                // Feed passing messages to the race engine of the decoder's track
+                if let Message::Passing(ref passing) = message
+                    && let Some(track_id) = router.track_for(passing.decoder_id.as_deref()).await
+                {
+                    let engine = relay_engines.engine(&track_id).await;
+                    engine.lock().await.apply(RaceCommand::Passing {
+                        passing: passing.clone(),
+                        captured_at_us: Some(now_unix_micros()),
+                    });
                 }
 
                 // Broadcast raw P3 message to all WebSocket clients
@@ -163,11 +219,7 @@ async fn run_api_role(args: &Args, pool: sqlx::SqlitePool) -> anyhow::Result<()>
             warn!("Decoder message relay ended");
         });
 
-        info!(
-            host = %args.decoder_host,
-            port = %args.decoder_port,
-            "Decoder connection enabled"
-        );
+        info!(endpoint = %endpoint, "Decoder connection enabled");
     } else {
         info!("Running in no-decoder mode (UI only)");
     }
@@ -182,3 +234,60 @@ async fn run_api_role(args: &Args, pool: sqlx::SqlitePool) -> anyhow::Result<()>
 
     Ok(())
 }
+
+/// Routes local decoder passings to a track: the configured `--track-id`,
+/// else the track whose timing loops name the decoder.
+struct DecoderTrackRouter {
+    configured: Option<String>,
+    pool: sqlx::SqlitePool,
+    by_decoder: HashMap<String, String>,
+    warned: HashSet<Option<String>>,
+}
+
+impl DecoderTrackRouter {
+    fn new(configured: Option<String>, pool: sqlx::SqlitePool) -> Self {
+        Self {
+            configured,
+            pool,
+            by_decoder: HashMap::new(),
+            warned: HashSet::new(),
+        }
+    }
+
+    async fn track_for(&mut self, decoder_id: Option<&str>) -> Option<String> {
+        if let Some(track_id) = &self.configured {
+            return Some(track_id.clone());
+        }
+        if let Some(decoder_id) = decoder_id {
+            if let Some(track_id) = self.by_decoder.get(decoder_id) {
+                return Some(track_id.clone());
+            }
+            match db::queries::tracks::track_for_decoder(&self.pool, decoder_id).await {
+                Ok(Some(track_id)) => {
+                    self.by_decoder
+                        .insert(decoder_id.to_string(), track_id.clone());
+                    return Some(track_id);
+                }
+                Ok(None) => {}
+                Err(error) => {
+                    warn!(error = %error, decoder_id = %decoder_id, "Failed to look up decoder track");
+                    return None;
+                }
+            }
+        }
+        // Unmapped decoders are retried on every passing so a loop added
+        // later is picked up, but only warned about once
+        if self.warned.insert(decoder_id.map(str::to_string)) {
+            warn!(decoder_id = ?decoder_id, "Dropping passings from decoder with no track, pass --track-id or map it to a timing loop");
+        }
+        None
+    }
+}
+
+/// Wall clock in µs; decoder RTC runs on UTC, so race rules compare the two
+fn now_unix_micros() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_micros().try_into().unwrap_or(u64::MAX))
+        .unwrap_or(0)
+}
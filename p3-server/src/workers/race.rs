@@ -1,25 +1,33 @@
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use anyhow::anyhow;
-use async_nats::error::Error as NatsError;
 use async_nats::HeaderMap;
+use async_nats::error::Error as NatsError;
 use async_nats::jetstream;
 use async_nats::jetstream::consumer::pull::MessagesErrorKind;
-use async_nats::jetstream::consumer::AckPolicy;
+use async_nats::jetstream::consumer::{AckPolicy, DeliverPolicy};
 use futures_util::StreamExt;
 use p3_contracts::{
-    FinishResultV1, LoopConfigV1, RACE_EVENTS_ENVELOPE_CONTRACT_VERSION_V1,
-    RaceControlIntentEnvelopeV1, RaceControlIntentV1, RaceEventEnvelopeV1, RaceEventPayloadV1,
-    RawIngestEnvelopeV1, RiderPositionV1, StagedRiderV1, TrackConfigV1, build_race_events_subject,
+    FinishResultV1, KnownRiderV1, LoopConfigV1, PracticeLapV1, PracticeLeaderboardV1,
+    PracticeStandingV1, RACE_CONTROL_INTENT_ENVELOPE_CONTRACT_VERSION_V1,
+    RACE_EVENTS_ENVELOPE_CONTRACT_VERSION_V1, RaceControlIntentEnvelopeV1, RaceControlIntentV1,
+    RaceEventEnvelopeV1, RaceEventPayloadV1, RaceRulesV1, RawIngestEnvelopeV1, RiderPositionV1,
+    SectorTimeV1, StagedRiderV1, StartModeV1, TimingChangeV1, TimingCorrectionV1, TrackConfigV1,
+    build_race_control_subject, build_race_events_subject, build_raw_ingest_subject,
 };
 use p3_parser::Message;
+use serde::{Deserialize, Serialize};
 use tokio::sync::{mpsc, oneshot};
 use tracing::{info, warn};
 use uuid::Uuid;
 
-use crate::domain::race_event::{FinishResult, LoopConfig, RaceEvent, RiderPosition, StagedRider, TrackConfig};
-use crate::engine::{RaceEngine, RacePhase};
+use crate::domain::race_event::{
+    FinishResult, KnownRider, LoopConfig, PracticeLap, PracticeLeaderboard, PracticeStanding,
+    RaceEvent, RaceRules, RiderPosition, StagedRider, StartMode, TimingChange, TimingCorrection,
+    TrackConfig,
+};
+use crate::engine::{RULES_CHECK_INTERVAL, RaceCommand, RaceState, TrackClock, apply};
 use crate::ingest::publisher::{
     RACE_CONTROL_STREAM_NAME, RACE_CONTROL_SUBJECT_PATTERN, RAW_INGEST_STREAM_NAME,
     RAW_INGEST_SUBJECT_PATTERN, connect_jetstream_and_provision_raw_race_events_and_race_control,
@@ -27,7 +35,11 @@ use crate::ingest::publisher::{
 
 const RACE_WORKER_RAW_CONSUMER: &str = "race_worker_raw_v1";
 const RACE_WORKER_CONTROL_CONSUMER: &str = "race_worker_control_v1";
+const RACE_WORKER_SNAPSHOT_BUCKET: &str = "race_worker_snapshots_v1";
+/// How often track actors save their engine state for recovery
+const SNAPSHOT_INTERVAL: Duration = Duration::from_secs(5);
 
+#[derive(Clone)]
 enum TrackActorPayload {
     Raw(RawIngestEnvelopeV1),
     Control(RaceControlIntentEnvelopeV1),
@@ -35,11 +47,14 @@ enum TrackActorPayload {
 
 struct TrackActorInput {
     payload: TrackActorPayload,
+    /// Sequence of the message in its stream
+    stream_sequence: u64,
     result_tx: oneshot::Sender<anyhow::Result<()>>,
 }
 
 pub async fn run_race_worker(nats_url: &str) -> anyhow::Result<()> {
-    let jetstream = connect_jetstream_and_provision_raw_race_events_and_race_control(nats_url).await?;
+    let jetstream =
+        connect_jetstream_and_provision_raw_race_events_and_race_control(nats_url).await?;
     let raw_stream = jetstream.get_stream(RAW_INGEST_STREAM_NAME).await?;
     let control_stream = jetstream.get_stream(RACE_CONTROL_STREAM_NAME).await?;
     let raw_consumer = get_or_create_consumer(
@@ -57,6 +72,19 @@ pub async fn run_race_worker(nats_url: &str) -> anyhow::Result<()> {
     let mut raw_messages = raw_consumer.messages().await?;
     let mut control_messages = control_consumer.messages().await?;
     let mut track_actors: HashMap<String, mpsc::Sender<TrackActorInput>> = HashMap::new();
+    // Tracks with saved state resume now, so their race rules keep running
+    // before the next message arrives
+    let mut snapshot_keys = get_or_create_snapshot_bucket(&jetstream)
+        .await?
+        .keys()
+        .await?;
+    while let Some(track_id) = snapshot_keys.next().await {
+        let track_id = track_id?;
+        track_actors.insert(
+            track_id.clone(),
+            spawn_track_actor(track_id, jetstream.clone()),
+        );
+    }
     let mut raw_open = true;
     let mut control_open = true;
 
@@ -176,13 +204,28 @@ async fn dispatch_to_track_actor(
     payload: TrackActorPayload,
     message: jetstream::Message,
 ) -> anyhow::Result<()> {
+    let stream_sequence = match message.info() {
+        Ok(info) => info.stream_sequence,
+        Err(error) => {
+            warn!(error = %error, "Failed to read message info, leaving message unacked");
+            return Ok(());
+        }
+    };
     let actor = track_actors
         .entry(track_id.clone())
         .or_insert_with(|| spawn_track_actor(track_id, jetstream))
         .clone();
 
     let (result_tx, result_rx) = oneshot::channel();
-    if actor.send(TrackActorInput { payload, result_tx }).await.is_err() {
+    if actor
+        .send(TrackActorInput {
+            payload,
+            stream_sequence,
+            result_tx,
+        })
+        .await
+        .is_err()
+    {
         warn!("Race track actor unavailable, leaving message unacked");
         return Ok(());
     }
@@ -228,176 +271,694 @@ async fn get_or_create_consumer(
     Ok(consumer)
 }
 
-fn spawn_track_actor(
-    track_id: String,
-    jetstream: jetstream::Context,
-) -> mpsc::Sender<TrackActorInput> {
+fn spawn_track_actor<S: TrackStore>(track_id: String, store: S) -> mpsc::Sender<TrackActorInput> {
     let (tx, mut rx) = mpsc::channel::<TrackActorInput>(256);
 
     tokio::spawn(async move {
-        let (event_tx, _) = tokio::sync::broadcast::channel::<Arc<RaceEvent>>(64);
-        let mut engine = RaceEngine::new(event_tx);
+        let (mut engine, mut applied) = match recover_track(&store, &track_id).await {
+            Ok(recovered) => recovered,
+            Err(error) => {
+                warn!(track_id = %track_id, error = %error, "Failed to recover track, starting fresh");
+                (RaceState::new(), AppliedSequences::default())
+            }
+        };
+        let mut rules_tick = tokio::time::interval(RULES_CHECK_INTERVAL);
+        let mut snapshot_tick = tokio::time::interval(SNAPSHOT_INTERVAL);
+        let mut saved = None;
+        // A published rules tick the actor hasn't applied yet
+        let mut pending_tick = None;
+        let mut track_clock = TrackClock::default();
+        track_clock.observe(&engine);
 
-        while let Some(input) = rx.recv().await {
-            let result = match input.payload {
-                TrackActorPayload::Raw(envelope) => {
-                    process_raw_envelope(&jetstream, &track_id, &mut engine, &envelope).await
+        loop {
+            tokio::select! {
+                input = rx.recv() => {
+                    let Some(input) = input else {
+                        break;
+                    };
+                    let result = process_input(
+                        &store,
+                        &track_id,
+                        &mut engine,
+                        &mut applied,
+                        &input.payload,
+                        input.stream_sequence,
+                    )
+                    .await;
+                    track_clock.observe(&engine);
+                    if result.is_ok()
+                        && let TrackActorPayload::Control(envelope) = &input.payload
+                    {
+                        if pending_tick == Some(envelope.event_id) {
+                            pending_tick = None;
+                        }
+                        // The API reads operator intents' outcome from the snapshot
+                        save_snapshot(&store, &track_id, &engine, applied, &mut saved).await;
+                    }
+                    let _ = input.result_tx.send(result);
+                }
+                _ = rules_tick.tick() => {
+                    // The engine can't see a tick's rulings until it comes
+                    // back from the stream, so don't rule on them again
+                    if pending_tick.is_some() {
+                        continue;
+                    }
+                    let Some(now_us) = track_clock.now_us() else {
+                        continue;
+                    };
+                    match record_rules_tick(&store, &track_id, &engine, now_us).await {
+                        Ok(published) => pending_tick = published,
+                        Err(error) => {
+                            warn!(track_id = %track_id, error = %error, "Failed to record race rules tick");
+                        }
+                    }
                 }
-                TrackActorPayload::Control(envelope) => {
-                    process_control_envelope(&jetstream, &track_id, &mut engine, &envelope).await
+                _ = snapshot_tick.tick() => {
+                    save_snapshot(&store, &track_id, &engine, applied, &mut saved).await;
                 }
-            };
-            let _ = input.result_tx.send(result);
+            }
         }
     });
 
     tx
 }
 
-async fn process_raw_envelope(
+/// Save a track's engine to the snapshot bucket, unless it is saved as of
+/// `applied` already.
+async fn save_snapshot<S: TrackStore>(
+    store: &S,
+    track_id: &str,
+    engine: &RaceState,
+    applied: AppliedSequences,
+    saved: &mut Option<AppliedSequences>,
+) {
+    if *saved == Some(applied) {
+        return;
+    }
+    let snapshot = TrackSnapshot {
+        applied,
+        engine: engine.clone(),
+    };
+    match store.save_snapshot(track_id, &snapshot).await {
+        Ok(()) => *saved = Some(applied),
+        Err(error) => {
+            warn!(track_id = %track_id, error = %error, "Failed to save track snapshot");
+        }
+    }
+}
+
+/// Apply a stream message to a track's engine and publish its events.
+///
+/// A message the engine already holds, restored from a snapshot or replayed
+/// on recovery, is only acked. When publishing fails the engine is rolled
+/// back, so the redelivered message is applied once.
+async fn process_input<S: TrackStore>(
+    store: &S,
+    track_id: &str,
+    engine: &mut RaceState,
+    applied: &mut AppliedSequences,
+    payload: &TrackActorPayload,
+    stream_sequence: u64,
+) -> anyhow::Result<()> {
+    if applied.includes(payload, stream_sequence) {
+        return Ok(());
+    }
+    let previous = engine.clone();
+    let events = apply_payload(track_id, engine, payload);
+    if let Err(error) = store.publish_events(track_id, events).await {
+        *engine = previous;
+        return Err(error);
+    }
+    applied.advance(payload, stream_sequence);
+    Ok(())
+}
+
+/// Last raw and control stream sequences a track's engine has applied.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct AppliedSequences {
+    raw: u64,
+    control: u64,
+}
+
+impl AppliedSequences {
+    fn includes(&self, payload: &TrackActorPayload, stream_sequence: u64) -> bool {
+        match payload {
+            TrackActorPayload::Raw(_) => stream_sequence <= self.raw,
+            TrackActorPayload::Control(_) => stream_sequence <= self.control,
+        }
+    }
+
+    fn advance(&mut self, payload: &TrackActorPayload, stream_sequence: u64) {
+        match payload {
+            TrackActorPayload::Raw(_) => self.raw = self.raw.max(stream_sequence),
+            TrackActorPayload::Control(_) => self.control = self.control.max(stream_sequence),
+        }
+    }
+}
+
+/// A track's engine state, saved periodically to the snapshot bucket.
+#[derive(Clone, Serialize, Deserialize)]
+struct TrackSnapshot {
+    applied: AppliedSequences,
+    engine: RaceState,
+}
+
+/// The streams and snapshot bucket a track actor works against.
+trait TrackStore: Clone + Send + Sync + 'static {
+    /// The track's saved snapshot, as stored
+    fn load_snapshot(
+        &self,
+        track_id: &str,
+    ) -> impl Future<Output = anyhow::Result<Option<Vec<u8>>>> + Send;
+
+    fn save_snapshot(
+        &self,
+        track_id: &str,
+        snapshot: &TrackSnapshot,
+    ) -> impl Future<Output = anyhow::Result<()>> + Send;
+
+    /// Highest raw and control stream sequences the worker has acked
+    fn ack_floor(&self) -> impl Future<Output = anyhow::Result<AppliedSequences>> + Send;
+
+    /// The track's messages stored after `applied`, through the streams'
+    /// last sequence
+    fn read_track_messages(
+        &self,
+        track_id: &str,
+        applied: AppliedSequences,
+    ) -> impl Future<Output = anyhow::Result<Vec<ReplayMessage>>> + Send;
+
+    fn publish_events(
+        &self,
+        track_id: &str,
+        events: Vec<OutgoingEvent>,
+    ) -> impl Future<Output = anyhow::Result<()>> + Send;
+
+    fn publish_control_intent(
+        &self,
+        envelope: &RaceControlIntentEnvelopeV1,
+    ) -> impl Future<Output = anyhow::Result<()>> + Send;
+}
+
+impl TrackStore for jetstream::Context {
+    async fn load_snapshot(&self, track_id: &str) -> anyhow::Result<Option<Vec<u8>>> {
+        let store = get_or_create_snapshot_bucket(self).await?;
+        Ok(store.get(track_id).await?.map(|bytes| bytes.to_vec()))
+    }
+
+    async fn save_snapshot(&self, track_id: &str, snapshot: &TrackSnapshot) -> anyhow::Result<()> {
+        let store = get_or_create_snapshot_bucket(self).await?;
+        store
+            .put(track_id, serde_json::to_vec(snapshot)?.into())
+            .await?;
+        Ok(())
+    }
+
+    async fn ack_floor(&self) -> anyhow::Result<AppliedSequences> {
+        Ok(AppliedSequences {
+            raw: ack_floor(self, RAW_INGEST_STREAM_NAME, RACE_WORKER_RAW_CONSUMER).await?,
+            control: ack_floor(self, RACE_CONTROL_STREAM_NAME, RACE_WORKER_CONTROL_CONSUMER)
+                .await?,
+        })
+    }
+
+    async fn read_track_messages(
+        &self,
+        track_id: &str,
+        applied: AppliedSequences,
+    ) -> anyhow::Result<Vec<ReplayMessage>> {
+        let mut messages = read_stream_messages(
+            self,
+            RAW_INGEST_STREAM_NAME,
+            build_raw_ingest_subject(track_id),
+            applied.raw,
+        )
+        .await?;
+        messages.extend(
+            read_stream_messages(
+                self,
+                RACE_CONTROL_STREAM_NAME,
+                build_race_control_subject(track_id),
+                applied.control,
+            )
+            .await?,
+        );
+        Ok(messages)
+    }
+
+    async fn publish_events(
+        &self,
+        track_id: &str,
+        events: Vec<OutgoingEvent>,
+    ) -> anyhow::Result<()> {
+        for event in events {
+            publish_event_payload(
+                self,
+                track_id,
+                event.source_event_id,
+                event.ts_us,
+                event.payload,
+                event.msg_id,
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    async fn publish_control_intent(
+        &self,
+        envelope: &RaceControlIntentEnvelopeV1,
+    ) -> anyhow::Result<()> {
+        let mut headers = HeaderMap::new();
+        headers.insert("Nats-Msg-Id", envelope.event_id.to_string());
+        self.publish_with_headers(
+            build_race_control_subject(&envelope.track_id),
+            headers,
+            serde_json::to_vec(envelope)?.into(),
+        )
+        .await?
+        .await?;
+        Ok(())
+    }
+}
+
+/// Rebuild a track's engine after a worker restart.
+///
+/// Starts from the latest snapshot, then applies every message the streams
+/// hold for the track after it, through their last sequence, in the order
+/// they were stored. Events of acked messages went out before the restart.
+/// Those of later messages are published now, as the earlier actor may not
+/// have got to them, and their message IDs dedupe the ones it did. The
+/// durable consumers then redeliver the unacked messages, which are acked
+/// without being applied again.
+async fn recover_track<S: TrackStore>(
+    store: &S,
+    track_id: &str,
+) -> anyhow::Result<(RaceState, AppliedSequences)> {
+    let snapshot = match store.load_snapshot(track_id).await? {
+        Some(bytes) => match serde_json::from_slice::<TrackSnapshot>(&bytes) {
+            Ok(snapshot) => Some(snapshot),
+            Err(error) => {
+                warn!(track_id = %track_id, error = %error, "Ignoring unreadable track snapshot");
+                None
+            }
+        },
+        None => None,
+    };
+    let applied = snapshot
+        .as_ref()
+        .map(|snapshot| snapshot.applied)
+        .unwrap_or_default();
+
+    let acked = store.ack_floor().await?;
+    let replay = store.read_track_messages(track_id, applied).await?;
+
+    let (engine, applied, unacked_events) = replay_track(track_id, snapshot, replay, acked);
+    store.publish_events(track_id, unacked_events).await?;
+    Ok((engine, applied))
+}
+
+/// Restore a snapshot and apply the messages after it, in the order the
+/// streams stored them, returning the events of those not yet `acked`.
+fn replay_track(
+    track_id: &str,
+    snapshot: Option<TrackSnapshot>,
+    mut replay: Vec<ReplayMessage>,
+    acked: AppliedSequences,
+) -> (RaceState, AppliedSequences, Vec<OutgoingEvent>) {
+    let restored = snapshot.is_some();
+    let (mut engine, mut applied) = match snapshot {
+        Some(snapshot) => (snapshot.engine, snapshot.applied),
+        None => (RaceState::new(), AppliedSequences::default()),
+    };
+
+    replay.sort_by_key(|message| (message.published, message.stream_sequence));
+    let mut replayed = 0;
+    let mut unacked_events = Vec::new();
+    for message in replay {
+        let Some(payload) = message.payload else {
+            continue;
+        };
+        if applied.includes(&payload, message.stream_sequence) {
+            continue;
+        }
+        let events = apply_payload(track_id, &mut engine, &payload);
+        if !acked.includes(&payload, message.stream_sequence) {
+            unacked_events.extend(events);
+        }
+        applied.advance(&payload, message.stream_sequence);
+        replayed += 1;
+    }
+
+    if restored || replayed > 0 {
+        info!(
+            track_id = %track_id,
+            raw_sequence = applied.raw,
+            control_sequence = applied.control,
+            replayed,
+            phase = engine.phase().name(),
+            "Recovered track state"
+        );
+    }
+    (engine, applied, unacked_events)
+}
+
+/// A track's engine as its actor last saved it, for the API to read.
+///
+/// Saved after every race control intent and otherwise every
+/// [`SNAPSHOT_INTERVAL`], so the latest passings may be missing. `None`
+/// until the worker has saved the track.
+pub async fn load_track_engine(
     jetstream: &jetstream::Context,
     track_id: &str,
-    engine: &mut RaceEngine,
+) -> anyhow::Result<Option<RaceState>> {
+    let Ok(store) = jetstream.get_key_value(RACE_WORKER_SNAPSHOT_BUCKET).await else {
+        return Ok(None);
+    };
+    let Some(bytes) = store.get(track_id).await? else {
+        return Ok(None);
+    };
+    let snapshot: TrackSnapshot = serde_json::from_slice(&bytes)?;
+    Ok(Some(snapshot.engine))
+}
+
+async fn get_or_create_snapshot_bucket(
+    jetstream: &jetstream::Context,
+) -> anyhow::Result<jetstream::kv::Store> {
+    if let Ok(store) = jetstream.get_key_value(RACE_WORKER_SNAPSHOT_BUCKET).await {
+        return Ok(store);
+    }
+
+    let store = jetstream
+        .create_key_value(jetstream::kv::Config {
+            bucket: RACE_WORKER_SNAPSHOT_BUCKET.to_string(),
+            description: "Race worker track engine snapshots".to_string(),
+            history: 1,
+            ..Default::default()
+        })
+        .await?;
+    Ok(store)
+}
+
+/// Highest stream sequence the worker's durable consumer has acked.
+async fn ack_floor(
+    jetstream: &jetstream::Context,
+    stream_name: &str,
+    durable_name: &str,
+) -> anyhow::Result<u64> {
+    let mut consumer = jetstream
+        .get_stream(stream_name)
+        .await?
+        .get_consumer::<jetstream::consumer::pull::Config>(durable_name)
+        .await
+        .map_err(|error| anyhow!("Failed to get consumer {durable_name}: {error}"))?;
+    Ok(consumer.info().await?.ack_floor.stream_sequence)
+}
+
+#[derive(Clone)]
+struct ReplayMessage {
+    published: time::OffsetDateTime,
+    stream_sequence: u64,
+    /// `None` for messages that failed to parse
+    payload: Option<TrackActorPayload>,
+}
+
+/// Messages on `subject` stored after stream sequence `after`.
+async fn read_stream_messages(
+    jetstream: &jetstream::Context,
+    stream_name: &str,
+    subject: String,
+    after: u64,
+) -> anyhow::Result<Vec<ReplayMessage>> {
+    let mut consumer = jetstream
+        .get_stream(stream_name)
+        .await?
+        .create_consumer(jetstream::consumer::pull::Config {
+            filter_subject: subject,
+            deliver_policy: DeliverPolicy::ByStartSequence {
+                start_sequence: after + 1,
+            },
+            ack_policy: AckPolicy::None,
+            inactive_threshold: Duration::from_secs(30),
+            ..Default::default()
+        })
+        .await?;
+    if consumer.info().await?.num_pending == 0 {
+        return Ok(Vec::new());
+    }
+
+    let mut replay = Vec::new();
+    let mut messages = consumer.messages().await?;
+    while let Some(message) = messages.next().await {
+        let message = message?;
+        let info = message
+            .info()
+            .map_err(|error| anyhow!("Failed to read replay message info: {error}"))?;
+        let payload = if stream_name == RAW_INGEST_STREAM_NAME {
+            serde_json::from_slice(&message.payload)
+                .ok()
+                .map(TrackActorPayload::Raw)
+        } else {
+            serde_json::from_slice(&message.payload)
+                .ok()
+                .map(TrackActorPayload::Control)
+        };
+        replay.push(ReplayMessage {
+            published: info.published,
+            stream_sequence: info.stream_sequence,
+            payload,
+        });
+        if info.pending == 0 {
+            break;
+        }
+    }
+    Ok(replay)
+}
+
+/// A race event ready to publish, with the message ID that dedupes it.
+struct OutgoingEvent {
+    source_event_id: Uuid,
+    ts_us: u64,
+    payload: RaceEventPayloadV1,
+    msg_id: String,
+}
+
+fn apply_payload(
+    track_id: &str,
+    engine: &mut RaceState,
+    payload: &TrackActorPayload,
+) -> Vec<OutgoingEvent> {
+    match payload {
+        TrackActorPayload::Raw(envelope) => apply_raw_envelope(track_id, engine, envelope),
+        TrackActorPayload::Control(envelope) => apply_control_envelope(track_id, engine, envelope),
+    }
+}
+
+fn apply_raw_envelope(
+    track_id: &str,
+    engine: &mut RaceState,
     raw: &RawIngestEnvelopeV1,
-) -> anyhow::Result<()> {
-    publish_event_payload(
-        jetstream,
-        track_id,
-        raw.event_id,
-        raw.captured_at_us,
-        RaceEventPayloadV1::DecoderMessage {
+) -> Vec<OutgoingEvent> {
+    let mut events = vec![OutgoingEvent {
+        source_event_id: raw.event_id,
+        ts_us: raw.captured_at_us,
+        payload: RaceEventPayloadV1::DecoderMessage {
             message: raw.payload.clone(),
         },
-        format!("{track_id}:{}:decoder_message", raw.event_id),
-    )
-    .await?;
+        msg_id: format!("{track_id}:{}:decoder_message", raw.event_id),
+    }];
 
     if let Message::Passing(passing) = &raw.payload {
-        let events = engine.process_passing(passing);
-
-        for (index, event) in events.into_iter().enumerate() {
+        let passing_events = run(
+            engine,
+            RaceCommand::Passing {
+                passing: passing.clone(),
+                captured_at_us: Some(raw.captured_at_us),
+            },
+        );
+        for (index, event) in passing_events.into_iter().enumerate() {
             let Some(payload) = map_domain_event_to_payload(event) else {
                 continue;
             };
 
-            let msg_id = format!("{track_id}:{}:passing:{}", raw.event_id, index);
-            publish_event_payload(
-                jetstream,
-                track_id,
-                raw.event_id,
-                raw.captured_at_us,
+            events.push(OutgoingEvent {
+                source_event_id: raw.event_id,
+                ts_us: raw.captured_at_us,
                 payload,
-                msg_id,
-            )
-            .await?;
+                msg_id: format!("{track_id}:{}:passing:{}", raw.event_id, index),
+            });
         }
     }
 
-    Ok(())
+    events
 }
 
-async fn process_control_envelope(
-    jetstream: &jetstream::Context,
+/// Apply the race rules between passings, so a race with nobody left
+/// crossing loops still ends.
+///
+/// A tick that rules on anything is published to the race control stream
+/// rather than applied here. The actor applies it when the consumer
+/// delivers it, like an operator's intent, and a recovering actor replays
+/// it with the same time, `now_us` on the track's time base. Returns the
+/// event ID of the published tick.
+async fn record_rules_tick<S: TrackStore>(
+    store: &S,
     track_id: &str,
-    engine: &mut RaceEngine,
+    engine: &RaceState,
+    now_us: u64,
+) -> anyhow::Result<Option<Uuid>> {
+    let (_, events) = apply(engine.clone(), RaceCommand::CheckTimeouts { now_us });
+    if events.is_empty() {
+        return Ok(None);
+    }
+
+    let envelope = RaceControlIntentEnvelopeV1 {
+        event_id: Uuid::new_v4(),
+        contract_version: RACE_CONTROL_INTENT_ENVELOPE_CONTRACT_VERSION_V1.to_string(),
+        track_id: track_id.to_string(),
+        ts_us: now_unix_micros(),
+        intent: RaceControlIntentV1::CheckTimeouts { now_us },
+    };
+    store.publish_control_intent(&envelope).await?;
+    Ok(Some(envelope.event_id))
+}
+
+fn apply_control_envelope(
+    track_id: &str,
+    engine: &mut RaceState,
     control: &RaceControlIntentEnvelopeV1,
-) -> anyhow::Result<()> {
-    let mut index = 0usize;
+) -> Vec<OutgoingEvent> {
+    let mut events = Vec::new();
+    let mut push = |payload: RaceEventPayloadV1, name: &str| {
+        let msg_id = format!(
+            "{track_id}:{}:control:{}:{name}",
+            control.event_id,
+            events.len()
+        );
+        events.push(OutgoingEvent {
+            source_event_id: control.event_id,
+            ts_us: control.ts_us,
+            payload,
+            msg_id,
+        });
+    };
 
-    match &control.intent {
+    let (outcome, name) = match &control.intent {
         RaceControlIntentV1::Stage {
             track_config,
             moto_id,
             class_name,
             round_type,
             riders,
+            laps,
+            start_mode,
+            rules,
         } => {
-            engine.set_track(map_track_config(track_config));
-            engine.stage_moto(
-                moto_id.clone(),
-                class_name.clone(),
-                round_type.clone(),
-                riders.iter().cloned().map(map_staged_rider).collect(),
+            run(
+                engine,
+                RaceCommand::SetTrack(map_track_config(track_config)),
             );
-
-            if let RacePhase::Staged {
-                moto_id: active_moto,
-                ..
-            } = engine.phase()
-            {
-                if active_moto == moto_id {
-                    publish_event_payload(
-                        jetstream,
-                        track_id,
-                        control.event_id,
-                        control.ts_us,
-                        RaceEventPayloadV1::RaceStaged {
-                            moto_id: moto_id.clone(),
-                            class_name: class_name.clone(),
-                            round_type: round_type.clone(),
-                            riders: riders.clone(),
-                        },
-                        format!("{track_id}:{}:control:{index}:race_staged", control.event_id),
-                    )
-                    .await?;
-                    index += 1;
-                } else {
-                    warn!(
-                        track_id = %track_id,
-                        requested_moto = %moto_id,
-                        active_moto = %active_moto,
-                        "Stage intent did not become active stage"
-                    );
-                }
-            } else {
+            run(engine, RaceCommand::SetRaceRules(map_race_rules(rules)));
+            let staged = run(
+                engine,
+                RaceCommand::Stage {
+                    moto_id: moto_id.clone(),
+                    class_name: class_name.clone(),
+                    round_type: round_type.clone(),
+                    laps: *laps,
+                    start_mode: map_start_mode(*start_mode),
+                    riders: riders.iter().cloned().map(map_staged_rider).collect(),
+                },
+            );
+            if staged.is_empty() {
                 warn!(track_id = %track_id, "Stage intent was rejected by race engine");
             }
+            (staged, "race_staged")
         }
-        RaceControlIntentV1::Reset => {
-            engine.reset();
-
-            publish_event_payload(
-                jetstream,
-                track_id,
-                control.event_id,
-                control.ts_us,
-                RaceEventPayloadV1::RaceReset,
-                format!("{track_id}:{}:control:{index}:race_reset", control.event_id),
-            )
-            .await?;
-            index += 1;
-        }
-        RaceControlIntentV1::ForceFinish => {
-            if let Some(event) = engine.force_finish()
-                && let Some(payload) = map_domain_event_to_payload(event)
-            {
-                publish_event_payload(
-                    jetstream,
-                    track_id,
-                    control.event_id,
-                    control.ts_us,
-                    payload,
-                    format!("{track_id}:{}:control:{index}:race_finished", control.event_id),
-                )
-                .await?;
-                index += 1;
+        RaceControlIntentV1::AddToStartList { moto_id, riders } => {
+            let command = RaceCommand::AddToStartList {
+                moto_id: moto_id.clone(),
+                riders: riders.iter().cloned().map(map_staged_rider).collect(),
+            };
+            (run(engine, command), "start_list_updated")
+        }
+        RaceControlIntentV1::BindTransponder {
+            moto_id,
+            rider_id,
+            transponder_id,
+        } => {
+            let command = RaceCommand::BindTransponder {
+                moto_id: moto_id.clone(),
+                rider_id: rider_id.clone(),
+                transponder_id: *transponder_id,
+            };
+            (run(engine, command), "bind_transponder")
+        }
+        RaceControlIntentV1::CorrectTiming {
+            moto_id,
+            correction,
+        } => {
+            let command = RaceCommand::CorrectTiming {
+                moto_id: moto_id.clone(),
+                correction: map_correction(correction.clone()),
+            };
+            (run(engine, command), "correct_timing")
+        }
+        RaceControlIntentV1::Reset => (run(engine, RaceCommand::Reset), "race_reset"),
+        RaceControlIntentV1::ForceFinish { moto_id } => (
+            run(
+                engine,
+                RaceCommand::ForceFinish {
+                    moto_id: moto_id.clone(),
+                },
+            ),
+            "race_finished",
+        ),
+        RaceControlIntentV1::StartPractice {
+            session_id,
+            track_config,
+            riders,
+        } => {
+            run(
+                engine,
+                RaceCommand::SetTrack(map_track_config(track_config)),
+            );
+            let started = run(
+                engine,
+                RaceCommand::StartPractice {
+                    session_id: session_id.clone(),
+                    riders: riders.iter().cloned().map(map_known_rider).collect(),
+                },
+            );
+            if started.is_empty() {
+                warn!(track_id = %track_id, "Practice intent was rejected by race engine");
             }
+            (started, "practice_started")
         }
+        RaceControlIntentV1::StopPractice => {
+            (run(engine, RaceCommand::StopPractice), "practice_ended")
+        }
+        RaceControlIntentV1::CheckTimeouts { now_us } => (
+            run(engine, RaceCommand::CheckTimeouts { now_us: *now_us }),
+            "race_rules",
+        ),
+    };
+    for payload in outcome.into_iter().filter_map(map_domain_event_to_payload) {
+        push(payload, name);
     }
 
     if let Some(snapshot_payload) = map_domain_event_to_payload(engine.state_snapshot()) {
-        publish_event_payload(
-            jetstream,
-            track_id,
-            control.event_id,
-            control.ts_us,
-            snapshot_payload,
-            format!("{track_id}:{}:control:{index}:state_snapshot", control.event_id),
-        )
-        .await?;
+        push(snapshot_payload, "state_snapshot");
     }
 
-    Ok(())
+    events
+}
+
+/// Run a command through the engine core, updating the track's state.
+fn run(engine: &mut RaceState, command: RaceCommand) -> Vec<RaceEvent> {
+    let (state, events) = apply(std::mem::take(engine), command);
+    *engine = state;
+    events
 }
 
 async fn publish_event_payload(
@@ -430,18 +991,25 @@ async fn publish_event_payload(
     Ok(())
 }
 
-fn map_domain_event_to_payload(event: RaceEvent) -> Option<RaceEventPayloadV1> {
+pub(crate) fn map_domain_event_to_payload(event: RaceEvent) -> Option<RaceEventPayloadV1> {
     match event {
         RaceEvent::RaceStaged {
             moto_id,
             class_name,
             round_type,
             riders,
+            laps,
+            start_mode,
         } => Some(RaceEventPayloadV1::RaceStaged {
             moto_id,
             class_name,
             round_type,
-            riders: riders.into_iter().map(map_staged_rider_from_domain).collect(),
+            riders: riders
+                .into_iter()
+                .map(map_staged_rider_from_domain)
+                .collect(),
+            laps,
+            start_mode: map_start_mode_from_domain(start_mode),
         }),
         RaceEvent::GateDrop {
             moto_id,
@@ -450,9 +1018,28 @@ fn map_domain_event_to_payload(event: RaceEvent) -> Option<RaceEventPayloadV1> {
             moto_id,
             timestamp_us,
         }),
+        RaceEvent::RiderStarted {
+            moto_id,
+            rider_id,
+            timestamp_us,
+        } => Some(RaceEventPayloadV1::RiderStarted {
+            moto_id,
+            rider_id,
+            timestamp_us,
+        }),
+        RaceEvent::StartListUpdated { moto_id, riders } => {
+            Some(RaceEventPayloadV1::StartListUpdated {
+                moto_id,
+                riders: riders
+                    .into_iter()
+                    .map(map_staged_rider_from_domain)
+                    .collect(),
+            })
+        }
         RaceEvent::SplitTime {
             moto_id,
             rider_id,
+            lap,
             loop_name,
             is_finish,
             elapsed_us,
@@ -461,16 +1048,41 @@ fn map_domain_event_to_payload(event: RaceEvent) -> Option<RaceEventPayloadV1> {
         } => Some(RaceEventPayloadV1::SplitTime {
             moto_id,
             rider_id,
+            lap,
             loop_name,
             is_finish,
             elapsed_us,
             position,
             gap_to_leader_us,
         }),
+        RaceEvent::SplitCorrected {
+            moto_id,
+            rider_id,
+            lap,
+            loop_name,
+            is_finish,
+            previous_elapsed_us,
+            elapsed_us,
+            position,
+            gap_to_leader_us,
+        } => Some(RaceEventPayloadV1::SplitCorrected {
+            moto_id,
+            rider_id,
+            lap,
+            loop_name,
+            is_finish,
+            previous_elapsed_us,
+            elapsed_us,
+            position,
+            gap_to_leader_us,
+        }),
         RaceEvent::PositionsUpdate { moto_id, positions } => {
             Some(RaceEventPayloadV1::PositionsUpdate {
                 moto_id,
-                positions: positions.into_iter().map(map_position_from_domain).collect(),
+                positions: positions
+                    .into_iter()
+                    .map(map_position_from_domain)
+                    .collect(),
             })
         }
         RaceEvent::RiderFinished {
@@ -490,7 +1102,59 @@ fn map_domain_event_to_payload(event: RaceEvent) -> Option<RaceEventPayloadV1> {
             moto_id,
             results: results.into_iter().map(map_result_from_domain).collect(),
         }),
+        RaceEvent::TimingCorrected {
+            moto_id,
+            correction,
+        } => Some(RaceEventPayloadV1::TimingCorrected {
+            moto_id,
+            correction: map_correction_from_domain(correction),
+        }),
+        RaceEvent::UnknownTransponder {
+            moto_id,
+            transponder_id,
+            loop_name,
+            timestamp_us,
+        } => Some(RaceEventPayloadV1::UnknownTransponder {
+            moto_id,
+            transponder_id,
+            loop_name,
+            timestamp_us,
+        }),
+        RaceEvent::UnmappedDecoder {
+            moto_id,
+            decoder_id,
+            transponder_id,
+            timestamp_us,
+        } => Some(RaceEventPayloadV1::UnmappedDecoder {
+            moto_id,
+            decoder_id,
+            transponder_id,
+            timestamp_us,
+        }),
+        RaceEvent::ClockDrift {
+            decoder_id,
+            drift_ppm,
+            offset_us,
+            timestamp_us,
+        } => Some(RaceEventPayloadV1::ClockDrift {
+            decoder_id,
+            drift_ppm,
+            offset_us,
+            timestamp_us,
+        }),
         RaceEvent::RaceReset => Some(RaceEventPayloadV1::RaceReset),
+        RaceEvent::PracticeStarted { session_id } => {
+            Some(RaceEventPayloadV1::PracticeStarted { session_id })
+        }
+        RaceEvent::PracticeUpdate { lap, leaderboard } => {
+            Some(RaceEventPayloadV1::PracticeUpdate {
+                lap: map_practice_lap_from_domain(lap),
+                leaderboard: map_practice_leaderboard_from_domain(leaderboard),
+            })
+        }
+        RaceEvent::PracticeEnded { leaderboard } => Some(RaceEventPayloadV1::PracticeEnded {
+            leaderboard: map_practice_leaderboard_from_domain(leaderboard),
+        }),
         RaceEvent::StateSnapshot {
             phase,
             moto_id,
@@ -501,16 +1165,26 @@ fn map_domain_event_to_payload(event: RaceEvent) -> Option<RaceEventPayloadV1> {
             gate_drop_time_us,
             finished_count,
             total_riders,
+            laps,
+            practice,
         } => Some(RaceEventPayloadV1::StateSnapshot {
             phase,
             moto_id,
             class_name,
             round_type,
-            riders: riders.into_iter().map(map_staged_rider_from_domain).collect(),
-            positions: positions.into_iter().map(map_position_from_domain).collect(),
+            riders: riders
+                .into_iter()
+                .map(map_staged_rider_from_domain)
+                .collect(),
+            positions: positions
+                .into_iter()
+                .map(map_position_from_domain)
+                .collect(),
             gate_drop_time_us,
             finished_count,
             total_riders,
+            laps,
+            practice: practice.map(map_practice_leaderboard_from_domain),
         }),
     }
 }
@@ -524,6 +1198,14 @@ fn map_track_config(track_config: &TrackConfigV1) -> TrackConfig {
     }
 }
 
+fn map_race_rules(rules: &RaceRulesV1) -> RaceRules {
+    RaceRules {
+        dns_timeout_secs: rules.dns_timeout_secs,
+        dnf_timeout_secs: rules.dnf_timeout_secs,
+        max_race_secs: rules.max_race_secs,
+    }
+}
+
 fn map_loop_config(loop_config: &LoopConfigV1) -> LoopConfig {
     LoopConfig {
         loop_id: loop_config.loop_id.clone(),
@@ -532,6 +1214,7 @@ fn map_loop_config(loop_config: &LoopConfigV1) -> LoopConfig {
         position: loop_config.position,
         is_start: loop_config.is_start,
         is_finish: loop_config.is_finish,
+        debounce_ms: loop_config.debounce_ms,
     }
 }
 
@@ -557,6 +1240,129 @@ fn map_staged_rider_from_domain(rider: StagedRider) -> StagedRiderV1 {
     }
 }
 
+fn map_known_rider(rider: KnownRiderV1) -> KnownRider {
+    KnownRider {
+        rider_id: rider.rider_id,
+        first_name: rider.first_name,
+        last_name: rider.last_name,
+        plate_number: rider.plate_number,
+        transponder_id: rider.transponder_id,
+        transponder_string: rider.transponder_string,
+    }
+}
+
+fn map_practice_lap_from_domain(lap: PracticeLap) -> PracticeLapV1 {
+    PracticeLapV1 {
+        transponder_id: lap.transponder_id,
+        rider_id: lap.rider_id,
+        lap_number: lap.lap_number,
+        lap_time_us: lap.lap_time_us,
+        sectors: lap
+            .sectors
+            .into_iter()
+            .map(|sector| SectorTimeV1 {
+                from_loop: sector.from_loop,
+                to_loop: sector.to_loop,
+                time_us: sector.time_us,
+            })
+            .collect(),
+        personal_best: lap.personal_best,
+    }
+}
+
+fn map_practice_leaderboard_from_domain(leaderboard: PracticeLeaderboard) -> PracticeLeaderboardV1 {
+    let standings = |standings: Vec<PracticeStanding>| {
+        standings
+            .into_iter()
+            .map(|standing| PracticeStandingV1 {
+                position: standing.position,
+                transponder_id: standing.transponder_id,
+                rider_id: standing.rider_id,
+                first_name: standing.first_name,
+                last_name: standing.last_name,
+                plate_number: standing.plate_number,
+                laps: standing.laps,
+                best_lap_us: standing.best_lap_us,
+                last_lap_us: standing.last_lap_us,
+            })
+            .collect()
+    };
+
+    PracticeLeaderboardV1 {
+        session_id: leaderboard.session_id,
+        best_laps: standings(leaderboard.best_laps),
+        last_laps: standings(leaderboard.last_laps),
+    }
+}
+
+fn map_correction(correction: TimingCorrectionV1) -> TimingCorrection {
+    TimingCorrection {
+        correction_id: correction.correction_id,
+        operator: correction.operator,
+        reason: correction.reason,
+        change: match correction.change {
+            TimingChangeV1::InsertPassing {
+                rider_id,
+                loop_id,
+                timestamp_us,
+            } => TimingChange::InsertPassing {
+                rider_id,
+                loop_id,
+                timestamp_us,
+            },
+            TimingChangeV1::VoidPassing { passing_id } => TimingChange::VoidPassing { passing_id },
+            TimingChangeV1::HandTimedFinish {
+                rider_id,
+                elapsed_us,
+            } => TimingChange::HandTimedFinish {
+                rider_id,
+                elapsed_us,
+            },
+        },
+    }
+}
+
+pub(crate) fn map_correction_from_domain(correction: TimingCorrection) -> TimingCorrectionV1 {
+    TimingCorrectionV1 {
+        correction_id: correction.correction_id,
+        operator: correction.operator,
+        reason: correction.reason,
+        change: match correction.change {
+            TimingChange::InsertPassing {
+                rider_id,
+                loop_id,
+                timestamp_us,
+            } => TimingChangeV1::InsertPassing {
+                rider_id,
+                loop_id,
+                timestamp_us,
+            },
+            TimingChange::VoidPassing { passing_id } => TimingChangeV1::VoidPassing { passing_id },
+            TimingChange::HandTimedFinish {
+                rider_id,
+                elapsed_us,
+            } => TimingChangeV1::HandTimedFinish {
+                rider_id,
+                elapsed_us,
+            },
+        },
+    }
+}
+
+pub(crate) fn map_start_mode(start_mode: StartModeV1) -> StartMode {
+    match start_mode {
+        StartModeV1::GateDrop => StartMode::GateDrop,
+        StartModeV1::TimeTrial => StartMode::TimeTrial,
+    }
+}
+
+fn map_start_mode_from_domain(start_mode: StartMode) -> StartModeV1 {
+    match start_mode {
+        StartMode::GateDrop => StartModeV1::GateDrop,
+        StartMode::TimeTrial => StartModeV1::TimeTrial,
+    }
+}
+
 fn map_position_from_domain(position: RiderPosition) -> RiderPositionV1 {
     RiderPositionV1 {
         rider_id: position.rider_id,
@@ -570,6 +1376,7 @@ fn map_position_from_domain(position: RiderPosition) -> RiderPositionV1 {
         gap_to_leader_us: position.gap_to_leader_us,
         finished: position.finished,
         dnf: position.dnf,
+        laps_completed: position.laps_completed,
     }
 }
 
@@ -584,5 +1391,574 @@ fn map_result_from_domain(result: FinishResult) -> FinishResultV1 {
         gap_to_leader_us: result.gap_to_leader_us,
         dnf: result.dnf,
         dns: result.dns,
+        lap_times_us: result.lap_times_us,
+    }
+}
+
+/// Wall clock in µs; decoder RTC runs on UTC, so race rules compare the two
+fn now_unix_micros() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_micros().try_into().unwrap_or(u64::MAX))
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    use p3_contracts::{EventIdContext, RAW_INGEST_ENVELOPE_CONTRACT_VERSION_V1};
+    use p3_parser::PassingMessage;
+
+    const TRACK_ID: &str = "track-1";
+
+    fn stage_intent() -> RaceControlIntentEnvelopeV1 {
+        let loop_config =
+            |loop_id: &str, decoder_id: &str, position, is_start, is_finish| LoopConfigV1 {
+                loop_id: loop_id.into(),
+                name: loop_id.into(),
+                decoder_id: decoder_id.into(),
+                position,
+                is_start,
+                is_finish,
+                debounce_ms: 0,
+            };
+        let rider = |n: u32| StagedRiderV1 {
+            rider_id: format!("rider-{n}"),
+            first_name: "Rider".into(),
+            last_name: n.to_string(),
+            plate_number: n.to_string(),
+            transponder_id: 1000 + n,
+            lane: n,
+        };
+        RaceControlIntentEnvelopeV1 {
+            event_id: Uuid::from_u128(1),
+            contract_version: RACE_CONTROL_INTENT_ENVELOPE_CONTRACT_VERSION_V1.to_string(),
+            track_id: TRACK_ID.into(),
+            ts_us: 1,
+            intent: RaceControlIntentV1::Stage {
+                track_config: TrackConfigV1 {
+                    track_id: TRACK_ID.into(),
+                    name: "Test Track".into(),
+                    gate_beacon_id: 9992,
+                    loops: vec![
+                        loop_config("start", "D0000C01", 0, true, false),
+                        loop_config("finish", "D0000C03", 1, false, true),
+                    ],
+                },
+                moto_id: "moto-1".into(),
+                class_name: "Novice".into(),
+                round_type: "moto1".into(),
+                riders: (1..=3).map(rider).collect(),
+                laps: 2,
+                start_mode: StartModeV1::GateDrop,
+                rules: RaceRulesV1::default(),
+            },
+        }
+    }
+
+    fn passing_envelope(
+        seq: u64,
+        transponder_id: u32,
+        decoder_id: &str,
+        rtc_time_us: u64,
+    ) -> RawIngestEnvelopeV1 {
+        RawIngestEnvelopeV1 {
+            event_id: Uuid::from_u128(1_000 + u128::from(seq)),
+            contract_version: RAW_INGEST_ENVELOPE_CONTRACT_VERSION_V1.to_string(),
+            track_id: TRACK_ID.into(),
+            event_id_context: EventIdContext {
+                client_id: "client-1".into(),
+                boot_id: "boot-1".into(),
+                seq,
+            },
+            captured_at_us: rtc_time_us,
+            ingested_at_us: rtc_time_us,
+            message_type: "PASSING".into(),
+            payload: Message::Passing(PassingMessage {
+                passing_number: seq as u32,
+                transponder_id,
+                rtc_time_us,
+                utc_time_us: None,
+                strength: Some(100),
+                hits: Some(40),
+                transponder_string: None,
+                flags: 0,
+                decoder_id: Some(decoder_id.into()),
+                extra_fields: Vec::new(),
+            }),
+        }
+    }
+
+    fn correction_intent() -> RaceControlIntentEnvelopeV1 {
+        RaceControlIntentEnvelopeV1 {
+            event_id: Uuid::from_u128(2),
+            contract_version: RACE_CONTROL_INTENT_ENVELOPE_CONTRACT_VERSION_V1.to_string(),
+            track_id: TRACK_ID.into(),
+            ts_us: 2,
+            intent: RaceControlIntentV1::CorrectTiming {
+                moto_id: "moto-1".into(),
+                correction: TimingCorrectionV1 {
+                    correction_id: "c-1".into(),
+                    operator: "chief-timer".into(),
+                    reason: "missed read".into(),
+                    change: TimingChangeV1::InsertPassing {
+                        rider_id: "rider-3".into(),
+                        loop_id: "start".into(),
+                        timestamp_us: 10_500_000,
+                    },
+                },
+            },
+        }
+    }
+
+    /// A staged two-lap moto as it arrives from the streams, in order, with
+    /// a timing correction after the first lap.
+    fn race_messages() -> Vec<ReplayMessage> {
+        let passings = [
+            (9992, "D0000C01", 10_000_000),
+            (1001, "D0000C03", 20_000_000),
+            (1002, "D0000C03", 20_400_000),
+            (1003, "D0000C03", 21_000_000),
+            (1001, "D0000C03", 30_000_000),
+            (1002, "D0000C03", 29_900_000),
+            (1003, "D0000C03", 31_500_000),
+        ];
+        let start = time::OffsetDateTime::UNIX_EPOCH;
+        let mut messages = vec![ReplayMessage {
+            published: start,
+            stream_sequence: 1,
+            payload: Some(TrackActorPayload::Control(stage_intent())),
+        }];
+        for (index, (transponder_id, decoder_id, rtc_time_us)) in passings.into_iter().enumerate() {
+            let seq = index as u64 + 1;
+            messages.push(ReplayMessage {
+                published: start + time::Duration::seconds(seq as i64),
+                stream_sequence: seq,
+                payload: Some(TrackActorPayload::Raw(passing_envelope(
+                    seq,
+                    transponder_id,
+                    decoder_id,
+                    rtc_time_us,
+                ))),
+            });
+        }
+        messages.insert(
+            5,
+            ReplayMessage {
+                published: start + time::Duration::milliseconds(4_500),
+                stream_sequence: 2,
+                payload: Some(TrackActorPayload::Control(correction_intent())),
+            },
+        );
+        messages
+    }
+
+    /// Streams, snapshot bucket and race events held in memory.
+    #[derive(Clone, Default)]
+    struct MemoryStore(Arc<Mutex<MemoryStreams>>);
+
+    #[derive(Default)]
+    struct MemoryStreams {
+        messages: Vec<ReplayMessage>,
+        acked: AppliedSequences,
+        snapshot: Option<Vec<u8>>,
+        /// Race events by message ID, deduped like the race events stream
+        published: Vec<(String, serde_json::Value)>,
+        fail_publish: bool,
+    }
+
+    impl MemoryStore {
+        fn append(&self, message: &ReplayMessage) {
+            self.0.lock().unwrap().messages.push(message.clone());
+        }
+
+        /// Hand a stored message to the actor as the durable consumer
+        /// would, acking it once processed unless the worker is killed first.
+        async fn deliver(
+            &self,
+            actor: &mpsc::Sender<TrackActorInput>,
+            message: &ReplayMessage,
+            ack: bool,
+        ) -> anyhow::Result<()> {
+            let payload = message.payload.clone().unwrap();
+            let (result_tx, result_rx) = oneshot::channel();
+            actor
+                .send(TrackActorInput {
+                    payload: payload.clone(),
+                    stream_sequence: message.stream_sequence,
+                    result_tx,
+                })
+                .await?;
+            result_rx.await??;
+            if ack {
+                let mut streams = self.0.lock().unwrap();
+                streams.acked.advance(&payload, message.stream_sequence);
+            }
+            Ok(())
+        }
+
+        /// The race events published, and the passings of the engine the
+        /// actor saved next.
+        async fn outcome(&self) -> (Vec<(String, serde_json::Value)>, Vec<String>) {
+            tokio::time::sleep(SNAPSHOT_INTERVAL * 2).await;
+            let streams = self.0.lock().unwrap();
+            let snapshot: TrackSnapshot =
+                serde_json::from_slice(streams.snapshot.as_ref().unwrap()).unwrap();
+            let passings = snapshot
+                .engine
+                .recorded_passings(None)
+                .into_iter()
+                .map(|passing| passing.passing_id)
+                .collect();
+            (streams.published.clone(), passings)
+        }
+    }
+
+    impl TrackStore for MemoryStore {
+        async fn load_snapshot(&self, _track_id: &str) -> anyhow::Result<Option<Vec<u8>>> {
+            Ok(self.0.lock().unwrap().snapshot.clone())
+        }
+
+        async fn save_snapshot(
+            &self,
+            _track_id: &str,
+            snapshot: &TrackSnapshot,
+        ) -> anyhow::Result<()> {
+            self.0.lock().unwrap().snapshot = Some(serde_json::to_vec(snapshot)?);
+            Ok(())
+        }
+
+        async fn ack_floor(&self) -> anyhow::Result<AppliedSequences> {
+            Ok(self.0.lock().unwrap().acked)
+        }
+
+        async fn read_track_messages(
+            &self,
+            _track_id: &str,
+            applied: AppliedSequences,
+        ) -> anyhow::Result<Vec<ReplayMessage>> {
+            let streams = self.0.lock().unwrap();
+            Ok(streams
+                .messages
+                .iter()
+                .filter(|message| {
+                    message
+                        .payload
+                        .as_ref()
+                        .is_some_and(|payload| !applied.includes(payload, message.stream_sequence))
+                })
+                .cloned()
+                .collect())
+        }
+
+        async fn publish_events(
+            &self,
+            _track_id: &str,
+            events: Vec<OutgoingEvent>,
+        ) -> anyhow::Result<()> {
+            let mut streams = self.0.lock().unwrap();
+            if streams.fail_publish {
+                return Err(anyhow!("Race events stream unavailable"));
+            }
+            for event in events {
+                if streams
+                    .published
+                    .iter()
+                    .all(|(msg_id, _)| *msg_id != event.msg_id)
+                {
+                    let payload = serde_json::to_value(&event.payload)?;
+                    streams.published.push((event.msg_id, payload));
+                }
+            }
+            Ok(())
+        }
+
+        async fn publish_control_intent(
+            &self,
+            envelope: &RaceControlIntentEnvelopeV1,
+        ) -> anyhow::Result<()> {
+            let mut streams = self.0.lock().unwrap();
+            let last = streams.messages.last();
+            let message = ReplayMessage {
+                published: last.map_or(time::OffsetDateTime::UNIX_EPOCH, |m| m.published)
+                    + time::Duration::seconds(1),
+                stream_sequence: streams
+                    .messages
+                    .iter()
+                    .filter(|m| matches!(m.payload, Some(TrackActorPayload::Control(_))))
+                    .count() as u64
+                    + 1,
+                payload: Some(TrackActorPayload::Control(envelope.clone())),
+            };
+            streams.messages.push(message);
+            Ok(())
+        }
+    }
+
+    async fn uninterrupted_outcome(
+        messages: &[ReplayMessage],
+    ) -> (Vec<(String, serde_json::Value)>, Vec<String>) {
+        let store = MemoryStore::default();
+        let actor = spawn_track_actor(TRACK_ID.into(), store.clone());
+        for message in messages {
+            store.append(message);
+            store.deliver(&actor, message, true).await.unwrap();
+        }
+        store.outcome().await
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_worker_killed_mid_race_recovers_same_result() {
+        let messages = race_messages();
+        let expected = uninterrupted_outcome(&messages).await;
+        assert!(
+            expected
+                .0
+                .iter()
+                .any(|(_, payload)| payload["kind"] == "race_finished")
+        );
+
+        for killed_at in [0, 2, 5, 7] {
+            let store = MemoryStore::default();
+            let actor = spawn_track_actor(TRACK_ID.into(), store.clone());
+            for message in &messages[..killed_at] {
+                store.append(message);
+                store.deliver(&actor, message, true).await.unwrap();
+            }
+            // Applied, published and snapshotted, but never acked
+            store.append(&messages[killed_at]);
+            store
+                .deliver(&actor, &messages[killed_at], false)
+                .await
+                .unwrap();
+            tokio::time::sleep(SNAPSHOT_INTERVAL * 2).await;
+            drop(actor);
+
+            let actor = spawn_track_actor(TRACK_ID.into(), store.clone());
+            // The consumer redelivers the unacked message first
+            store
+                .deliver(&actor, &messages[killed_at], true)
+                .await
+                .unwrap();
+            for message in &messages[killed_at + 1..] {
+                store.append(message);
+                store.deliver(&actor, message, true).await.unwrap();
+            }
+            assert_eq!(store.outcome().await, expected, "killed at {killed_at}");
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_failed_publish_applies_message_once_on_redelivery() {
+        let messages = race_messages();
+        let expected = uninterrupted_outcome(&messages).await;
+
+        for failed_at in [2, 5] {
+            let store = MemoryStore::default();
+            let actor = spawn_track_actor(TRACK_ID.into(), store.clone());
+            for message in &messages[..failed_at] {
+                store.append(message);
+                store.deliver(&actor, message, true).await.unwrap();
+            }
+            store.append(&messages[failed_at]);
+            store.0.lock().unwrap().fail_publish = true;
+            assert!(
+                store
+                    .deliver(&actor, &messages[failed_at], true)
+                    .await
+                    .is_err()
+            );
+            store.0.lock().unwrap().fail_publish = false;
+            // Redelivered, then the race goes on
+            for (index, message) in messages[failed_at..].iter().enumerate() {
+                if index > 0 {
+                    store.append(message);
+                }
+                store.deliver(&actor, message, true).await.unwrap();
+            }
+            assert_eq!(store.outcome().await, expected, "failed at {failed_at}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rules_tick_is_replayed_with_its_recorded_time() {
+        let mut stage = stage_intent();
+        if let RaceControlIntentV1::Stage { rules, .. } = &mut stage.intent {
+            rules.dns_timeout_secs = Some(30);
+            rules.dnf_timeout_secs = Some(5);
+        }
+        let store = MemoryStore::default();
+        let mut messages: Vec<_> = race_messages()
+            .into_iter()
+            .enumerate()
+            .filter(|(index, _)| [0, 1, 2, 3, 6].contains(index))
+            .map(|(_, message)| message)
+            .collect();
+        messages[0].payload = Some(TrackActorPayload::Control(stage));
+        for message in &messages {
+            store.append(message);
+        }
+        let (engine, _, _) = replay_track(TRACK_ID, None, messages, AppliedSequences::default());
+
+        // Rider 1 has finished, rider 2 is on course and rider 3 never started
+        record_rules_tick(&store, TRACK_ID, &engine, 60_000_000)
+            .await
+            .unwrap();
+        let stored = store
+            .read_track_messages(TRACK_ID, AppliedSequences::default())
+            .await
+            .unwrap();
+        assert!(matches!(
+            &stored.last().unwrap().payload,
+            Some(TrackActorPayload::Control(RaceControlIntentEnvelopeV1 {
+                intent: RaceControlIntentV1::CheckTimeouts { .. },
+                ..
+            }))
+        ));
+
+        let (engine, _, events) = replay_track(TRACK_ID, None, stored, AppliedSequences::default());
+        let results = events
+            .iter()
+            .find_map(|event| match &event.payload {
+                RaceEventPayloadV1::RaceFinished { results, .. } => Some(results),
+                _ => None,
+            })
+            .unwrap();
+        let mut rulings: Vec<_> = results
+            .iter()
+            .map(|result| (result.rider_id.as_str(), result.dns, result.dnf))
+            .collect();
+        rulings.sort();
+        assert_eq!(
+            rulings,
+            vec![
+                ("rider-1", false, false),
+                ("rider-2", false, true),
+                ("rider-3", true, false),
+            ]
+        );
+
+        // A tick that rules on nothing isn't recorded
+        assert!(
+            record_rules_tick(&store, TRACK_ID, &engine, 60_000_000)
+                .await
+                .unwrap()
+                .is_none()
+        );
+        assert_eq!(store.0.lock().unwrap().messages.len(), 6);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_undelivered_rules_tick_is_not_published_again() {
+        let mut stage = stage_intent();
+        if let RaceControlIntentV1::Stage { rules, .. } = &mut stage.intent {
+            rules.dns_timeout_secs = Some(30);
+            rules.max_race_secs = Some(45);
+        }
+        let mut messages: Vec<_> = race_messages()
+            .into_iter()
+            .enumerate()
+            .filter(|(index, _)| [0, 1, 2, 3, 6].contains(index))
+            .map(|(_, message)| message)
+            .collect();
+        messages[0].payload = Some(TrackActorPayload::Control(stage));
+        let store = MemoryStore::default();
+        let actor = spawn_track_actor(TRACK_ID.into(), store.clone());
+        for message in &messages {
+            store.append(message);
+            store.deliver(&actor, message, true).await.unwrap();
+        }
+
+        let ticks = |store: &MemoryStore| -> Vec<ReplayMessage> {
+            store
+                .0
+                .lock()
+                .unwrap()
+                .messages
+                .iter()
+                .filter(|message| {
+                    matches!(
+                        &message.payload,
+                        Some(TrackActorPayload::Control(RaceControlIntentEnvelopeV1 {
+                            intent: RaceControlIntentV1::CheckTimeouts { .. },
+                            ..
+                        }))
+                    )
+                })
+                .cloned()
+                .collect()
+        };
+        // Track time runs on from the last passing, at 30 s. Rider 3's DNS
+        // is due at 40 s, and the consumer is slow to hand the tick back.
+        tokio::time::sleep(RULES_CHECK_INTERVAL * 15).await;
+        let published = ticks(&store);
+        assert_eq!(published.len(), 1);
+
+        // Once it is applied, time runs out for rider 2 at 55 s
+        store.deliver(&actor, &published[0], true).await.unwrap();
+        tokio::time::sleep(RULES_CHECK_INTERVAL * 15).await;
+        let published = ticks(&store);
+        assert_eq!(published.len(), 2);
+        store.deliver(&actor, &published[1], true).await.unwrap();
+        tokio::time::sleep(RULES_CHECK_INTERVAL * 15).await;
+        assert_eq!(ticks(&store).len(), 2);
+
+        let (events, _) = store.outcome().await;
+        assert!(
+            events
+                .iter()
+                .any(|(_, payload)| payload["kind"] == "race_finished")
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_control_intent_is_saved_for_the_api_at_once() {
+        let messages = race_messages();
+        let store = MemoryStore::default();
+        let actor = spawn_track_actor(TRACK_ID.into(), store.clone());
+        // Well before the next snapshot tick
+        tokio::time::sleep(SNAPSHOT_INTERVAL / 2).await;
+        store.append(&messages[0]);
+        store.deliver(&actor, &messages[0], true).await.unwrap();
+
+        let snapshot: TrackSnapshot =
+            serde_json::from_slice(store.0.lock().unwrap().snapshot.as_ref().unwrap()).unwrap();
+        assert_eq!(snapshot.engine.phase().name(), "staged");
+        assert_eq!(snapshot.applied.control, 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_worker_drops_correction_that_does_not_apply() {
+        let messages = race_messages();
+        let store = MemoryStore::default();
+        let actor = spawn_track_actor(TRACK_ID.into(), store.clone());
+        for message in &messages[..3] {
+            store.append(message);
+            store.deliver(&actor, message, true).await.unwrap();
+        }
+
+        let mut correction = correction_intent();
+        if let RaceControlIntentV1::CorrectTiming { correction, .. } = &mut correction.intent {
+            correction.change = TimingChangeV1::VoidPassing {
+                passing_id: "D0000C03:99".into(),
+            };
+        }
+        let message = ReplayMessage {
+            published: messages[2].published,
+            stream_sequence: 2,
+            payload: Some(TrackActorPayload::Control(correction)),
+        };
+        store.append(&message);
+        store.deliver(&actor, &message, true).await.unwrap();
+
+        let (events, passings) = store.outcome().await;
+        assert!(
+            !events
+                .iter()
+                .any(|(_, payload)| payload["kind"] == "timing_corrected")
+        );
+        assert_eq!(passings.len(), 1);
     }
 }
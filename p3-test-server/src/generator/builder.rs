@@ -1,7 +1,8 @@
 //! Message builder for P3 protocol messages.
 //!
-//! Combines TLV encoding, escape sequences, and CRC calculation to generate
-//! complete P3 messages that match the output of real MyLaps ProChip decoders.
+//! Builds P3 messages from raw field values and encodes them with
+//! [`p3_parser::Encoder`], which produces frames byte-identical to the output
+//! of real MyLaps ProChip decoders.
 //!
 //! # Message Structure
 //! ```text
@@ -17,13 +18,11 @@
 //! // Returns complete P3 STATUS message with valid CRC
 //! ```
 
-use crate::generator::tlv::{TlvBuilder, TlvError};
-use p3_protocol::{
-    EOR, HEADER_SIZE, MessageType, OFFSET_CRC, OFFSET_SOR, SOR, VERSION, calculate_crc, encode,
-};
+use crate::generator::tlv::TlvError;
+use p3_parser::{Message, PassingMessage, StatusMessage, VersionMessage};
 
 #[cfg(test)]
-use p3_protocol::validate_crc;
+use p3_protocol::{EOR, SOR, VERSION, validate_crc};
 use std::time::{SystemTime, SystemTimeError, UNIX_EPOCH};
 use thiserror::Error;
 
@@ -84,7 +83,7 @@ pub fn current_timestamp_micros() -> Result<u64, BuilderError> {
 ///
 /// let time = SystemTime::UNIX_EPOCH + Duration::from_secs(1609459200); // 2021-01-01
 /// let micros = system_time_to_micros(time).unwrap();
-/// assert_eq!(micros, 1609459200_000000);
+/// assert_eq!(micros, 1_609_459_200_000_000);
 /// ```
 pub fn system_time_to_micros(time: SystemTime) -> Result<u64, BuilderError> {
     Ok(time.duration_since(UNIX_EPOCH)?.as_micros() as u64)
@@ -106,7 +105,7 @@ pub fn system_time_to_micros(time: SystemTime) -> Result<u64, BuilderError> {
 /// use p3_test_server::generator::builder::micros_to_system_time;
 /// use std::time::{SystemTime, Duration, UNIX_EPOCH};
 ///
-/// let micros = 1609459200_000000u64; // 2021-01-01 00:00:00 UTC
+/// let micros = 1_609_459_200_000_000u64; // 2021-01-01 00:00:00 UTC
 /// let time = micros_to_system_time(micros);
 /// assert_eq!(time, UNIX_EPOCH + Duration::from_micros(micros));
 /// ```
@@ -134,7 +133,7 @@ pub fn micros_to_system_time(micros: u64) -> SystemTime {
 /// ```
 /// use p3_test_server::generator::builder::format_timestamp;
 ///
-/// let micros = 1609459200_000000u64; // 2021-01-01 00:00:00
+/// let micros = 1_609_459_200_000_000u64; // 2021-01-01 00:00:00
 /// let formatted = format_timestamp(micros);
 /// assert_eq!(formatted, "2021-01-01 00:00:00.000000");
 /// ```
@@ -205,6 +204,7 @@ pub fn format_timestamp(micros: u64) -> String {
 ///
 /// # Returns
 /// Complete escaped P3 PASSING message with valid CRC
+#[allow(clippy::too_many_arguments)]
 pub fn build_passing(
     passing_number: u32,
     transponder: u32,
@@ -215,25 +215,21 @@ pub fn build_passing(
     string: Option<&[u8; 8]>,
     decoder_id: u32,
 ) -> Result<Vec<u8>, BuilderError> {
-    // Build TLV body in the correct field order
-    let mut tlv = TlvBuilder::new()
-        .add_u32(0x01, passing_number) // PASSING_NUMBER
-        .add_u32(0x03, transponder); // TRANSPONDER
-
-    // Add string field if provided (rider passings have this, gate passings don't)
-    if let Some(s) = string {
-        tlv = tlv.add_bytes(0x0A, s)?; // STRING
-    }
-
-    let tlv_body = tlv
-        .add_u16(0x05, strength) // STRENGTH
-        .add_u16(0x06, hits) // HITS
-        .add_u64(0x04, rtc_time) // RTC_TIME
-        .add_u16(0x08, flags) // FLAGS
-        .add_u32(0x81, decoder_id) // DECODER_ID
-        .build();
-
-    Ok(build_message(MessageType::Passing, tlv_body))
+    let message = Message::Passing(PassingMessage {
+        passing_number,
+        transponder_id: transponder,
+        rtc_time_us: rtc_time,
+        utc_time_us: None,
+        strength: Some(strength),
+        hits: Some(hits),
+        // Rider passings have a STRING field, gate passings don't
+        transponder_string: string.map(|s| String::from_utf8_lossy(s).into_owned()),
+        flags,
+        decoder_id: Some(decoder_id_hex(&decoder_id.to_le_bytes())),
+        extra_fields: Vec::new(),
+    });
+
+    Ok(message.to_wire()?)
 }
 
 /// Build a complete P3 STATUS message.
@@ -254,16 +250,18 @@ pub fn build_status(
     satinuse: u8,
     decoder_id: u32,
 ) -> Vec<u8> {
-    // Build TLV body
-    let tlv_body = TlvBuilder::new()
-        .add_u16(0x01, noise)
-        .add_i16(0x07, temperature)
-        .add_u8(0x06, gps_status)
-        .add_u8(0x0A, satinuse)
-        .add_u32(0x81, decoder_id)
-        .build();
-
-    build_message(MessageType::Status, tlv_body)
+    let message = Message::Status(StatusMessage {
+        noise,
+        gps_status,
+        temperature,
+        satellites: satinuse,
+        decoder_id: Some(decoder_id_hex(&decoder_id.to_le_bytes())),
+        extra_fields: Vec::new(),
+    });
+
+    message
+        .to_wire()
+        .expect("STATUS has no variable-length fields and a well-formed decoder ID")
 }
 
 /// Build a complete P3 VERSION message.
@@ -285,69 +283,20 @@ pub fn build_version(
     version_string: &str,
     build: u16,
 ) -> Result<Vec<u8>, BuilderError> {
-    // Build TLV body
-    let tlv_body = TlvBuilder::new()
-        .add_u64(0x20, decoder_id)
-        .add_bytes(0x21, description.as_bytes())?
-        .add_bytes(0x22, version_string.as_bytes())?
-        .add_u16(0x23, build)
-        .build();
-
-    Ok(build_message(MessageType::Version, tlv_body))
+    let message = Message::Version(VersionMessage {
+        decoder_id: decoder_id_hex(&decoder_id.to_le_bytes()),
+        description: description.to_string(),
+        version: version_string.to_string(),
+        build: Some(build),
+        extra_fields: Vec::new(),
+    });
+
+    Ok(message.to_wire()?)
 }
 
-/// Core message building function - constructs complete P3 message with CRC.
-///
-/// # Process
-/// 1. Build unescaped message with placeholder CRC
-/// 2. Calculate CRC on unescaped message
-/// 3. Insert CRC at bytes 4-5
-/// 4. Apply escape encoding to data section (excluding SOR/EOR)
-///
-/// # Arguments
-/// * `msg_type` - Message type (PASSING, STATUS, VERSION)
-/// * `tlv_body` - Pre-encoded TLV fields
-///
-/// # Returns
-/// Complete escaped message ready for transmission
-fn build_message(msg_type: MessageType, tlv_body: Vec<u8>) -> Vec<u8> {
-    // Calculate unescaped length (entire message including SOR/EOR)
-    let unescaped_length = (HEADER_SIZE + tlv_body.len() + 1) as u16; // +1 for EOR
-
-    // Build unescaped message with zero CRC
-    let mut unescaped = Vec::new();
-    unescaped.push(SOR);
-    unescaped.push(VERSION);
-    unescaped.extend_from_slice(&unescaped_length.to_le_bytes()); // LENGTH
-    unescaped.extend_from_slice(&[0x00, 0x00]); // CRC placeholder
-    unescaped.extend_from_slice(&[0x00, 0x00]); // RESERVED
-    unescaped.extend_from_slice(&msg_type.to_u16().to_le_bytes()); // TYPE
-    unescaped.extend_from_slice(&tlv_body); // TLV body
-    unescaped.push(EOR);
-
-    // Calculate CRC on complete unescaped message
-    let crc_value = calculate_crc(&unescaped);
-
-    // Insert CRC (little-endian)
-    unescaped[OFFSET_CRC] = (crc_value & 0xFF) as u8;
-    unescaped[OFFSET_CRC + 1] = ((crc_value >> 8) & 0xFF) as u8;
-
-    // Apply escape encoding to the data section (excluding SOR and EOR)
-    // Extract: SOR + data + EOR
-    let sor_byte = unescaped[OFFSET_SOR];
-    let eor_byte = unescaped[unescaped.len() - 1];
-    let data_section = &unescaped[1..unescaped.len() - 1];
-
-    // Escape the data section
-    let escaped_data = encode(data_section);
-
-    // Reassemble: SOR + escaped_data + EOR
-    let mut final_message = Vec::new();
-    final_message.push(sor_byte);
-    final_message.extend_from_slice(&escaped_data);
-    final_message.push(eor_byte);
-
-    final_message
+/// Format decoder ID bytes as the wire-order hex string used by parsed messages
+fn decoder_id_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02X}", b)).collect()
 }
 
 /// Helper function to build a rider PASSING message with a specific decoder ID.
@@ -387,16 +336,23 @@ pub fn build_gate_passing(
     rtc_time: u64,
     decoder_id: u32,
 ) -> Vec<u8> {
-    // Build TLV body for gate - no STRING, STRENGTH, or HITS fields
-    let tlv_body = TlvBuilder::new()
-        .add_u32(0x01, passing_number) // PASSING_NUMBER
-        .add_u32(0x03, transponder) // TRANSPONDER
-        .add_u64(0x04, rtc_time) // RTC_TIME
-        .add_u16(0x08, 0x0000) // FLAGS
-        .add_u32(0x81, decoder_id) // DECODER_ID
-        .build();
-
-    build_message(MessageType::Passing, tlv_body)
+    // Gate passings have no STRING, STRENGTH, or HITS fields
+    let message = Message::Passing(PassingMessage {
+        passing_number,
+        transponder_id: transponder,
+        rtc_time_us: rtc_time,
+        utc_time_us: None,
+        strength: None,
+        hits: None,
+        transponder_string: None,
+        flags: 0x0000,
+        decoder_id: Some(decoder_id_hex(&decoder_id.to_le_bytes())),
+        extra_fields: Vec::new(),
+    });
+
+    message
+        .to_wire()
+        .expect("gate PASSING has no variable-length fields and a well-formed decoder ID")
 }
 
 /// Convenience function to build a rider PASSING message with current timestamp.
@@ -571,12 +527,12 @@ mod tests {
         // Test known timestamp: 2021-01-01 00:00:00 UTC
         let time = UNIX_EPOCH + Duration::from_secs(1609459200);
         let micros = system_time_to_micros(time).unwrap();
-        assert_eq!(micros, 1609459200_000000);
+        assert_eq!(micros, 1_609_459_200_000_000);
 
         // Test with microseconds precision
-        let time = UNIX_EPOCH + Duration::from_micros(1609459200_123456);
+        let time = UNIX_EPOCH + Duration::from_micros(1_609_459_200_123_456);
         let micros = system_time_to_micros(time).unwrap();
-        assert_eq!(micros, 1609459200_123456);
+        assert_eq!(micros, 1_609_459_200_123_456);
     }
 
     #[test]
@@ -593,8 +549,8 @@ mod tests {
     fn test_current_timestamp_micros() {
         // Just verify it returns a reasonable value (after 2020, before 2100)
         let now = current_timestamp_micros().unwrap();
-        let year_2020_micros = 1577836800_000000u64; // 2020-01-01
-        let year_2100_micros = 4102444800_000000u64; // 2100-01-01
+        let year_2020_micros = 1_577_836_800_000_000u64; // 2020-01-01
+        let year_2100_micros = 4_102_444_800_000_000u64; // 2100-01-01
 
         assert!(
             now > year_2020_micros && now < year_2100_micros,
@@ -1,4 +1,5 @@
 pub mod generator;
+pub mod replay;
 pub mod simulator;
 pub mod transport;
 
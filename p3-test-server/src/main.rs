@@ -1,9 +1,11 @@
 use clap::Parser;
 use p3_test_server::generator::builder::current_timestamp_micros;
+use p3_test_server::replay::CaptureReplay;
 use p3_test_server::simulator::DecoderSimulator;
 use p3_test_server::transport::TcpTransport;
 use rand::rngs::SmallRng;
 use rand::{Rng, SeedableRng};
+use std::path::PathBuf;
 use tracing::info;
 
 #[derive(Parser, Debug)]
@@ -25,6 +27,21 @@ struct Args {
     /// Number of riders for the full-race scenario (3-8)
     #[arg(long, default_value = "6")]
     riders: usize,
+
+    /// Capture file for the replay scenario (see `p3-parser record`)
+    #[arg(long, required_if_eq("scenario", "replay"))]
+    capture: Option<PathBuf>,
+
+    /// Replay speed multiplier (2.0 = twice as fast as recorded)
+    #[arg(long, default_value = "1.0", value_parser = parse_speed)]
+    speed: f64,
+}
+
+fn parse_speed(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(speed) if speed.is_finite() && speed > 0.0 => Ok(speed),
+        _ => Err(format!("'{}' is not a positive number", value)),
+    }
 }
 
 /// Simulated decoder IDs for different timing loops
@@ -55,7 +72,7 @@ fn make_riders(count: usize) -> Vec<SimRider> {
 
     rider_data
         .into_iter()
-        .take(count.min(8).max(3))
+        .take(count.clamp(3, 8))
         .map(|(tid, s, sf)| SimRider {
             transponder_id: tid,
             string: *s,
@@ -202,11 +219,40 @@ async fn main() -> anyhow::Result<()> {
     info!("Scenario: {}", args.scenario);
     info!("Max clients: {}", args.max_clients);
 
-    let (transport, handle) =
+    let (mut transport, handle) =
         TcpTransport::new(args.port, args.max_clients, args.chunk_size).await?;
 
+    if args.scenario == "replay" {
+        // The capture already holds the decoder's own STATUS frames and is
+        // replayed verbatim, so no simulator runs alongside it
+        let path = args.capture.expect("--capture is required by clap");
+        let replay = CaptureReplay::open(&path)?.with_speed(args.speed);
+        info!(
+            "Running replay scenario: {} ({} records, recorded from {})",
+            path.display(),
+            replay.records().len(),
+            replay.metadata().source
+        );
+
+        tokio::spawn(async move {
+            info!("Waiting 5s for clients to connect...");
+            tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
+            if let Err(e) = replay.run(&handle).await {
+                tracing::error!("Replay failed: {}", e);
+            }
+        });
+
+        info!("Press Ctrl+C to stop");
+        transport.run().await?;
+        return Ok(());
+    }
+
     let simulator = DecoderSimulator::new(handle);
 
+    if let Some(resend_requests) = transport.take_resend_requests() {
+        tokio::spawn(simulator.clone().serve_resend_requests(resend_requests));
+    }
+
     let sim_clone = simulator.clone();
     tokio::spawn(async move {
         sim_clone.start_status_loop().await;
@@ -0,0 +1,169 @@
+//! Replay of recorded decoder sessions
+//!
+//! Sends the raw bytes of a capture file (see [`p3_parser::capture`]) to all
+//! connected clients exactly as they were read on the track, one record per
+//! write, preserving the original inter-arrival timing scaled by a speed
+//! multiplier.
+
+use crate::transport::{SendError, TransportHandle};
+use p3_parser::{CaptureMetadata, CaptureReader, CaptureRecord, CaptureResult};
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+use tokio::time::{Duration, Instant, sleep_until};
+use tracing::{debug, info};
+
+/// A loaded capture ready to be replayed
+pub struct CaptureReplay {
+    metadata: CaptureMetadata,
+    records: Vec<CaptureRecord>,
+    speed: f64,
+}
+
+impl CaptureReplay {
+    /// Load a capture file at real-time speed
+    pub fn open(path: impl AsRef<Path>) -> CaptureResult<Self> {
+        Self::from_reader(BufReader::new(File::open(path)?))
+    }
+
+    pub fn from_reader(reader: impl Read) -> CaptureResult<Self> {
+        let mut reader = CaptureReader::new(reader)?;
+        let metadata = reader.metadata().clone();
+        let records = reader.by_ref().collect::<CaptureResult<Vec<_>>>()?;
+
+        Ok(Self {
+            metadata,
+            records,
+            speed: 1.0,
+        })
+    }
+
+    /// Play back `speed` times faster than recorded (0.5 = half speed)
+    ///
+    /// # Panics
+    /// If `speed` is not a positive finite number.
+    pub fn with_speed(mut self, speed: f64) -> Self {
+        assert!(
+            speed.is_finite() && speed > 0.0,
+            "replay speed must be positive"
+        );
+        self.speed = speed;
+        self
+    }
+
+    pub fn metadata(&self) -> &CaptureMetadata {
+        &self.metadata
+    }
+
+    pub fn records(&self) -> &[CaptureRecord] {
+        &self.records
+    }
+
+    /// Send offset of each record from the start of playback, at the configured speed
+    ///
+    /// Offsets are relative to the first record rather than to the previous
+    /// one, so sleep overshoot never accumulates. A timestamp earlier than
+    /// the first record (wall clock stepped back) is sent immediately.
+    pub fn schedule(&self) -> Vec<Duration> {
+        let Some(first) = self.records.first() else {
+            return Vec::new();
+        };
+
+        self.records
+            .iter()
+            .map(|record| {
+                let elapsed_us = record.captured_at_us.saturating_sub(first.captured_at_us);
+                Duration::from_secs_f64(elapsed_us as f64 / 1_000_000.0 / self.speed)
+            })
+            .collect()
+    }
+
+    /// Send every record to connected clients with the recorded timing
+    ///
+    /// Returns the number of bytes sent.
+    pub async fn run(&self, handle: &TransportHandle) -> Result<u64, SendError> {
+        info!(
+            "Replaying {} records from {} at {}x",
+            self.records.len(),
+            self.metadata.source,
+            self.speed
+        );
+
+        let start = Instant::now();
+        let mut bytes = 0u64;
+
+        for (record, offset) in self.records.iter().zip(self.schedule()) {
+            sleep_until(start + offset).await;
+            debug!("Replaying {} bytes at +{:?}", record.data.len(), offset);
+            handle.send(record.data.clone()).await?;
+            bytes += record.data.len() as u64;
+        }
+
+        info!(
+            "Replay finished: {} bytes in {:.1}s",
+            bytes,
+            start.elapsed().as_secs_f64()
+        );
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use p3_parser::CaptureWriter;
+
+    fn replay(timestamps: &[u64]) -> CaptureReplay {
+        let metadata = CaptureMetadata {
+            source: "tcp://127.0.0.1:5403".to_string(),
+            started_at_us: 0,
+            recorder: "test".to_string(),
+            description: None,
+        };
+        let mut writer = CaptureWriter::new(Vec::new(), &metadata).unwrap();
+        for &ts in timestamps {
+            writer.write_record(ts, &[0x00]).unwrap();
+        }
+        CaptureReplay::from_reader(writer.into_inner().as_slice()).unwrap()
+    }
+
+    #[test]
+    fn test_schedule_preserves_inter_arrival_times() {
+        let schedule = replay(&[5_000_000, 5_250_000, 6_000_000]).schedule();
+        assert_eq!(
+            schedule,
+            vec![
+                Duration::ZERO,
+                Duration::from_millis(250),
+                Duration::from_secs(1)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_schedule_scales_with_speed() {
+        let schedule = replay(&[0, 1_000_000, 3_000_000])
+            .with_speed(4.0)
+            .schedule();
+        assert_eq!(
+            schedule,
+            vec![
+                Duration::ZERO,
+                Duration::from_millis(250),
+                Duration::from_millis(750)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_schedule_sends_backwards_timestamps_immediately() {
+        let schedule = replay(&[2_000_000, 1_000_000]).schedule();
+        assert_eq!(schedule, vec![Duration::ZERO, Duration::ZERO]);
+    }
+
+    #[test]
+    #[should_panic(expected = "replay speed must be positive")]
+    fn test_zero_speed_is_rejected() {
+        replay(&[]).with_speed(0.0);
+    }
+}
@@ -2,15 +2,15 @@
 
 mod state;
 
-pub use state::DecoderState;
+pub use state::{DecoderState, PASSING_HISTORY_CAPACITY};
 
 use crate::generator::builder::{
     build_gate_passing, build_gate_passing_with_escape, build_rider_passing, build_status,
     current_timestamp_micros,
 };
-use crate::transport::{SendError, TransportHandle};
+use crate::transport::{ResendRequest, SendError, TransportHandle};
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use tokio::sync::{Mutex, mpsc};
 use tokio::time::{Duration, interval};
 use tracing::{debug, error, info};
 
@@ -112,6 +112,7 @@ impl DecoderSimulator {
             did
         );
 
+        state.record_passing(passing_number, &message);
         self.handle.send(message).await?;
         Ok(())
     }
@@ -139,6 +140,7 @@ impl DecoderSimulator {
             passing_number, transponder, did
         );
 
+        state.record_passing(passing_number, &message);
         self.handle.send(message).await?;
         Ok(())
     }
@@ -163,10 +165,36 @@ impl DecoderSimulator {
             passing_number, transponder
         );
 
+        state.record_passing(passing_number, &message);
         self.handle.send(message).await?;
         Ok(())
     }
 
+    /// Answer RESEND requests from connected clients out of the passing history
+    ///
+    /// Passings that have aged out of history are silently omitted, as a real
+    /// decoder does once its internal buffer wraps.
+    pub async fn serve_resend_requests(self, mut requests: mpsc::Receiver<ResendRequest>) {
+        while let Some(request) = requests.recv().await {
+            let frames = self
+                .state
+                .lock()
+                .await
+                .passings_in_range(request.from_passing, request.until_passing);
+
+            info!(
+                "RESEND {}..={}: replaying {} passings",
+                request.from_passing,
+                request.until_passing,
+                frames.len()
+            );
+
+            let _ = request
+                .reply
+                .send(frames.into_iter().map(Into::into).collect());
+        }
+    }
+
     /// Get a reference to the decoder state (for inspection/modification)
     pub fn state(&self) -> Arc<Mutex<DecoderState>> {
         Arc::clone(&self.state)
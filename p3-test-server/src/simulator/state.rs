@@ -1,5 +1,10 @@
 //! Decoder state management
 
+use std::collections::VecDeque;
+
+/// Number of PASSING frames kept for RESEND requests
+pub const PASSING_HISTORY_CAPACITY: usize = 10_000;
+
 /// Decoder state tracking
 #[derive(Debug, Clone)]
 pub struct DecoderState {
@@ -20,6 +25,9 @@ pub struct DecoderState {
 
     /// Number of GPS satellites in use
     pub gps_satellites: u8,
+
+    /// Recently sent PASSING frames keyed by passing number (oldest first)
+    passing_history: VecDeque<(u32, Vec<u8>)>,
 }
 
 impl DecoderState {
@@ -32,6 +40,7 @@ impl DecoderState {
             temperature_celsius_x10: 16,
             gps_has_fix: true,
             gps_satellites: 0,
+            passing_history: VecDeque::new(),
         }
     }
 
@@ -40,6 +49,24 @@ impl DecoderState {
         self.passing_number += 1;
         self.passing_number
     }
+
+    /// Remember a sent PASSING frame so it can be retransmitted verbatim
+    pub fn record_passing(&mut self, passing_number: u32, frame: &[u8]) {
+        if self.passing_history.len() >= PASSING_HISTORY_CAPACITY {
+            self.passing_history.pop_front();
+        }
+        self.passing_history
+            .push_back((passing_number, frame.to_vec()));
+    }
+
+    /// Frames for passings in `from..=until` still held in history, in send order
+    pub fn passings_in_range(&self, from: u32, until: u32) -> Vec<Vec<u8>> {
+        self.passing_history
+            .iter()
+            .filter(|(n, _)| (from..=until).contains(n))
+            .map(|(_, frame)| frame.clone())
+            .collect()
+    }
 }
 
 impl Default for DecoderState {
@@ -47,3 +74,33 @@ impl Default for DecoderState {
         Self::new(0x000C00D0) // Default decoder ID from live captures
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_passings_in_range() {
+        let mut state = DecoderState::default();
+        for n in 1..=5u32 {
+            state.record_passing(n, &[n as u8]);
+        }
+
+        assert_eq!(
+            state.passings_in_range(2, 4),
+            vec![vec![2], vec![3], vec![4]]
+        );
+        assert!(state.passings_in_range(6, 10).is_empty());
+    }
+
+    #[test]
+    fn test_history_is_bounded() {
+        let mut state = DecoderState::default();
+        for n in 0..(PASSING_HISTORY_CAPACITY as u32 + 10) {
+            state.record_passing(n, &[]);
+        }
+
+        assert!(state.passings_in_range(0, 9).is_empty());
+        assert_eq!(state.passings_in_range(10, 10).len(), 1);
+    }
+}
@@ -1,12 +1,16 @@
+use super::ResendRequest;
 use bytes::Bytes;
+use p3_parser::Message;
+use p3_parser::stream::MessageFramer;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::TcpStream;
-use tokio::sync::mpsc;
-use tracing::{debug, error, info};
+use tokio::sync::{mpsc, oneshot};
+use tracing::{debug, error, info, warn};
 
 pub struct Connection {
     stream: TcpStream,
     rx: mpsc::Receiver<Bytes>,
+    resend_tx: mpsc::Sender<ResendRequest>,
     peer_addr: std::net::SocketAddr,
 }
 
@@ -14,11 +18,13 @@ impl Connection {
     pub fn new(
         stream: TcpStream,
         rx: mpsc::Receiver<Bytes>,
+        resend_tx: mpsc::Sender<ResendRequest>,
         peer_addr: std::net::SocketAddr,
     ) -> Self {
         Self {
             stream,
             rx,
+            resend_tx,
             peer_addr,
         }
     }
@@ -26,12 +32,13 @@ impl Connection {
     /// Run the connection handler loop
     ///
     /// Receives messages from channel and writes them to the TCP stream.
-    /// Monitors the TCP connection for disconnects.
+    /// Monitors the TCP connection for disconnects and inbound RESEND requests.
     /// Supports chunked sending for fragmentation testing.
     pub async fn run(mut self, chunk_size: Option<usize>) -> Result<(), std::io::Error> {
         info!("Client connected: {}", self.peer_addr);
 
-        let mut buf = [0u8; 1];
+        let mut buf = [0u8; 256];
+        let mut framer = MessageFramer::new();
 
         loop {
             tokio::select! {
@@ -43,9 +50,26 @@ impl Connection {
                             break;
                         }
                         Ok(n) => {
-                            // Unexpected data received (P3 clients shouldn't send data except RESEND)
-                            debug!("Received {} unexpected bytes from {}", n, self.peer_addr);
-                            // Continue running - this might be a RESEND request in the future
+                            // P3 clients only ever send RESEND requests
+                            for framed in framer.feed(&buf[..n]) {
+                                match framed {
+                                    Ok(Message::Resend(resend)) => {
+                                        if let Err(e) = self
+                                            .handle_resend(resend.from_passing, resend.until_passing, chunk_size)
+                                            .await
+                                        {
+                                            error!("Failed to answer RESEND from {}: {}", self.peer_addr, e);
+                                            return Ok(());
+                                        }
+                                    }
+                                    Ok(other) => {
+                                        debug!("Ignoring unexpected {:?} from {}", other, self.peer_addr);
+                                    }
+                                    Err(e) => {
+                                        warn!("Unparsable data from {}: {}", self.peer_addr, e);
+                                    }
+                                }
+                            }
                         }
                         Err(e) => {
                             error!("Read error from {}: {}", self.peer_addr, e);
@@ -77,6 +101,42 @@ impl Connection {
         Ok(())
     }
 
+    /// Forward a RESEND request to the simulator and write the replayed frames
+    async fn handle_resend(
+        &mut self,
+        from_passing: u32,
+        until_passing: u32,
+        chunk_size: Option<usize>,
+    ) -> Result<(), std::io::Error> {
+        info!(
+            "RESEND request from {}: passings {}..={}",
+            self.peer_addr, from_passing, until_passing
+        );
+
+        let (reply, reply_rx) = oneshot::channel();
+        let request = ResendRequest {
+            from_passing,
+            until_passing,
+            reply,
+        };
+
+        if self.resend_tx.send(request).await.is_err() {
+            warn!("RESEND not supported by this server, ignoring");
+            return Ok(());
+        }
+
+        // Dropped reply means no simulator is serving requests
+        let Ok(frames) = reply_rx.await else {
+            warn!("RESEND not supported by this server, ignoring");
+            return Ok(());
+        };
+
+        for frame in frames {
+            self.send_message(&frame, chunk_size).await?;
+        }
+        Ok(())
+    }
+
     async fn send_message(
         &mut self,
         message: &[u8],
@@ -4,7 +4,7 @@ use bytes::Bytes;
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::net::TcpListener;
-use tokio::sync::{Semaphore, mpsc};
+use tokio::sync::{Semaphore, mpsc, oneshot};
 use tracing::{debug, error, info, warn};
 
 use connection::Connection;
@@ -26,6 +26,16 @@ impl TransportHandle {
     }
 }
 
+/// A RESEND request received from a single client
+///
+/// The simulator answers through `reply` with the original PASSING frames;
+/// the requesting connection writes them back to that client only.
+pub struct ResendRequest {
+    pub from_passing: u32,
+    pub until_passing: u32,
+    pub reply: oneshot::Sender<Vec<Bytes>>,
+}
+
 /// Internal message types for the broadcast channel
 enum BroadcastMessage {
     Data(Bytes),
@@ -40,6 +50,8 @@ pub struct TcpTransport {
     chunk_size: Option<usize>,
     next_client_id: ClientId,
     clients: HashMap<ClientId, mpsc::Sender<Bytes>>,
+    resend_tx: mpsc::Sender<ResendRequest>,
+    resend_rx: Option<mpsc::Receiver<ResendRequest>>,
 }
 
 impl TcpTransport {
@@ -68,6 +80,7 @@ impl TcpTransport {
         // Channel for broadcasting messages to all clients
         // Buffer size of 32 allows simulator to queue messages without blocking
         let (broadcast_tx, broadcast_rx) = mpsc::channel(32);
+        let (resend_tx, resend_rx) = mpsc::channel(8);
 
         let transport = Self {
             listener,
@@ -77,6 +90,8 @@ impl TcpTransport {
             chunk_size,
             next_client_id: 0,
             clients: HashMap::new(),
+            resend_tx,
+            resend_rx: Some(resend_rx),
         };
 
         let handle = TransportHandle { tx: broadcast_tx };
@@ -84,7 +99,23 @@ impl TcpTransport {
         Ok((transport, handle))
     }
 
+    /// Address the listener is bound to (useful when binding port 0)
+    pub fn local_addr(&self) -> Result<std::net::SocketAddr, std::io::Error> {
+        self.listener.local_addr()
+    }
+
+    /// Take the receiver for RESEND requests sent by clients
+    ///
+    /// Returns `None` after the first call. If never taken, RESEND requests
+    /// are logged and ignored.
+    pub fn take_resend_requests(&mut self) -> Option<mpsc::Receiver<ResendRequest>> {
+        self.resend_rx.take()
+    }
+
     pub async fn run(mut self) -> Result<(), std::io::Error> {
+        // Nobody serves RESEND requests; close the channel so connections don't wait on it
+        self.resend_rx = None;
+
         // Semaphore to limit concurrent connections
         let connection_semaphore = Arc::new(Semaphore::new(self.max_clients));
 
@@ -120,9 +151,10 @@ impl TcpTransport {
                             // Spawn connection handler
                             let chunk_size = self.chunk_size;
                             let broadcast_tx = self.broadcast_tx.clone();
+                            let resend_tx = self.resend_tx.clone();
 
                             tokio::spawn(async move {
-                                let connection = Connection::new(stream, client_rx, addr);
+                                let connection = Connection::new(stream, client_rx, resend_tx, addr);
                                 if let Err(e) = connection.run(chunk_size).await {
                                     error!("Connection error for {}: {}", addr, e);
                                 }
@@ -0,0 +1,81 @@
+//! Integration tests for capture replay over a real TCP connection.
+//!
+//! A recorded session must reach the client byte-for-byte, including frames
+//! split across reads, with the recorded timing scaled by the speed multiplier.
+
+use p3_parser::stream::MessageFramer;
+use p3_parser::{CaptureMetadata, CaptureWriter, Message};
+use p3_test_server::generator::builder::{build_gate_passing, build_status};
+use p3_test_server::replay::CaptureReplay;
+use p3_test_server::transport::TcpTransport;
+use tokio::io::AsyncReadExt;
+use tokio::net::TcpStream;
+use tokio::time::{Duration, Instant, sleep, timeout};
+
+const DECODER_ID: u32 = 0x000C00D0;
+
+/// Gate passing split across two reads 200ms apart, then a STATUS 200ms later
+fn recorded_session() -> (Vec<u8>, Vec<u8>) {
+    let passing = build_gate_passing(1, 9992, 1_762_286_699_916_839, DECODER_ID);
+    let status = build_status(53, 16, 1, 0, DECODER_ID);
+    let (head, tail) = passing.split_at(7);
+
+    let metadata = CaptureMetadata {
+        source: "tcp://10.0.0.20:5403".to_string(),
+        started_at_us: 1_000_000,
+        recorder: "replay_tests".to_string(),
+        description: None,
+    };
+    let mut writer = CaptureWriter::new(Vec::new(), &metadata).unwrap();
+    writer.write_record(1_000_000, head).unwrap();
+    writer.write_record(1_200_000, tail).unwrap();
+    writer.write_record(1_400_000, &status).unwrap();
+
+    let wire = [passing.as_slice(), status.as_slice()].concat();
+    (writer.into_inner(), wire)
+}
+
+#[tokio::test]
+async fn test_replay_is_byte_for_byte_with_scaled_timing() {
+    let (capture, wire) = recorded_session();
+    let replay = CaptureReplay::from_reader(capture.as_slice())
+        .unwrap()
+        .with_speed(2.0);
+
+    let (transport, handle) = TcpTransport::new(0, 4, None).await.unwrap();
+    let port = transport.local_addr().unwrap().port();
+    tokio::spawn(transport.run());
+
+    let mut client = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+    // Give the transport a moment to register the client before broadcasting
+    sleep(Duration::from_millis(100)).await;
+
+    let started = Instant::now();
+    let sender = tokio::spawn(async move { replay.run(&handle).await });
+
+    let mut received = Vec::new();
+    let mut buf = [0u8; 1024];
+    while received.len() < wire.len() {
+        let n = timeout(Duration::from_secs(5), client.read(&mut buf))
+            .await
+            .expect("timed out waiting for replay")
+            .unwrap();
+        assert!(n > 0, "server closed connection");
+        received.extend_from_slice(&buf[..n]);
+    }
+    let elapsed = started.elapsed();
+
+    assert_eq!(received, wire);
+    assert_eq!(sender.await.unwrap().unwrap(), wire.len() as u64);
+    // 400ms recorded at 2x
+    assert!(elapsed >= Duration::from_millis(195), "{:?}", elapsed);
+    assert!(elapsed < Duration::from_millis(400), "{:?}", elapsed);
+
+    let messages: Vec<_> = MessageFramer::new()
+        .feed(&received)
+        .into_iter()
+        .map(Result::unwrap)
+        .collect();
+    assert!(matches!(messages[0], Message::Passing(_)));
+    assert!(matches!(messages[1], Message::Status(_)));
+}
@@ -0,0 +1,87 @@
+//! Integration tests for RESEND handling over a real TCP connection.
+//!
+//! A client that missed passings sends a RESEND request; the simulator must
+//! answer with the original PASSING frames, byte-for-byte, to that client.
+
+use p3_parser::Message;
+use p3_parser::stream::MessageFramer;
+use p3_protocol::build_resend_request;
+use p3_test_server::simulator::DecoderSimulator;
+use p3_test_server::transport::TcpTransport;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::{Duration, sleep, timeout};
+
+/// Read until `count` PASSING messages have arrived, returning their passing numbers
+async fn read_passings(
+    stream: &mut TcpStream,
+    framer: &mut MessageFramer,
+    count: usize,
+) -> Vec<u32> {
+    let mut passings = Vec::new();
+    let mut buf = [0u8; 1024];
+
+    while passings.len() < count {
+        let n = timeout(Duration::from_secs(5), stream.read(&mut buf))
+            .await
+            .expect("timed out waiting for passings")
+            .unwrap();
+        assert!(n > 0, "server closed connection");
+
+        for framed in framer.feed(&buf[..n]) {
+            if let Message::Passing(passing) = framed.unwrap() {
+                passings.push(passing.passing_number);
+            }
+        }
+    }
+
+    passings
+}
+
+#[tokio::test]
+async fn test_resend_replays_requested_passings() {
+    let (mut transport, handle) = TcpTransport::new(0, 4, None).await.unwrap();
+    let port = transport.local_addr().unwrap().port();
+    let resend_requests = transport.take_resend_requests().unwrap();
+
+    let simulator = DecoderSimulator::new(handle);
+    tokio::spawn(simulator.clone().serve_resend_requests(resend_requests));
+    tokio::spawn(transport.run());
+
+    let mut client = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+    let mut framer = MessageFramer::new();
+
+    // Give the transport a moment to register the client before broadcasting
+    sleep(Duration::from_millis(100)).await;
+
+    for _ in 0..5 {
+        simulator.send_gate_passing(9992, None).await.unwrap();
+    }
+    assert_eq!(
+        read_passings(&mut client, &mut framer, 5).await,
+        vec![1, 2, 3, 4, 5]
+    );
+
+    client.write_all(&build_resend_request(2, 3)).await.unwrap();
+    assert_eq!(read_passings(&mut client, &mut framer, 2).await, vec![2, 3]);
+}
+
+#[tokio::test]
+async fn test_resend_without_simulator_is_ignored() {
+    let (transport, handle) = TcpTransport::new(0, 4, None).await.unwrap();
+    let port = transport.local_addr().unwrap().port();
+    tokio::spawn(transport.run());
+
+    let simulator = DecoderSimulator::new(handle);
+    let mut client = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+    let mut framer = MessageFramer::new();
+
+    sleep(Duration::from_millis(100)).await;
+
+    // Connection must stay usable after an unanswerable RESEND
+    client.write_all(&build_resend_request(1, 1)).await.unwrap();
+    sleep(Duration::from_millis(50)).await;
+    simulator.send_gate_passing(9992, None).await.unwrap();
+
+    assert_eq!(read_passings(&mut client, &mut framer, 1).await, vec![1]);
+}
@@ -0,0 +1,542 @@
+use crate::outbox::Outbox;
+use p3_contracts::{
+    TRACK_INGEST_CONTRACT_VERSION_V2, TrackIngestBatchRequest, TrackIngestBatchResponse,
+    TrackIngestEvent,
+};
+use rand::Rng;
+use reqwest::StatusCode;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::Notify;
+use tokio::time::{Duration, Instant, MissedTickBehavior, interval, sleep};
+use tracing::{error, info, warn};
+
+#[derive(Debug, Clone)]
+pub struct DeliveryConfig {
+    pub ingest_url: String,
+    pub track_id: String,
+    /// Max events per POST
+    pub batch_size: usize,
+    /// Max serialized event bytes per POST; larger batches are split up front
+    pub max_batch_bytes: usize,
+    pub backoff_base: Duration,
+    pub backoff_max: Duration,
+    /// Consecutive failed attempts that open the circuit breaker
+    pub breaker_threshold: u32,
+    /// How long an open breaker blocks delivery before a single probe
+    pub breaker_cooldown: Duration,
+    /// NDJSON file receiving events the server permanently rejected
+    pub dead_letter_path: PathBuf,
+}
+
+/// Result of one [`Delivery::drain`] pass
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Drain {
+    /// The outbox is empty
+    Empty,
+    /// Delivery failed or the breaker is open; call again after this long
+    RetryIn(Duration),
+}
+
+/// Why a batch was not delivered
+#[derive(Debug)]
+enum SendError {
+    /// 400/422: a contract violation the server will never accept as-is
+    Rejected { status: StatusCode, body: String },
+    /// 413: the batch must be retried in smaller pieces
+    TooLarge,
+    /// Network errors, timeouts, 5xx and any other status: try again later
+    Retryable(String),
+}
+
+fn classify(status: StatusCode, body: String) -> SendError {
+    match status {
+        StatusCode::BAD_REQUEST | StatusCode::UNPROCESSABLE_ENTITY => {
+            SendError::Rejected { status, body }
+        }
+        StatusCode::PAYLOAD_TOO_LARGE => SendError::TooLarge,
+        // Auth, routing and throttling problems are fixed on the server side;
+        // the events themselves are fine, so keep them queued
+        _ => SendError::Retryable(format!("HTTP {}: {}", status, body)),
+    }
+}
+
+/// Delivery state served next to the outbox stats
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DeliveryStats {
+    pub breaker: BreakerState,
+    pub consecutive_failures: u32,
+    pub delivered_batches: u64,
+    pub split_batches: u64,
+    pub dead_lettered_events: u64,
+    pub last_error: Option<String>,
+    /// Delay before the next attempt while retrying, in milliseconds
+    pub retry_in_ms: Option<u64>,
+}
+
+/// Drains the outbox to the central ingest API.
+///
+/// Batches go out in `seq` order and are acked only once handled: either
+/// accepted, or (for a single permanently rejected event) written to the
+/// dead-letter file. Rejected and oversized batches are halved until the
+/// offending event is isolated, so one bad event never blocks the rest.
+/// Retryable failures back off exponentially with jitter, and repeated
+/// failures open a circuit breaker that pauses delivery for a cooldown.
+pub struct Delivery {
+    http: reqwest::Client,
+    config: DeliveryConfig,
+    outbox: Outbox,
+    backoff: Backoff,
+    breaker: CircuitBreaker,
+    stats: Arc<Mutex<DeliveryStats>>,
+}
+
+impl Delivery {
+    pub fn new(http: reqwest::Client, config: DeliveryConfig, outbox: Outbox) -> Self {
+        Self {
+            http,
+            backoff: Backoff::new(config.backoff_base, config.backoff_max),
+            breaker: CircuitBreaker::new(config.breaker_threshold, config.breaker_cooldown),
+            config,
+            outbox,
+            stats: Arc::new(Mutex::new(DeliveryStats::default())),
+        }
+    }
+
+    pub fn stats(&self) -> Arc<Mutex<DeliveryStats>> {
+        self.stats.clone()
+    }
+
+    /// Drain the outbox every `flush_interval`, or sooner when woken
+    pub async fn run(mut self, flush_interval: Duration, wake: Arc<Notify>) {
+        let mut flush_tick = interval(flush_interval);
+        flush_tick.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            tokio::select! {
+                _ = flush_tick.tick() => {}
+                _ = wake.notified() => {}
+            }
+
+            loop {
+                match self.drain().await {
+                    Ok(Drain::Empty) => break,
+                    Ok(Drain::RetryIn(delay)) => sleep(delay).await,
+                    Err(e) => {
+                        error!(error = %e, "Outbox delivery failed");
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Send queued events until the outbox is empty or delivery must pause
+    pub async fn drain(&mut self) -> anyhow::Result<Drain> {
+        loop {
+            if let Some(delay) = self.breaker.open_for(Instant::now()) {
+                self.update_stats(|s| s.retry_in_ms = Some(delay.as_millis() as u64));
+                return Ok(Drain::RetryIn(delay));
+            }
+
+            let events = self.outbox.peek(self.config.batch_size).await?;
+            if events.is_empty() {
+                self.update_stats(|s| s.retry_in_ms = None);
+                return Ok(Drain::Empty);
+            }
+
+            if let Err(reason) = self.deliver(events).await? {
+                let now = Instant::now();
+                self.breaker.record_failure(now);
+                let delay = self
+                    .breaker
+                    .open_for(now)
+                    .unwrap_or_else(|| self.backoff.next_delay());
+
+                let (breaker, failures) =
+                    (self.breaker.state(now), self.breaker.consecutive_failures());
+                warn!(
+                    error = %reason,
+                    retry_in_ms = delay.as_millis() as u64,
+                    consecutive_failures = failures,
+                    breaker = ?breaker,
+                    "Batch delivery failed, backing off",
+                );
+                self.update_stats(|s| {
+                    s.breaker = breaker;
+                    s.consecutive_failures = failures;
+                    s.last_error = Some(reason);
+                    s.retry_in_ms = Some(delay.as_millis() as u64);
+                });
+                return Ok(Drain::RetryIn(delay));
+            }
+        }
+    }
+
+    /// Handle one outbox batch, splitting it as needed.
+    ///
+    /// Returns `Err(reason)` on the first retryable failure; everything acked
+    /// before it stays acked.
+    async fn deliver(
+        &mut self,
+        events: Vec<TrackIngestEvent>,
+    ) -> anyhow::Result<Result<(), String>> {
+        let mut queue: VecDeque<_> = split_by_bytes(events, self.config.max_batch_bytes).into();
+
+        while let Some(mut batch) = queue.pop_front() {
+            let last_seq = batch.last().map_or(0, |e| e.event_id_context.seq);
+
+            match self.send(&batch).await {
+                Ok(()) => {
+                    self.outbox.ack_through(last_seq).await?;
+                    self.record_reachable();
+                    self.update_stats(|s| s.delivered_batches += 1);
+                }
+                Err(SendError::Retryable(reason)) => return Ok(Err(reason)),
+                Err(e) if batch.len() > 1 => {
+                    self.record_reachable();
+                    let tail = batch.split_off(batch.len() / 2);
+                    info!(
+                        error = ?e,
+                        head = batch.len(),
+                        tail = tail.len(),
+                        "Splitting batch to isolate rejected or oversized events",
+                    );
+                    queue.push_front(tail);
+                    queue.push_front(batch);
+                    self.update_stats(|s| s.split_batches += 1);
+                }
+                Err(e) => {
+                    self.record_reachable();
+                    self.dead_letter(&batch[0], &e)?;
+                    self.outbox.ack_through(last_seq).await?;
+                    self.update_stats(|s| s.dead_lettered_events += 1);
+                }
+            }
+        }
+
+        Ok(Ok(()))
+    }
+
+    async fn send(&self, events: &[TrackIngestEvent]) -> Result<(), SendError> {
+        let request = TrackIngestBatchRequest {
+            contract_version: TRACK_INGEST_CONTRACT_VERSION_V2.to_string(),
+            track_id: self.config.track_id.clone(),
+            events: events.to_vec(),
+        };
+
+        let response = self
+            .http
+            .post(&self.config.ingest_url)
+            .json(&request)
+            .send()
+            .await
+            .map_err(|e| SendError::Retryable(e.to_string()))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(classify(status, body));
+        }
+
+        match response.json::<TrackIngestBatchResponse>().await {
+            Ok(summary) => info!(
+                sent = events.len(),
+                accepted = summary.accepted,
+                duplicates = summary.duplicates,
+                "Delivered batch to central server",
+            ),
+            Err(e) => warn!(
+                error = %e,
+                sent = events.len(),
+                "Batch accepted but response body could not be parsed",
+            ),
+        }
+        Ok(())
+    }
+
+    /// Append a permanently rejected event to the dead-letter file.
+    ///
+    /// Synced to disk before the caller acks the event out of the outbox.
+    fn dead_letter(&self, event: &TrackIngestEvent, error: &SendError) -> anyhow::Result<()> {
+        #[derive(Serialize)]
+        struct DeadLetter<'a> {
+            dead_lettered_at_us: u64,
+            status: u16,
+            reason: &'a str,
+            event: &'a TrackIngestEvent,
+        }
+
+        let (status, reason) = match error {
+            SendError::Rejected { status, body } => (status.as_u16(), body.as_str()),
+            SendError::TooLarge => (413, "single event exceeds the server's size limit"),
+            SendError::Retryable(reason) => (0, reason.as_str()),
+        };
+        let mut line = serde_json::to_vec(&DeadLetter {
+            dead_lettered_at_us: now_unix_micros(),
+            status,
+            reason,
+            event,
+        })?;
+        line.push(b'\n');
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.config.dead_letter_path)?;
+        file.write_all(&line)?;
+        file.sync_data()?;
+
+        error!(
+            seq = event.event_id_context.seq,
+            event_id = %event.event_id,
+            status,
+            reason,
+            path = %self.config.dead_letter_path.display(),
+            "Central server rejected event, moved to dead-letter file",
+        );
+        Ok(())
+    }
+
+    /// Any HTTP response other than a retryable one proves the server is up
+    fn record_reachable(&mut self) {
+        self.breaker.record_success();
+        self.backoff.reset();
+        self.update_stats(|s| {
+            s.breaker = BreakerState::Closed;
+            s.consecutive_failures = 0;
+        });
+    }
+
+    fn update_stats(&self, update: impl FnOnce(&mut DeliveryStats)) {
+        if let Ok(mut stats) = self.stats.lock() {
+            update(&mut stats);
+        }
+    }
+}
+
+/// Split `events` into consecutive batches of at most `max_bytes` serialized
+/// event bytes; an event larger than `max_bytes` travels alone
+fn split_by_bytes(events: Vec<TrackIngestEvent>, max_bytes: usize) -> Vec<Vec<TrackIngestEvent>> {
+    let mut batches = Vec::new();
+    let mut current = Vec::new();
+    let mut current_bytes = 0;
+
+    for event in events {
+        let size = serde_json::to_vec(&event).map_or(0, |json| json.len());
+        if !current.is_empty() && current_bytes + size > max_bytes {
+            batches.push(std::mem::take(&mut current));
+            current_bytes = 0;
+        }
+        current_bytes += size;
+        current.push(event);
+    }
+    if !current.is_empty() {
+        batches.push(current);
+    }
+    batches
+}
+
+/// Exponential backoff with equal jitter.
+///
+/// Attempt `n` waits between half and all of `min(max, base * 2^n)`: the
+/// floor keeps retries from hammering a struggling server, and the random
+/// half keeps many track clients from retrying in lockstep.
+#[derive(Debug)]
+pub struct Backoff {
+    base: Duration,
+    max: Duration,
+    attempt: u32,
+}
+
+impl Backoff {
+    pub fn new(base: Duration, max: Duration) -> Self {
+        Self {
+            base,
+            max,
+            attempt: 0,
+        }
+    }
+
+    pub fn next_delay(&mut self) -> Duration {
+        let ceiling = self
+            .base
+            .saturating_mul(1u32 << self.attempt.min(20))
+            .min(self.max);
+        self.attempt = self.attempt.saturating_add(1);
+
+        let half = ceiling / 2;
+        let jitter = rand::rng().random_range(0..=(ceiling - half).as_micros() as u64);
+        half + Duration::from_micros(jitter)
+    }
+
+    pub fn reset(&mut self) {
+        self.attempt = 0;
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BreakerState {
+    #[default]
+    Closed,
+    /// Delivery paused until the cooldown ends
+    Open,
+    /// Cooldown over; the next attempt decides between closed and open
+    HalfOpen,
+}
+
+/// Opens after `threshold` consecutive failures and stays open for
+/// `cooldown`. The first attempt after the cooldown is a probe: success
+/// closes the breaker, failure reopens it for another cooldown.
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    threshold: u32,
+    cooldown: Duration,
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+}
+
+impl CircuitBreaker {
+    pub fn new(threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            threshold: threshold.max(1),
+            cooldown,
+            consecutive_failures: 0,
+            open_until: None,
+        }
+    }
+
+    pub fn state(&self, now: Instant) -> BreakerState {
+        match self.open_until {
+            None => BreakerState::Closed,
+            Some(until) if now < until => BreakerState::Open,
+            Some(_) => BreakerState::HalfOpen,
+        }
+    }
+
+    /// Time left before the next attempt is allowed, if the breaker is open
+    pub fn open_for(&self, now: Instant) -> Option<Duration> {
+        self.open_until
+            .filter(|until| now < *until)
+            .map(|until| until - now)
+    }
+
+    pub fn consecutive_failures(&self) -> u32 {
+        self.consecutive_failures
+    }
+
+    pub fn record_success(&mut self) {
+        self.consecutive_failures = 0;
+        self.open_until = None;
+    }
+
+    pub fn record_failure(&mut self, now: Instant) {
+        self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+        if self.state(now) == BreakerState::HalfOpen || self.consecutive_failures >= self.threshold
+        {
+            self.open_until = Some(now + self.cooldown);
+        }
+    }
+}
+
+fn now_unix_micros() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_micros() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_grows_within_jitter_bounds() {
+        let mut backoff = Backoff::new(Duration::from_millis(100), Duration::from_secs(1));
+
+        for ceiling_ms in [100, 200, 400, 800, 1000, 1000] {
+            let delay = backoff.next_delay();
+            let ceiling = Duration::from_millis(ceiling_ms);
+            assert!(
+                delay >= ceiling / 2,
+                "{:?} below half of {:?}",
+                delay,
+                ceiling
+            );
+            assert!(delay <= ceiling, "{:?} above {:?}", delay, ceiling);
+        }
+
+        backoff.reset();
+        assert!(backoff.next_delay() <= Duration::from_millis(100));
+    }
+
+    #[test]
+    fn test_breaker_opens_after_threshold() {
+        let mut breaker = CircuitBreaker::new(3, Duration::from_secs(30));
+        let now = Instant::now();
+
+        breaker.record_failure(now);
+        breaker.record_failure(now);
+        assert_eq!(breaker.state(now), BreakerState::Closed);
+        assert_eq!(breaker.open_for(now), None);
+
+        breaker.record_failure(now);
+        assert_eq!(breaker.state(now), BreakerState::Open);
+        assert_eq!(breaker.open_for(now), Some(Duration::from_secs(30)));
+        assert_eq!(
+            breaker.open_for(now + Duration::from_secs(10)),
+            Some(Duration::from_secs(20))
+        );
+    }
+
+    #[test]
+    fn test_half_open_probe_decides_state() {
+        let mut breaker = CircuitBreaker::new(1, Duration::from_secs(30));
+        let start = Instant::now();
+        breaker.record_failure(start);
+
+        let after = start + Duration::from_secs(30);
+        assert_eq!(breaker.state(after), BreakerState::HalfOpen);
+        assert_eq!(breaker.open_for(after), None);
+
+        // Failed probe reopens for a full cooldown
+        breaker.record_failure(after);
+        assert_eq!(breaker.open_for(after), Some(Duration::from_secs(30)));
+
+        // Successful probe closes
+        let later = after + Duration::from_secs(30);
+        breaker.record_success();
+        assert_eq!(breaker.state(later), BreakerState::Closed);
+        assert_eq!(breaker.consecutive_failures(), 0);
+    }
+
+    #[test]
+    fn test_classify_statuses() {
+        let rejected =
+            |status| matches!(classify(status, String::new()), SendError::Rejected { .. });
+        assert!(rejected(StatusCode::BAD_REQUEST));
+        assert!(rejected(StatusCode::UNPROCESSABLE_ENTITY));
+        assert!(matches!(
+            classify(StatusCode::PAYLOAD_TOO_LARGE, String::new()),
+            SendError::TooLarge
+        ));
+        for status in [
+            StatusCode::INTERNAL_SERVER_ERROR,
+            StatusCode::SERVICE_UNAVAILABLE,
+            StatusCode::TOO_MANY_REQUESTS,
+            StatusCode::UNAUTHORIZED,
+            StatusCode::NOT_FOUND,
+        ] {
+            assert!(matches!(
+                classify(status, String::new()),
+                SendError::Retryable(_)
+            ));
+        }
+    }
+}
@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+
+/// Detects holes in the decoder's PASSING sequence.
+///
+/// Decoders number passings monotonically. When the link drops, the passings
+/// emitted while we were away never reach us; the next passing we see jumps
+/// ahead. The tracker remembers the highest passing number per decoder across
+/// reconnects so that jump can be turned into a RESEND range.
+#[derive(Debug, Default)]
+pub struct PassingGapTracker {
+    max_gap: u32,
+    highest_by_decoder: HashMap<Option<String>, u32>,
+}
+
+impl PassingGapTracker {
+    /// `max_gap` bounds the size of a range worth requesting. Gaps larger than
+    /// this are treated as a decoder restart or renumbering and skipped.
+    /// A `max_gap` of 0 disables gap detection entirely.
+    pub fn new(max_gap: u32) -> Self {
+        Self {
+            max_gap,
+            highest_by_decoder: HashMap::new(),
+        }
+    }
+
+    /// Record a passing number and return the inclusive missing range, if any.
+    ///
+    /// Numbers at or below the highest seen (late arrivals or resent passings)
+    /// never produce a gap. A number far below the highest seen resets the
+    /// baseline, since the decoder has most likely restarted its counter.
+    pub fn observe(&mut self, decoder_id: Option<&str>, passing_number: u32) -> Option<(u32, u32)> {
+        if self.max_gap == 0 {
+            return None;
+        }
+
+        let key = decoder_id.map(str::to_string);
+        let Some(highest) = self.highest_by_decoder.get_mut(&key) else {
+            self.highest_by_decoder.insert(key, passing_number);
+            return None;
+        };
+
+        if passing_number <= *highest {
+            if *highest - passing_number > self.max_gap {
+                *highest = passing_number;
+            }
+            return None;
+        }
+
+        let from = *highest + 1;
+        let until = passing_number - 1;
+        *highest = passing_number;
+
+        if from > until || until - from + 1 > self.max_gap {
+            return None;
+        }
+
+        Some((from, until))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_consecutive_passings_have_no_gap() {
+        let mut tracker = PassingGapTracker::new(100);
+        assert_eq!(tracker.observe(Some("D1"), 10), None);
+        assert_eq!(tracker.observe(Some("D1"), 11), None);
+        assert_eq!(tracker.observe(Some("D1"), 12), None);
+    }
+
+    #[test]
+    fn test_jump_reports_missing_range() {
+        let mut tracker = PassingGapTracker::new(100);
+        tracker.observe(Some("D1"), 10);
+        assert_eq!(tracker.observe(Some("D1"), 15), Some((11, 14)));
+        assert_eq!(tracker.observe(Some("D1"), 16), None);
+    }
+
+    #[test]
+    fn test_resent_passings_do_not_reopen_gap() {
+        let mut tracker = PassingGapTracker::new(100);
+        tracker.observe(Some("D1"), 10);
+        tracker.observe(Some("D1"), 15);
+
+        for n in 11..=14 {
+            assert_eq!(tracker.observe(Some("D1"), n), None);
+        }
+        assert_eq!(tracker.observe(Some("D1"), 16), None);
+    }
+
+    #[test]
+    fn test_gap_larger_than_max_is_skipped() {
+        let mut tracker = PassingGapTracker::new(5);
+        tracker.observe(Some("D1"), 10);
+        assert_eq!(tracker.observe(Some("D1"), 100), None);
+        assert_eq!(tracker.observe(Some("D1"), 103), Some((101, 102)));
+    }
+
+    #[test]
+    fn test_decoder_restart_resets_baseline() {
+        let mut tracker = PassingGapTracker::new(5);
+        tracker.observe(Some("D1"), 5000);
+        assert_eq!(tracker.observe(Some("D1"), 1), None);
+        assert_eq!(tracker.observe(Some("D1"), 4), Some((2, 3)));
+    }
+
+    #[test]
+    fn test_decoders_tracked_independently() {
+        let mut tracker = PassingGapTracker::new(100);
+        tracker.observe(Some("D1"), 10);
+        tracker.observe(Some("D2"), 500);
+        assert_eq!(tracker.observe(Some("D1"), 12), Some((11, 11)));
+        assert_eq!(tracker.observe(Some("D2"), 501), None);
+    }
+
+    #[test]
+    fn test_zero_max_gap_disables_detection() {
+        let mut tracker = PassingGapTracker::new(0);
+        tracker.observe(Some("D1"), 10);
+        assert_eq!(tracker.observe(Some("D1"), 20), None);
+    }
+}
@@ -0,0 +1,9 @@
+//! Track-side forwarding of decoder messages to the central server.
+//!
+//! The binary reads the local decoder, appends every decoded message to the
+//! on-disk [`outbox::Outbox`], and a [`delivery::Delivery`] task drains the
+//! outbox to the central ingest API.
+
+pub mod delivery;
+pub mod gap;
+pub mod outbox;
@@ -1,20 +1,32 @@
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::routing::get;
+use axum::{Json, Router};
 use clap::Parser as ClapParser;
-use p3_contracts::{
-    EventIdContext, TRACK_INGEST_CONTRACT_VERSION_V2, TrackIngestBatchRequest,
-    TrackIngestBatchResponse, TrackIngestEvent, message_type_from_message,
-};
+use p3_contracts::{EventIdContext, TrackIngestEvent, message_type_from_message};
+use p3_parser::Message;
 use p3_parser::stream::MessageFramer;
+use p3_protocol::build_resend_request;
+use p3_track_client::delivery::{Backoff, Delivery, DeliveryConfig, DeliveryStats};
+use p3_track_client::gap::PassingGapTracker;
+use p3_track_client::outbox::{Outbox, OutboxLimits, OutboxStats};
+use p3_transport::DecoderEndpoint;
+use serde::Serialize;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
 use std::time::{SystemTime, UNIX_EPOCH};
-use tokio::io::AsyncReadExt;
-use tokio::net::TcpStream;
-use tokio::time::{Duration, MissedTickBehavior, interval, sleep};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::sync::Notify;
+use tokio::time::{Duration, sleep};
 use tracing::{error, info, warn};
 use uuid::Uuid;
 
 #[derive(ClapParser, Debug)]
 #[command(
     name = "p3-track-client",
-    about = "Track-side client: reads local P3 decoder TCP, decodes messages, forwards JSON to central server"
+    about = "Track-side client: reads the local P3 decoder (TCP, serial or UDP), decodes messages, forwards JSON to central server"
 )]
 struct Args {
     /// Unique ID of this track-side client instance
@@ -25,6 +37,11 @@ struct Args {
     #[arg(long)]
     track_id: String,
 
+    /// Local decoder URI: tcp://host:port, serial:///dev/ttyUSB0?baud=9600 or
+    /// udp://0.0.0.0:5403 (defaults to tcp://<decoder-host>:<decoder-port>)
+    #[arg(long, conflicts_with_all = ["decoder_host", "decoder_port"])]
+    decoder: Option<DecoderEndpoint>,
+
     /// Local decoder hostname/IP (physically at the track)
     #[arg(long, default_value = "localhost")]
     decoder_host: String,
@@ -41,13 +58,29 @@ struct Args {
     #[arg(long, default_value = "50")]
     batch_size: usize,
 
+    /// Max serialized event bytes per ingest POST (larger batches are split)
+    #[arg(long, default_value = "1048576")]
+    max_batch_bytes: usize,
+
     /// Flush interval in milliseconds if batch is not full
     #[arg(long, default_value = "1000")]
     flush_interval_ms: u64,
 
-    /// Max in-memory unsent events before oldest events are dropped
-    #[arg(long, default_value = "5000")]
-    max_buffer_events: usize,
+    /// On-disk outbox holding events until the central server accepts them
+    #[arg(long, default_value = "track-outbox.db")]
+    outbox_path: PathBuf,
+
+    /// Max undelivered events kept on disk before the oldest are dropped
+    #[arg(long, default_value = "1000000")]
+    outbox_max_events: u64,
+
+    /// Max undelivered event data (MiB) kept on disk before the oldest are dropped
+    #[arg(long, default_value = "512")]
+    outbox_max_mb: u64,
+
+    /// Address for the outbox stats endpoint (GET /stats)
+    #[arg(long, default_value = "127.0.0.1:3101")]
+    stats_addr: SocketAddr,
 
     /// Reconnect delay to local decoder after disconnect/failure
     #[arg(long, default_value = "3")]
@@ -56,6 +89,30 @@ struct Args {
     /// HTTP request timeout in seconds
     #[arg(long, default_value = "10")]
     http_timeout_secs: u64,
+
+    /// First retry delay after a failed delivery (doubles per attempt, jittered)
+    #[arg(long, default_value = "500")]
+    retry_base_ms: u64,
+
+    /// Upper bound on the retry delay
+    #[arg(long, default_value = "60")]
+    retry_max_secs: u64,
+
+    /// Consecutive delivery failures that open the circuit breaker
+    #[arg(long, default_value = "5")]
+    breaker_threshold: u32,
+
+    /// Seconds the open breaker pauses delivery before probing again
+    #[arg(long, default_value = "30")]
+    breaker_cooldown_secs: u64,
+
+    /// NDJSON file for events the central server permanently rejects (HTTP 400/422)
+    #[arg(long, default_value = "track-dead-letter.ndjson")]
+    dead_letter_path: PathBuf,
+
+    /// Largest passing-number gap to request via RESEND (0 disables recovery)
+    #[arg(long, default_value = "1000")]
+    resend_max_gap: u32,
 }
 
 #[tokio::main]
@@ -75,88 +132,138 @@ async fn run(args: Args) -> anyhow::Result<()> {
         .timeout(Duration::from_secs(args.http_timeout_secs))
         .build()?;
 
-    let boot_id = Uuid::new_v4().to_string();
-    let mut next_seq: u64 = 1;
+    let outbox = Outbox::open(
+        &args.outbox_path,
+        OutboxLimits {
+            max_events: args.outbox_max_events,
+            max_bytes: args.outbox_max_mb.saturating_mul(1024 * 1024),
+        },
+    )
+    .await?;
+    let delivery = Delivery::new(
+        http,
+        DeliveryConfig {
+            ingest_url,
+            track_id: args.track_id.clone(),
+            batch_size: args.batch_size,
+            max_batch_bytes: args.max_batch_bytes,
+            backoff_base: Duration::from_millis(args.retry_base_ms),
+            backoff_max: Duration::from_secs(args.retry_max_secs),
+            breaker_threshold: args.breaker_threshold,
+            breaker_cooldown: Duration::from_secs(args.breaker_cooldown_secs),
+            dead_letter_path: args.dead_letter_path.clone(),
+        },
+        outbox.clone(),
+    );
+    let wake_delivery = Arc::new(Notify::new());
+
+    tokio::spawn(serve_stats(
+        args.stats_addr,
+        StatsState {
+            outbox: outbox.clone(),
+            delivery: delivery.stats(),
+        },
+    ));
+    tokio::spawn(delivery.run(
+        Duration::from_millis(args.flush_interval_ms),
+        wake_delivery.clone(),
+    ));
+
+    let mut append_backoff = Backoff::new(
+        Duration::from_millis(args.retry_base_ms),
+        Duration::from_secs(args.retry_max_secs),
+    );
+    // Survives reconnects so passings missed while disconnected are detected
+    let mut gaps = PassingGapTracker::new(args.resend_max_gap);
+    let endpoint = args
+        .decoder
+        .clone()
+        .unwrap_or_else(|| DecoderEndpoint::tcp(args.decoder_host.clone(), args.decoder_port));
 
     loop {
         info!(
-            decoder_host = %args.decoder_host,
-            decoder_port = args.decoder_port,
+            decoder = %endpoint,
             track_id = %args.track_id,
             client_id = %args.client_id,
             "Connecting to local track decoder",
         );
 
-        match TcpStream::connect((args.decoder_host.as_str(), args.decoder_port)).await {
+        match endpoint.open().await {
             Ok(mut stream) => {
                 info!("Connected to local decoder");
 
                 let mut framer = MessageFramer::new();
-                let mut pending: Vec<TrackIngestEvent> = Vec::with_capacity(args.batch_size.max(8));
-                let mut flush_tick = interval(Duration::from_millis(args.flush_interval_ms));
-                flush_tick.set_missed_tick_behavior(MissedTickBehavior::Delay);
+                let mut chunk = [0u8; 4096];
 
                 loop {
-                    let mut chunk = [0u8; 4096];
-                    tokio::select! {
-                        read_res = stream.read(&mut chunk) => {
-                            let n = match read_res {
-                                Ok(n) => n,
-                                Err(e) => {
-                                    warn!(error = %e, "Decoder socket read error");
-                                    break;
-                                }
-                            };
+                    let n = match stream.read(&mut chunk).await {
+                        Ok(n) => n,
+                        Err(e) => {
+                            warn!(error = %e, "Decoder read error");
+                            break;
+                        }
+                    };
 
-                            if n == 0 {
-                                warn!("Decoder connection closed");
-                                break;
-                            }
+                    if n == 0 {
+                        warn!("Decoder connection closed");
+                        break;
+                    }
 
-                            for framed in framer.feed(&chunk[..n]) {
-                                match framed {
-                                    Ok(message) => {
-                                        pending.push(TrackIngestEvent {
-                                            event_id: Uuid::new_v4(),
-                                            track_id: args.track_id.clone(),
-                                            event_id_context: EventIdContext {
-                                                client_id: args.client_id.clone(),
-                                                boot_id: boot_id.clone(),
-                                                seq: next_seq,
-                                            },
-                                            captured_at_us: now_unix_micros(),
-                                            message_type: message_type_from_message(&message)
-                                                .to_string(),
-                                            payload: message,
-                                        });
-                                        next_seq = next_seq.saturating_add(1);
-
-                                        if pending.len() >= args.batch_size {
-                                            flush_batch(&http, &ingest_url, &args, &mut pending).await?;
-                                        }
-                                    }
-                                    Err(e) => {
-                                        warn!(error = %e, "Skipping unparsable message from decoder");
+                    let mut events = Vec::new();
+                    for framed in framer.feed(&chunk[..n]) {
+                        match framed {
+                            Ok(message) => {
+                                if let Message::Passing(passing) = &message
+                                    && let Some((from, until)) = gaps.observe(
+                                        passing.decoder_id.as_deref(),
+                                        passing.passing_number,
+                                    )
+                                {
+                                    info!(from, until, "Passing gap detected, requesting RESEND");
+                                    if let Err(e) =
+                                        stream.write_all(&build_resend_request(from, until)).await
+                                    {
+                                        warn!(error = %e, "Failed to send RESEND request to decoder");
                                     }
                                 }
+
+                                events.push(TrackIngestEvent {
+                                    event_id: Uuid::new_v4(),
+                                    track_id: args.track_id.clone(),
+                                    event_id_context: EventIdContext {
+                                        client_id: args.client_id.clone(),
+                                        boot_id: outbox.boot_id().to_string(),
+                                        seq: outbox.take_seq(),
+                                    },
+                                    captured_at_us: now_unix_micros(),
+                                    message_type: message_type_from_message(&message).to_string(),
+                                    payload: message,
+                                });
                             }
-                        }
-                        _ = flush_tick.tick() => {
-                            if !pending.is_empty() {
-                                flush_batch(&http, &ingest_url, &args, &mut pending).await?;
+                            Err(e) => {
+                                warn!(error = %e, "Skipping unparsable message from decoder");
                             }
                         }
                     }
 
-                    trim_pending_if_needed(&args, &mut pending);
+                    // Commit this read to disk before reading the next one
+                    append_until_committed(&outbox, &events, &mut append_backoff).await;
+                    if outbox.pending_events() >= args.batch_size as u64 {
+                        wake_delivery.notify_one();
+                    }
                 }
 
-                if !pending.is_empty() {
-                    flush_batch(&http, &ingest_url, &args, &mut pending).await?;
-                }
+                let stats = framer.stats();
+                info!(
+                    frames = stats.frames,
+                    bytes_skipped = stats.bytes_skipped,
+                    crc_failures = stats.crc_failures,
+                    resyncs = stats.resyncs,
+                    "Decoder stream framing summary",
+                );
             }
             Err(e) => {
-                warn!(error = %e, "Failed to connect to local decoder");
+                warn!(error = %e, "Failed to open local decoder");
             }
         }
 
@@ -164,85 +271,79 @@ async fn run(args: Args) -> anyhow::Result<()> {
     }
 }
 
-fn trim_pending_if_needed(args: &Args, pending: &mut Vec<TrackIngestEvent>) {
-    if pending.len() <= args.max_buffer_events {
-        return;
+/// Write a read's events to the outbox, retrying until it succeeds.
+///
+/// A busy database or full disk holds up the decoder reads instead of
+/// losing the events or stopping the client.
+async fn append_until_committed(
+    outbox: &Outbox,
+    events: &[TrackIngestEvent],
+    backoff: &mut Backoff,
+) {
+    loop {
+        match outbox.append(events).await {
+            Ok(()) => {
+                backoff.reset();
+                return;
+            }
+            Err(e) => {
+                let delay = backoff.next_delay();
+                warn!(
+                    error = %e,
+                    events = events.len(),
+                    retry_in_ms = delay.as_millis() as u64,
+                    "Failed to write decoder events to outbox, retrying",
+                );
+                sleep(delay).await;
+            }
+        }
     }
+}
 
-    let to_drop = pending.len() - args.max_buffer_events;
-    pending.drain(..to_drop);
-    warn!(
-        dropped_events = to_drop,
-        max_buffer_events = args.max_buffer_events,
-        "Dropped oldest unsent events due to backpressure",
-    );
+#[derive(Clone)]
+struct StatsState {
+    outbox: Outbox,
+    delivery: Arc<Mutex<DeliveryStats>>,
 }
 
-async fn flush_batch(
-    http: &reqwest::Client,
-    ingest_url: &str,
-    args: &Args,
-    pending: &mut Vec<TrackIngestEvent>,
-) -> anyhow::Result<()> {
-    if pending.is_empty() {
-        return Ok(());
-    }
+#[derive(Serialize)]
+struct ClientStats {
+    #[serde(flatten)]
+    outbox: OutboxStats,
+    delivery: DeliveryStats,
+}
 
-    let events = std::mem::take(pending);
-    let event_count = events.len();
-    let request = TrackIngestBatchRequest {
-        contract_version: TRACK_INGEST_CONTRACT_VERSION_V2.to_string(),
-        track_id: args.track_id.clone(),
-        events,
-    };
+async fn serve_stats(addr: SocketAddr, state: StatsState) {
+    let app = Router::new()
+        .route("/stats", get(stats_handler))
+        .with_state(state);
 
-    let response = http.post(ingest_url).json(&request).send().await;
-    match response {
-        Ok(resp) if resp.status().is_success() => {
-            let body = resp.json::<TrackIngestBatchResponse>().await;
-            match body {
-                Ok(summary) => {
-                    info!(
-                        sent = event_count,
-                        accepted = summary.accepted,
-                        duplicates = summary.duplicates,
-                        "Delivered batch to central server",
-                    );
-                }
-                Err(e) => {
-                    warn!(
-                        error = %e,
-                        sent = event_count,
-                        "Batch accepted but response body could not be parsed",
-                    );
-                }
-            }
-            Ok(())
-        }
-        Ok(resp) => {
-            let status = resp.status();
-            let body = resp.text().await.unwrap_or_default();
-            *pending = request.events;
-            error!(
-                status = %status,
-                body = %body,
-                queued_events = pending.len(),
-                "Central server rejected ingest batch",
-            );
-            Ok(())
-        }
+    let listener = match TcpListener::bind(addr).await {
+        Ok(listener) => listener,
         Err(e) => {
-            *pending = request.events;
-            warn!(
-                error = %e,
-                queued_events = pending.len(),
-                "Failed to send batch to central server",
-            );
-            Ok(())
+            error!(error = %e, addr = %addr, "Failed to bind stats endpoint");
+            return;
         }
+    };
+    info!(addr = %addr, "Stats endpoint listening on /stats");
+    if let Err(e) = axum::serve(listener, app).await {
+        error!(error = %e, "Stats endpoint stopped");
     }
 }
 
+async fn stats_handler(State(state): State<StatsState>) -> Result<Json<ClientStats>, StatusCode> {
+    let outbox = state.outbox.stats().await.map_err(|e| {
+        warn!(error = %e, "Failed to read outbox stats");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let delivery = state
+        .delivery
+        .lock()
+        .map(|stats| stats.clone())
+        .unwrap_or_default();
+    Ok(Json(ClientStats { outbox, delivery }))
+}
+
 fn now_unix_micros() -> u64 {
     let dur = SystemTime::now()
         .duration_since(UNIX_EPOCH)
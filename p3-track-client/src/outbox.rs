@@ -0,0 +1,490 @@
+use p3_contracts::TrackIngestEvent;
+use serde::Serialize;
+use sqlx::sqlite::{
+    SqliteConnectOptions, SqliteJournalMode, SqlitePool, SqlitePoolOptions, SqliteSynchronous,
+};
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// Durable write-ahead queue of ingest events awaiting delivery.
+///
+/// Events are committed to SQLite before the decoder read that produced them
+/// is considered handled, and leave the queue only once the central server
+/// has accepted them. The client's `boot_id` and next `seq` live in the same
+/// database, so a restarted client keeps its idempotency-key sequence and
+/// replays anything undelivered in `seq` order.
+///
+/// Disk use is bounded by [`OutboxLimits`]: past either limit the oldest
+/// undelivered events are dropped. Freed pages are reused by later inserts,
+/// so the file does not grow much beyond the peak queue size.
+#[derive(Clone)]
+pub struct Outbox {
+    pool: SqlitePool,
+    limits: OutboxLimits,
+    boot_id: Arc<str>,
+    counters: Arc<Counters>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct OutboxLimits {
+    pub max_events: u64,
+    /// Serialized event bytes, not counting SQLite overhead
+    pub max_bytes: u64,
+}
+
+#[derive(Default)]
+struct Counters {
+    next_seq: AtomicU64,
+    pending_events: AtomicU64,
+    pending_bytes: AtomicU64,
+    appended_events: AtomicU64,
+    delivered_events: AtomicU64,
+    dropped_events: AtomicU64,
+}
+
+/// Snapshot served by the client's stats endpoint
+#[derive(Debug, Clone, Serialize)]
+pub struct OutboxStats {
+    pub boot_id: String,
+    pub next_seq: u64,
+    pub pending_events: u64,
+    pub pending_bytes: u64,
+    pub oldest_pending_seq: Option<u64>,
+    pub oldest_pending_captured_at_us: Option<u64>,
+    pub max_events: u64,
+    pub max_bytes: u64,
+    /// Counters since this process started
+    pub appended_events: u64,
+    pub delivered_events: u64,
+    pub dropped_events: u64,
+}
+
+impl Outbox {
+    /// Open (or create) the outbox database at `path`.
+    ///
+    /// A new database gets a fresh `boot_id`; an existing one resumes its
+    /// `boot_id` and `seq` counter and keeps its undelivered events.
+    pub async fn open(path: &Path, limits: OutboxLimits) -> anyhow::Result<Self> {
+        let options = SqliteConnectOptions::new()
+            .filename(path)
+            .create_if_missing(true)
+            .journal_mode(SqliteJournalMode::Wal)
+            // Survive power loss at the track, not just a process crash
+            .synchronous(SqliteSynchronous::Full);
+        let pool = SqlitePoolOptions::new()
+            .max_connections(4)
+            .connect_with(options)
+            .await?;
+
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS outbox_meta (key TEXT PRIMARY KEY, value TEXT NOT NULL)",
+        )
+        .execute(&pool)
+        .await?;
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS outbox_events (
+                seq INTEGER PRIMARY KEY,
+                captured_at_us INTEGER NOT NULL,
+                size INTEGER NOT NULL,
+                event_json TEXT NOT NULL
+            )",
+        )
+        .execute(&pool)
+        .await?;
+
+        let boot_id = match read_meta(&pool, "boot_id").await? {
+            Some(boot_id) => boot_id,
+            None => {
+                let boot_id = Uuid::new_v4().to_string();
+                write_meta(&pool, "boot_id", &boot_id).await?;
+                boot_id
+            }
+        };
+        let stored_next_seq = read_meta(&pool, "next_seq")
+            .await?
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(1);
+
+        let (pending_events, pending_bytes, max_seq): (i64, i64, Option<i64>) =
+            sqlx::query_as("SELECT COUNT(*), COALESCE(SUM(size), 0), MAX(seq) FROM outbox_events")
+                .fetch_one(&pool)
+                .await?;
+        let next_seq = stored_next_seq.max(max_seq.map_or(1, |s| s as u64 + 1));
+
+        let counters = Counters {
+            next_seq: AtomicU64::new(next_seq),
+            pending_events: AtomicU64::new(pending_events as u64),
+            pending_bytes: AtomicU64::new(pending_bytes as u64),
+            ..Counters::default()
+        };
+
+        info!(
+            path = %path.display(),
+            boot_id = %boot_id,
+            next_seq,
+            pending_events,
+            "Outbox opened",
+        );
+
+        let outbox = Self {
+            pool,
+            limits,
+            boot_id: boot_id.into(),
+            counters: Arc::new(counters),
+        };
+        outbox.trim().await?;
+        Ok(outbox)
+    }
+
+    pub fn boot_id(&self) -> &str {
+        &self.boot_id
+    }
+
+    /// Reserve the next `seq` for a new event.
+    ///
+    /// A reserved seq that is never appended leaves a harmless gap. Seqs of
+    /// appended events are never handed out again, even after a restart.
+    pub fn take_seq(&self) -> u64 {
+        self.counters.next_seq.fetch_add(1, Ordering::Relaxed)
+    }
+
+    pub fn pending_events(&self) -> u64 {
+        self.counters.pending_events.load(Ordering::Relaxed)
+    }
+
+    /// Durably store `events` in one transaction, then enforce the limits
+    pub async fn append(&self, events: &[TrackIngestEvent]) -> anyhow::Result<()> {
+        let Some(last) = events.last() else {
+            return Ok(());
+        };
+
+        let mut tx = self.pool.begin().await?;
+        let mut bytes = 0u64;
+        for event in events {
+            let json = serde_json::to_string(event)?;
+            bytes += json.len() as u64;
+            sqlx::query(
+                "INSERT INTO outbox_events (seq, captured_at_us, size, event_json) VALUES (?, ?, ?, ?)",
+            )
+            .bind(event.event_id_context.seq as i64)
+            .bind(event.captured_at_us as i64)
+            .bind(json.len() as i64)
+            .bind(json)
+            .execute(&mut *tx)
+            .await?;
+        }
+        sqlx::query(
+            "INSERT INTO outbox_meta (key, value) VALUES ('next_seq', ?)
+             ON CONFLICT(key) DO UPDATE SET value = MAX(CAST(value AS INTEGER), CAST(excluded.value AS INTEGER))",
+        )
+        .bind((last.event_id_context.seq + 1).to_string())
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        let count = events.len() as u64;
+        self.counters
+            .pending_events
+            .fetch_add(count, Ordering::Relaxed);
+        self.counters
+            .pending_bytes
+            .fetch_add(bytes, Ordering::Relaxed);
+        self.counters
+            .appended_events
+            .fetch_add(count, Ordering::Relaxed);
+
+        self.trim().await
+    }
+
+    /// Oldest `limit` undelivered events in `seq` order
+    pub async fn peek(&self, limit: usize) -> anyhow::Result<Vec<TrackIngestEvent>> {
+        let rows: Vec<(String,)> =
+            sqlx::query_as("SELECT event_json FROM outbox_events ORDER BY seq LIMIT ?")
+                .bind(limit as i64)
+                .fetch_all(&self.pool)
+                .await?;
+
+        rows.into_iter()
+            .map(|(json,)| Ok(serde_json::from_str(&json)?))
+            .collect()
+    }
+
+    /// Remove every event up to and including `seq` after delivery
+    pub async fn ack_through(&self, seq: u64) -> anyhow::Result<()> {
+        let removed = self
+            .delete(
+                "DELETE FROM outbox_events WHERE seq <= ? RETURNING size",
+                seq as i64,
+            )
+            .await?;
+        self.counters
+            .delivered_events
+            .fetch_add(removed, Ordering::Relaxed);
+        Ok(())
+    }
+
+    pub async fn stats(&self) -> anyhow::Result<OutboxStats> {
+        let oldest: Option<(i64, i64)> =
+            sqlx::query_as("SELECT seq, captured_at_us FROM outbox_events ORDER BY seq LIMIT 1")
+                .fetch_optional(&self.pool)
+                .await?;
+
+        let load = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+        Ok(OutboxStats {
+            boot_id: self.boot_id.to_string(),
+            next_seq: load(&self.counters.next_seq),
+            pending_events: load(&self.counters.pending_events),
+            pending_bytes: load(&self.counters.pending_bytes),
+            oldest_pending_seq: oldest.map(|(seq, _)| seq as u64),
+            oldest_pending_captured_at_us: oldest.map(|(_, at)| at as u64),
+            max_events: self.limits.max_events,
+            max_bytes: self.limits.max_bytes,
+            appended_events: load(&self.counters.appended_events),
+            delivered_events: load(&self.counters.delivered_events),
+            dropped_events: load(&self.counters.dropped_events),
+        })
+    }
+
+    /// Drop the oldest events until both limits hold again
+    async fn trim(&self) -> anyhow::Result<()> {
+        loop {
+            let events = self.pending_events();
+            let bytes = self.counters.pending_bytes.load(Ordering::Relaxed);
+            let excess_events = events.saturating_sub(self.limits.max_events);
+            let excess_bytes = bytes.saturating_sub(self.limits.max_bytes);
+            if excess_events == 0 && excess_bytes == 0 {
+                return Ok(());
+            }
+
+            // Estimate how many average-sized events cover the byte overrun
+            let average = (bytes / events.max(1)).max(1);
+            let to_drop = excess_events.max(excess_bytes.div_ceil(average)).max(1);
+
+            let dropped = self
+                .delete(
+                    "DELETE FROM outbox_events WHERE seq IN
+                     (SELECT seq FROM outbox_events ORDER BY seq LIMIT ?) RETURNING size",
+                    to_drop as i64,
+                )
+                .await?;
+            if dropped == 0 {
+                return Ok(());
+            }
+
+            self.counters
+                .dropped_events
+                .fetch_add(dropped, Ordering::Relaxed);
+            warn!(
+                dropped_events = dropped,
+                max_events = self.limits.max_events,
+                max_bytes = self.limits.max_bytes,
+                "Outbox full, dropped oldest undelivered events",
+            );
+        }
+    }
+
+    /// Run a `DELETE ... RETURNING size` and settle the pending counters
+    async fn delete(&self, sql: &str, bind: i64) -> anyhow::Result<u64> {
+        let sizes: Vec<(i64,)> = sqlx::query_as(sql).bind(bind).fetch_all(&self.pool).await?;
+
+        let count = sizes.len() as u64;
+        let bytes: u64 = sizes.iter().map(|(size,)| *size as u64).sum();
+        self.counters
+            .pending_events
+            .fetch_sub(count, Ordering::Relaxed);
+        self.counters
+            .pending_bytes
+            .fetch_sub(bytes, Ordering::Relaxed);
+        Ok(count)
+    }
+}
+
+async fn read_meta(pool: &SqlitePool, key: &str) -> anyhow::Result<Option<String>> {
+    let row: Option<(String,)> = sqlx::query_as("SELECT value FROM outbox_meta WHERE key = ?")
+        .bind(key)
+        .fetch_optional(pool)
+        .await?;
+    Ok(row.map(|(value,)| value))
+}
+
+async fn write_meta(pool: &SqlitePool, key: &str, value: &str) -> anyhow::Result<()> {
+    sqlx::query(
+        "INSERT INTO outbox_meta (key, value) VALUES (?, ?)
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+    )
+    .bind(key)
+    .bind(value)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use p3_contracts::EventIdContext;
+    use p3_parser::{Message, StatusMessage};
+    use std::path::PathBuf;
+
+    const UNLIMITED: OutboxLimits = OutboxLimits {
+        max_events: u64::MAX,
+        max_bytes: u64::MAX,
+    };
+
+    /// Database file removed when the test ends
+    struct TempDb(PathBuf);
+
+    impl TempDb {
+        fn new() -> Self {
+            Self(std::env::temp_dir().join(format!("p3-outbox-{}.db", Uuid::new_v4())))
+        }
+    }
+
+    impl Drop for TempDb {
+        fn drop(&mut self) {
+            for suffix in ["", "-wal", "-shm"] {
+                let _ = std::fs::remove_file(format!("{}{}", self.0.display(), suffix));
+            }
+        }
+    }
+
+    fn event(outbox: &Outbox) -> TrackIngestEvent {
+        let seq = outbox.take_seq();
+        TrackIngestEvent {
+            event_id: Uuid::new_v4(),
+            track_id: "track-a".to_string(),
+            event_id_context: EventIdContext {
+                client_id: "client-1".to_string(),
+                boot_id: outbox.boot_id().to_string(),
+                seq,
+            },
+            captured_at_us: 1_000_000 + seq,
+            message_type: "STATUS".to_string(),
+            payload: Message::Status(StatusMessage {
+                noise: 53,
+                gps_status: 1,
+                temperature: 16,
+                satellites: 0,
+                decoder_id: Some("D0000C00".to_string()),
+                extra_fields: Vec::new(),
+            }),
+        }
+    }
+
+    fn seqs(events: &[TrackIngestEvent]) -> Vec<u64> {
+        events.iter().map(|e| e.event_id_context.seq).collect()
+    }
+
+    #[tokio::test]
+    async fn test_events_survive_restart_in_seq_order() {
+        let db = TempDb::new();
+
+        let outbox = Outbox::open(&db.0, UNLIMITED).await.unwrap();
+        let boot_id = outbox.boot_id().to_string();
+        let events: Vec<_> = (0..5).map(|_| event(&outbox)).collect();
+        outbox.append(&events[..3]).await.unwrap();
+        outbox.append(&events[3..]).await.unwrap();
+        outbox.ack_through(2).await.unwrap();
+        drop(outbox);
+
+        let reopened = Outbox::open(&db.0, UNLIMITED).await.unwrap();
+        assert_eq!(reopened.boot_id(), boot_id);
+        assert_eq!(reopened.pending_events(), 3);
+        let pending = reopened.peek(10).await.unwrap();
+        assert_eq!(seqs(&pending), vec![3, 4, 5]);
+        assert_eq!(pending[0].event_id, events[2].event_id);
+        // New events continue the sequence instead of reusing keys
+        assert_eq!(reopened.take_seq(), 6);
+    }
+
+    #[tokio::test]
+    async fn test_reserved_seq_is_not_reused_after_restart() {
+        let db = TempDb::new();
+
+        let outbox = Outbox::open(&db.0, UNLIMITED).await.unwrap();
+        let delivered = event(&outbox);
+        outbox.append(&[delivered]).await.unwrap();
+        outbox.ack_through(1).await.unwrap();
+        drop(outbox);
+
+        // The queue is empty, but seq 1 has been used under this boot_id
+        let reopened = Outbox::open(&db.0, UNLIMITED).await.unwrap();
+        assert_eq!(reopened.pending_events(), 0);
+        assert_eq!(reopened.take_seq(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_event_limit_drops_oldest() {
+        let db = TempDb::new();
+        let outbox = Outbox::open(
+            &db.0,
+            OutboxLimits {
+                max_events: 3,
+                max_bytes: u64::MAX,
+            },
+        )
+        .await
+        .unwrap();
+
+        let events: Vec<_> = (0..5).map(|_| event(&outbox)).collect();
+        outbox.append(&events).await.unwrap();
+
+        assert_eq!(seqs(&outbox.peek(10).await.unwrap()), vec![3, 4, 5]);
+        let stats = outbox.stats().await.unwrap();
+        assert_eq!(stats.pending_events, 3);
+        assert_eq!(stats.dropped_events, 2);
+        assert_eq!(stats.oldest_pending_seq, Some(3));
+    }
+
+    #[tokio::test]
+    async fn test_byte_limit_drops_oldest() {
+        let db = TempDb::new();
+        let outbox = Outbox::open(&db.0, UNLIMITED).await.unwrap();
+        let one = event(&outbox);
+        let size = serde_json::to_string(&one).unwrap().len() as u64;
+        drop(outbox);
+
+        let outbox = Outbox::open(
+            &db.0,
+            OutboxLimits {
+                max_events: u64::MAX,
+                max_bytes: size * 2 + size / 2,
+            },
+        )
+        .await
+        .unwrap();
+        let events: Vec<_> = (0..4).map(|_| event(&outbox)).collect();
+        outbox.append(&events).await.unwrap();
+
+        let stats = outbox.stats().await.unwrap();
+        assert_eq!(stats.pending_events, 2);
+        assert!(stats.pending_bytes <= stats.max_bytes);
+        // Nothing was appended before reopening, so seqs restart at 1
+        assert_eq!(seqs(&outbox.peek(10).await.unwrap()), vec![3, 4]);
+    }
+
+    #[tokio::test]
+    async fn test_stats_track_delivery() {
+        let db = TempDb::new();
+        let outbox = Outbox::open(&db.0, UNLIMITED).await.unwrap();
+
+        let events: Vec<_> = (0..4).map(|_| event(&outbox)).collect();
+        outbox.append(&events).await.unwrap();
+        let batch = outbox.peek(3).await.unwrap();
+        outbox
+            .ack_through(batch.last().unwrap().event_id_context.seq)
+            .await
+            .unwrap();
+
+        let stats = outbox.stats().await.unwrap();
+        assert_eq!(stats.appended_events, 4);
+        assert_eq!(stats.delivered_events, 3);
+        assert_eq!(stats.pending_events, 1);
+        assert_eq!(stats.oldest_pending_seq, Some(4));
+        assert_eq!(stats.oldest_pending_captured_at_us, Some(1_000_004));
+        assert_eq!(stats.next_seq, 5);
+    }
+}
@@ -0,0 +1,362 @@
+//! Integration tests for outbox delivery against a local axum stub of the
+//! central ingest API.
+//!
+//! Each test scripts the stub's responses per request, drains a real on-disk
+//! outbox, and checks what reached the server, what is left queued and what
+//! landed in the dead-letter file.
+
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::routing::post;
+use axum::{Json, Router};
+use p3_contracts::{
+    EventIdContext, TrackIngestBatchRequest, TrackIngestBatchResponse, TrackIngestEvent,
+};
+use p3_parser::{Message, StatusMessage};
+use p3_track_client::delivery::{BreakerState, Delivery, DeliveryConfig, Drain};
+use p3_track_client::outbox::{Outbox, OutboxLimits};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tokio::net::TcpListener;
+use tokio::time::Duration;
+use uuid::Uuid;
+
+/// Decides the stub's status for a request, given the seqs in the batch and
+/// how many requests came before it
+type Script = Box<dyn Fn(&[u64], usize) -> StatusCode + Send + Sync>;
+
+#[derive(Clone)]
+struct Stub {
+    script: Arc<Script>,
+    /// Seqs of every batch received, in arrival order
+    requests: Arc<Mutex<Vec<Vec<u64>>>>,
+}
+
+impl Stub {
+    fn requests(&self) -> Vec<Vec<u64>> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// Seqs the stub answered with 200, in order
+    fn accepted(&self) -> Vec<u64> {
+        let requests = self.requests();
+        requests
+            .iter()
+            .enumerate()
+            .filter(|(i, seqs)| (self.script)(seqs, *i).is_success())
+            .flat_map(|(_, seqs)| seqs.clone())
+            .collect()
+    }
+}
+
+async fn ingest(
+    State(stub): State<Stub>,
+    Json(request): Json<TrackIngestBatchRequest>,
+) -> Result<Json<TrackIngestBatchResponse>, (StatusCode, String)> {
+    let seqs: Vec<u64> = request
+        .events
+        .iter()
+        .map(|e| e.event_id_context.seq)
+        .collect();
+    let index = {
+        let mut requests = stub.requests.lock().unwrap();
+        requests.push(seqs.clone());
+        requests.len() - 1
+    };
+
+    let status = (stub.script)(&seqs, index);
+    if status.is_success() {
+        Ok(Json(TrackIngestBatchResponse {
+            accepted: seqs.len(),
+            duplicates: 0,
+        }))
+    } else {
+        Err((status, format!("stub status {}", status.as_u16())))
+    }
+}
+
+async fn start_stub(
+    script: impl Fn(&[u64], usize) -> StatusCode + Send + Sync + 'static,
+) -> (Stub, String) {
+    let stub = Stub {
+        script: Arc::new(Box::new(script)),
+        requests: Arc::new(Mutex::new(Vec::new())),
+    };
+    let app = Router::new()
+        .route("/api/ingest/batch", post(ingest))
+        .with_state(stub.clone());
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/api/ingest/batch", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    (stub, url)
+}
+
+/// Outbox database and dead-letter file, removed when the test ends
+struct Fixture {
+    db: PathBuf,
+    dead_letter: PathBuf,
+}
+
+impl Fixture {
+    fn new() -> Self {
+        let id = Uuid::new_v4();
+        let dir = std::env::temp_dir();
+        Self {
+            db: dir.join(format!("p3-delivery-{}.db", id)),
+            dead_letter: dir.join(format!("p3-delivery-{}.ndjson", id)),
+        }
+    }
+
+    async fn outbox_with(&self, events: usize) -> Outbox {
+        let outbox = Outbox::open(
+            &self.db,
+            OutboxLimits {
+                max_events: u64::MAX,
+                max_bytes: u64::MAX,
+            },
+        )
+        .await
+        .unwrap();
+
+        let events: Vec<_> = (0..events).map(|_| status_event(&outbox)).collect();
+        outbox.append(&events).await.unwrap();
+        outbox
+    }
+
+    fn config(&self, ingest_url: String) -> DeliveryConfig {
+        DeliveryConfig {
+            ingest_url,
+            track_id: "track-a".to_string(),
+            batch_size: 50,
+            max_batch_bytes: 1024 * 1024,
+            backoff_base: Duration::from_millis(10),
+            backoff_max: Duration::from_millis(40),
+            breaker_threshold: 100,
+            breaker_cooldown: Duration::from_secs(60),
+            dead_letter_path: self.dead_letter.clone(),
+        }
+    }
+
+    /// Seqs of the events in the dead-letter file, with their recorded status
+    fn dead_lettered(&self) -> Vec<(u64, u64)> {
+        let Ok(contents) = std::fs::read_to_string(&self.dead_letter) else {
+            return Vec::new();
+        };
+        contents
+            .lines()
+            .map(|line| {
+                let record: serde_json::Value = serde_json::from_str(line).unwrap();
+                (
+                    record["event"]["event_id_context"]["seq"].as_u64().unwrap(),
+                    record["status"].as_u64().unwrap(),
+                )
+            })
+            .collect()
+    }
+}
+
+impl Drop for Fixture {
+    fn drop(&mut self) {
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", self.db.display(), suffix));
+        }
+        let _ = std::fs::remove_file(&self.dead_letter);
+    }
+}
+
+fn status_event(outbox: &Outbox) -> TrackIngestEvent {
+    let seq = outbox.take_seq();
+    TrackIngestEvent {
+        event_id: Uuid::new_v4(),
+        track_id: "track-a".to_string(),
+        event_id_context: EventIdContext {
+            client_id: "client-1".to_string(),
+            boot_id: outbox.boot_id().to_string(),
+            seq,
+        },
+        captured_at_us: 1_000_000 + seq,
+        message_type: "STATUS".to_string(),
+        payload: Message::Status(StatusMessage {
+            noise: 53,
+            gps_status: 1,
+            temperature: 16,
+            satellites: 0,
+            decoder_id: Some("D0000C00".to_string()),
+            extra_fields: Vec::new(),
+        }),
+    }
+}
+
+/// Drain until empty, sleeping through retries, with an overall deadline
+async fn drain_to_empty(delivery: &mut Delivery) {
+    tokio::time::timeout(Duration::from_secs(10), async {
+        loop {
+            match delivery.drain().await.unwrap() {
+                Drain::Empty => return,
+                Drain::RetryIn(delay) => tokio::time::sleep(delay).await,
+            }
+        }
+    })
+    .await
+    .expect("outbox did not drain");
+}
+
+#[tokio::test]
+async fn test_server_errors_are_retried_with_backoff() {
+    let (stub, url) = start_stub(|_, index| match index {
+        0 => StatusCode::SERVICE_UNAVAILABLE,
+        1 => StatusCode::BAD_GATEWAY,
+        _ => StatusCode::OK,
+    })
+    .await;
+    let fixture = Fixture::new();
+    let outbox = fixture.outbox_with(5).await;
+    let mut delivery = Delivery::new(reqwest::Client::new(), fixture.config(url), outbox.clone());
+
+    let first = delivery.drain().await.unwrap();
+    let Drain::RetryIn(delay) = first else {
+        panic!("expected a retry, got {:?}", first);
+    };
+    assert!(delay >= Duration::from_millis(5) && delay <= Duration::from_millis(10));
+    assert_eq!(outbox.pending_events(), 5);
+
+    drain_to_empty(&mut delivery).await;
+
+    // The same batch is resent until accepted, nothing is lost or reordered
+    assert_eq!(stub.requests(), vec![vec![1, 2, 3, 4, 5]; 3]);
+    assert_eq!(outbox.pending_events(), 0);
+    assert!(fixture.dead_lettered().is_empty());
+    let stats = delivery.stats().lock().unwrap().clone();
+    assert_eq!(stats.delivered_batches, 1);
+    assert_eq!(stats.consecutive_failures, 0);
+}
+
+#[tokio::test]
+async fn test_network_errors_are_retried() {
+    // Nothing listens on this port
+    let closed = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/api/ingest/batch", closed.local_addr().unwrap());
+    drop(closed);
+
+    let fixture = Fixture::new();
+    let outbox = fixture.outbox_with(3).await;
+    let mut delivery = Delivery::new(reqwest::Client::new(), fixture.config(url), outbox.clone());
+
+    assert!(matches!(delivery.drain().await.unwrap(), Drain::RetryIn(_)));
+    assert_eq!(outbox.pending_events(), 3);
+    let stats = delivery.stats().lock().unwrap().clone();
+    assert_eq!(stats.consecutive_failures, 1);
+    assert!(stats.last_error.is_some());
+}
+
+#[tokio::test]
+async fn test_rejected_event_is_isolated_and_dead_lettered() {
+    // Any batch containing seq 3 violates the contract
+    let (stub, url) = start_stub(|seqs, _| {
+        if seqs.contains(&3) {
+            StatusCode::BAD_REQUEST
+        } else {
+            StatusCode::OK
+        }
+    })
+    .await;
+    let fixture = Fixture::new();
+    let outbox = fixture.outbox_with(6).await;
+    let mut delivery = Delivery::new(reqwest::Client::new(), fixture.config(url), outbox.clone());
+
+    drain_to_empty(&mut delivery).await;
+
+    assert_eq!(stub.accepted(), vec![1, 2, 4, 5, 6]);
+    assert_eq!(fixture.dead_lettered(), vec![(3, 400)]);
+    assert_eq!(outbox.pending_events(), 0);
+    let stats = delivery.stats().lock().unwrap().clone();
+    assert_eq!(stats.dead_lettered_events, 1);
+    assert!(stats.split_batches >= 2);
+}
+
+#[tokio::test]
+async fn test_payload_too_large_splits_batch() {
+    let (stub, url) = start_stub(|seqs, _| {
+        if seqs.len() > 2 {
+            StatusCode::PAYLOAD_TOO_LARGE
+        } else {
+            StatusCode::OK
+        }
+    })
+    .await;
+    let fixture = Fixture::new();
+    let outbox = fixture.outbox_with(7).await;
+    let mut delivery = Delivery::new(reqwest::Client::new(), fixture.config(url), outbox.clone());
+
+    drain_to_empty(&mut delivery).await;
+
+    assert_eq!(stub.accepted(), (1..=7).collect::<Vec<_>>());
+    assert!(fixture.dead_lettered().is_empty());
+    assert_eq!(outbox.pending_events(), 0);
+}
+
+#[tokio::test]
+async fn test_batches_are_split_by_bytes_before_sending() {
+    let (stub, url) = start_stub(|_, _| StatusCode::OK).await;
+    let fixture = Fixture::new();
+    let outbox = fixture.outbox_with(6).await;
+    let event_size = serde_json::to_vec(&outbox.peek(1).await.unwrap()[0])
+        .unwrap()
+        .len();
+
+    let mut config = fixture.config(url);
+    config.max_batch_bytes = event_size * 2;
+    let mut delivery = Delivery::new(reqwest::Client::new(), config, outbox);
+
+    drain_to_empty(&mut delivery).await;
+
+    assert_eq!(stub.requests(), vec![vec![1, 2], vec![3, 4], vec![5, 6]]);
+}
+
+#[tokio::test]
+async fn test_breaker_opens_and_probes_after_cooldown() {
+    let (stub, url) = start_stub(|_, index| {
+        if index < 3 {
+            StatusCode::INTERNAL_SERVER_ERROR
+        } else {
+            StatusCode::OK
+        }
+    })
+    .await;
+    let fixture = Fixture::new();
+    let outbox = fixture.outbox_with(2).await;
+    let mut config = fixture.config(url);
+    config.breaker_threshold = 2;
+    config.breaker_cooldown = Duration::from_millis(300);
+    let mut delivery = Delivery::new(reqwest::Client::new(), config, outbox.clone());
+
+    // First failure backs off, second opens the breaker for the cooldown
+    assert!(
+        matches!(delivery.drain().await.unwrap(), Drain::RetryIn(d) if d < Duration::from_millis(100))
+    );
+    let Drain::RetryIn(cooldown) = delivery.drain().await.unwrap() else {
+        panic!("expected breaker to hold delivery");
+    };
+    assert!(cooldown > Duration::from_millis(200));
+    assert_eq!(delivery.stats().lock().unwrap().breaker, BreakerState::Open);
+
+    // While open, drains do not touch the server
+    assert!(matches!(delivery.drain().await.unwrap(), Drain::RetryIn(_)));
+    assert_eq!(stub.requests().len(), 2);
+
+    // The half-open probe fails and reopens the breaker straight away
+    tokio::time::sleep(cooldown).await;
+    assert!(
+        matches!(delivery.drain().await.unwrap(), Drain::RetryIn(d) if d > Duration::from_millis(200))
+    );
+    assert_eq!(stub.requests().len(), 3);
+
+    // The next probe succeeds and closes it
+    drain_to_empty(&mut delivery).await;
+    assert_eq!(stub.requests().len(), 4);
+    assert_eq!(outbox.pending_events(), 0);
+    let stats = delivery.stats().lock().unwrap().clone();
+    assert_eq!(stats.breaker, BreakerState::Closed);
+    assert_eq!(stats.consecutive_failures, 0);
+}
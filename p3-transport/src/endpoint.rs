@@ -0,0 +1,314 @@
+use crate::{DecoderTransport, EndpointError, EndpointResult, TransportError, TransportResult};
+use std::fmt;
+use std::net::SocketAddr;
+use std::str::FromStr;
+
+/// Default decoder port for TCP and UDP
+pub const DEFAULT_PORT: u16 = 5403;
+
+/// Default serial line speed (8N1, no flow control)
+///
+/// NOTE: Not validated against a live RS-232 decoder; override with `?baud=`.
+pub const DEFAULT_BAUD_RATE: u32 = 9600;
+
+/// Where to reach a decoder, parsed from a URI
+///
+/// | URI | Meaning |
+/// |-----|---------|
+/// | `tcp://host[:port]` | Connect to the decoder's TCP server (port defaults to 5403) |
+/// | `serial://path[?baud=N]` | Open a tty, e.g. `serial:///dev/ttyUSB0?baud=19200` or `serial://COM3` |
+/// | `udp://addr:port[?peer=addr:port]` | Bind locally and receive datagrams from the decoder |
+///
+/// UDP writes (RESEND requests) go to `peer` when given, otherwise to the
+/// sender of the most recent datagram.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecoderEndpoint {
+    Tcp {
+        host: String,
+        port: u16,
+    },
+    Serial {
+        path: String,
+        baud_rate: u32,
+    },
+    Udp {
+        bind: SocketAddr,
+        peer: Option<SocketAddr>,
+    },
+}
+
+impl DecoderEndpoint {
+    pub fn tcp(host: impl Into<String>, port: u16) -> Self {
+        Self::Tcp {
+            host: host.into(),
+            port,
+        }
+    }
+
+    /// URI scheme, which is also the name of the cargo feature providing it
+    pub fn scheme(&self) -> &'static str {
+        match self {
+            Self::Tcp { .. } => "tcp",
+            Self::Serial { .. } => "serial",
+            Self::Udp { .. } => "udp",
+        }
+    }
+
+    /// Open the transport
+    ///
+    /// TCP connects, serial opens the tty in raw 8N1 mode, and UDP binds the
+    /// local address. None of them retry; reconnecting is up to the caller.
+    pub async fn open(&self) -> TransportResult<Box<dyn DecoderTransport>> {
+        match self {
+            #[cfg(feature = "tcp")]
+            Self::Tcp { host, port } => Ok(Box::new(
+                tokio::net::TcpStream::connect((host.as_str(), *port)).await?,
+            )),
+            #[cfg(feature = "serial")]
+            Self::Serial { path, baud_rate } => {
+                Ok(Box::new(crate::serial::open(path, *baud_rate)?))
+            }
+            #[cfg(feature = "udp")]
+            Self::Udp { bind, peer } => Ok(Box::new(
+                crate::udp::UdpTransport::bind(*bind, *peer).await?,
+            )),
+            #[allow(unreachable_patterns)]
+            other => Err(TransportError::Disabled(other.scheme())),
+        }
+    }
+}
+
+impl FromStr for DecoderEndpoint {
+    type Err = EndpointError;
+
+    fn from_str(uri: &str) -> EndpointResult<Self> {
+        let (scheme, rest) = uri
+            .split_once("://")
+            .ok_or_else(|| EndpointError::MissingScheme(uri.to_string()))?;
+        let (target, query) = rest.split_once('?').unwrap_or((rest, ""));
+        let params = parse_query(query)?;
+
+        match scheme.to_ascii_lowercase().as_str() {
+            "tcp" => {
+                reject_params(&params, &[])?;
+                let (host, port) = split_host_port(target)?;
+                Ok(Self::Tcp {
+                    host: host.to_string(),
+                    port: port.unwrap_or(DEFAULT_PORT),
+                })
+            }
+            "serial" => {
+                reject_params(&params, &["baud"])?;
+                if target.is_empty() {
+                    return Err(EndpointError::InvalidAddress(uri.to_string()));
+                }
+                let baud_rate = match param(&params, "baud") {
+                    Some(baud) => baud
+                        .parse()
+                        .ok()
+                        .filter(|baud| *baud > 0)
+                        .ok_or_else(|| EndpointError::InvalidQuery(format!("baud={}", baud)))?,
+                    None => DEFAULT_BAUD_RATE,
+                };
+                Ok(Self::Serial {
+                    path: target.to_string(),
+                    baud_rate,
+                })
+            }
+            "udp" => {
+                reject_params(&params, &["peer"])?;
+                let bind = parse_socket_addr(target)?;
+                let peer = param(&params, "peer").map(parse_socket_addr).transpose()?;
+                Ok(Self::Udp { bind, peer })
+            }
+            _ => Err(EndpointError::UnknownScheme(scheme.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for DecoderEndpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tcp { host, port } if host.contains(':') => {
+                write!(f, "tcp://[{}]:{}", host, port)
+            }
+            Self::Tcp { host, port } => write!(f, "tcp://{}:{}", host, port),
+            Self::Serial { path, baud_rate } => write!(f, "serial://{}?baud={}", path, baud_rate),
+            Self::Udp { bind, peer: None } => write!(f, "udp://{}", bind),
+            Self::Udp {
+                bind,
+                peer: Some(peer),
+            } => write!(f, "udp://{}?peer={}", bind, peer),
+        }
+    }
+}
+
+fn parse_query(query: &str) -> EndpointResult<Vec<(&str, &str)>> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            pair.split_once('=')
+                .ok_or_else(|| EndpointError::InvalidQuery(pair.to_string()))
+        })
+        .collect()
+}
+
+fn param<'a>(params: &[(&str, &'a str)], key: &str) -> Option<&'a str> {
+    params.iter().find(|(k, _)| *k == key).map(|(_, v)| *v)
+}
+
+fn reject_params(params: &[(&str, &str)], allowed: &[&str]) -> EndpointResult<()> {
+    match params.iter().find(|(k, _)| !allowed.contains(k)) {
+        Some((k, v)) => Err(EndpointError::InvalidQuery(format!("{}={}", k, v))),
+        None => Ok(()),
+    }
+}
+
+/// Split `host`, `host:port`, `[v6]` or `[v6]:port`
+fn split_host_port(target: &str) -> EndpointResult<(&str, Option<u16>)> {
+    let invalid = || EndpointError::InvalidAddress(target.to_string());
+
+    let (host, port) = if let Some(bracketed) = target.strip_prefix('[') {
+        let (host, after) = bracketed.split_once(']').ok_or_else(invalid)?;
+        match after {
+            "" => (host, None),
+            _ => (host, Some(after.strip_prefix(':').ok_or_else(invalid)?)),
+        }
+    } else {
+        match target.split_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (target, None),
+        }
+    };
+
+    if host.is_empty() {
+        return Err(invalid());
+    }
+    let port = port
+        .map(|p| p.parse::<u16>().map_err(|_| invalid()))
+        .transpose()?;
+    Ok((host, port))
+}
+
+fn parse_socket_addr(addr: &str) -> EndpointResult<SocketAddr> {
+    addr.parse()
+        .map_err(|_| EndpointError::InvalidAddress(addr.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(uri: &str) -> EndpointResult<DecoderEndpoint> {
+        uri.parse()
+    }
+
+    #[test]
+    fn test_parse_tcp() {
+        assert_eq!(
+            parse("tcp://10.0.0.20:5403").unwrap(),
+            DecoderEndpoint::tcp("10.0.0.20", 5403)
+        );
+        assert_eq!(
+            parse("tcp://decoder.local").unwrap(),
+            DecoderEndpoint::tcp("decoder.local", DEFAULT_PORT)
+        );
+        assert_eq!(
+            parse("TCP://[::1]:6000").unwrap(),
+            DecoderEndpoint::tcp("::1", 6000)
+        );
+        assert!(matches!(
+            parse("tcp://host:99999"),
+            Err(EndpointError::InvalidAddress(_))
+        ));
+        assert!(matches!(
+            parse("tcp://:5403"),
+            Err(EndpointError::InvalidAddress(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_serial() {
+        assert_eq!(
+            parse("serial:///dev/ttyUSB0?baud=19200").unwrap(),
+            DecoderEndpoint::Serial {
+                path: "/dev/ttyUSB0".to_string(),
+                baud_rate: 19200,
+            }
+        );
+        assert_eq!(
+            parse("serial://COM3").unwrap(),
+            DecoderEndpoint::Serial {
+                path: "COM3".to_string(),
+                baud_rate: DEFAULT_BAUD_RATE,
+            }
+        );
+        assert_eq!(
+            parse("serial:///dev/ttyS0?baud=fast"),
+            Err(EndpointError::InvalidQuery("baud=fast".to_string()))
+        );
+        assert!(matches!(
+            parse("serial://"),
+            Err(EndpointError::InvalidAddress(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_udp() {
+        assert_eq!(
+            parse("udp://0.0.0.0:5403").unwrap(),
+            DecoderEndpoint::Udp {
+                bind: "0.0.0.0:5403".parse().unwrap(),
+                peer: None,
+            }
+        );
+        assert_eq!(
+            parse("udp://0.0.0.0:5403?peer=10.0.0.20:5403").unwrap(),
+            DecoderEndpoint::Udp {
+                bind: "0.0.0.0:5403".parse().unwrap(),
+                peer: Some("10.0.0.20:5403".parse().unwrap()),
+            }
+        );
+        // Binding needs an IP, not a hostname
+        assert!(matches!(
+            parse("udp://localhost:5403"),
+            Err(EndpointError::InvalidAddress(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            parse("10.0.0.20:5403"),
+            Err(EndpointError::MissingScheme("10.0.0.20:5403".to_string()))
+        );
+        assert_eq!(
+            parse("http://10.0.0.20"),
+            Err(EndpointError::UnknownScheme("http".to_string()))
+        );
+        assert_eq!(
+            parse("tcp://10.0.0.20?baud=9600"),
+            Err(EndpointError::InvalidQuery("baud=9600".to_string()))
+        );
+        assert_eq!(
+            parse("serial:///dev/ttyS0?baud"),
+            Err(EndpointError::InvalidQuery("baud".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_display_round_trips() {
+        for uri in [
+            "tcp://10.0.0.20:5403",
+            "tcp://[::1]:5403",
+            "serial:///dev/ttyUSB0?baud=9600",
+            "udp://0.0.0.0:5403",
+            "udp://0.0.0.0:5403?peer=10.0.0.20:5403",
+        ] {
+            let endpoint = parse(uri).unwrap();
+            assert_eq!(endpoint.to_string(), uri);
+            assert_eq!(parse(&endpoint.to_string()).unwrap(), endpoint);
+        }
+    }
+}
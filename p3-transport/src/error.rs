@@ -0,0 +1,31 @@
+use thiserror::Error;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum EndpointError {
+    /// URI has no `scheme://` prefix
+    #[error("Missing scheme in decoder URI {0:?}: expected tcp://, serial:// or udp://")]
+    MissingScheme(String),
+
+    #[error("Unknown decoder URI scheme {0:?}: expected tcp, serial or udp")]
+    UnknownScheme(String),
+
+    #[error("Invalid address {0:?}")]
+    InvalidAddress(String),
+
+    #[error("Invalid query parameter {0:?}")]
+    InvalidQuery(String),
+}
+
+pub type EndpointResult<T> = Result<T, EndpointError>;
+
+#[derive(Debug, Error)]
+pub enum TransportError {
+    /// The endpoint's transport was not compiled in
+    #[error("{0} transport is not available: build with the `{0}` feature")]
+    Disabled(&'static str),
+
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+pub type TransportResult<T> = Result<T, TransportError>;
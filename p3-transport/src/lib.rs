@@ -0,0 +1,37 @@
+//! Byte transports to MyLaps P3 decoders
+//!
+//! Decoders are reached over TCP (the ProChip default on port 5403), a
+//! serial/RS-232 line, or UDP. All three carry the same escaped P3 frames,
+//! so consumers open a [`DecoderEndpoint`] parsed from a URI and treat the
+//! result as an ordinary async byte stream:
+//!
+//! ```text
+//! tcp://10.0.0.20:5403
+//! serial:///dev/ttyUSB0?baud=9600
+//! udp://0.0.0.0:5403?peer=10.0.0.20:5403
+//! ```
+//!
+//! Each transport sits behind a cargo feature of the same name (`tcp`,
+//! `serial`, `udp`); all are enabled by default. URIs for a disabled
+//! transport still parse, but opening them fails with
+//! [`TransportError::Disabled`].
+
+mod endpoint;
+mod error;
+#[cfg(feature = "serial")]
+pub mod serial;
+#[cfg(feature = "udp")]
+pub mod udp;
+
+pub use endpoint::*;
+pub use error::*;
+
+use tokio::io::{AsyncRead, AsyncWrite};
+
+/// An open byte stream to a decoder
+///
+/// Reads yield raw wire bytes for a `MessageFramer`; writes carry requests
+/// such as RESEND back to the decoder.
+pub trait DecoderTransport: AsyncRead + AsyncWrite + Send + Unpin {}
+
+impl<T> DecoderTransport for T where T: AsyncRead + AsyncWrite + Send + Unpin {}
@@ -0,0 +1,20 @@
+//! Serial/RS-232 transport
+//!
+//! Older decoders and some track installs expose P3 over a serial line
+//! (often through a USB adapter). The wire bytes are the same escaped frames
+//! as over TCP, so the port is used as a plain byte stream.
+
+use tokio_serial::{DataBits, FlowControl, Parity, SerialPortBuilderExt, StopBits};
+
+pub use tokio_serial::SerialStream;
+
+/// Open `path` at `baud_rate`, 8N1, no flow control, in raw mode
+pub fn open(path: &str, baud_rate: u32) -> std::io::Result<SerialStream> {
+    let stream = tokio_serial::new(path, baud_rate)
+        .data_bits(DataBits::Eight)
+        .parity(Parity::None)
+        .stop_bits(StopBits::One)
+        .flow_control(FlowControl::None)
+        .open_native_async()?;
+    Ok(stream)
+}
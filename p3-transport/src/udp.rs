@@ -0,0 +1,177 @@
+//! UDP transport
+//!
+//! Decoders configured for UDP push each batch of frames as a datagram to a
+//! listening host. [`UdpTransport`] presents those datagrams as a byte stream
+//! so the usual framer can consume them unchanged; a frame split across
+//! datagrams is reassembled by the framer like a frame split across reads.
+
+use std::io;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::task::{Context, Poll, ready};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::UdpSocket;
+
+/// Largest datagram accepted; longer ones are truncated by the OS
+const MAX_DATAGRAM: usize = 65_536;
+
+/// A bound UDP socket read and written as a byte stream
+///
+/// Reads return datagram payloads in arrival order, from any sender. Writes
+/// send one datagram per call to the configured peer, or to the sender of
+/// the latest datagram when no peer was given.
+pub struct UdpTransport {
+    socket: UdpSocket,
+    peer: Option<SocketAddr>,
+    fixed_peer: bool,
+    buffer: Box<[u8]>,
+    /// Length of the current datagram in `buffer`
+    len: usize,
+    /// Bytes of the current datagram already returned to the reader
+    consumed: usize,
+}
+
+impl UdpTransport {
+    pub async fn bind(addr: SocketAddr, peer: Option<SocketAddr>) -> io::Result<Self> {
+        Ok(Self {
+            socket: UdpSocket::bind(addr).await?,
+            peer,
+            fixed_peer: peer.is_some(),
+            buffer: vec![0; MAX_DATAGRAM].into_boxed_slice(),
+            len: 0,
+            consumed: 0,
+        })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.socket.local_addr()
+    }
+
+    /// Where writes currently go, if anywhere
+    pub fn peer(&self) -> Option<SocketAddr> {
+        self.peer
+    }
+}
+
+impl AsyncRead for UdpTransport {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+
+        // An empty datagram must not surface as a zero-length read (EOF)
+        while this.consumed == this.len {
+            let mut datagram = ReadBuf::new(&mut this.buffer);
+            let from = ready!(this.socket.poll_recv_from(cx, &mut datagram))?;
+            this.len = datagram.filled().len();
+            this.consumed = 0;
+            if !this.fixed_peer {
+                this.peer = Some(from);
+            }
+        }
+
+        let n = buf.remaining().min(this.len - this.consumed);
+        buf.put_slice(&this.buffer[this.consumed..this.consumed + n]);
+        this.consumed += n;
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncWrite for UdpTransport {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let Some(peer) = self.peer else {
+            return Poll::Ready(Err(io::Error::new(
+                io::ErrorKind::NotConnected,
+                "no UDP peer yet: set ?peer= or wait for a datagram from the decoder",
+            )));
+        };
+        let n = ready!(self.socket.poll_send_to(cx, buf, peer))?;
+        Poll::Ready(Ok(n))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::time::{Duration, timeout};
+
+    async fn loopback() -> (UdpTransport, UdpSocket) {
+        let transport = UdpTransport::bind("127.0.0.1:0".parse().unwrap(), None)
+            .await
+            .unwrap();
+        let decoder = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        decoder
+            .connect(transport.local_addr().unwrap())
+            .await
+            .unwrap();
+        (transport, decoder)
+    }
+
+    async fn read(transport: &mut UdpTransport, buf: &mut [u8]) -> usize {
+        timeout(Duration::from_secs(5), transport.read(buf))
+            .await
+            .expect("timed out waiting for datagram")
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_datagram_larger_than_read_buffer_is_split() {
+        let (mut transport, decoder) = loopback().await;
+        decoder.send(&[1, 2, 3, 4, 5]).await.unwrap();
+        decoder.send(&[6]).await.unwrap();
+
+        let mut buf = [0u8; 3];
+        assert_eq!(read(&mut transport, &mut buf).await, 3);
+        assert_eq!(buf, [1, 2, 3]);
+        assert_eq!(read(&mut transport, &mut buf).await, 2);
+        assert_eq!(&buf[..2], &[4, 5]);
+        assert_eq!(read(&mut transport, &mut buf).await, 1);
+        assert_eq!(buf[0], 6);
+    }
+
+    #[tokio::test]
+    async fn test_empty_datagram_is_not_eof() {
+        let (mut transport, decoder) = loopback().await;
+        decoder.send(&[]).await.unwrap();
+        decoder.send(&[0x8E]).await.unwrap();
+
+        let mut buf = [0u8; 16];
+        assert_eq!(read(&mut transport, &mut buf).await, 1);
+        assert_eq!(buf[0], 0x8E);
+    }
+
+    #[tokio::test]
+    async fn test_writes_reply_to_latest_sender() {
+        let (mut transport, decoder) = loopback().await;
+
+        let err = transport.write_all(&[0x8E]).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotConnected);
+
+        decoder.send(&[0x8E]).await.unwrap();
+        read(&mut transport, &mut [0u8; 16]).await;
+        assert_eq!(transport.peer(), Some(decoder.local_addr().unwrap()));
+
+        transport.write_all(&[0x8E, 0x02, 0x8F]).await.unwrap();
+        let mut buf = [0u8; 16];
+        let n = timeout(Duration::from_secs(5), decoder.recv(&mut buf))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(&buf[..n], &[0x8E, 0x02, 0x8F]);
+    }
+}
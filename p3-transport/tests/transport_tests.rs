@@ -0,0 +1,159 @@
+//! Integration tests opening each transport from its endpoint URI.
+//!
+//! Serial runs against a pseudo-terminal pair: the test holds the master side
+//! as the "decoder" and the transport opens the slave tty by path. Frames
+//! include bytes a cooked tty would mangle (0x03 is ^C, 0x0D is CR), so a
+//! byte-for-byte round trip also proves the port was put into raw mode.
+#![cfg(all(feature = "tcp", feature = "serial", feature = "udp"))]
+
+use p3_protocol::{MessageType, build_frame, build_resend_request};
+use p3_transport::DecoderEndpoint;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::time::{Duration, timeout};
+
+/// STATUS frame whose body contains ^C, CR and LF
+fn status_frame() -> Vec<u8> {
+    build_frame(
+        MessageType::Status,
+        &[
+            0x01, 0x02, 0x03, 0x00, 0x06, 0x01, 0x0D, 0x07, 0x02, 0x0A, 0x00,
+        ],
+    )
+}
+
+async fn read_exactly(transport: &mut (impl AsyncReadExt + Unpin), len: usize) -> Vec<u8> {
+    let mut received = vec![0u8; len];
+    timeout(Duration::from_secs(5), transport.read_exact(&mut received))
+        .await
+        .expect("timed out waiting for decoder bytes")
+        .unwrap();
+    received
+}
+
+#[tokio::test]
+async fn test_tcp_round_trip() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let endpoint: DecoderEndpoint = format!("tcp://127.0.0.1:{}", port).parse().unwrap();
+
+    let (transport, accepted) = tokio::join!(endpoint.open(), listener.accept());
+    let mut transport = transport.unwrap();
+    let (mut decoder, _) = accepted.unwrap();
+
+    let frame = status_frame();
+    decoder.write_all(&frame).await.unwrap();
+    assert_eq!(read_exactly(&mut transport, frame.len()).await, frame);
+
+    let resend = build_resend_request(10, 12);
+    transport.write_all(&resend).await.unwrap();
+    assert_eq!(read_exactly(&mut decoder, resend.len()).await, resend);
+}
+
+#[tokio::test]
+async fn test_udp_round_trip() {
+    let decoder = tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    // Reserve a free port for the transport, then hand it over
+    let port = std::net::UdpSocket::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let endpoint: DecoderEndpoint = format!(
+        "udp://127.0.0.1:{}?peer={}",
+        port,
+        decoder.local_addr().unwrap()
+    )
+    .parse()
+    .unwrap();
+    let mut transport = endpoint.open().await.unwrap();
+
+    // RESEND can go out before anything is received when the peer is fixed
+    let resend = build_resend_request(10, 12);
+    transport.write_all(&resend).await.unwrap();
+    let mut buf = [0u8; 64];
+    let (n, from) = timeout(Duration::from_secs(5), decoder.recv_from(&mut buf))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(&buf[..n], resend.as_slice());
+
+    // One frame split over two datagrams reads back as one contiguous stream
+    let frame = status_frame();
+    let (head, tail) = frame.split_at(5);
+    decoder.send_to(head, from).await.unwrap();
+    decoder.send_to(tail, from).await.unwrap();
+    assert_eq!(read_exactly(&mut transport, frame.len()).await, frame);
+}
+
+#[cfg(unix)]
+mod serial {
+    use super::*;
+    use std::ffi::CStr;
+    use std::fs::File;
+    use std::io::{Read, Write};
+    use std::os::fd::FromRawFd;
+    use std::sync::mpsc;
+
+    /// Open a pseudo-terminal pair, returning the master and the slave path
+    ///
+    /// The slave fd is returned too so the pair stays up for the whole test.
+    fn openpty() -> (File, File, String) {
+        let mut master = 0;
+        let mut slave = 0;
+        let mut name = [0 as libc::c_char; 128];
+        // SAFETY: all pointers are valid for the call; termios/winsize may be null
+        let rc = unsafe {
+            libc::openpty(
+                &mut master,
+                &mut slave,
+                name.as_mut_ptr(),
+                std::ptr::null(),
+                std::ptr::null(),
+            )
+        };
+        assert_eq!(rc, 0, "openpty failed: {}", std::io::Error::last_os_error());
+
+        // SAFETY: openpty wrote a NUL-terminated path and returned two owned fds
+        let path = unsafe { CStr::from_ptr(name.as_ptr()) }
+            .to_str()
+            .unwrap()
+            .to_string();
+        let (master, slave) = unsafe { (File::from_raw_fd(master), File::from_raw_fd(slave)) };
+        (master, slave, path)
+    }
+
+    /// Blocking read of `len` bytes from the pty master, bounded by a timeout
+    fn read_master(master: &File, len: usize) -> Vec<u8> {
+        let mut master = master.try_clone().unwrap();
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let mut buf = vec![0u8; len];
+            let _ = tx.send(master.read_exact(&mut buf).map(|_| buf));
+        });
+        rx.recv_timeout(std::time::Duration::from_secs(5))
+            .expect("timed out reading pty master")
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_serial_round_trip_over_pty() {
+        let (mut master, _slave, path) = openpty();
+        let endpoint: DecoderEndpoint = format!("serial://{}?baud=9600", path).parse().unwrap();
+        let mut transport = endpoint.open().await.unwrap();
+
+        let frame = status_frame();
+        master.write_all(&frame).unwrap();
+        assert_eq!(read_exactly(&mut transport, frame.len()).await, frame);
+
+        let resend = build_resend_request(10, 12);
+        transport.write_all(&resend).await.unwrap();
+        transport.flush().await.unwrap();
+        assert_eq!(read_master(&master, resend.len()), resend);
+    }
+
+    #[tokio::test]
+    async fn test_serial_missing_device_fails_to_open() {
+        let endpoint: DecoderEndpoint = "serial:///dev/does-not-exist-p3".parse().unwrap();
+        assert!(endpoint.open().await.is_err());
+    }
+}